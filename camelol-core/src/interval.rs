@@ -0,0 +1,111 @@
+use crate::scale::{Scale, WHEEL_SIZE};
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+const NAMES: [&str; WHEEL_SIZE] = [
+    "unison",
+    "minor second",
+    "major second",
+    "minor third",
+    "major third",
+    "perfect fourth",
+    "tritone",
+    "perfect fifth",
+    "minor sixth",
+    "major sixth",
+    "minor seventh",
+    "major seventh",
+];
+
+/// The distance between two tonics, measured upward within one octave.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Interval {
+    pub semitones: usize,
+}
+
+impl Interval {
+    pub const fn new(semitones: usize) -> Self {
+        Self {
+            semitones: semitones % WHEEL_SIZE,
+        }
+    }
+
+    /// The interval from the tonic of `from` up to the tonic of `to`, so `ChangeIndex(1)` is a
+    /// perfect fifth and going from 8A to its relative major 8B is a minor third.
+    pub const fn between(from: Scale, to: Scale) -> Self {
+        Self::new(to.tonic() + WHEEL_SIZE - from.tonic())
+    }
+
+    /// How far the tonic moves taking the shorter way, up or down, so a perfect fifth up is the
+    /// same five semitones as the perfect fourth down.
+    pub const fn movement(self) -> usize {
+        if self.semitones > WHEEL_SIZE / 2 {
+            WHEEL_SIZE - self.semitones
+        } else {
+            self.semitones
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        NAMES[self.semitones]
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseIntervalError;
+
+impl Display for ParseIntervalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid interval")
+    }
+}
+
+impl core::error::Error for ParseIntervalError {}
+
+/// Parses an interval name like `perfect fifth`.
+impl FromStr for Interval {
+    type Err = ParseIntervalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NAMES
+            .iter()
+            .position(|name| s.trim().eq_ignore_ascii_case(name))
+            .map(Self::new)
+            .ok_or(ParseIntervalError)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Interval {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Interval {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IntervalVisitor;
+
+        impl serde::de::Visitor<'_> for IntervalVisitor {
+            type Value = Interval;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                write!(f, "an interval like `perfect fifth`")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Interval, E> {
+                v.parse()
+                    .map_err(|_| E::custom(format_args!("invalid interval `{v}`")))
+            }
+        }
+
+        deserializer.deserialize_str(IntervalVisitor)
+    }
+}
@@ -0,0 +1,26 @@
+//! The harmonic rules of the camelot wheel without the standard library or allocation, backed by
+//! fixed-size lookup tables so they can run on embedded controllers and in plugin hosts.
+
+#![no_std]
+
+mod interval;
+mod mode;
+mod name;
+mod numbering;
+mod open_key;
+mod scale;
+mod table;
+mod transition;
+
+pub use interval::{Interval, ParseIntervalError};
+pub use mode::{ModalKey, Mode};
+pub use name::{note_name, note_name_in, parse_english_note, Accidental, KeyName, Locale};
+pub use numbering::WheelNumbering;
+pub use open_key::OpenKey;
+pub use scale::{scale, ParseScaleError, Scale, ScaleKind, KEY_COUNT, WHEEL_SIZE};
+pub use table::{
+    distance, distances_from, neighbors, shortest_path, KeyPath, DISTANCES, FIRST_HOPS, NEIGHBORS,
+};
+pub use transition::{
+    make_transition, make_transition_on, ScaleTransition, TRANSITIONS, TRANSITION_COUNT,
+};
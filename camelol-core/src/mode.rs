@@ -0,0 +1,132 @@
+use crate::name::{parse_english_note, Accidental, FLAT_NAMES, SHARP_NAMES};
+use crate::scale::{ParseScaleError, Scale, ScaleKind, WHEEL_SIZE};
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+/// The seven modes of the major scale.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Mode {
+    Ionian,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Aeolian,
+    Locrian,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 7] = [
+        Mode::Ionian,
+        Mode::Dorian,
+        Mode::Phrygian,
+        Mode::Lydian,
+        Mode::Mixolydian,
+        Mode::Aeolian,
+        Mode::Locrian,
+    ];
+
+    /// Semitones from the tonic of the parent major scale up to the tonic of the mode.
+    pub const fn degree(self) -> usize {
+        match self {
+            Mode::Ionian => 0,
+            Mode::Dorian => 2,
+            Mode::Phrygian => 4,
+            Mode::Lydian => 5,
+            Mode::Mixolydian => 7,
+            Mode::Aeolian => 9,
+            Mode::Locrian => 11,
+        }
+    }
+
+    /// Major for modes with a major third, minor for the rest.
+    pub const fn kind(self) -> ScaleKind {
+        match self {
+            Mode::Ionian | Mode::Lydian | Mode::Mixolydian => ScaleKind::Major,
+            Mode::Dorian | Mode::Phrygian | Mode::Aeolian | Mode::Locrian => ScaleKind::Minor,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Mode::Ionian => "Ionian",
+            Mode::Dorian => "Dorian",
+            Mode::Phrygian => "Phrygian",
+            Mode::Lydian => "Lydian",
+            Mode::Mixolydian => "Mixolydian",
+            Mode::Aeolian => "Aeolian",
+            Mode::Locrian => "Locrian",
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A tonic and a mode, like D Dorian.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ModalKey {
+    /// Pitch class of the tonic in semitones above C.
+    pub tonic: usize,
+    pub mode: Mode,
+}
+
+impl ModalKey {
+    pub const fn new(tonic: usize, mode: Mode) -> Self {
+        Self {
+            tonic: tonic % WHEEL_SIZE,
+            mode,
+        }
+    }
+
+    /// The wheel key with the same notes, on the major side for modes with a major third and the
+    /// minor side otherwise. D Dorian and G Mixolydian share the notes of C major, so they land
+    /// on 8A and 8B.
+    pub const fn scale(self) -> Scale {
+        let parent = (self.tonic + WHEEL_SIZE - self.mode.degree()) % WHEEL_SIZE;
+        let major = Scale::from_tonic(parent, ScaleKind::Major);
+        match self.mode.kind() {
+            ScaleKind::Major => major,
+            ScaleKind::Minor => major.swap_kind(),
+        }
+    }
+}
+
+impl From<Scale> for ModalKey {
+    fn from(scale: Scale) -> Self {
+        let mode = match scale.kind {
+            ScaleKind::Major => Mode::Ionian,
+            ScaleKind::Minor => Mode::Aeolian,
+        };
+        Self::new(scale.tonic(), mode)
+    }
+}
+
+/// Written like `D Dorian`, with black-key tonics spelled the way the wheel key is.
+impl Display for ModalKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let names = match self.scale().conventional_accidental() {
+            Accidental::Flat => &FLAT_NAMES,
+            Accidental::Sharp => &SHARP_NAMES,
+        };
+        write!(f, "{} {}", names[self.tonic], self.mode)
+    }
+}
+
+/// Parses a tonic followed by a mode name, like `D dorian` or `F# Mixolydian`.
+impl FromStr for ModalKey {
+    type Err = ParseScaleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tonic, rest) = parse_english_note(s.trim()).ok_or(ParseScaleError)?;
+        let rest = rest.trim_start();
+        let mode = Mode::ALL
+            .into_iter()
+            .find(|mode| rest.eq_ignore_ascii_case(mode.name()))
+            .ok_or(ParseScaleError)?;
+        Ok(Self::new(tonic, mode))
+    }
+}
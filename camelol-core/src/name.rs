@@ -0,0 +1,391 @@
+use crate::scale::{scale, ParseScaleError, Scale, ScaleKind, WHEEL_SIZE};
+use core::fmt::{self, Display, Formatter};
+
+/// Note names by pitch class, in semitones above C.
+pub(crate) const SHARP_NAMES: [&str; WHEEL_SIZE] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+pub(crate) const FLAT_NAMES: [&str; WHEEL_SIZE] = [
+    "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
+];
+const GERMAN_SHARP_NAMES: [&str; WHEEL_SIZE] = [
+    "C", "Cis", "D", "Dis", "E", "F", "Fis", "G", "Gis", "A", "Ais", "H",
+];
+const GERMAN_FLAT_NAMES: [&str; WHEEL_SIZE] = [
+    "C", "Des", "D", "Es", "E", "F", "Ges", "G", "As", "A", "B", "H",
+];
+
+/// Pitch classes of the natural notes, by letter from C.
+const LETTER_PITCHES: [usize; 7] = [0, 2, 4, 5, 7, 9, 11];
+/// Names of every letter from C, from two flats to two sharps.
+const SPELLED_NAMES: [[&str; 5]; 7] = [
+    ["Cbb", "Cb", "C", "C#", "C##"],
+    ["Dbb", "Db", "D", "D#", "D##"],
+    ["Ebb", "Eb", "E", "E#", "E##"],
+    ["Fbb", "Fb", "F", "F#", "F##"],
+    ["Gbb", "Gb", "G", "G#", "G##"],
+    ["Abb", "Ab", "A", "A#", "A##"],
+    ["Bbb", "Bb", "B", "B#", "B##"],
+];
+const GERMAN_SPELLED_NAMES: [[&str; 5]; 7] = [
+    ["Ceses", "Ces", "C", "Cis", "Cisis"],
+    ["Deses", "Des", "D", "Dis", "Disis"],
+    ["Eses", "Es", "E", "Eis", "Eisis"],
+    ["Feses", "Fes", "F", "Fis", "Fisis"],
+    ["Geses", "Ges", "G", "Gis", "Gisis"],
+    ["Ases", "As", "A", "Ais", "Aisis"],
+    ["Heses", "B", "H", "His", "Hisis"],
+];
+
+/// Semitones above the tonic of each degree of the major and natural minor scales.
+const MAJOR_STEPS: [usize; 7] = [0, 2, 4, 5, 7, 9, 11];
+const MINOR_STEPS: [usize; 7] = [0, 2, 3, 5, 7, 8, 10];
+
+/// Whether black-key tonics are spelled as sharps (`C#`) or flats (`Db`).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Accidental {
+    Flat,
+    Sharp,
+}
+
+/// The language key names are written in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum Locale {
+    /// `B minor`, `Bb major`
+    #[default]
+    English,
+    /// `h-Moll`, `B-Dur`: B is written H and Bb is B, sharps end in -is and flats in -es, and
+    /// minor keys are lowercase.
+    German,
+}
+
+/// The English name of a pitch class in semitones above C, like `F#` or `Gb`.
+pub const fn note_name(pitch_class: usize, accidental: Accidental) -> &'static str {
+    note_name_in(pitch_class, accidental, Locale::English)
+}
+
+/// Like [`note_name`] in `locale`, like `Fis` or `Ges` in German.
+pub const fn note_name_in(
+    pitch_class: usize,
+    accidental: Accidental,
+    locale: Locale,
+) -> &'static str {
+    let names = match (locale, accidental) {
+        (Locale::English, Accidental::Flat) => &FLAT_NAMES,
+        (Locale::English, Accidental::Sharp) => &SHARP_NAMES,
+        (Locale::German, Accidental::Flat) => &GERMAN_FLAT_NAMES,
+        (Locale::German, Accidental::Sharp) => &GERMAN_SHARP_NAMES,
+    };
+    names[pitch_class % WHEEL_SIZE]
+}
+
+/// The letter an English note name is on, from C.
+const fn letter(name: &str) -> usize {
+    match name.as_bytes()[0] {
+        b'C' => 0,
+        b'D' => 1,
+        b'E' => 2,
+        b'F' => 3,
+        b'G' => 4,
+        b'A' => 5,
+        _ => 6,
+    }
+}
+
+impl Scale {
+    /// Pitch class of the tonic in semitones above C, in standard camelot numbering. Moving one
+    /// step around the wheel moves the tonic up a fifth, and each major key is the relative
+    /// major of the minor key next to it. [`WheelNumbering`](crate::WheelNumbering) numbers it
+    /// otherwise.
+    pub const fn tonic(self) -> usize {
+        let minor = (8 + 7 * self.index) % WHEEL_SIZE;
+        match self.kind {
+            ScaleKind::Minor => minor,
+            ScaleKind::Major => (minor + 3) % WHEEL_SIZE,
+        }
+    }
+
+    /// The seven pitch classes of the scale in semitones above C, starting from its tonic, so
+    /// 8A has A, B, C, D, E, F and G. Minor keys are natural minor.
+    pub const fn notes(self) -> [usize; 7] {
+        let steps = match self.kind {
+            ScaleKind::Major => MAJOR_STEPS,
+            ScaleKind::Minor => MINOR_STEPS,
+        };
+        let mut notes = [0; 7];
+        let mut degree = 0;
+        while degree < 7 {
+            notes[degree] = (self.tonic() + steps[degree]) % WHEEL_SIZE;
+            degree += 1;
+        }
+        notes
+    }
+
+    /// The English names of [`Scale::notes`], with a black-key tonic spelled using
+    /// `accidental` and every other degree on the next letter, so each letter comes up once,
+    /// like Cb in Gb major.
+    pub const fn note_names(self, accidental: Accidental) -> [&'static str; 7] {
+        self.note_names_in(accidental, Locale::English)
+    }
+
+    /// Like [`Scale::note_names`] in `locale`, like `Ces` for Cb in German.
+    pub const fn note_names_in(self, accidental: Accidental, locale: Locale) -> [&'static str; 7] {
+        let spelled = match locale {
+            Locale::English => &SPELLED_NAMES,
+            Locale::German => &GERMAN_SPELLED_NAMES,
+        };
+        let notes = self.notes();
+        let tonic = letter(note_name(notes[0], accidental));
+        let mut names = [""; 7];
+        let mut degree = 0;
+        while degree < 7 {
+            let letter = (tonic + degree) % 7;
+            // Two flats are at 0, the natural note at 2 and two sharps at 4.
+            let offset = (notes[degree] + WHEEL_SIZE + 2 - LETTER_PITCHES[letter]) % WHEEL_SIZE;
+            names[degree] = spelled[letter][offset];
+            degree += 1;
+        }
+        names
+    }
+
+    /// The key with the given tonic pitch class and mode, the inverse of [`Scale::tonic`].
+    pub const fn from_tonic(tonic: usize, kind: ScaleKind) -> Self {
+        // Seven fifths are one semitone up, so multiplying by 7 turns semitones into wheel steps.
+        let tonic = tonic % WHEEL_SIZE;
+        let index = match kind {
+            ScaleKind::Minor => (tonic + WHEEL_SIZE - 8) * 7 % WHEEL_SIZE,
+            ScaleKind::Major => (tonic + 1) * 7 % WHEEL_SIZE,
+        };
+        scale(index, kind)
+    }
+
+    /// Parses a musical key name like `F#m`, `Db major` or, in German, `fis-Moll`.
+    pub fn from_key_name(input: &str, locale: Locale) -> Result<Self, ParseScaleError> {
+        let input = input.trim();
+        match locale {
+            Locale::English => parse_english(input),
+            Locale::German => parse_german(input),
+        }
+        .ok_or(ParseScaleError)
+    }
+
+    /// The spelling with the smaller key signature. 2B and 2A have six accidentals either way
+    /// and are written the way they usually are, as F# major and Eb minor.
+    pub const fn conventional_accidental(self) -> Accidental {
+        // Fifths above C major of the key signature: 8B and 8A have none, 9B and 9A one sharp.
+        let fifths = (self.index + WHEEL_SIZE - 7) % WHEEL_SIZE;
+        match (fifths, self.kind) {
+            (6, ScaleKind::Major) => Accidental::Sharp,
+            (6, ScaleKind::Minor) => Accidental::Flat,
+            (1..=5, _) => Accidental::Sharp,
+            _ => Accidental::Flat,
+        }
+    }
+
+    /// The musical name of the key with its conventional spelling, displayed like `A minor`.
+    pub const fn key_name(self) -> KeyName {
+        self.key_name_with(self.conventional_accidental())
+    }
+
+    /// The musical name of the key with black-key tonics spelled using `accidental`.
+    pub const fn key_name_with(self, accidental: Accidental) -> KeyName {
+        KeyName {
+            scale: self,
+            accidental,
+            locale: Locale::English,
+        }
+    }
+}
+
+/// Splits an English note name like `F#` off the front of `input`, returning its pitch class.
+pub fn parse_english_note(input: &str) -> Option<(usize, &str)> {
+    let mut chars = input.chars();
+    let mut tonic = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let mut rest = chars.as_str();
+    if let Some(after) = rest.strip_prefix(['#', '♯']) {
+        tonic += 1;
+        rest = after;
+    } else if let Some(after) = rest.strip_prefix(['b', '♭']) {
+        tonic += WHEEL_SIZE - 1;
+        rest = after;
+    } else if let Some(after) = strip_word(rest.trim_start_matches(SEPARATORS), "sharp") {
+        tonic += 1;
+        rest = after;
+    } else if let Some(after) = strip_word(rest.trim_start_matches(SEPARATORS), "flat") {
+        tonic += WHEEL_SIZE - 1;
+        rest = after;
+    }
+
+    Some((tonic % WHEEL_SIZE, rest))
+}
+
+/// What may come between the parts of a key name, as in `F#-minor`, `A min` or `C:maj`.
+const SEPARATORS: [char; 4] = ['-', ' ', ':', '_'];
+
+/// Strips `word` off the front of `input`, ignoring case.
+fn strip_word<'a>(input: &'a str, word: &str) -> Option<&'a str> {
+    input
+        .get(..word.len())
+        .filter(|start| start.eq_ignore_ascii_case(word))
+        .map(|_| &input[word.len()..])
+}
+
+fn parse_english(input: &str) -> Option<Scale> {
+    let (tonic, rest) = parse_english_note(input)?;
+    let mode = rest.trim_start_matches(SEPARATORS).trim_end_matches('.');
+    let kind = if ["", "maj", "major"]
+        .iter()
+        .any(|word| mode.eq_ignore_ascii_case(word))
+    {
+        ScaleKind::Major
+    } else if ["m", "min", "minor"]
+        .iter()
+        .any(|word| mode.eq_ignore_ascii_case(word))
+    {
+        ScaleKind::Minor
+    } else {
+        return None;
+    };
+
+    Some(Scale::from_tonic(tonic, kind))
+}
+
+fn parse_german(input: &str) -> Option<Scale> {
+    let mut chars = input.chars();
+    let letter = chars.next()?;
+    let mut tonic = match letter.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 10,
+        'H' => 11,
+        _ => return None,
+    };
+
+    let mut rest = chars.as_str();
+    if let Some(after) = rest.strip_prefix("is") {
+        tonic += 1;
+        rest = after;
+    } else if let Some(after) = rest.strip_prefix("es").or_else(|| {
+        matches!(tonic, 4 | 9)
+            .then(|| rest.strip_prefix('s'))
+            .flatten()
+    }) {
+        tonic += WHEEL_SIZE - 1;
+        rest = after;
+    }
+
+    let mode = rest.trim_start_matches(SEPARATORS);
+    let kind = if mode.is_empty() {
+        // Without a mode, lowercase names are minor keys and uppercase names major keys.
+        if letter.is_ascii_lowercase() {
+            ScaleKind::Minor
+        } else {
+            ScaleKind::Major
+        }
+    } else if mode.eq_ignore_ascii_case("dur") {
+        ScaleKind::Major
+    } else if mode.eq_ignore_ascii_case("moll") {
+        ScaleKind::Minor
+    } else {
+        return None;
+    };
+
+    Some(Scale::from_tonic(tonic, kind))
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct KeyName {
+    scale: Scale,
+    accidental: Accidental,
+    locale: Locale,
+}
+
+impl KeyName {
+    /// The same name written in `locale`.
+    pub const fn in_locale(self, locale: Locale) -> Self {
+        Self { locale, ..self }
+    }
+}
+
+impl Display for KeyName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = note_name_in(self.scale.tonic(), self.accidental, self.locale);
+        match (self.locale, self.scale.kind) {
+            (Locale::English, ScaleKind::Minor) => write!(f, "{name} minor"),
+            (Locale::English, ScaleKind::Major) => write!(f, "{name} major"),
+            (Locale::German, ScaleKind::Minor) => {
+                let (first, rest) = name.split_at(1);
+                let first = first.as_bytes()[0].to_ascii_lowercase() as char;
+                write!(f, "{first}{rest}-Moll")
+            }
+            (Locale::German, ScaleKind::Major) => write!(f, "{name}-Dur"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pitch class of an English note name, with any number of sharps or flats.
+    fn pitch_class(name: &str) -> usize {
+        let sharps = name.matches('#').count();
+        let flats = name[1..].matches('b').count();
+        (LETTER_PITCHES[letter(name)] + WHEEL_SIZE + sharps - flats) % WHEEL_SIZE
+    }
+
+    #[test]
+    fn spells_every_letter_once() {
+        for index in 0..WHEEL_SIZE {
+            for kind in [ScaleKind::Minor, ScaleKind::Major] {
+                let key = scale(index, kind);
+                for accidental in [Accidental::Flat, Accidental::Sharp] {
+                    let names = key.note_names(accidental);
+                    let mut letters = names.map(|name| name.as_bytes()[0]);
+                    letters.sort_unstable();
+                    assert!(
+                        letters.windows(2).all(|pair| pair[0] != pair[1]),
+                        "{key} with {accidental:?}: {names:?}"
+                    );
+                    for (name, note) in names.into_iter().zip(key.notes()) {
+                        assert_eq!(pitch_class(name), note, "{key} with {accidental:?}: {name}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spells_notes_past_the_black_keys() {
+        assert_eq!(
+            scale(1, ScaleKind::Minor).note_names(Accidental::Flat),
+            ["Eb", "F", "Gb", "Ab", "Bb", "Cb", "Db"]
+        );
+        assert_eq!(
+            scale(1, ScaleKind::Major).note_names(Accidental::Sharp),
+            ["F#", "G#", "A#", "B", "C#", "D#", "E#"]
+        );
+        assert_eq!(
+            scale(1, ScaleKind::Major).note_names(Accidental::Flat),
+            ["Gb", "Ab", "Bb", "Cb", "Db", "Eb", "F"]
+        );
+        assert_eq!(
+            scale(1, ScaleKind::Major).note_names_in(Accidental::Flat, Locale::German),
+            ["Ges", "As", "B", "Ces", "Des", "Es", "F"]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::scale::{scale, Scale, ScaleKind, WHEEL_SIZE};
+
+/// Which musical key every wheel number stands for. Standard camelot numbering puts Ab minor
+/// on 1A and A minor on 8A, which [`Scale::tonic`] and the key names follow. Other numberings
+/// turn the same wheel by a number of steps, so transitions and paths between wheel numbers
+/// stay the same and only the keys they name change.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct WheelNumbering {
+    /// Steps from a wheel number to the standard camelot number of the same key.
+    rotation: usize,
+}
+
+impl WheelNumbering {
+    /// Standard camelot numbering, with A minor on 8A and C major on 8B.
+    pub const CAMELOT: Self = Self::rotated(0);
+    /// Numbering like Open Key's, with A minor on 1A and C major on 1B.
+    pub const OPEN_KEY: Self = Self::rotated(7);
+
+    /// Wheel numbers `steps` behind standard camelot, so key 1A is the one camelot numbers
+    /// `1 + steps`A.
+    pub const fn rotated(steps: usize) -> Self {
+        Self {
+            rotation: steps % WHEEL_SIZE,
+        }
+    }
+
+    /// The numbering in which `number` stands for the standard camelot key `key`, or `None` if
+    /// one of them is minor and the other major.
+    pub const fn aligning(number: Scale, key: Scale) -> Option<Self> {
+        if number.kind as u8 != key.kind as u8 {
+            return None;
+        }
+        Some(Self::rotated(key.index + WHEEL_SIZE - number.index))
+    }
+
+    /// Steps from a wheel number to the standard camelot number of the same key.
+    pub const fn rotation(self) -> usize {
+        self.rotation
+    }
+
+    /// The standard camelot key that `key` stands for in this numbering.
+    pub const fn standard(self, key: Scale) -> Scale {
+        scale((key.index + self.rotation) % WHEEL_SIZE, key.kind)
+    }
+
+    /// The key numbered like this that stands for the standard camelot key `key`, the inverse
+    /// of [`WheelNumbering::standard`].
+    pub const fn from_standard(self, key: Scale) -> Scale {
+        scale(
+            (key.index + WHEEL_SIZE - self.rotation) % WHEEL_SIZE,
+            key.kind,
+        )
+    }
+
+    /// Pitch class of the tonic of `key` in semitones above C.
+    pub const fn tonic(self, key: Scale) -> usize {
+        self.standard(key).tonic()
+    }
+
+    /// The key numbered like this with the given tonic pitch class and mode.
+    pub const fn from_tonic(self, tonic: usize, kind: ScaleKind) -> Scale {
+        self.from_standard(Scale::from_tonic(tonic, kind))
+    }
+}
@@ -0,0 +1,41 @@
+use crate::scale::{scale, ParseScaleError, Scale, ScaleKind, WHEEL_SIZE};
+use core::fmt::{self, Display, Formatter};
+
+/// A key in Open Key notation, like `1m` for A minor and `1d` for C major. Open Key numbers
+/// the same circle of fifths as the camelot wheel, starting at C major and A minor, on 8B and
+/// 8A, with `m` for minor and `d` for major keys.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct OpenKey(pub Scale);
+
+impl Scale {
+    /// The key in Open Key notation, displayed like `1m`.
+    pub const fn open_key(self) -> OpenKey {
+        OpenKey(self)
+    }
+
+    /// Parses an Open Key code like `1m` or `12d`.
+    pub fn from_open_key(input: &str) -> Result<Self, ParseScaleError> {
+        let input = input.trim();
+        let kind = match input.chars().last() {
+            Some('m') => ScaleKind::Minor,
+            Some('d') => ScaleKind::Major,
+            _ => return Err(ParseScaleError),
+        };
+        let number = input[..input.len() - 1]
+            .parse::<usize>()
+            .ok()
+            .filter(|number| (1..=WHEEL_SIZE).contains(number))
+            .ok_or(ParseScaleError)?;
+        Ok(scale((number + 6) % WHEEL_SIZE, kind))
+    }
+}
+
+impl Display for OpenKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let kind = match self.0.kind {
+            ScaleKind::Minor => 'm',
+            ScaleKind::Major => 'd',
+        };
+        write!(f, "{}{kind}", (self.0.index + 5) % WHEEL_SIZE + 1)
+    }
+}
@@ -0,0 +1,206 @@
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+/// Number of positions around the wheel.
+pub const WHEEL_SIZE: usize = 12;
+
+/// Number of keys on the wheel, one minor and one major per position.
+pub const KEY_COUNT: usize = WHEEL_SIZE * 2;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ScaleKind {
+    Major,
+    Minor,
+}
+
+impl ScaleKind {
+    pub const fn swap(self) -> Self {
+        match self {
+            ScaleKind::Minor => ScaleKind::Major,
+            ScaleKind::Major => ScaleKind::Minor,
+        }
+    }
+}
+
+impl Display for ScaleKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Minor => write!(f, "A"),
+            Self::Major => write!(f, "B"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Scale {
+    pub index: usize,
+    pub kind: ScaleKind,
+}
+
+const fn mod_cyclic(num: isize, modulus: usize) -> isize {
+    let modulus = modulus as isize;
+    ((num % modulus) + modulus) % modulus
+}
+
+impl Scale {
+    pub const fn swap_kind(self) -> Self {
+        Self {
+            kind: self.kind.swap(),
+            ..self
+        }
+    }
+
+    pub const fn change_index(self, amount: isize) -> Self {
+        self.change_index_on(amount, WHEEL_SIZE)
+    }
+
+    /// Like [`Scale::change_index`] on a wheel with `size` positions.
+    pub const fn change_index_on(self, amount: isize, size: usize) -> Self {
+        let index = mod_cyclic((self.index as isize) + amount, size);
+        Self {
+            index: index as usize,
+            ..self
+        }
+    }
+
+    /// The relative major or minor, with the same notes and key signature, like C major (8B)
+    /// for A minor (8A).
+    pub const fn relative(self) -> Self {
+        self.swap_kind()
+    }
+
+    /// The parallel major or minor, on the same tonic, like A major (11B) for A minor (8A).
+    pub const fn parallel(self) -> Self {
+        match self.kind {
+            ScaleKind::Minor => self.swap_kind().change_index(3),
+            ScaleKind::Major => self.swap_kind().change_index(-3),
+        }
+    }
+
+    /// The key a fifth up, one step clockwise, like E minor (9A) for A minor (8A).
+    pub const fn dominant(self) -> Self {
+        self.change_index(1)
+    }
+
+    /// The key a fifth down, one step anticlockwise, like D minor (7A) for A minor (8A).
+    pub const fn subdominant(self) -> Self {
+        self.change_index(-1)
+    }
+
+    /// Whether the scale's index is one of the wheel's positions.
+    pub const fn is_valid(self) -> bool {
+        self.index < WHEEL_SIZE
+    }
+
+    /// Position of the scale on the wheel: wheel number first, A before B.
+    pub const fn position(self) -> usize {
+        let kind = match self.kind {
+            ScaleKind::Minor => 0,
+            ScaleKind::Major => 1,
+        };
+        self.index * 2 + kind
+    }
+
+    /// The inverse of [`Scale::position`].
+    pub const fn from_position(position: usize) -> Option<Self> {
+        Self::from_position_on(position, WHEEL_SIZE)
+    }
+
+    /// Like [`Scale::from_position`] on a wheel with `size` positions.
+    pub const fn from_position_on(position: usize, size: usize) -> Option<Self> {
+        let kind = match position % 2 {
+            0 => ScaleKind::Minor,
+            _ => ScaleKind::Major,
+        };
+        if position < size * 2 {
+            Some(scale(position / 2, kind))
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Scale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.index + 1, self.kind)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseScaleError;
+
+impl Display for ParseScaleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid camelot key")
+    }
+}
+
+impl core::error::Error for ParseScaleError {}
+
+impl FromStr for Scale {
+    type Err = ParseScaleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_on(s, WHEEL_SIZE)
+    }
+}
+
+impl Scale {
+    /// Parses a camelot code like `8A` on a wheel with `size` positions, so `20A` is valid on a
+    /// 24-position wheel. Codes typed in a hurry are read too: the letter may be lowercase and
+    /// the number may have leading zeros or be followed by a space, so `08 a` is 8A.
+    pub fn parse_on(s: &str, size: usize) -> Result<Self, ParseScaleError> {
+        let s = s.trim();
+        let split = s.len().checked_sub(1).filter(|&i| s.is_char_boundary(i));
+        let (number, kind) = s.split_at(split.ok_or(ParseScaleError)?);
+
+        let kind = match kind {
+            "A" | "a" => ScaleKind::Minor,
+            "B" | "b" => ScaleKind::Major,
+            _ => return Err(ParseScaleError),
+        };
+        let number = number.trim_end();
+        if !number.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseScaleError);
+        }
+        let number = number.parse::<usize>().map_err(|_| ParseScaleError)?;
+        if !(1..=size).contains(&number) {
+            return Err(ParseScaleError);
+        }
+
+        Ok(scale(number - 1, kind))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Scale {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Scale {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ScaleVisitor;
+
+        impl serde::de::Visitor<'_> for ScaleVisitor {
+            type Value = Scale;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                write!(f, "a camelot key like `8A`")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Scale, E> {
+                v.parse()
+                    .map_err(|_| E::custom(format_args!("invalid camelot key `{v}`")))
+            }
+        }
+
+        deserializer.deserialize_str(ScaleVisitor)
+    }
+}
+
+pub const fn scale(index: usize, kind: ScaleKind) -> Scale {
+    Scale { index, kind }
+}
@@ -0,0 +1,164 @@
+use crate::scale::{scale, Scale, ScaleKind, KEY_COUNT};
+use crate::transition::{make_transition, TRANSITIONS, TRANSITION_COUNT};
+
+/// The key at `position` of the wheel.
+const fn key_at(position: usize) -> Scale {
+    match position % 2 {
+        0 => scale(position / 2, ScaleKind::Minor),
+        _ => scale(position / 2, ScaleKind::Major),
+    }
+}
+
+const fn neighbor_table() -> [[Scale; TRANSITION_COUNT]; KEY_COUNT] {
+    let mut table = [[scale(0, ScaleKind::Minor); TRANSITION_COUNT]; KEY_COUNT];
+    let mut position = 0;
+    while position < KEY_COUNT {
+        let source = key_at(position);
+        let mut transition = 0;
+        while transition < TRANSITION_COUNT {
+            table[position][transition] = make_transition(source, TRANSITIONS[transition]);
+            transition += 1;
+        }
+        position += 1;
+    }
+    table
+}
+
+/// `NEIGHBORS[key.position()][i]` is where `TRANSITIONS[i]` leads from `key`.
+pub const NEIGHBORS: [[Scale; TRANSITION_COUNT]; KEY_COUNT] = neighbor_table();
+
+/// Where each of [`TRANSITIONS`] leads from `scale`, or `None` if `scale` isn't on the wheel.
+pub const fn neighbors(scale: Scale) -> Option<&'static [Scale; TRANSITION_COUNT]> {
+    if scale.is_valid() {
+        Some(&NEIGHBORS[scale.position()])
+    } else {
+        None
+    }
+}
+
+/// Breadth-first search from the key at `position`, returning each key's distance and the
+/// key it was first reached from, both indexed by position.
+const fn search(position: usize) -> ([Option<u8>; KEY_COUNT], [Option<Scale>; KEY_COUNT]) {
+    let mut distances = [None; KEY_COUNT];
+    let mut parents = [None; KEY_COUNT];
+    let mut queue = [0; KEY_COUNT];
+    let (mut head, mut tail) = (0, 1);
+    queue[0] = position;
+    distances[position] = Some(0);
+
+    while head < tail {
+        let current = queue[head];
+        head += 1;
+        let distance = match distances[current] {
+            Some(distance) => distance,
+            None => 0,
+        };
+        let mut transition = 0;
+        while transition < TRANSITION_COUNT {
+            let next = NEIGHBORS[current][transition];
+            if distances[next.position()].is_none() {
+                distances[next.position()] = Some(distance + 1);
+                parents[next.position()] = Some(key_at(current));
+                queue[tail] = next.position();
+                tail += 1;
+            }
+            transition += 1;
+        }
+    }
+
+    (distances, parents)
+}
+
+type Tables = (
+    [[Option<u8>; KEY_COUNT]; KEY_COUNT],
+    [[Option<Scale>; KEY_COUNT]; KEY_COUNT],
+);
+
+const fn path_tables() -> Tables {
+    let mut distances = [[None; KEY_COUNT]; KEY_COUNT];
+    let mut first_hops = [[None; KEY_COUNT]; KEY_COUNT];
+    let mut source = 0;
+    while source < KEY_COUNT {
+        let (row, parents) = search(source);
+        distances[source] = row;
+        let mut target = 0;
+        while target < KEY_COUNT {
+            // Walk back from the target until the key reached straight from the source.
+            let mut hop = target;
+            while let Some(parent) = parents[hop] {
+                if parent.position() == source {
+                    break;
+                }
+                hop = parent.position();
+            }
+            if target != source && row[target].is_some() {
+                first_hops[source][target] = Some(key_at(hop));
+            }
+            target += 1;
+        }
+        source += 1;
+    }
+    (distances, first_hops)
+}
+
+const PATH_TABLES: Tables = path_tables();
+
+/// `DISTANCES[from.position()][to.position()]` is the number of transitions on the shortest
+/// path from `from` to `to`, or `None` if `to` can't be reached.
+pub const DISTANCES: [[Option<u8>; KEY_COUNT]; KEY_COUNT] = PATH_TABLES.0;
+
+/// `FIRST_HOPS[from.position()][to.position()]` is the key to move to first on a shortest path
+/// from `from` to `to`, or `None` if `to` is `from` or can't be reached.
+pub const FIRST_HOPS: [[Option<Scale>; KEY_COUNT]; KEY_COUNT] = PATH_TABLES.1;
+
+/// Distance from `source` to every key, indexed by position. `None` marks unreachable keys.
+/// Returns `None` if `source` isn't on the wheel.
+pub const fn distances_from(source: Scale) -> Option<[Option<u8>; KEY_COUNT]> {
+    if source.is_valid() {
+        Some(DISTANCES[source.position()])
+    } else {
+        None
+    }
+}
+
+/// Number of transitions on the shortest path from `from` to `to`.
+pub const fn distance(from: Scale, to: Scale) -> Option<u8> {
+    if from.is_valid() && to.is_valid() {
+        DISTANCES[from.position()][to.position()]
+    } else {
+        None
+    }
+}
+
+/// A sequence of keys held in a fixed-size buffer.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct KeyPath {
+    keys: [Scale; KEY_COUNT],
+    len: usize,
+}
+
+impl KeyPath {
+    pub fn keys(&self) -> &[Scale] {
+        &self.keys[..self.len]
+    }
+}
+
+/// One shortest path from `from` to `to`, both ends included.
+pub const fn shortest_path(from: Scale, to: Scale) -> Option<KeyPath> {
+    let Some(distance) = distance(from, to) else {
+        return None;
+    };
+    let len = distance as usize + 1;
+
+    let mut keys = [from; KEY_COUNT];
+    let mut i = 1;
+    while i < len {
+        keys[i] = match FIRST_HOPS[keys[i - 1].position()][to.position()] {
+            Some(hop) => hop,
+            None => to,
+        };
+        i += 1;
+    }
+
+    Some(KeyPath { keys, len })
+}
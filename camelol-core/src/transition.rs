@@ -0,0 +1,57 @@
+use crate::scale::{Scale, ScaleKind, WHEEL_SIZE};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScaleTransition {
+    Vertical,
+    Diagonal,
+    ChangeIndex(isize),
+    MajorToMinor,
+    FlatToMinor,
+}
+
+pub const TRANSITION_COUNT: usize = 10;
+
+/// Every transition the wheel allows, in the order the graph adds them.
+pub const TRANSITIONS: [ScaleTransition; TRANSITION_COUNT] = [
+    ScaleTransition::Vertical,
+    ScaleTransition::Diagonal,
+    ScaleTransition::MajorToMinor,
+    ScaleTransition::FlatToMinor,
+    ScaleTransition::ChangeIndex(1),
+    ScaleTransition::ChangeIndex(2),
+    ScaleTransition::ChangeIndex(7),
+    ScaleTransition::ChangeIndex(-1),
+    ScaleTransition::ChangeIndex(-2),
+    ScaleTransition::ChangeIndex(-7),
+];
+
+pub const fn make_transition(scale: Scale, transition: ScaleTransition) -> Scale {
+    make_transition_on(scale, transition, WHEEL_SIZE)
+}
+
+/// Like [`make_transition`] on a wheel with `size` positions. The transitions move the same
+/// number of positions whatever the size, so a 24-position quarter-tone wheel keeps the shape
+/// of the camelot rules.
+pub const fn make_transition_on(scale: Scale, transition: ScaleTransition, size: usize) -> Scale {
+    match (transition, scale.kind) {
+        (ScaleTransition::Vertical, _) => scale.swap_kind(),
+        (ScaleTransition::ChangeIndex(amount), _) => scale.change_index_on(amount, size),
+        (ScaleTransition::Diagonal, ScaleKind::Major) => scale.swap_kind().change_index_on(1, size),
+        (ScaleTransition::Diagonal, ScaleKind::Minor) => {
+            scale.swap_kind().change_index_on(-1, size)
+        }
+        (ScaleTransition::FlatToMinor, ScaleKind::Minor) => {
+            scale.swap_kind().change_index_on(-4, size)
+        }
+        (ScaleTransition::FlatToMinor, ScaleKind::Major) => {
+            scale.swap_kind().change_index_on(4, size)
+        }
+        (ScaleTransition::MajorToMinor, ScaleKind::Minor) => {
+            scale.swap_kind().change_index_on(3, size)
+        }
+        (ScaleTransition::MajorToMinor, ScaleKind::Major) => {
+            scale.swap_kind().change_index_on(-3, size)
+        }
+    }
+}
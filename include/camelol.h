@@ -0,0 +1,84 @@
+#ifndef CAMELOL_H
+#define CAMELOL_H
+
+/* Generated with cbindgen, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of [`camelol_paths`], owned by the caller until passed to [`camelol_paths_free`].
+ */
+typedef struct CamelolPaths CamelolPaths;
+
+/**
+ * Parses a camelot code like `"8A"`, returning its key or -1.
+ *
+ * # Safety
+ *
+ * `input` must be a valid nul-terminated string.
+ */
+int32_t camelol_parse_key(const char *input);
+
+/**
+ * Writes the nul-terminated camelot code of `key_id` into `buffer`, returning the code's length
+ * without the terminator, or -1 if the key is invalid or the buffer is too small.
+ *
+ * # Safety
+ *
+ * `buffer` must be valid for writes of `length` bytes.
+ */
+int32_t camelol_key_name(int32_t key_id, char *buffer, size_t length);
+
+/**
+ * Number of transitions between two keys, or -1.
+ */
+int32_t camelol_distance(int32_t from, int32_t to);
+
+/**
+ * Compatibility score between two keys from 0 to 1, or -1 for invalid keys.
+ */
+double camelol_compatibility(int32_t from, int32_t to);
+
+/**
+ * The `n` shortest paths between two keys, or null for invalid keys or an `n` over
+ * [`MAX_PATH_COUNT`] (100).
+ */
+struct CamelolPaths *camelol_paths(int32_t from, int32_t to, size_t n);
+
+/**
+ * # Safety
+ *
+ * `paths` must come from [`camelol_paths`] and not have been freed.
+ */
+size_t camelol_paths_count(const struct CamelolPaths *paths);
+
+/**
+ * Number of keys on the path at `index`, including both ends.
+ *
+ * # Safety
+ *
+ * `paths` must come from [`camelol_paths`] and not have been freed.
+ */
+size_t camelol_path_length(const struct CamelolPaths *paths, size_t index);
+
+/**
+ * The key at `step` on the path at `index`, or -1.
+ *
+ * # Safety
+ *
+ * `paths` must come from [`camelol_paths`] and not have been freed.
+ */
+int32_t camelol_path_key(const struct CamelolPaths *paths, size_t index, size_t step);
+
+/**
+ * # Safety
+ *
+ * `paths` must come from [`camelol_paths`] and not have been freed already.
+ */
+void camelol_paths_free(struct CamelolPaths *paths);
+
+#endif  /* CAMELOL_H */
@@ -0,0 +1,51 @@
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+
+/// Identifies requests to APIs that ask clients to, like MusicBrainz.
+const USER_AGENT: &str = concat!(
+    "camelol/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/rerz/camelol )"
+);
+
+/// Fetches `url` from a web API, authorized with an OAuth access `token` if it takes one, and
+/// reads the JSON response.
+pub(crate) fn get_json<T: DeserializeOwned>(
+    service: &'static str,
+    url: &str,
+    token: Option<&str>,
+) -> Result<T> {
+    let error = |message| Error::Api { service, message };
+    tracing::debug!(service, url, "requesting");
+    let mut request = ureq::get(url).set("User-Agent", USER_AGENT);
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {token}"));
+    }
+    let response = request
+        .call()
+        .map_err(|failure| match failure {
+            ureq::Error::Status(401, _) => {
+                error("the access token is invalid or expired".to_string())
+            }
+            ureq::Error::Status(403, _) => error(format!("access to {url} was refused")),
+            ureq::Error::Status(404, _) => error(format!("{url} was not found")),
+            failure => error(failure.to_string()),
+        })
+        .inspect_err(|failure| tracing::warn!(service, url, %failure, "request failed"))?;
+    Ok(response.into_json()?)
+}
+
+/// Encodes `query` for a URL query string, with spaces as `+`.
+#[cfg(any(feature = "beatport", feature = "musicbrainz"))]
+pub(crate) fn encode_query(query: &str) -> String {
+    query
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
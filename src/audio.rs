@@ -0,0 +1,518 @@
+use crate::detect::{detect_key_with, KeyEstimate, KeyProfile};
+use crate::error::Result;
+use crate::library::{file_modified, find_energy, find_key, KeySegment, Track};
+use crate::loudness::{loudness, Loudness};
+use crate::scale::{Locale, Scale};
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
+use symphonia::core::probe::Hint;
+
+/// File extensions of the formats that can be analyzed.
+pub const AUDIO_EXTENSIONS: &[&str] = &["aif", "aiff", "flac", "m4a", "mp3", "mp4", "ogg", "wav"];
+
+/// Audio is mixed down and decimated to about this rate before analysis, which still covers
+/// every note the chroma looks at.
+const ANALYSIS_RATE: u32 = 11025;
+/// Notes from C2 to B6 are folded into the chroma.
+const LOWEST_NOTE: i32 = 36;
+const HIGHEST_NOTE: i32 = 95;
+const CHROMA_FRAME: usize = 4096;
+/// Onset energy is measured over overlapping windows, long enough to smooth out beating
+/// between sustained notes.
+const ONSET_WINDOW: usize = 1024;
+const ONSET_HOP: usize = 128;
+const MIN_TEMPO: f64 = 80.0;
+const MAX_TEMPO: f64 = 160.0;
+/// Modulations are looked for in blocks of this many seconds, each judged together with its
+/// neighbours on both sides.
+const SEGMENT_BLOCK: f64 = 10.0;
+/// Keys that last fewer blocks than this are passing chords rather than modulations.
+const MIN_SEGMENT_BLOCKS: usize = 3;
+
+/// Decoded mono audio.
+pub struct Audio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    /// `Artist - Title` from the file's tags, if it has both.
+    pub name: Option<String>,
+}
+
+impl Audio {
+    /// Length in seconds.
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / f64::from(self.sample_rate)
+    }
+}
+
+/// What the tags of an audio file say about it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioTags {
+    /// `Artist - Title`, if the file has both.
+    pub name: Option<String>,
+    /// The initial key, as ID3's `TKEY` frame or an `INITIALKEY` comment in Vorbis and MP4
+    /// tags, written as a camelot code, an Open Key code or a key name.
+    pub key: Option<Scale>,
+    pub tempo: Option<f64>,
+    /// Length in seconds, if the container records it.
+    pub duration: Option<f64>,
+    /// The energy level from a comment like Mixed In Key's `8A - Energy 6`.
+    pub energy: Option<u8>,
+}
+
+/// The key in a tag holding nothing else, where unlike in a comment a bare note name like `C`
+/// is a key, and so is an Open Key code.
+fn tag_key(value: &str) -> Option<Scale> {
+    let value = value.trim();
+    find_key(value)
+        .or_else(|| Scale::from_open_key(value).ok())
+        .or_else(|| Scale::from_key_name(value, Locale::English).ok())
+}
+
+impl AudioTags {
+    fn new(tags: &[Tag]) -> Self {
+        let tag = |key| {
+            tags.iter()
+                .find(|tag| tag.std_key == Some(key))
+                .map(|tag| tag.value.to_string())
+        };
+        let artist = tag(StandardTagKey::Artist);
+        let title = tag(StandardTagKey::TrackTitle);
+        Self {
+            name: artist
+                .zip(title)
+                .map(|(artist, title)| format!("{artist} - {title}")),
+            // Symphonia has no standard key for the initial key, so it's found by its
+            // name in each tag format.
+            key: tags
+                .iter()
+                .filter(|tag| {
+                    let name = tag.key.to_lowercase();
+                    name == "tkey" || name == "key" || name.ends_with("initialkey")
+                })
+                .find_map(|tag| tag_key(&tag.value.to_string())),
+            tempo: tag(StandardTagKey::Bpm)
+                .and_then(|tempo| tempo.trim().parse().ok())
+                .filter(|tempo: &f64| *tempo > 0.0),
+            duration: None,
+            energy: tags
+                .iter()
+                .filter(|tag| tag.std_key == Some(StandardTagKey::Comment))
+                .find_map(|tag| find_energy(&tag.value.to_string())),
+        }
+    }
+}
+
+/// Opens `path` with the format reader for its container, along with the tags found while
+/// probing it and those in the container itself.
+fn open_audio(path: &Path) -> Result<(Box<dyn FormatReader>, Vec<Tag>)> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut tags = Vec::new();
+    if let Some(revision) = probed
+        .metadata
+        .get()
+        .as_ref()
+        .and_then(|metadata| metadata.current())
+    {
+        tags.extend_from_slice(revision.tags());
+    }
+    let mut format = probed.format;
+    if let Some(revision) = format.metadata().current() {
+        tags.extend_from_slice(revision.tags());
+    }
+    Ok((format, tags))
+}
+
+/// Reads the tags of `path` without decoding any audio.
+pub fn read_tags(path: &Path) -> Result<AudioTags> {
+    let (format, tags) = open_audio(path)?;
+    let duration = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .and_then(|track| {
+            let params = &track.codec_params;
+            Some(params.n_frames? as f64 / f64::from(params.sample_rate?))
+        });
+    Ok(AudioTags {
+        duration,
+        ..AudioTags::new(&tags)
+    })
+}
+
+/// The track `path` holds according to its tags, or `None` if they don't give its key.
+pub fn tagged_track(path: &Path) -> Result<Option<Track>> {
+    let tags = read_tags(path)?;
+    tracing::debug!(path = %path.display(), key = ?tags.key, tempo = tags.tempo, "read tags");
+    Ok(tags.key.map(|key| Track {
+        tempo: tags.tempo,
+        path: Some(path.to_path_buf()),
+        duration: tags.duration,
+        modified: file_modified(path),
+        energy: tags.energy,
+        ..Track::new(tags.name.unwrap_or_else(|| file_track_name(path)), key)
+    }))
+}
+
+/// Decodes the first audio track of `path`, mixed down to mono and decimated to about
+/// [`ANALYSIS_RATE`].
+pub fn decode_audio(path: &Path) -> Result<Audio> {
+    let (mut format, tags) = open_audio(path)?;
+    let name = AudioTags::new(&tags).name;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(DecodeError::Unsupported("no audio track"))?;
+    let track_id = track.id;
+    let rate = track.codec_params.sample_rate.unwrap_or(44100);
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let factor = (rate / ANALYSIS_RATE).max(1) as usize;
+    let mut mono = Vec::new();
+    let (mut sum, mut count) = (0.0, 0);
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                break;
+            }
+            Err(error) => return Err(error.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame doesn't spoil the rest of the file.
+            Err(DecodeError::DecodeError(error)) => {
+                tracing::debug!(path = %path.display(), error, "skipped a corrupt frame");
+                continue;
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        // Averaging blocks of samples doubles as a crude low-pass filter.
+        for frame in buffer.samples().chunks(channels) {
+            sum += frame.iter().sum::<f32>() / channels as f32;
+            count += 1;
+            if count == factor {
+                mono.push(sum / factor as f32);
+                (sum, count) = (0.0, 0);
+            }
+        }
+    }
+
+    Ok(Audio {
+        samples: mono,
+        sample_rate: rate / factor as u32,
+        name,
+    })
+}
+
+/// Total strength of each pitch class from C up, measured with a Goertzel filter on each note
+/// from C2 to B6 over Hann-windowed frames.
+pub fn chroma(audio: &Audio) -> [f64; 12] {
+    let mut chroma = [0.0; 12];
+    for frame in frame_chromas(audio) {
+        for (total, strength) in chroma.iter_mut().zip(frame) {
+            *total += strength;
+        }
+    }
+    chroma
+}
+
+/// The chroma of each frame of `CHROMA_FRAME` samples.
+pub(crate) fn frame_chromas(audio: &Audio) -> Vec<[f64; 12]> {
+    let window: Vec<f64> = (0..CHROMA_FRAME)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / CHROMA_FRAME as f64).cos())
+        .collect();
+    let coefficients: Vec<(usize, f64)> = (LOWEST_NOTE..=HIGHEST_NOTE)
+        .map(|note| {
+            let frequency = 440.0 * 2f64.powf(f64::from(note - 69) / 12.0);
+            let omega = 2.0 * PI * frequency / f64::from(audio.sample_rate);
+            (note as usize % 12, 2.0 * omega.cos())
+        })
+        .collect();
+
+    let mut chromas = vec![];
+    for frame in audio.samples.chunks_exact(CHROMA_FRAME) {
+        let mut chroma = [0.0; 12];
+        for &(pitch_class, coefficient) in &coefficients {
+            let (mut previous, mut before) = (0.0, 0.0);
+            for (sample, weight) in frame.iter().zip(&window) {
+                let current = f64::from(*sample) * weight + coefficient * previous - before;
+                (before, previous) = (previous, current);
+            }
+            let power = previous * previous + before * before - coefficient * previous * before;
+            chroma[pitch_class] += power.max(0.0).sqrt();
+        }
+        chromas.push(chroma);
+    }
+    chromas
+}
+
+/// The keys the audio moves through, found by estimating the key of every few seconds and
+/// keeping the keys that last long enough to be modulations. Audio in one key is a single
+/// segment, and silence has none.
+pub fn key_segments(audio: &Audio) -> Vec<KeySegment> {
+    key_segments_with(audio, KeyProfile::default())
+}
+
+/// Like [`key_segments`], estimating keys with `profile`.
+pub fn key_segments_with(audio: &Audio, profile: KeyProfile) -> Vec<KeySegment> {
+    let frames = frame_chromas(audio);
+    let frame_seconds = CHROMA_FRAME as f64 / f64::from(audio.sample_rate);
+    let per_block = ((SEGMENT_BLOCK / frame_seconds).round() as usize).max(1);
+    let blocks: Vec<[f64; 12]> = frames
+        .chunks(per_block)
+        .map(|block| {
+            let mut chroma = [0.0; 12];
+            for frame in block {
+                for (total, strength) in chroma.iter_mut().zip(frame) {
+                    *total += strength;
+                }
+            }
+            chroma
+        })
+        .collect();
+
+    // Runs of blocks in the same key, as (key, first block, block count).
+    let mut runs: Vec<(Scale, usize, usize)> = vec![];
+    for index in 0..blocks.len() {
+        let mut chroma = [0.0; 12];
+        for block in &blocks[index.saturating_sub(1)..(index + 2).min(blocks.len())] {
+            for (total, strength) in chroma.iter_mut().zip(block) {
+                *total += strength;
+            }
+        }
+        let Some(KeyEstimate { key, .. }) = detect_key_with(&chroma, profile) else {
+            continue;
+        };
+        match runs.last_mut() {
+            Some((last, _, count)) if *last == key => *count += 1,
+            _ => runs.push((key, index, 1)),
+        }
+    }
+
+    // Short runs are folded into the run before them, or after them at the start.
+    let mut segments: Vec<(Scale, usize, usize)> = vec![];
+    for (key, start, count) in runs {
+        match segments.last_mut() {
+            Some((last, _, total)) if *last == key || count < MIN_SEGMENT_BLOCKS => *total += count,
+            Some((last, _, total)) if *total < MIN_SEGMENT_BLOCKS => {
+                *last = key;
+                *total += count;
+            }
+            _ => segments.push((key, start, count)),
+        }
+    }
+    segments
+        .into_iter()
+        .enumerate()
+        .map(|(index, (key, start, _))| KeySegment {
+            // The first key counts from the top, even if the track opens with silence.
+            start: if index == 0 {
+                0.0
+            } else {
+                start as f64 * per_block as f64 * frame_seconds
+            },
+            key,
+        })
+        .collect()
+}
+
+/// The loudness of every window of the audio, on a log scale, [`frame_rate`] times a second.
+pub(crate) fn frame_energies(audio: &Audio) -> Vec<f64> {
+    audio
+        .samples
+        .windows(ONSET_WINDOW)
+        .step_by(ONSET_HOP)
+        .map(|window| {
+            let energy: f64 = window.iter().map(|&sample| f64::from(sample).powi(2)).sum();
+            (1.0 + 1000.0 * energy).ln()
+        })
+        .collect()
+}
+
+/// Windows of [`frame_energies`] per second.
+pub(crate) fn frame_rate(audio: &Audio) -> f64 {
+    f64::from(audio.sample_rate) / ONSET_HOP as f64
+}
+
+/// How much louder every window of `energies` is than the one before, or 0 where it's quieter.
+pub(crate) fn onset_envelope(energies: &[f64]) -> Vec<f64> {
+    energies
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).max(0.0))
+        .collect()
+}
+
+/// Estimates the tempo in beats per minute from the autocorrelation of the onset envelope,
+/// between 80 and 160 BPM. Returns `None` for silence or audio too short to tell.
+pub fn estimate_tempo(audio: &Audio) -> Option<f64> {
+    let onsets = onset_envelope(&frame_energies(audio));
+    let rate = frame_rate(audio);
+    let shortest = (60.0 * rate / MAX_TEMPO).floor() as usize;
+    let longest = (60.0 * rate / MIN_TEMPO).ceil() as usize;
+    if onsets.len() < 4 * longest {
+        return None;
+    }
+    let autocorrelation =
+        |lag: usize| -> f64 { onsets.iter().zip(&onsets[lag..]).map(|(a, b)| a * b).sum() };
+    // Counting the beat after next too favors the real beat over its off-beats.
+    let score = |lag: usize| autocorrelation(lag) + 0.5 * autocorrelation(2 * lag);
+    let scores: Vec<f64> = (shortest - 1..=longest + 1).map(score).collect();
+    let (best, &peak) = scores[1..scores.len() - 1]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if peak <= 0.0 {
+        return None;
+    }
+
+    // Refine the peak between lags with a parabola through its neighbors.
+    let (left, right) = (scores[best], scores[best + 2]);
+    let curvature = left - 2.0 * peak + right;
+    let offset = if curvature < 0.0 {
+        0.5 * (left - right) / curvature
+    } else {
+        0.0
+    };
+    let lag = (shortest + best) as f64 + offset;
+    Some(60.0 * rate / lag)
+}
+
+/// What analysis finds out about a track.
+pub(crate) struct Analysis {
+    pub key: Scale,
+    /// How well the chroma fits the key's profile, from 0 to 1.
+    pub confidence: f64,
+    pub tempo: Option<f64>,
+    /// Empty unless the track modulates.
+    pub segments: Vec<KeySegment>,
+    pub loudness: Option<Loudness>,
+}
+
+/// Estimates the key with `profile`, key segments, tempo and loudness of decoded audio. Returns
+/// `None` if it's silent.
+pub(crate) fn analyze_decoded(audio: &Audio, profile: KeyProfile) -> Option<Analysis> {
+    let KeyEstimate { key, correlation } = detect_key_with(&chroma(audio), profile)?;
+    let segments = key_segments_with(audio, profile);
+    Some(Analysis {
+        key,
+        confidence: correlation.clamp(0.0, 1.0),
+        tempo: estimate_tempo(audio),
+        segments: if segments.len() > 1 { segments } else { vec![] },
+        loudness: loudness(audio),
+    })
+}
+
+/// The name of a track without tags: its file name without the extension.
+pub(crate) fn file_track_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Estimates the key and tempo of an audio file. Returns `None` if it's silent.
+pub fn analyze_audio(path: &Path) -> Result<Option<Track>> {
+    analyze_audio_with(path, KeyProfile::default())
+}
+
+/// Like [`analyze_audio`], estimating the key with `profile`.
+pub fn analyze_audio_with(path: &Path, profile: KeyProfile) -> Result<Option<Track>> {
+    tracing::debug!(path = %path.display(), %profile, "analyzing");
+    Ok(analyzed_track_with(path, &decode_audio(path)?, profile))
+}
+
+/// Like [`analyze_audio`], for `audio` already decoded from `path`.
+pub fn analyzed_track(path: &Path, audio: &Audio) -> Option<Track> {
+    analyzed_track_with(path, audio, KeyProfile::default())
+}
+
+/// Like [`analyze_audio_with`], for `audio` already decoded from `path`.
+pub fn analyzed_track_with(path: &Path, audio: &Audio, profile: KeyProfile) -> Option<Track> {
+    let analysis = analyze_decoded(audio, profile);
+    match &analysis {
+        Some(analysis) => tracing::info!(
+            path = %path.display(),
+            key = %analysis.key,
+            confidence = analysis.confidence,
+            tempo = analysis.tempo,
+            segments = analysis.segments.len(),
+            loudness = analysis.loudness.map(|loudness| loudness.integrated),
+            "analyzed"
+        ),
+        None => tracing::warn!(path = %path.display(), "silent, no key found"),
+    }
+    analysis.map(|analysis| Track {
+        confidence: Some(analysis.confidence),
+        tempo: analysis.tempo,
+        path: Some(path.to_path_buf()),
+        duration: Some(audio.duration()),
+        modified: file_modified(path),
+        segments: analysis.segments,
+        energy: analysis.loudness.map(|loudness| loudness.energy()),
+        ..Track::new(
+            audio.name.clone().unwrap_or_else(|| file_track_name(path)),
+            analysis.key,
+        )
+    })
+}
+
+/// Every audio file under `dir`, including subdirectories. Linked directories are followed,
+/// but each directory is only listed once, so links looping back don't go on forever.
+pub fn audio_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut listed = HashSet::new();
+    collect_audio_files(dir, &mut listed, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_audio_files(
+    dir: &Path,
+    listed: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    if !listed.insert(fs::canonicalize(dir)?) {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_audio_files(&path, listed, files)?;
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::audio::{chroma, decode_audio, read_tags};
+use crate::detect::{detect_key_with, KeyEstimate, KeyProfile};
+use crate::error::Result;
+use crate::scale::Scale;
+use std::path::{Path, PathBuf};
+
+/// The key an audio file's tags give next to the key detected in its audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyAudit {
+    pub path: PathBuf,
+    pub tagged: Scale,
+    pub detected: Scale,
+    /// How well the chroma fits the detected key's profile, from 0 to 1.
+    pub confidence: f64,
+}
+
+impl KeyAudit {
+    pub fn agrees(&self) -> bool {
+        self.tagged == self.detected
+    }
+}
+
+/// Compares the key tagged in the audio file at `path` with the key `profile` detects in its
+/// audio. Returns `None` for files without a key tag, which are only decoded when they have
+/// one, and for silent ones.
+pub fn audit_key(path: &Path, profile: KeyProfile) -> Result<Option<KeyAudit>> {
+    let Some(tagged) = read_tags(path)?.key else {
+        return Ok(None);
+    };
+    let Some(KeyEstimate { key, correlation }) =
+        detect_key_with(&chroma(&decode_audio(path)?), profile)
+    else {
+        tracing::warn!(path = %path.display(), "silent, no key found");
+        return Ok(None);
+    };
+    tracing::debug!(path = %path.display(), %tagged, detected = %key, correlation, "audited");
+    Ok(Some(KeyAudit {
+        path: path.to_path_buf(),
+        tagged,
+        detected: key,
+        confidence: correlation.clamp(0.0, 1.0),
+    }))
+}
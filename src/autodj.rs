@@ -0,0 +1,107 @@
+use crate::distance::DistanceMatrix;
+use crate::library::Track;
+use crate::matches::{track_match, TrackMatch};
+
+/// Share of its score a track loses for every energy level it is away from the track before.
+const ENERGY_STEP_PENALTY: f64 = 0.1;
+
+/// A rolling queue of the next tracks to play from a pool, chosen one mix at a time by how
+/// well their keys, tempos and energies follow on, and chosen again whenever a suggestion is
+/// accepted or rejected.
+#[derive(Debug, Clone)]
+pub struct AutoDj<'a> {
+    tracks: &'a [Track],
+    distances: &'a DistanceMatrix,
+    pitch_range: f64,
+    length: usize,
+    /// Indices of the tracks played so far, the one playing last.
+    played: Vec<usize>,
+    rejected: Vec<bool>,
+    queue: Vec<TrackMatch>,
+}
+
+impl<'a> AutoDj<'a> {
+    /// Starts playing `start` of `tracks` with a queue of up to `length` tracks after it, mixed
+    /// like [`best_matches`](crate::best_matches) scores them, within `pitch_range` percent.
+    pub fn new(
+        tracks: &'a [Track],
+        distances: &'a DistanceMatrix,
+        start: usize,
+        length: usize,
+        pitch_range: f64,
+    ) -> Self {
+        let mut dj = Self {
+            tracks,
+            distances,
+            pitch_range,
+            length,
+            played: vec![start],
+            rejected: vec![false; tracks.len()],
+            queue: vec![],
+        };
+        dj.refill();
+        dj
+    }
+
+    /// Indices of the tracks played so far, in order.
+    pub fn played(&self) -> &[usize] {
+        &self.played
+    }
+
+    /// Index of the track playing now.
+    pub fn current(&self) -> usize {
+        *self.played.last().expect("an auto-DJ starts with a track")
+    }
+
+    /// The tracks queued to play next, in order, each scored against the one before it.
+    pub fn queue(&self) -> &[TrackMatch] {
+        &self.queue
+    }
+
+    /// Plays the track at `position` of the queue, 0 for the next one, and queues again from
+    /// it. Tracks queued before it stay in the pool. Returns its index, or `None` if the
+    /// queue is shorter than that.
+    pub fn accept(&mut self, position: usize) -> Option<usize> {
+        let track = self.queue.get(position)?.to;
+        self.played.push(track);
+        self.refill();
+        Some(track)
+    }
+
+    /// Leaves the track at `position` of the queue out for the rest of the session and queues
+    /// again. Returns its index, or `None` if the queue is shorter than that.
+    pub fn reject(&mut self, position: usize) -> Option<usize> {
+        let track = self.queue.get(position)?.to;
+        self.rejected[track] = true;
+        self.refill();
+        Some(track)
+    }
+
+    /// How well `from` mixes into `to`, with energy jumps taken off the score.
+    fn score(&self, from: usize, to: usize) -> Option<TrackMatch> {
+        let (track, other) = (&self.tracks[from], &self.tracks[to]);
+        let mut found = track_match(track, other, to, self.distances, self.pitch_range)?;
+        if let (Some(energy), Some(other)) = (track.energy, other.energy) {
+            let jump = f64::from(energy.abs_diff(other));
+            found.score *= (1.0 - jump * ENERGY_STEP_PENALTY).max(0.0);
+        }
+        (found.score > 0.0).then_some(found)
+    }
+
+    /// Queues the best next track greedily from the current one until the queue is full or
+    /// the pool has nothing left that mixes. Ties go to the track listed first.
+    fn refill(&mut self) {
+        self.queue.clear();
+        let mut last = self.current();
+        while self.queue.len() < self.length {
+            let best = (0..self.tracks.len())
+                .filter(|&track| !self.rejected[track] && !self.played.contains(&track))
+                .filter(|&track| self.queue.iter().all(|queued| queued.to != track))
+                .filter_map(|track| self.score(last, track))
+                .max_by(|a, b| a.score.total_cmp(&b.score).then(b.to.cmp(&a.to)));
+            let Some(best) = best else { break };
+            last = best.to;
+            self.queue.push(best);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::error::Result;
+use crate::scale::Scale;
+use crate::search::Path;
+use crate::wheel::Wheel;
+use rayon::prelude::*;
+
+/// Runs the path search for every `(from, to)` query in parallel, returning the `n` shortest
+/// paths of each in query order. `progress` is called from the worker threads after every
+/// finished query. Fails if any query names a key that isn't on the wheel.
+pub fn batch_paths(
+    wheel: &Wheel,
+    queries: &[(Scale, Scale)],
+    n: usize,
+    progress: impl Fn() + Sync,
+) -> Result<Vec<Vec<Path>>> {
+    tracing::info!(queries = queries.len(), n, "searching paths in parallel");
+    queries
+        .par_iter()
+        .map(|&(from, to)| {
+            let paths = wheel.paths(from, to, n);
+            match &paths {
+                Ok(paths) => tracing::debug!(%from, %to, found = paths.len(), "searched paths"),
+                Err(error) => tracing::warn!(%from, %to, %error, "path search failed"),
+            }
+            progress();
+            paths
+        })
+        .collect()
+}
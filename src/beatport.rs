@@ -0,0 +1,115 @@
+use crate::api::{encode_query, get_json};
+use crate::error::Result;
+use crate::library::{Track, TrackLookup};
+use crate::scale::Scale;
+use serde::Deserialize;
+
+const API_URL: &str = "https://api.beatport.com/v4";
+
+#[derive(Deserialize)]
+struct SearchPage {
+    tracks: Vec<BeatportTrack>,
+}
+
+#[derive(Deserialize)]
+struct BeatportTrack {
+    name: String,
+    mix_name: Option<String>,
+    artists: Vec<Artist>,
+    key: Option<BeatportKey>,
+    bpm: Option<f64>,
+    length_ms: Option<u64>,
+    genre: Option<Genre>,
+}
+
+#[derive(Deserialize)]
+struct Genre {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Artist {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BeatportKey {
+    camelot_number: usize,
+    camelot_letter: String,
+}
+
+impl BeatportTrack {
+    /// Whether this is `title` by `artist`, in any mix when `title` doesn't name one.
+    fn matches(&self, artist: &str, title: &str) -> bool {
+        let title = title.trim().to_lowercase();
+        let name = self.name.to_lowercase();
+        let mixed = self
+            .mix_name
+            .as_ref()
+            .map(|mix| format!("{name} ({})", mix.to_lowercase()));
+        let artist = artist.trim().to_lowercase();
+        (title == name || mixed.is_some_and(|mixed| mixed == title))
+            && self
+                .artists
+                .iter()
+                .any(|credited| artist.contains(&credited.name.to_lowercase()))
+    }
+}
+
+/// Looks up keys and tempos of tracks in the Beatport catalog.
+pub struct BeatportClient {
+    token: String,
+    api: String,
+}
+
+impl BeatportClient {
+    /// A client authorized with an OAuth access `token`.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self::with_api_url(token, API_URL)
+    }
+
+    /// A client for the API at `api`, like a caching proxy.
+    pub fn with_api_url(token: impl Into<String>, api: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            api: api.into(),
+        }
+    }
+}
+
+impl TrackLookup for BeatportClient {
+    /// The catalog search for `name`.
+    fn request(&self, name: &str) -> Option<String> {
+        let (artist, title) = name.split_once(" - ")?;
+        let query = encode_query(&format!("{artist} {title}"));
+        Some(format!(
+            "{}/catalog/search/?type=tracks&per_page=25&q={query}",
+            self.api
+        ))
+    }
+
+    /// Finds `name` in the catalog, in any mix when it doesn't name one.
+    fn lookup(&self, name: &str) -> Result<Option<Track>> {
+        let (Some(url), Some((artist, title))) = (self.request(name), name.split_once(" - "))
+        else {
+            return Ok(None);
+        };
+        let page: SearchPage = get_json("Beatport", &url, Some(&self.token))?;
+        Ok(page
+            .tracks
+            .into_iter()
+            .filter(|track| track.matches(artist, title))
+            .find_map(|track| {
+                let key = track.key?;
+                let key = format!("{}{}", key.camelot_number, key.camelot_letter)
+                    .parse::<Scale>()
+                    .ok()?;
+                Some(Track {
+                    tempo: track.bpm.filter(|&bpm| bpm > 0.0),
+                    duration: track.length_ms.map(|length| length as f64 / 1000.0),
+                    genre: track.genre.map(|genre| genre.name),
+                    ..Track::new(name.trim(), key)
+                })
+            }))
+    }
+}
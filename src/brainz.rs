@@ -0,0 +1,260 @@
+use crate::api::{encode_query, get_json};
+use crate::error::{Error, Result};
+use crate::fingerprint::fpcalc;
+use crate::library::{absolute_path, Track, TrackLookup};
+use crate::scale::{Locale, Scale};
+use serde::Deserialize;
+use serde_json::Value;
+use std::cell::Cell;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MUSICBRAINZ_URL: &str = "https://musicbrainz.org/ws/2";
+const ACOUSTICBRAINZ_URL: &str = "https://acousticbrainz.org/api/v1";
+const ACOUSTID_URL: &str = "https://api.acoustid.org/v2";
+/// MusicBrainz asks clients to send at most one request per second.
+const MUSICBRAINZ_INTERVAL: Duration = Duration::from_secs(1);
+/// Recordings scoring lower than this in the MusicBrainz search are too loose a match.
+const MIN_SEARCH_SCORE: u32 = 90;
+/// AcoustID matches scoring lower than this, from 0 to 1, are too loose a match.
+const MIN_FINGERPRINT_SCORE: f64 = 0.8;
+
+#[derive(Deserialize)]
+struct RecordingSearch {
+    recordings: Vec<Recording>,
+}
+
+#[derive(Deserialize)]
+struct Recording {
+    id: String,
+    score: u32,
+}
+
+/// What `fpcalc -json` prints.
+#[derive(Deserialize)]
+struct Chromaprint {
+    duration: f64,
+    fingerprint: String,
+}
+
+#[derive(Deserialize)]
+struct FingerprintLookup {
+    #[serde(default)]
+    results: Vec<FingerprintMatch>,
+}
+
+#[derive(Deserialize)]
+struct FingerprintMatch {
+    score: f64,
+    #[serde(default)]
+    recordings: Vec<FingerprintRecording>,
+}
+
+#[derive(Deserialize)]
+struct FingerprintRecording {
+    id: String,
+    title: Option<String>,
+    #[serde(default)]
+    artists: Vec<Artist>,
+}
+
+#[derive(Deserialize)]
+struct Artist {
+    name: String,
+}
+
+impl FingerprintRecording {
+    /// `Artist - Title`, with every artist of the recording.
+    fn name(&self) -> Option<String> {
+        let artists: Vec<&str> = self
+            .artists
+            .iter()
+            .map(|artist| artist.name.as_str())
+            .collect();
+        let title = self.title.as_deref().filter(|_| !artists.is_empty())?;
+        Some(format!("{} - {title}", artists.join(", ")))
+    }
+}
+
+/// The Chromaprint fingerprint of the audio file at `path`, from Chromaprint's `fpcalc`.
+fn chromaprint(path: &Path) -> Result<Chromaprint> {
+    fpcalc(path, &["-json"])
+}
+
+/// Looks up tracks on MusicBrainz by artist and title, and the keys and tempos AcousticBrainz
+/// measured for the matching recordings. Neither needs an account. With an AcoustID API key,
+/// audio files are looked up by their Chromaprint fingerprint instead, which takes `fpcalc`.
+pub struct BrainzClient {
+    musicbrainz: String,
+    acousticbrainz: String,
+    acoustid: String,
+    acoustid_key: Option<String>,
+    last_search: Cell<Option<Instant>>,
+}
+
+impl Default for BrainzClient {
+    fn default() -> Self {
+        Self::with_api_urls(MUSICBRAINZ_URL, ACOUSTICBRAINZ_URL)
+    }
+}
+
+impl BrainzClient {
+    /// A client for the APIs at `musicbrainz` and `acousticbrainz`, like mirrors.
+    pub fn with_api_urls(
+        musicbrainz: impl Into<String>,
+        acousticbrainz: impl Into<String>,
+    ) -> Self {
+        Self {
+            musicbrainz: musicbrainz.into(),
+            acousticbrainz: acousticbrainz.into(),
+            acoustid: ACOUSTID_URL.to_string(),
+            acoustid_key: None,
+            last_search: Cell::new(None),
+        }
+    }
+
+    /// Looks up audio files by fingerprint on AcoustID with the application API `key`.
+    pub fn with_acoustid_key(self, key: impl Into<String>) -> Self {
+        Self {
+            acoustid_key: Some(key.into()),
+            ..self
+        }
+    }
+
+    fn recordings(&self, url: &str) -> Result<Vec<String>> {
+        if let Some(last) = self.last_search.get() {
+            thread::sleep(MUSICBRAINZ_INTERVAL.saturating_sub(last.elapsed()));
+        }
+        let search: Result<RecordingSearch> = get_json("MusicBrainz", url, None);
+        self.last_search.set(Some(Instant::now()));
+        Ok(search?
+            .recordings
+            .into_iter()
+            .filter(|recording| recording.score >= MIN_SEARCH_SCORE)
+            .map(|recording| recording.id)
+            .collect())
+    }
+
+    /// Looks up the audio file at `path` by its fingerprint, naming the track after the first
+    /// matching recording that has an artist and title, or after the file otherwise.
+    fn lookup_file(&self, path: &Path) -> Result<Option<Track>> {
+        let key = self.acoustid_key.as_deref().ok_or_else(|| Error::Api {
+            service: "AcoustID",
+            message: "an API key is needed to look up audio files by fingerprint".to_string(),
+        })?;
+        let chromaprint = chromaprint(path)?;
+        let lookup: FingerprintLookup = get_json(
+            "AcoustID",
+            &format!(
+                "{}/lookup?client={}&meta=recordings&duration={}&fingerprint={}",
+                self.acoustid,
+                encode_query(key),
+                chromaprint.duration.round() as u64,
+                encode_query(&chromaprint.fingerprint)
+            ),
+            None,
+        )?;
+        let recordings: Vec<FingerprintRecording> = lookup
+            .results
+            .into_iter()
+            .filter(|found| found.score >= MIN_FINGERPRINT_SCORE)
+            .flat_map(|found| found.recordings)
+            .collect();
+        if recordings.is_empty() {
+            return Ok(None);
+        }
+        let name = recordings
+            .iter()
+            .find_map(FingerprintRecording::name)
+            .or_else(|| Some(path.file_stem()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| path.display().to_string());
+        let ids: Vec<String> = recordings
+            .into_iter()
+            .map(|recording| recording.id)
+            .collect();
+        Ok(self.analyzed(&name, &ids)?.map(|track| Track {
+            path: Some(absolute_path(path)),
+            ..track
+        }))
+    }
+
+    /// `name` with the key of the first of the MusicBrainz `recordings` AcousticBrainz has
+    /// data for.
+    fn analyzed(&self, name: &str, recordings: &[String]) -> Result<Option<Track>> {
+        let analyses: Value = get_json(
+            "AcousticBrainz",
+            &format!(
+                concat!(
+                    "{}/low-level?recording_ids={}",
+                    "&features=tonal.key_key;tonal.key_scale;tonal.key_strength;rhythm.bpm"
+                ),
+                self.acousticbrainz,
+                recordings.join(";")
+            ),
+            None,
+        )?;
+        Ok(recordings.iter().find_map(|id| {
+            // Each recording maps submission offsets to analyses; the first is as good as any.
+            let analysis = &analyses[id]["0"];
+            let tonal = &analysis["tonal"];
+            let key = format!(
+                "{} {}",
+                tonal["key_key"].as_str()?,
+                tonal["key_scale"].as_str()?
+            );
+            Some(Track {
+                confidence: tonal["key_strength"]
+                    .as_f64()
+                    .map(|strength| strength.clamp(0.0, 1.0)),
+                tempo: analysis["rhythm"]["bpm"].as_f64().filter(|&bpm| bpm > 0.0),
+                ..Track::new(
+                    name.trim(),
+                    Scale::from_key_name(&key, Locale::English).ok()?,
+                )
+            })
+        }))
+    }
+}
+
+impl TrackLookup for BrainzClient {
+    /// The MusicBrainz recording search for `name`, or the AcoustID lookup for an audio file.
+    fn request(&self, name: &str) -> Option<String> {
+        let file = Path::new(name.trim());
+        if file.is_file() {
+            return Some(format!(
+                "fpcalc -json {}, then {}/lookup",
+                file.display(),
+                self.acoustid
+            ));
+        }
+        let (artist, title) = name.split_once(" - ")?;
+        let query = format!(
+            "artist:\"{}\" AND recording:\"{}\"",
+            artist.trim().replace('"', ""),
+            title.trim().replace('"', "")
+        );
+        Some(format!(
+            "{}/recording?fmt=json&limit=5&query={}",
+            self.musicbrainz,
+            encode_query(&query)
+        ))
+    }
+
+    /// Takes the key of the first matching recording AcousticBrainz has data for, matched by
+    /// fingerprint for audio files.
+    fn lookup(&self, name: &str) -> Result<Option<Track>> {
+        let file = Path::new(name.trim());
+        if file.is_file() {
+            return self.lookup_file(file);
+        }
+        let Some(url) = self.request(name) else {
+            return Ok(None);
+        };
+        let recordings = self.recordings(&url)?;
+        if recordings.is_empty() {
+            return Ok(None);
+        }
+        self.analyzed(name, &recordings)
+    }
+}
@@ -0,0 +1,254 @@
+use crate::audio::{analyze_decoded, decode_audio, file_track_name};
+use crate::detect::KeyProfile;
+use crate::error::Result;
+use crate::library::{file_modified, KeySegment, Track};
+use crate::loudness::Loudness;
+use crate::scale::Scale;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::UNIX_EPOCH;
+
+/// Analysis results stored by the hash of the analyzed file's contents, so unchanged files
+/// are never decoded twice, even after being moved or renamed. The hash of every file is kept
+/// with its size and modification time, so files that haven't changed since aren't read
+/// again to hash either. Files can be analyzed through
+/// it from several threads at once.
+pub struct AnalysisCache {
+    connection: Mutex<Connection>,
+}
+
+/// What's stored about an analyzed file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedAnalysis {
+    /// `Artist - Title` from the file's tags. Files without them are named after wherever they
+    /// are now.
+    pub name: Option<String>,
+    pub key: Scale,
+    /// From 0 to 1, missing from analyses cached before it was stored.
+    pub confidence: Option<f64>,
+    pub tempo: Option<f64>,
+    /// Length in seconds.
+    pub duration: f64,
+    /// Empty unless the track modulates.
+    pub segments: Vec<KeySegment>,
+    /// Missing from analyses cached before it was stored.
+    pub loudness: Option<Loudness>,
+}
+
+/// BLAKE3 hash of the contents of `path`, in hex.
+pub fn file_hash(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+impl AnalysisCache {
+    /// Opens the cache database at `path`, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS analyses (
+                hash TEXT PRIMARY KEY,
+                name TEXT,
+                key TEXT NOT NULL,
+                tempo REAL,
+                duration REAL,
+                segments TEXT,
+                confidence REAL,
+                loudness REAL,
+                short_term_loudness REAL
+            )",
+            (),
+        )?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS files (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT NOT NULL
+            )",
+            (),
+        )?;
+        // Caches from older versions get the columns added since.
+        for (column, kind) in [
+            ("duration", "REAL"),
+            ("segments", "TEXT"),
+            ("confidence", "REAL"),
+            ("loudness", "REAL"),
+            ("short_term_loudness", "REAL"),
+        ] {
+            let exists = connection
+                .prepare("SELECT 1 FROM pragma_table_info('analyses') WHERE name = ?1")?
+                .exists([column])?;
+            if !exists {
+                connection.execute(
+                    &format!("ALTER TABLE analyses ADD COLUMN {column} {kind}"),
+                    (),
+                )?;
+            }
+        }
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn get(&self, hash: &str) -> Result<Option<CachedAnalysis>> {
+        let row = self
+            .connection()
+            .query_row(
+                "SELECT name, key, tempo, duration, segments, confidence, loudness,
+                    short_term_loudness FROM analyses WHERE hash = ?1",
+                [hash],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get(2)?,
+                        row.get::<_, Option<f64>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get(5)?,
+                        row.get::<_, Option<f64>>(6)?,
+                        row.get::<_, Option<f64>>(7)?,
+                    ))
+                },
+            )
+            .optional()?;
+        // Entries with keys this version can't read, or from before durations and segments were
+        // stored, are analyzed again.
+        Ok(row.and_then(
+            |(name, key, tempo, duration, segments, confidence, integrated, short_term)| {
+                Some(CachedAnalysis {
+                    name,
+                    key: key.parse().ok()?,
+                    confidence,
+                    tempo,
+                    duration: duration?,
+                    segments: serde_json::from_str(&segments?).ok()?,
+                    loudness: integrated
+                        .zip(short_term)
+                        .map(|(integrated, short_term)| Loudness {
+                            integrated,
+                            short_term_max: short_term,
+                        }),
+                })
+            },
+        ))
+    }
+
+    pub fn insert(&self, hash: &str, analysis: &CachedAnalysis) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO analyses (hash, name, key, tempo, duration, segments,
+                confidence, loudness, short_term_loudness)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                hash,
+                analysis.name,
+                analysis.key.to_string(),
+                analysis.tempo,
+                analysis.duration,
+                serde_json::to_string(&analysis.segments).expect("segments serialize"),
+                analysis.confidence,
+                analysis.loudness.map(|loudness| loudness.integrated),
+                analysis.loudness.map(|loudness| loudness.short_term_max)
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The [`file_hash`] of `path`, from when it was last hashed if its size and modification
+    /// time are the same as then.
+    pub fn hash(&self, path: &Path) -> Result<String> {
+        let metadata = path.metadata()?;
+        let size = i64::try_from(metadata.len()).unwrap_or(i64::MAX);
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| i64::try_from(since.as_nanos()).unwrap_or(i64::MAX));
+        let key = path.to_string_lossy();
+        if let Some(modified) = modified {
+            let known = self
+                .connection()
+                .query_row(
+                    "SELECT hash FROM files WHERE path = ?1 AND size = ?2 AND modified = ?3",
+                    params![key, size, modified],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?;
+            if let Some(hash) = known {
+                return Ok(hash);
+            }
+        }
+        let hash = file_hash(path)?;
+        if let Some(modified) = modified {
+            self.connection().execute(
+                "INSERT OR REPLACE INTO files (path, size, modified, hash) VALUES (?1, ?2, ?3, ?4)",
+                params![key, size, modified, hash],
+            )?;
+        }
+        Ok(hash)
+    }
+
+    /// Like [`analyze_audio`](crate::analyze_audio), but only analyzes files that aren't in
+    /// the cache yet.
+    pub fn analyze(&self, path: &Path) -> Result<Option<Track>> {
+        self.analyze_with(path, KeyProfile::default())
+    }
+
+    /// Like [`analyze`](Self::analyze), estimating keys with `profile`. Analyses with other
+    /// profiles than the default are kept apart from those with it.
+    pub fn analyze_with(&self, path: &Path, profile: KeyProfile) -> Result<Option<Track>> {
+        let mut hash = self.hash(path)?;
+        if profile != KeyProfile::default() {
+            hash = format!("{hash}:{profile}");
+        }
+        let analysis = match self.get(&hash)? {
+            Some(analysis) => {
+                tracing::debug!(path = %path.display(), %hash, "found in the cache");
+                analysis
+            }
+            None => {
+                tracing::debug!(path = %path.display(), %hash, "analyzing");
+                let audio = decode_audio(path)?;
+                let Some(analysis) = analyze_decoded(&audio, profile) else {
+                    tracing::warn!(path = %path.display(), "silent, no key found");
+                    return Ok(None);
+                };
+                tracing::info!(path = %path.display(), key = %analysis.key, "analyzed");
+                let analysis = CachedAnalysis {
+                    duration: audio.duration(),
+                    name: audio.name,
+                    key: analysis.key,
+                    confidence: Some(analysis.confidence),
+                    tempo: analysis.tempo,
+                    segments: analysis.segments,
+                    loudness: analysis.loudness,
+                };
+                self.insert(&hash, &analysis)?;
+                analysis
+            }
+        };
+        Ok(Some(Track {
+            confidence: analysis.confidence,
+            tempo: analysis.tempo,
+            path: Some(path.to_path_buf()),
+            duration: Some(analysis.duration),
+            modified: file_modified(path),
+            segments: analysis.segments,
+            energy: analysis.loudness.map(|loudness| loudness.energy()),
+            ..Track::new(
+                analysis.name.unwrap_or_else(|| file_track_name(path)),
+                analysis.key,
+            )
+        }))
+    }
+}
@@ -0,0 +1,360 @@
+use crate::color::{wheel_color, Rgb};
+use crate::error::{Error, Result};
+use crate::scale::{make_nodes, note_name, Scale, ScaleKind};
+use camelol_core::parse_english_note;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Share of the layering score kept when the tonics are a semitone or a tritone apart, the
+/// intervals that clash hardest when two keys sound at once.
+const CLASHING_TONICS: f64 = 0.25;
+
+/// The seven pitch classes of `scale`, starting from its tonic, see [`Scale::notes`].
+pub fn scale_notes(scale: Scale) -> [usize; 7] {
+    scale.notes()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+    Major,
+    Minor,
+    Diminished,
+}
+
+/// A triad built from a scale's own notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Triad {
+    /// Pitch class of the root in semitones above C.
+    pub root: usize,
+    pub quality: Quality,
+}
+
+impl Triad {
+    /// The chord symbol, like `Am`, `C` or `Bdim`, spelled the way `key` spells its notes.
+    pub fn name(&self, key: Scale) -> String {
+        let root = note_name(self.root, key.conventional_accidental());
+        match self.quality {
+            Quality::Major => root.to_string(),
+            Quality::Minor => format!("{root}m"),
+            Quality::Diminished => format!("{root}dim"),
+        }
+    }
+}
+
+/// The triads on each degree of `scale`, starting from its tonic.
+pub fn diatonic_triads(scale: Scale) -> [Triad; 7] {
+    let notes = scale_notes(scale);
+    let interval = |from: usize, to: usize| (notes[to % 7] + 12 - notes[from]) % 12;
+    std::array::from_fn(|degree| {
+        let quality = match (interval(degree, degree + 2), interval(degree, degree + 4)) {
+            (4, _) => Quality::Major,
+            (_, 6) => Quality::Diminished,
+            _ => Quality::Minor,
+        };
+        Triad {
+            root: notes[degree],
+            quality,
+        }
+    })
+}
+
+/// The roman numeral of `degree` (0 for the tonic) for a chord of `quality`, like `IV`, `vi` or
+/// `vii°`.
+pub fn roman_numeral(degree: usize, quality: Quality) -> String {
+    const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+    let numeral = NUMERALS[degree % 7];
+    match quality {
+        Quality::Major => numeral.to_string(),
+        Quality::Minor => numeral.to_lowercase(),
+        Quality::Diminished => format!("{}°", numeral.to_lowercase()),
+    }
+}
+
+/// A note that belongs to both keys, with its scale degree in each, counted from 1.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SharedNote {
+    pub note: String,
+    pub from_degree: usize,
+    pub to_degree: usize,
+}
+
+/// A triad that is diatonic to both keys, with its roman numeral in each.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SharedChord {
+    pub name: String,
+    pub quality: Quality,
+    pub from_numeral: String,
+    pub to_numeral: String,
+}
+
+/// What two keys have in common harmonically, for judging whether material in one will sit
+/// over material in the other.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mashup {
+    pub from: Scale,
+    pub to: Scale,
+    pub from_color: Rgb,
+    pub to_color: Rgb,
+    pub notes: Vec<SharedNote>,
+    pub chords: Vec<SharedChord>,
+    /// How well the keys sound at the same time, see [`layering_score`].
+    pub score: f64,
+}
+
+/// How well material in `a` and `b` sounds played at the same time, from 1 for keys with the
+/// same notes down towards 0. Unlike the transition score this isn't about distance on the
+/// wheel but about the notes the keys share, and it's cut hard when their tonics are a
+/// semitone or a tritone apart.
+pub fn layering_score(a: Scale, b: Scale) -> f64 {
+    let b_notes = scale_notes(b);
+    let shared = scale_notes(a)
+        .iter()
+        .filter(|note| b_notes.contains(note))
+        .count();
+    let score = shared as f64 / 7.0;
+    match (b.tonic() + 12 - a.tonic()) % 12 {
+        1 | 6 | 11 => score * CLASHING_TONICS,
+        _ => score,
+    }
+}
+
+/// The notes and triads `from` and `to` share, in the order they appear in `from`.
+pub fn mashup(from: Scale, to: Scale) -> Mashup {
+    let (from_notes, to_notes) = (scale_notes(from), scale_notes(to));
+    let notes = from_notes
+        .iter()
+        .enumerate()
+        .filter_map(|(from_degree, note)| {
+            let to_degree = to_notes.iter().position(|other| other == note)?;
+            Some(SharedNote {
+                note: note_name(*note, from.conventional_accidental()).to_string(),
+                from_degree: from_degree + 1,
+                to_degree: to_degree + 1,
+            })
+        })
+        .collect();
+
+    let to_triads = diatonic_triads(to);
+    let chords = diatonic_triads(from)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(from_degree, triad)| {
+            let to_degree = to_triads.iter().position(|other| *other == triad)?;
+            Some(SharedChord {
+                name: triad.name(from),
+                quality: triad.quality,
+                from_numeral: roman_numeral(from_degree, triad.quality),
+                to_numeral: roman_numeral(to_degree, triad.quality),
+            })
+        })
+        .collect();
+
+    Mashup {
+        from,
+        to,
+        from_color: wheel_color(from),
+        to_color: wheel_color(to),
+        notes,
+        chords,
+        score: layering_score(from, to),
+    }
+}
+
+/// A chord symbol like `Am`, `G7`, `Bdim`, `Fmaj7`, `Dsus4` or `C/E`, as the notes it plays.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chord {
+    /// The symbol as given.
+    pub name: String,
+    /// Pitch class of the root in semitones above C.
+    pub root: usize,
+    /// The triad's quality, if it's major, minor or diminished rather than suspended,
+    /// augmented or a power chord.
+    pub quality: Option<Quality>,
+    /// Pitch classes of every note, root first.
+    pub notes: Vec<usize>,
+}
+
+impl FromStr for Chord {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let invalid = || Error::InvalidChord(input.to_string());
+        let (symbol, bass) = match input.split_once('/') {
+            Some((symbol, bass)) => (symbol, Some(bass)),
+            None => (input, None),
+        };
+        let (root, mut rest) = parse_english_note(symbol).ok_or_else(invalid)?;
+        // Semitones above the root.
+        let (mut third, mut fifth, mut added) = (Some(4), 7, Vec::new());
+        while !rest.is_empty() {
+            let strip =
+                |suffixes: &[&str]| suffixes.iter().find_map(|suffix| rest.strip_prefix(suffix));
+            rest = if let Some(after) = strip(&["maj7", "maj9", "M7", "Δ"]) {
+                added.push(11);
+                after
+            } else if let Some(after) = strip(&["maj"]) {
+                after
+            } else if let Some(after) = strip(&["min", "m"]) {
+                third = Some(3);
+                after
+            } else if let Some(after) = strip(&["dim", "°", "o"]) {
+                (third, fifth) = (Some(3), 6);
+                after
+            } else if let Some(after) = strip(&["ø"]) {
+                (third, fifth) = (Some(3), 6);
+                added.push(10);
+                after
+            } else if let Some(after) = strip(&["aug", "+"]) {
+                fifth = 8;
+                after
+            } else if let Some(after) = strip(&["sus2"]) {
+                third = Some(2);
+                after
+            } else if let Some(after) = strip(&["sus4", "sus"]) {
+                third = Some(5);
+                after
+            } else if let Some(after) = strip(&["add9", "add2"]) {
+                added.push(2);
+                after
+            } else if let Some(after) = strip(&["b5"]) {
+                fifth = 6;
+                after
+            } else if let Some(after) = strip(&["7", "9", "11", "13"]) {
+                // A diminished seventh chord stacks minor thirds all the way up.
+                added.push(if (third, fifth) == (Some(3), 6) {
+                    9
+                } else {
+                    10
+                });
+                after
+            } else if let Some(after) = strip(&["6"]) {
+                added.push(9);
+                after
+            } else if let Some(after) = strip(&["5"]) {
+                third = None;
+                after
+            } else {
+                return Err(invalid());
+            };
+        }
+        let quality = match (third, fifth) {
+            (Some(4), 7) => Some(Quality::Major),
+            (Some(3), 7) => Some(Quality::Minor),
+            (Some(3), 6) => Some(Quality::Diminished),
+            _ => None,
+        };
+        let bass = match bass.map(parse_english_note) {
+            None => None,
+            Some(Some((bass, ""))) => Some(bass),
+            Some(_) => return Err(invalid()),
+        };
+        let mut notes = vec![root];
+        let intervals = third.into_iter().chain([fifth]).chain(added);
+        for note in intervals.map(|interval| (root + interval) % 12).chain(bass) {
+            if !notes.contains(&note) {
+                notes.push(note);
+            }
+        }
+        Ok(Self {
+            name: input.to_string(),
+            root,
+            quality,
+            notes,
+        })
+    }
+}
+
+/// Parses a progression of chord symbols separated by spaces, commas, bars or dashes between
+/// spaces, like `Am F C G` or `| Dm7 | G7 | Cmaj7 |`.
+pub fn parse_progression(input: &str) -> Result<Vec<Chord>> {
+    input
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '|'))
+        .filter(|part| !part.is_empty() && *part != "-")
+        .map(str::parse)
+        .collect()
+}
+
+/// How well a chord progression fits a key.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressionFit {
+    pub key: Scale,
+    pub color: Rgb,
+    /// Chords all of whose notes are in the key, counting the raised seventh of minor keys,
+    /// which their dominant chords use.
+    pub fitting: usize,
+    /// Share of all the notes played that are in the key.
+    pub notes: f64,
+    /// Whether the progression starts or ends on the key's own tonic chord.
+    pub tonic: bool,
+    /// The roman numeral of every chord's triad in the key, if it's rooted on one of its degrees
+    /// and one of its major, minor or diminished triads.
+    pub numerals: Vec<Option<String>>,
+}
+
+/// The notes of `key`, with the raised seventh of minor keys.
+fn key_notes(key: Scale) -> Vec<usize> {
+    let mut notes = scale_notes(key).to_vec();
+    if key.kind == ScaleKind::Minor {
+        notes.push((key.tonic() + 11) % 12);
+    }
+    notes
+}
+
+/// Every key of the wheel by how well `chords` fit it, best first: the most fitting chords,
+/// then the most notes in the key, then keys whose tonic chord opens or closes the
+/// progression, which tells relative majors and minors apart.
+pub fn progression_keys(chords: &[Chord]) -> Vec<ProgressionFit> {
+    let played: usize = chords.iter().map(|chord| chord.notes.len()).sum();
+    let mut fits: Vec<ProgressionFit> = make_nodes()
+        .into_iter()
+        .map(|key| {
+            let notes = key_notes(key);
+            let in_key = |chord: &Chord| {
+                chord
+                    .notes
+                    .iter()
+                    .filter(|note| notes.contains(note))
+                    .count()
+            };
+            let tonic_quality = match key.kind {
+                ScaleKind::Major => Quality::Major,
+                ScaleKind::Minor => Quality::Minor,
+            };
+            let is_tonic =
+                |chord: &&Chord| chord.root == key.tonic() && chord.quality == Some(tonic_quality);
+            let numerals = chords
+                .iter()
+                .map(|chord| {
+                    let quality = chord.quality?;
+                    // The raised seventh is still the seventh degree.
+                    let degree = notes.iter().position(|&note| note == chord.root)?.min(6);
+                    (in_key(chord) == chord.notes.len()).then(|| roman_numeral(degree, quality))
+                })
+                .collect();
+            ProgressionFit {
+                key,
+                color: wheel_color(key),
+                fitting: chords
+                    .iter()
+                    .filter(|chord| in_key(chord) == chord.notes.len())
+                    .count(),
+                notes: if played == 0 {
+                    0.0
+                } else {
+                    chords.iter().map(in_key).sum::<usize>() as f64 / played as f64
+                },
+                tonic: chords.first().filter(is_tonic).is_some()
+                    || chords.last().filter(is_tonic).is_some(),
+                numerals,
+            }
+        })
+        .collect();
+    fits.sort_by(|a, b| {
+        b.fitting
+            .cmp(&a.fitting)
+            .then(b.notes.total_cmp(&a.notes))
+            .then(b.tonic.cmp(&a.tonic))
+    });
+    fits
+}
@@ -0,0 +1,141 @@
+use crate::library::Track;
+use crate::scale::{Scale, ScaleKind};
+use camelol_core::WHEEL_SIZE;
+use itertools::Itertools;
+
+/// Tracks from a group of keys close on the wheel, which mix into each other in a step or two.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCluster {
+    /// The keys of the group, in order going up the wheel from the first.
+    pub keys: Vec<Scale>,
+    /// Indices into the tracks, in the order of their keys and by tempo within each key.
+    pub tracks: Vec<usize>,
+}
+
+impl KeyCluster {
+    /// A cluster of the `tracks` whose main key is one of `keys`.
+    fn of(tracks: &[Track], keys: Vec<Scale>) -> Self {
+        let order = |i: usize| keys.iter().position(|&key| key == tracks[i].key);
+        let mut members: Vec<usize> = (0..tracks.len()).filter(|&i| order(i).is_some()).collect();
+        members.sort_by(|&a, &b| {
+            order(a).cmp(&order(b)).then_with(|| {
+                tracks[a]
+                    .tempo
+                    .unwrap_or(0.0)
+                    .total_cmp(&tracks[b].tempo.unwrap_or(0.0))
+            })
+        });
+        Self {
+            keys,
+            tracks: members,
+        }
+    }
+
+    /// The group as wheel numbers like `11-2`, or `8` for just one, when it has both keys of
+    /// every number in a stretch, and otherwise its keys like `8A+9A+8B`.
+    pub fn name(&self) -> String {
+        let numbers: Vec<usize> = self.keys.iter().map(|key| key.index).dedup().collect();
+        let whole = self.keys.len() == numbers.len() * 2
+            && numbers.iter().all_unique()
+            && numbers
+                .windows(2)
+                .all(|pair| pair[1] == (pair[0] + 1) % WHEEL_SIZE);
+        match (whole, numbers.first(), numbers.last()) {
+            (true, Some(first), Some(last)) if first == last => format!("{}", first + 1),
+            (true, Some(first), Some(last)) => format!("{}-{}", first + 1, last + 1),
+            _ => self.keys.iter().join("+"),
+        }
+    }
+}
+
+/// Load of a split of the wheel into stretches: the most tracks in any one stretch, then the
+/// sum of the squares of their sizes, so evener splits come first, then the same for their
+/// lengths, so no stretch spans more of the wheel than it has to.
+type Load = (usize, usize, usize);
+
+/// The best way to split `counts` into `parts` stretches of at least one wheel number each, as
+/// the load and the length of every stretch.
+fn split(counts: &[usize], parts: usize) -> (Load, Vec<usize>) {
+    // best[c][j] is the best load of splitting the first j numbers into c stretches, with the
+    // length of the last one.
+    let n = counts.len();
+    let mut best = vec![vec![None::<(Load, usize)>; n + 1]; parts + 1];
+    best[0][0] = Some(((0, 0, 0), 0));
+    for c in 1..=parts {
+        for j in c..=n {
+            best[c][j] = (c - 1..j)
+                .filter_map(|i| {
+                    let ((most, squares, spans), _) = best[c - 1][i]?;
+                    let size: usize = counts[i..j].iter().sum();
+                    let length = j - i;
+                    let load = (
+                        most.max(size),
+                        squares + size * size,
+                        spans + length * length,
+                    );
+                    Some((load, length))
+                })
+                .min_by_key(|&(load, _)| load);
+        }
+    }
+    let (load, _) = best[parts][n].expect("every number can be split off on its own");
+    let mut lengths = vec![];
+    let (mut c, mut j) = (parts, n);
+    while c > 0 {
+        let (_, length) = best[c][j].expect("split stretches were counted");
+        lengths.push(length);
+        (c, j) = (c - 1, j - length);
+    }
+    lengths.reverse();
+    (load, lengths)
+}
+
+/// Splits `tracks` by their main keys into up to `count` stretches of the wheel, each as close
+/// in size as the keys allow, for crates of tracks that mix well with each other. Stretches
+/// take the A and B keys of their wheel numbers together, and empty ones are left out.
+pub fn key_clusters(tracks: &[Track], count: usize) -> Vec<KeyCluster> {
+    let parts = count.clamp(1, WHEEL_SIZE);
+    let mut counts = [0; WHEEL_SIZE];
+    for track in tracks {
+        counts[track.key.index % WHEEL_SIZE] += 1;
+    }
+    // Stretches may start anywhere on the wheel, so the split is tried from every number.
+    let (start, lengths) = (0..WHEEL_SIZE)
+        .map(|start| {
+            let rotated: Vec<usize> = (0..WHEEL_SIZE)
+                .map(|i| counts[(start + i) % WHEEL_SIZE])
+                .collect();
+            let (load, lengths) = split(&rotated, parts);
+            (load, start, lengths)
+        })
+        .min_by_key(|(load, start, _)| (*load, *start))
+        .map(|(_, start, lengths)| (start, lengths))
+        .expect("the wheel has numbers");
+
+    let mut first = start;
+    let mut clusters = vec![];
+    for length in lengths {
+        let keys = (first..first + length)
+            .flat_map(|number| {
+                [ScaleKind::Minor, ScaleKind::Major].map(|kind| Scale {
+                    index: number % WHEEL_SIZE,
+                    kind,
+                })
+            })
+            .collect();
+        clusters.push(KeyCluster::of(tracks, keys));
+        first = (first + length) % WHEEL_SIZE;
+    }
+    clusters.retain(|cluster| !cluster.tracks.is_empty());
+    clusters
+}
+
+/// Splits `tracks` by their main keys into the groups of keys of `communities`, like those of
+/// [`key_communities`](crate::key_communities), leaving out groups without tracks.
+pub fn community_clusters(tracks: &[Track], communities: &[Vec<Scale>]) -> Vec<KeyCluster> {
+    communities
+        .iter()
+        .map(|keys| KeyCluster::of(tracks, keys.clone()))
+        .filter(|cluster| !cluster.tracks.is_empty())
+        .collect()
+}
@@ -0,0 +1,77 @@
+use crate::scale::{Scale, ScaleKind};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Display for Rgb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+}
+
+/// Written as hex like `#86EAD9`, so UIs can use it as it is.
+impl Serialize for Rgb {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+        };
+        match (hex.len(), hex.starts_with('#')) {
+            (7, true) => channel(1).zip(channel(3)).zip(channel(5)),
+            _ => None,
+        }
+        .map(|((r, g), b)| Rgb(r, g, b))
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid color `{hex}`")))
+    }
+}
+
+// The wheel's colors run from turquoise at 1 through green, yellow, red and purple to blue at 12.
+// Minor keys use the lighter shade of their segment.
+const MINOR_COLORS: [Rgb; 12] = [
+    Rgb(0x86, 0xEA, 0xD9),
+    Rgb(0x86, 0xEA, 0xA7),
+    Rgb(0x96, 0xEA, 0x86),
+    Rgb(0xC8, 0xEA, 0x86),
+    Rgb(0xEA, 0xD9, 0x86),
+    Rgb(0xEA, 0xA7, 0x86),
+    Rgb(0xEA, 0x86, 0x96),
+    Rgb(0xEA, 0x86, 0xC8),
+    Rgb(0xD9, 0x86, 0xEA),
+    Rgb(0xA7, 0x86, 0xEA),
+    Rgb(0x86, 0x96, 0xEA),
+    Rgb(0x86, 0xC8, 0xEA),
+];
+
+const MAJOR_COLORS: [Rgb; 12] = [
+    Rgb(0x1D, 0xED, 0xCA),
+    Rgb(0x1D, 0xED, 0x62),
+    Rgb(0x3F, 0xED, 0x1D),
+    Rgb(0xA7, 0xED, 0x1D),
+    Rgb(0xED, 0xCA, 0x1D),
+    Rgb(0xED, 0x62, 0x1D),
+    Rgb(0xED, 0x1D, 0x3F),
+    Rgb(0xED, 0x1D, 0xA7),
+    Rgb(0xCA, 0x1D, 0xED),
+    Rgb(0x62, 0x1D, 0xED),
+    Rgb(0x1D, 0x3F, 0xED),
+    Rgb(0x1D, 0xA7, 0xED),
+];
+
+/// The color of a key's segment on the camelot wheel. Keys past 12 on larger wheels wrap
+/// around to the start of the colors.
+pub fn wheel_color(scale: Scale) -> Rgb {
+    let index = scale.index % MINOR_COLORS.len();
+    match scale.kind {
+        ScaleKind::Minor => MINOR_COLORS[index],
+        ScaleKind::Major => MAJOR_COLORS[index],
+    }
+}
@@ -0,0 +1,205 @@
+use crate::scale::Scale;
+use crate::transition::ScaleTransition;
+use crate::wheel::Wheel;
+
+/// How strongly every pair of keys is connected, counting transitions both ways, indexed by
+/// [`Scale::position`].
+fn weights(wheel: &Wheel, weight: &impl Fn(ScaleTransition) -> f64) -> Vec<Vec<f64>> {
+    let keys = wheel.scales().count();
+    let mut weights = vec![vec![0.0; keys]; keys];
+    for (from, transition, to) in wheel.transitions() {
+        if from == to {
+            continue;
+        }
+        let weight = weight(transition);
+        weights[from.position()][to.position()] += weight;
+        weights[to.position()][from.position()] += weight;
+    }
+    weights
+}
+
+/// The modularity of splitting the wheel's keys into `groups`: how much more of the weight of
+/// its transitions stays within the groups than it would with transitions drawn at random,
+/// from about -0.5 to 1. Higher means the groups hold together better.
+pub fn modularity(
+    wheel: &Wheel,
+    groups: &[Vec<Scale>],
+    weight: impl Fn(ScaleTransition) -> f64,
+) -> f64 {
+    let weights = weights(wheel, &weight);
+    let labels = |key: usize| {
+        groups
+            .iter()
+            .position(|group| group.iter().any(|scale| scale.position() == key))
+    };
+    score(
+        &weights,
+        &(0..weights.len()).map(labels).collect::<Vec<_>>(),
+    )
+}
+
+/// The modularity of the keys at `weights` labelled by their groups, where unlabelled keys
+/// count as outside every group.
+fn score(weights: &[Vec<f64>], labels: &[Option<usize>]) -> f64 {
+    let total: f64 = weights.iter().flatten().sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let groups = labels.iter().flatten().max().map_or(0, |last| last + 1);
+    let mut inside = vec![0.0; groups];
+    let mut degrees = vec![0.0; groups];
+    for (a, label) in labels.iter().enumerate() {
+        let Some(label) = *label else { continue };
+        degrees[label] += weights[a].iter().sum::<f64>();
+        for (b, other) in labels.iter().enumerate() {
+            if *other == Some(label) {
+                inside[label] += weights[a][b];
+            }
+        }
+    }
+    inside
+        .iter()
+        .zip(&degrees)
+        .map(|(inside, degree)| inside / total - (degree / total).powi(2))
+        .sum()
+}
+
+/// Moves single keys to the group of a key they're connected to for as long as that raises
+/// the modularity, which evens out what merging whole groups leaves behind. Groups keep at
+/// least one key, so their number stays the same.
+fn refine(weights: &[Vec<f64>], labels: &mut [Option<usize>]) {
+    let mut best = score(weights, labels);
+    loop {
+        let mut moved = false;
+        for key in 0..labels.len() {
+            let Some(current) = labels[key] else { continue };
+            if labels
+                .iter()
+                .filter(|&&label| label == Some(current))
+                .count()
+                == 1
+            {
+                continue;
+            }
+            let targets: Vec<usize> = (0..labels.len())
+                .filter(|&other| weights[key][other] > 0.0)
+                .filter_map(|other| labels[other])
+                .filter(|&label| label != current)
+                .collect();
+            for target in targets {
+                labels[key] = Some(target);
+                let score = score(weights, labels);
+                if score > best + 1e-12 {
+                    best = score;
+                    moved = true;
+                    break;
+                }
+                labels[key] = Some(current);
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+}
+
+/// Groups the wheel's keys into harmonic communities, keys that are connected more strongly
+/// to each other than to the rest of the wheel, with transitions counted at `weight`.
+///
+/// Every key starts on its own and the two connected groups whose merge raises the
+/// [`modularity`] most for groups of their sizes are merged until no merge helps, so the
+/// transitions decide how many groups there are. Single keys then move between groups while
+/// that raises the modularity. Groups come in wheel order, each starting where it begins going
+/// up the wheel, and equal merges are broken by wheel order so the result is the same every
+/// time.
+pub fn key_communities(wheel: &Wheel, weight: impl Fn(ScaleTransition) -> f64) -> Vec<Vec<Scale>> {
+    let weights = weights(wheel, &weight);
+    let total: f64 = weights.iter().flatten().sum();
+    let mut groups: Vec<Vec<Scale>> = wheel.scales().map(|key| vec![key]).collect();
+    // Shares of the total weight between every two groups and at every group's keys.
+    let mut between: Vec<Vec<f64>> = weights
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|weight| weight / total.max(f64::MIN_POSITIVE))
+                .collect()
+        })
+        .collect();
+    let mut degrees: Vec<f64> = between.iter().map(|row| row.iter().sum()).collect();
+
+    while groups.len() > 1 {
+        let best = (0..groups.len())
+            .flat_map(|a| (a + 1..groups.len()).map(move |b| (a, b)))
+            .map(|(a, b)| {
+                let gain = 2.0 * (between[a][b] - degrees[a] * degrees[b]);
+                // Weighing the gain by how alike the groups are in size keeps one group from
+                // swallowing the wheel, as in Wakita and Tsurumi's consolidation ratio.
+                let balance = if degrees[a].max(degrees[b]) > 0.0 {
+                    degrees[a].min(degrees[b]) / degrees[a].max(degrees[b])
+                } else {
+                    1.0
+                };
+                (between[a][b] > 0.0, gain * balance, a, b)
+            })
+            .max_by(|x, y| {
+                (x.0, x.1)
+                    .partial_cmp(&(y.0, y.1))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    // The first pair wins between equals.
+                    .then((y.2, y.3).cmp(&(x.2, x.3)))
+            });
+        let Some((true, gain, a, b)) = best else {
+            break;
+        };
+        if gain <= 0.0 {
+            break;
+        }
+        let merged = groups.remove(b);
+        groups[a].extend(merged);
+        let row = between.remove(b);
+        for (i, weight) in row.into_iter().enumerate() {
+            between[a][i] += weight;
+        }
+        for row in &mut between {
+            let weight = row.remove(b);
+            row[a] += weight;
+        }
+        let degree = degrees.remove(b);
+        degrees[a] += degree;
+    }
+
+    let keys = wheel.scales().count();
+    let mut labels = vec![None; keys];
+    for (label, group) in groups.iter().enumerate() {
+        for key in group {
+            labels[key.position()] = Some(label);
+        }
+    }
+    refine(&weights, &mut labels);
+    let scales: Vec<Scale> = wheel.scales().collect();
+    let mut groups: Vec<Vec<Scale>> = (0..groups.len())
+        .map(|label| {
+            scales
+                .iter()
+                .copied()
+                .filter(|key| labels[key.position()] == Some(label))
+                .collect()
+        })
+        .collect();
+    for group in &mut groups {
+        group.sort_by_key(|key| key.position());
+        // Start wrapping groups like 12A, 12B, 1A after the widest gap between their keys.
+        let start = (0..group.len())
+            .max_by_key(|&i| {
+                let previous = group[(i + group.len() - 1) % group.len()].position();
+                (
+                    (group[i].position() + keys - previous - 1) % keys,
+                    usize::MAX - i,
+                )
+            })
+            .unwrap_or(0);
+        group.rotate_left(start);
+    }
+    groups.sort_by_key(|group| group.first().map(|key| key.position()));
+    groups
+}
@@ -0,0 +1,137 @@
+use crate::error::{Error, Result};
+use crate::midi::send;
+use crate::render::Notation;
+use crate::scale::Scale;
+use camelol_core::KEY_COUNT;
+use midly::num::u7;
+use midly::MidiMessage;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// The kind of message a key is sent as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMessage {
+    ProgramChange,
+    /// A control change on the given controller number.
+    ControlChange(u8),
+}
+
+/// Parses `program` or `cc=N`.
+impl FromStr for KeyMessage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            None if s == "program" => Ok(KeyMessage::ProgramChange),
+            Some(("cc", controller)) => controller
+                .parse::<u8>()
+                .ok()
+                .filter(|controller| *controller < 128)
+                .map(KeyMessage::ControlChange)
+                .ok_or_else(|| format!("invalid controller number `{controller}`")),
+            _ => Err(format!("expected `program` or `cc=N`, got `{s}`")),
+        }
+    }
+}
+
+/// How keys are turned into MIDI messages for hardware to follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMapping {
+    /// MIDI channel from 0 to 15.
+    pub channel: u8,
+    pub message: KeyMessage,
+    /// Program or controller value sent for each key, indexed by [`Scale::position`]. Defaults
+    /// to the position itself, so 1A is 0 and 12B is 23.
+    values: [u8; KEY_COUNT],
+}
+
+impl KeyMapping {
+    pub fn new(channel: u8, message: KeyMessage) -> Self {
+        Self {
+            channel,
+            message,
+            values: std::array::from_fn(|position| position as u8),
+        }
+    }
+
+    /// Reads value overrides from a file with one `<key> <value>` pair per line. Blank lines
+    /// and lines starting with `#` are skipped.
+    pub fn load_values(&mut self, map: &str, notation: &Notation) -> Result<()> {
+        for (number, line) in map.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || Error::InvalidHistory {
+                line: number + 1,
+                key: line.to_string(),
+            };
+            let (key, value) = line.rsplit_once(char::is_whitespace).ok_or_else(invalid)?;
+            let key = notation.parse(key).map_err(|_| invalid())?;
+            let value = value
+                .parse::<u8>()
+                .ok()
+                .filter(|value| *value < 128)
+                .ok_or_else(invalid)?;
+            if let Some(slot) = self.values.get_mut(key.position()) {
+                *slot = value;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn value(&self, key: Scale) -> u8 {
+        self.values.get(key.position()).copied().unwrap_or(0)
+    }
+
+    /// Sends the message for `key`.
+    pub fn send(&self, output: &mut impl Write, key: Scale) -> io::Result<()> {
+        let value = u7::new(self.value(key));
+        let message = match self.message {
+            KeyMessage::ProgramChange => MidiMessage::ProgramChange { program: value },
+            KeyMessage::ControlChange(controller) => MidiMessage::Controller {
+                controller: u7::new(controller),
+                value,
+            },
+        };
+        send(output, self.channel, message)
+    }
+}
+
+/// A MIDI output that follows the current and suggested key, sending a message whenever
+/// either changes.
+pub struct KeyOutput {
+    output: Box<dyn Write + Send>,
+    current: Option<KeyMapping>,
+    suggested: Option<KeyMapping>,
+    last: (Option<Scale>, Option<Scale>),
+}
+
+impl KeyOutput {
+    pub fn new(
+        output: Box<dyn Write + Send>,
+        current: Option<KeyMapping>,
+        suggested: Option<KeyMapping>,
+    ) -> Self {
+        Self {
+            output,
+            current,
+            suggested,
+            last: (None, None),
+        }
+    }
+
+    pub fn update(&mut self, current: Scale, suggested: Option<Scale>) -> io::Result<()> {
+        if let Some(mapping) = &self.current {
+            if self.last.0 != Some(current) {
+                mapping.send(&mut self.output, current)?;
+            }
+        }
+        if let (Some(mapping), Some(suggested)) = (&self.suggested, suggested) {
+            if self.last.1 != Some(suggested) {
+                mapping.send(&mut self.output, suggested)?;
+            }
+        }
+        self.last = (Some(current), suggested.or(self.last.1));
+        Ok(())
+    }
+}
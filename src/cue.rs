@@ -0,0 +1,52 @@
+use crate::library::Track;
+use std::fmt::Write;
+
+/// CUE sheets count time in CD frames.
+const FRAMES_PER_SECOND: f64 = 75.0;
+
+/// Formats seconds as the `mm:ss:ff` of a CUE sheet index.
+fn cue_time(seconds: f64) -> String {
+    let frames = (seconds * FRAMES_PER_SECOND).round() as u64;
+    let seconds = frames / 75;
+    format!("{:02}:{:02}:{:02}", seconds / 60, seconds % 60, frames % 75)
+}
+
+/// Quotes `text` for a CUE sheet, which has no way to escape quotes.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "'"))
+}
+
+/// The file type CUE sheets expect for an audio file.
+fn file_type(file: &str) -> &'static str {
+    match file
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+    {
+        Some(extension) if extension == "mp3" => "MP3",
+        Some(extension) if extension == "aif" || extension == "aiff" => "AIFF",
+        _ => "WAVE",
+    }
+}
+
+/// Writes a CUE sheet splitting a recording of the mix in `file` at each of the tracks, played
+/// in order with `overlap` seconds of each transition shared by both tracks. Returns `None` if
+/// a track other than the last has no duration, since the tracks after it can't be placed.
+pub fn cue_sheet(tracks: &[Track], title: &str, file: &str, overlap: f64) -> Option<String> {
+    let mut cue = String::new();
+    let _ = writeln!(cue, "TITLE {}", quoted(title));
+    let _ = writeln!(cue, "FILE {} {}", quoted(file), file_type(file));
+    let mut start = 0.0;
+    for (number, track) in tracks.iter().enumerate() {
+        let (artist, title) = track.artist_and_title();
+        let _ = writeln!(cue, "  TRACK {:02} AUDIO", number + 1);
+        let _ = writeln!(cue, "    TITLE {}", quoted(title));
+        if let Some(artist) = artist {
+            let _ = writeln!(cue, "    PERFORMER {}", quoted(artist));
+        }
+        let _ = writeln!(cue, "    INDEX 01 {}", cue_time(start));
+        if number + 1 < tracks.len() {
+            start += (track.duration? - overlap).max(0.0);
+        }
+    }
+    Some(cue)
+}
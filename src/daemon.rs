@@ -0,0 +1,40 @@
+use crate::engine::{Engine, DEFAULT_SEARCH_TIME};
+use crate::library::TrackLibrary;
+use crate::rpc::answer_lines;
+use std::fs;
+use std::io::{self, BufReader};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+fn handle_client(engine: &Engine, library: &TrackLibrary, stream: UnixStream) -> io::Result<()> {
+    let writer = stream.try_clone()?;
+    answer_lines(engine, library, BufReader::new(stream), writer)
+}
+
+/// Answers newline-delimited JSON-RPC requests on a unix socket at `socket` until the process is
+/// stopped, with `library` kept loaded for the track queries. Each connection is served on its
+/// own thread. Path queries search for at most the engine's
+/// [`search_time`](Engine::search_time), or [`DEFAULT_SEARCH_TIME`] without one.
+pub fn run_daemon(mut engine: Engine, library: TrackLibrary, socket: &Path) -> io::Result<()> {
+    engine.search_time.get_or_insert(DEFAULT_SEARCH_TIME);
+    if socket.exists() {
+        fs::remove_file(socket)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    let engine = Arc::new(engine);
+    let library = Arc::new(library);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let engine = Arc::clone(&engine);
+        let library = Arc::clone(&library);
+        thread::spawn(move || {
+            if let Err(error) = handle_client(&engine, &library, stream) {
+                tracing::warn!(%error, "client connection failed");
+            }
+        });
+    }
+    Ok(())
+}
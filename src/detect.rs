@@ -0,0 +1,170 @@
+use crate::scale::{Scale, ScaleKind};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+/// Krumhansl-Kessler key profiles: how strongly each pitch class above the tonic is felt to
+/// belong to a major or minor key.
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+/// Temperley's profiles, from how often each pitch class appears in classical pieces.
+const TEMPERLEY_MAJOR_PROFILE: [f64; 12] =
+    [5.0, 2.0, 3.5, 2.0, 4.5, 4.0, 2.0, 4.5, 2.0, 3.5, 1.5, 4.0];
+const TEMPERLEY_MINOR_PROFILE: [f64; 12] =
+    [5.0, 2.0, 3.5, 4.5, 2.0, 4.0, 2.0, 4.5, 3.5, 2.0, 1.5, 4.0];
+/// Sha'ath's profiles from KeyFinder, fitted to electronic dance music.
+const SHAATH_MAJOR_PROFILE: [f64; 12] = [
+    7.239, 3.504, 3.584, 2.845, 5.819, 4.559, 2.448, 6.995, 3.391, 4.556, 4.074, 4.459,
+];
+const SHAATH_MINOR_PROFILE: [f64; 12] = [
+    7.003, 3.144, 4.359, 5.404, 3.672, 4.090, 3.908, 6.200, 3.634, 2.872, 5.355, 3.832,
+];
+
+/// The key profiles a histogram can be matched against. Each fits some music better than the
+/// others, so where they disagree the key is worth checking by ear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyProfile {
+    /// Krumhansl and Kessler's, from listeners rating how well each note fits a key.
+    #[default]
+    Krumhansl,
+    /// Temperley's, from classical scores.
+    Temperley,
+    /// Sha'ath's, tuned on electronic dance music, as KeyFinder uses.
+    Shaath,
+}
+
+impl KeyProfile {
+    pub const ALL: [KeyProfile; 3] = [
+        KeyProfile::Krumhansl,
+        KeyProfile::Temperley,
+        KeyProfile::Shaath,
+    ];
+
+    /// The major and the minor profile, from the tonic up.
+    fn weights(self) -> [(ScaleKind, &'static [f64; 12]); 2] {
+        let (major, minor) = match self {
+            KeyProfile::Krumhansl => (&MAJOR_PROFILE, &MINOR_PROFILE),
+            KeyProfile::Temperley => (&TEMPERLEY_MAJOR_PROFILE, &TEMPERLEY_MINOR_PROFILE),
+            KeyProfile::Shaath => (&SHAATH_MAJOR_PROFILE, &SHAATH_MINOR_PROFILE),
+        };
+        [(ScaleKind::Major, major), (ScaleKind::Minor, minor)]
+    }
+}
+
+impl Display for KeyProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            KeyProfile::Krumhansl => "krumhansl",
+            KeyProfile::Temperley => "temperley",
+            KeyProfile::Shaath => "shaath",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct KeyEstimate {
+    pub key: Scale,
+    /// Correlation of the pitch histogram with the key's profile, from -1 to 1.
+    pub correlation: f64,
+}
+
+/// Pearson correlation of `a` with `b`, or 0 when either is flat and nothing correlates.
+fn correlation(a: &[f64; 12], b: impl Fn(usize) -> f64) -> f64 {
+    let mean_a = a.iter().sum::<f64>() / 12.0;
+    let mean_b = (0..12).map(&b).sum::<f64>() / 12.0;
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (i, a) in a.iter().enumerate() {
+        let (da, db) = (a - mean_a, b(i) - mean_b);
+        covariance += da * db;
+        variance_a += da * da;
+        variance_b += db * db;
+    }
+    if variance_a == 0.0 || variance_b == 0.0 {
+        return 0.0;
+    }
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+/// Ranks every key by how well `histogram`, the total duration of each pitch class from C up,
+/// fits its Krumhansl profile. Best first, and empty if the histogram holds no notes at all.
+pub fn rank_keys(histogram: &[f64; 12]) -> Vec<KeyEstimate> {
+    rank_keys_with(histogram, KeyProfile::default())
+}
+
+/// Like [`rank_keys`], matching `histogram` against `profile`.
+pub fn rank_keys_with(histogram: &[f64; 12], profile: KeyProfile) -> Vec<KeyEstimate> {
+    if histogram.iter().all(|&weight| weight == 0.0) {
+        return vec![];
+    }
+
+    let mut estimates = (0..12)
+        .flat_map(|tonic| {
+            profile.weights().map(|(kind, profile)| KeyEstimate {
+                key: Scale::from_tonic(tonic, kind),
+                correlation: correlation(histogram, |i| profile[(i + 12 - tonic) % 12]),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    estimates.sort_by(|a, b| {
+        b.correlation
+            .partial_cmp(&a.correlation)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.key.position().cmp(&b.key.position()))
+    });
+    estimates
+}
+
+/// The key that best fits `histogram`, see [`rank_keys`].
+pub fn detect_key(histogram: &[f64; 12]) -> Option<KeyEstimate> {
+    rank_keys(histogram).into_iter().next()
+}
+
+/// The key that best fits `histogram` under `profile`, see [`rank_keys_with`].
+pub fn detect_key_with(histogram: &[f64; 12], profile: KeyProfile) -> Option<KeyEstimate> {
+    rank_keys_with(histogram, profile).into_iter().next()
+}
+
+/// The key every profile finds, and the one most of them agree on.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyConsensus {
+    /// The key found by the most profiles. Ties go to the key they fit better on average.
+    pub key: Scale,
+    /// Share of the profiles finding [`KeyConsensus::key`], from 0 to 1.
+    pub agreement: f64,
+    /// The best key under each profile, in the order of [`KeyProfile::ALL`].
+    pub estimates: Vec<(KeyProfile, KeyEstimate)>,
+}
+
+/// Detects the key of `histogram` under every profile, or `None` if it holds no notes at all.
+pub fn key_consensus(histogram: &[f64; 12]) -> Option<KeyConsensus> {
+    let estimates: Vec<(KeyProfile, KeyEstimate)> = KeyProfile::ALL
+        .into_iter()
+        .map(|profile| Some((profile, detect_key_with(histogram, profile)?)))
+        .collect::<Option<_>>()?;
+    // Votes and total correlation of every key found.
+    let mut votes: Vec<(Scale, usize, f64)> = vec![];
+    for (_, estimate) in &estimates {
+        match votes.iter_mut().find(|(key, ..)| *key == estimate.key) {
+            Some((_, count, total)) => {
+                *count += 1;
+                *total += estimate.correlation;
+            }
+            None => votes.push((estimate.key, 1, estimate.correlation)),
+        }
+    }
+    let (key, count, _) = votes.into_iter().max_by(|a, b| {
+        a.1.cmp(&b.1)
+            .then_with(|| (a.2 / a.1 as f64).total_cmp(&(b.2 / b.1 as f64)))
+    })?;
+    Some(KeyConsensus {
+        key,
+        agreement: count as f64 / estimates.len() as f64,
+        estimates,
+    })
+}
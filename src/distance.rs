@@ -0,0 +1,77 @@
+use crate::scale::{make_nodes_on, Scale};
+use crate::wheel::Wheel;
+use camelol_core::{DISTANCES, KEY_COUNT, TRANSITION_COUNT, WHEEL_SIZE};
+
+/// Shortest transition counts between every pair of scales, computed once up front.
+#[derive(Debug, Clone)]
+pub struct DistanceMatrix {
+    scales: Vec<Scale>,
+    size: usize,
+    distances: Vec<Option<usize>>,
+}
+
+impl DistanceMatrix {
+    /// The matrix for `wheel`. The standard wheel with every transition is read from the table
+    /// built at compile time, so it takes no search.
+    pub fn new(wheel: &Wheel) -> Self {
+        let standard = wheel.size() == WHEEL_SIZE
+            && wheel.graph().edge_count() == KEY_COUNT * TRANSITION_COUNT;
+        if standard {
+            return Self::standard();
+        }
+        let size = wheel
+            .scales()
+            .map(|scale| scale.position() + 1)
+            .max()
+            .unwrap_or(0);
+        let mut distances = vec![None; size * size];
+
+        for source in wheel.scales() {
+            let Ok(row_distances) = wheel.distances_from(source) else {
+                continue;
+            };
+            let row = source.position() * size;
+            for (target, distance) in row_distances.into_iter().enumerate().take(size) {
+                distances[row + target] = distance;
+            }
+        }
+
+        Self {
+            scales: wheel.scales().collect(),
+            size,
+            distances,
+        }
+    }
+
+    /// The matrix for the standard wheel, [`Wheel::new`].
+    pub fn standard() -> Self {
+        Self {
+            scales: make_nodes_on(WHEEL_SIZE),
+            size: KEY_COUNT,
+            distances: DISTANCES
+                .iter()
+                .flatten()
+                .map(|&distance| distance.map(usize::from))
+                .collect(),
+        }
+    }
+
+    /// Every scale of the wheel the matrix was computed for.
+    pub fn scales(&self) -> impl Iterator<Item = Scale> + '_ {
+        self.scales.iter().copied()
+    }
+
+    /// Number of transitions on the shortest path from `a` to `b`, or `None` if `b` can't be
+    /// reached from `a` or either key isn't on the wheel.
+    pub fn distance(&self, a: Scale, b: Scale) -> Option<usize> {
+        if a.position() >= self.size || b.position() >= self.size {
+            return None;
+        }
+        self.distances[a.position() * self.size + b.position()]
+    }
+
+    /// The longest shortest path between any two connected scales.
+    pub fn diameter(&self) -> usize {
+        self.distances.iter().flatten().copied().max().unwrap_or(0)
+    }
+}
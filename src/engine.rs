@@ -0,0 +1,209 @@
+use crate::chord::{mashup, Mashup};
+use crate::color::{wheel_color, Rgb};
+use crate::distance::DistanceMatrix;
+use crate::error::{Error, Result};
+use crate::scale::{ModalKey, Scale};
+use crate::search::{PathCost, SearchLimit};
+use crate::suggest::{compatibility, modal_compatibility, suggest, Suggestion};
+use crate::transition::{Interval, ScaleTransition};
+use crate::wheel::Wheel;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathSummary {
+    pub cost: u64,
+    pub keys: Vec<Scale>,
+    /// The wheel color of every key, like `#86EAD9`.
+    pub colors: Vec<Rgb>,
+    pub transitions: Vec<ScaleTransition>,
+    /// The interval between the tonics of every step.
+    pub intervals: Vec<Interval>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Neighbor {
+    pub key: Scale,
+    pub color: Rgb,
+    pub transition: ScaleTransition,
+    pub interval: Interval,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Reachable {
+    pub key: Scale,
+    pub color: Rgb,
+    pub steps: usize,
+}
+
+/// A key between two others, see [`Wheel::bridges`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bridge {
+    pub key: Scale,
+    pub color: Rgb,
+    /// Transitions from the first key to it.
+    pub from_steps: usize,
+    /// Transitions from it to the second key.
+    pub to_steps: usize,
+}
+
+/// Paths a query finds when it doesn't say how many.
+pub const DEFAULT_PATH_COUNT: usize = 10;
+/// Suggestions a query gives when it doesn't say how many.
+pub const DEFAULT_SUGGESTION_COUNT: usize = 5;
+/// Most paths one query can ask for, since every path takes longer to find than the one
+/// before it.
+pub const MAX_PATH_COUNT: usize = 100;
+/// How long a path query of a server or daemon searches before it fails, for engines without a
+/// [`search_time`](Engine::search_time) of their own, so no request keeps it busy forever.
+pub const DEFAULT_SEARCH_TIME: Duration = Duration::from_secs(10);
+
+pub(crate) fn default_path_count() -> usize {
+    DEFAULT_PATH_COUNT
+}
+
+pub(crate) fn default_suggestion_count() -> usize {
+    DEFAULT_SUGGESTION_COUNT
+}
+
+/// The wheel with its distance matrix, answering queries in serializable form for the server
+/// and daemon front-ends.
+#[derive(Debug, Clone)]
+pub struct Engine {
+    pub wheel: Wheel,
+    pub distances: DistanceMatrix,
+    /// How long a path query may search before it fails with [`Error::SearchStopped`], without
+    /// a limit if `None`.
+    ///
+    /// [`Error::SearchStopped`]: crate::Error::SearchStopped
+    pub search_time: Option<Duration>,
+}
+
+impl Engine {
+    pub fn new(wheel: Wheel) -> Self {
+        let distances = DistanceMatrix::new(&wheel);
+        Self {
+            wheel,
+            distances,
+            search_time: None,
+        }
+    }
+
+    /// The `n` shortest paths, up to [`MAX_PATH_COUNT`].
+    pub fn paths(&self, from: Scale, to: Scale, n: usize) -> Result<Vec<PathSummary>> {
+        if n > MAX_PATH_COUNT {
+            return Err(Error::TooManyPaths {
+                requested: n,
+                max: MAX_PATH_COUNT,
+            });
+        }
+        let limit = self
+            .search_time
+            .map_or_else(SearchLimit::default, SearchLimit::within);
+        Ok(self
+            .wheel
+            .paths_within(
+                from,
+                to,
+                n,
+                |from, transition, to| PathCost::Hops.step(from, transition, to),
+                &limit,
+            )?
+            .into_iter()
+            .map(|path| PathSummary {
+                cost: path.cost,
+                intervals: path.intervals(),
+                colors: path.path.iter().copied().map(wheel_color).collect(),
+                keys: path.path,
+                transitions: path.transition_path,
+            })
+            .collect())
+    }
+
+    pub fn neighbors(&self, from: Scale) -> Result<Vec<Neighbor>> {
+        Ok(self
+            .wheel
+            .neighbors(from)?
+            .into_iter()
+            .map(|(transition, key)| Neighbor {
+                key,
+                color: wheel_color(key),
+                transition,
+                interval: Interval::between(from, key),
+            })
+            .collect())
+    }
+
+    /// The keys one transition away that lead to `to`, with the transition from each.
+    pub fn predecessors(&self, to: Scale) -> Result<Vec<Neighbor>> {
+        Ok(self
+            .wheel
+            .predecessors(to)?
+            .into_iter()
+            .map(|(transition, key)| Neighbor {
+                key,
+                color: wheel_color(key),
+                transition,
+                interval: Interval::between(key, to),
+            })
+            .collect())
+    }
+
+    pub fn reachable(&self, from: Scale, steps: usize) -> Result<Vec<Reachable>> {
+        Ok(self
+            .wheel
+            .reachable(from, steps)?
+            .into_iter()
+            .map(|(key, steps)| Reachable {
+                key,
+                color: wheel_color(key),
+                steps,
+            })
+            .collect())
+    }
+
+    pub fn bridges(&self, from: Scale, to: Scale, steps: usize) -> Result<Vec<Bridge>> {
+        Ok(self
+            .wheel
+            .bridges(from, to, steps)?
+            .into_iter()
+            .map(|(key, from_steps, to_steps)| Bridge {
+                key,
+                color: wheel_color(key),
+                from_steps,
+                to_steps,
+            })
+            .collect())
+    }
+
+    pub fn distance(&self, from: Scale, to: Scale) -> Option<usize> {
+        self.distances.distance(from, to)
+    }
+
+    /// Compatibility score between two keys, see [`compatibility`].
+    pub fn compatibility(&self, from: Scale, to: Scale) -> f64 {
+        compatibility(self.distance(from, to))
+    }
+
+    /// Compatibility score between two keys in any mode, see [`modal_compatibility`].
+    pub fn modal_compatibility(&self, from: ModalKey, to: ModalKey) -> f64 {
+        modal_compatibility(&self.distances, from, to)
+    }
+
+    /// Whether two keys are the same or one transition apart.
+    pub fn compatible(&self, from: Scale, to: Scale) -> bool {
+        self.distance(from, to)
+            .is_some_and(|distance| distance <= 1)
+    }
+
+    /// The notes and chords two keys share, see [`mashup`].
+    pub fn mashup(&self, from: Scale, to: Scale) -> Mashup {
+        mashup(from, to)
+    }
+
+    pub fn suggest(&self, from: Scale, n: usize) -> Vec<Suggestion> {
+        let mut suggestions = suggest(&self.distances, from, None, 0.0);
+        suggestions.truncate(n);
+        suggestions
+    }
+}
@@ -0,0 +1,135 @@
+use crate::error::Result;
+use crate::library::Track;
+use crate::scale::{scale, Scale, ScaleKind};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Converts Engine DJ's key number to a key on the wheel. Engine counts up the circle of
+/// fifths from C major, with each major key followed by its relative minor.
+pub fn engine_key(key: i64) -> Option<Scale> {
+    let key = usize::try_from(key).ok().filter(|&key| key < 24)?;
+    let kind = if key % 2 == 0 {
+        ScaleKind::Major
+    } else {
+        ScaleKind::Minor
+    };
+    // C major and A minor are 8B and 8A.
+    Some(scale((7 + key / 2) % 12, kind))
+}
+
+/// The database inside an Engine library folder and the folder, given either of them.
+fn library_paths(path: &Path) -> (PathBuf, PathBuf) {
+    if path.is_dir() {
+        (path.join("Database2").join("m.db"), path.to_path_buf())
+    } else {
+        let folder = path
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        (path.to_path_buf(), folder.to_path_buf())
+    }
+}
+
+/// Reads a track from a row of `id, title, artist, filename, key, bpm, path, length, genre`,
+/// where the path is relative to the library `folder` and the length is in seconds.
+fn track_from_row(row: &rusqlite::Row, folder: &Path) -> rusqlite::Result<Option<Track>> {
+    let (title, artist, filename): (Option<String>, Option<String>, Option<String>) =
+        (row.get(1)?, row.get(2)?, row.get(3)?);
+    let Some(key) = row.get::<_, Option<i64>>(4)?.and_then(engine_key) else {
+        return Ok(None);
+    };
+    let name = match (artist, title) {
+        (Some(artist), Some(title)) if !artist.is_empty() => format!("{artist} - {title}"),
+        (_, Some(title)) if !title.is_empty() => title,
+        _ => match filename {
+            Some(filename) => Path::new(&filename)
+                .file_stem()
+                .map_or(filename.clone(), |stem| stem.to_string_lossy().into_owned()),
+            None => return Ok(None),
+        },
+    };
+    Ok(Some(Track {
+        name,
+        key,
+        confidence: None,
+        tempo: row.get::<_, Option<f64>>(5)?.filter(|&bpm| bpm > 0.0),
+        path: row
+            .get::<_, Option<String>>(6)?
+            .map(|path| folder.join(path)),
+        duration: row.get::<_, Option<f64>>(7)?.filter(|&length| length > 0.0),
+        energy: None,
+        genre: row
+            .get::<_, Option<String>>(8)?
+            .filter(|genre| !genre.is_empty()),
+        tags: vec![],
+        segments: vec![],
+        modified: None,
+    }))
+}
+
+/// Reads the analyzed tracks of an Engine DJ library, the `Engine Library` folder or its
+/// `m.db`. Tracks come in the library's order or, given a `playlist`, in the playlist's order.
+/// Returns `None` if there's no playlist with that name.
+///
+/// This reads the database of Engine DJ 2 and later. Crates from Engine Prime 1 databases
+/// aren't supported.
+pub fn read_engine_library(path: &Path, playlist: Option<&str>) -> Result<Option<Vec<Track>>> {
+    let (database, folder) = library_paths(path);
+    let connection = Connection::open_with_flags(database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut statement = connection.prepare(
+        "SELECT id, title, artist, filename, key, COALESCE(bpmAnalyzed, bpm), path, length, genre
+            FROM Track",
+    )?;
+    let mut tracks = HashMap::new();
+    let mut order = vec![];
+    let mut rows = statement.query(())?;
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        if let Some(track) = track_from_row(row, &folder)? {
+            order.push(id);
+            tracks.insert(id, track);
+        }
+    }
+
+    let ids = match playlist {
+        Some(name) => {
+            let Some(list) = connection
+                .query_row("SELECT id FROM Playlist WHERE title = ?1", [name], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .optional()?
+            else {
+                return Ok(None);
+            };
+            playlist_track_ids(&connection, list)?
+        }
+        None => order,
+    };
+    let tracks: Vec<Track> = ids.iter().filter_map(|id| tracks.remove(id)).collect();
+    tracing::info!(tracks = tracks.len(), "read the Engine DJ library");
+    Ok(Some(tracks))
+}
+
+/// Engine keeps each playlist as a linked list of entries, each pointing to the next.
+fn playlist_track_ids(connection: &Connection, list: i64) -> Result<Vec<i64>> {
+    let mut statement = connection
+        .prepare("SELECT id, trackId, nextEntityId FROM PlaylistEntity WHERE listId = ?1")?;
+    let entries: HashMap<i64, (i64, i64)> = statement
+        .query_map([list], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+        .collect::<rusqlite::Result<_>>()?;
+    let pointed_to: HashSet<i64> = entries.values().map(|&(_, next)| next).collect();
+    let mut next = entries.keys().copied().find(|id| !pointed_to.contains(id));
+
+    let mut ids = vec![];
+    // Entries are visited at most once, so a broken list can't loop forever.
+    let mut visited = HashSet::new();
+    while let Some(id) = next.filter(|id| visited.insert(*id)) {
+        let Some(&(track, following)) = entries.get(&id) else {
+            break;
+        };
+        ids.push(track);
+        next = Some(following);
+    }
+    Ok(ids)
+}
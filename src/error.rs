@@ -0,0 +1,124 @@
+use crate::scale::Scale;
+use crate::session::ParseSessionError;
+use std::io;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid key `{input}`{}", did_you_mean(.suggestions))]
+    InvalidKey {
+        input: String,
+        /// The valid keys closest to the input, best first.
+        suggestions: Vec<String>,
+    },
+    #[error("{0} is not on the wheel")]
+    KeyNotOnWheel(Scale),
+    #[error("invalid chord `{0}`")]
+    InvalidChord(String),
+    #[error("line {line}: invalid key `{key}`")]
+    InvalidHistory { line: usize, key: String },
+    #[error("line {line}: {message}")]
+    InvalidScript { line: usize, message: String },
+    #[error("line {line}: {message}")]
+    InvalidPlaylist { line: usize, message: String },
+    #[error("line {line}: {message}")]
+    InvalidLibrary { line: usize, message: String },
+    #[error(transparent)]
+    InvalidSession(#[from] ParseSessionError),
+    #[error("the search was stopped before it finished")]
+    SearchStopped,
+    #[error("asked for {requested} paths, but at most {max} can be found at once")]
+    TooManyPaths { requested: usize, max: usize },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[cfg(feature = "audio")]
+    #[error("invalid audio file: {0}")]
+    Audio(#[from] symphonia::core::errors::Error),
+    #[cfg(feature = "audio")]
+    #[error("{}: can't write the key tag, {message}", path.display())]
+    KeyTag {
+        path: std::path::PathBuf,
+        message: String,
+    },
+    #[cfg(any(feature = "audio", feature = "musicbrainz"))]
+    #[error("fpcalc: {0}")]
+    Fpcalc(String),
+    #[cfg(any(feature = "cache", feature = "engine"))]
+    #[error("database: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[cfg(any(feature = "spotify", feature = "beatport", feature = "musicbrainz"))]
+    #[error("{service}: {message}")]
+    Api {
+        service: &'static str,
+        message: String,
+    },
+    #[cfg(feature = "plugin")]
+    #[error("plugin: {0}")]
+    Plugin(String),
+    #[cfg(feature = "gui")]
+    #[error("gui: {0}")]
+    Gui(String),
+    #[cfg(feature = "itunes")]
+    #[error("invalid iTunes library: {0}")]
+    ITunes(#[from] plist::Error),
+    #[cfg(feature = "virtualdj")]
+    #[error("invalid VirtualDJ database: {0}")]
+    VirtualDj(#[from] quick_xml::Error),
+    #[cfg(feature = "midi")]
+    #[error("invalid MIDI file: {0}")]
+    Midi(#[from] midly::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// `, did you mean 8A or 8B?`, or nothing without suggestions.
+pub(crate) fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [only] => format!(", did you mean {only}?"),
+        [rest @ .., last] => format!(", did you mean {} or {last}?", rest.join(", ")),
+    }
+}
+
+/// Most suggestions offered for a typo.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The names of the `candidates` closest to `input`, if they're at most a third of its letters
+/// away, or one for short input. Case, spaces and dashes are ignored and swapped letters count
+/// as one typo. Each candidate is the text to compare and the name to suggest for it, and
+/// several candidates may suggest the same name.
+pub(crate) fn closest(
+    input: &str,
+    candidates: impl IntoIterator<Item = (String, String)>,
+) -> Vec<String> {
+    let normalize = |text: &str| -> String {
+        text.chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_' | ':'))
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let input = normalize(input);
+    let limit = (input.chars().count() / 3).max(1);
+    let matches: Vec<(usize, String)> = candidates
+        .into_iter()
+        .filter_map(|(text, name)| {
+            let text = normalize(&text);
+            let distance = strsim::osa_distance(&input, &text);
+            // Replacing every letter isn't a typo.
+            let longest = input.chars().count().max(text.chars().count());
+            (distance <= limit && distance < longest).then_some((distance, name))
+        })
+        .collect();
+    let Some(best) = matches.iter().map(|(distance, _)| *distance).min() else {
+        return vec![];
+    };
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in matches
+        .into_iter()
+        .filter(|(distance, _)| *distance == best)
+    {
+        if names.len() < MAX_SUGGESTIONS && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
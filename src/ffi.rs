@@ -0,0 +1,140 @@
+//! C interface. Keys are passed around as their wheel position (0 = 1A, 1 = 1B, ..., 23 = 12B),
+//! and functions taking keys return -1 for anything out of range.
+
+use crate::engine::{Engine, PathSummary};
+use crate::scale::Scale;
+use crate::wheel::Wheel;
+use std::ffi::{c_char, CStr};
+use std::sync::OnceLock;
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| Engine::new(Wheel::new()))
+}
+
+fn key(key: i32) -> Option<Scale> {
+    usize::try_from(key).ok().and_then(Scale::from_position)
+}
+
+/// Result of [`camelol_paths`], owned by the caller until passed to [`camelol_paths_free`].
+pub struct CamelolPaths(Vec<PathSummary>);
+
+/// Parses a camelot code like `"8A"`, returning its key or -1.
+///
+/// # Safety
+///
+/// `input` must be a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn camelol_parse_key(input: *const c_char) -> i32 {
+    if input.is_null() {
+        return -1;
+    }
+    CStr::from_ptr(input)
+        .to_str()
+        .ok()
+        .and_then(|input| input.parse::<Scale>().ok())
+        .map_or(-1, |scale| scale.position() as i32)
+}
+
+/// Writes the nul-terminated camelot code of `key_id` into `buffer`, returning the code's length
+/// without the terminator, or -1 if the key is invalid or the buffer is too small.
+///
+/// # Safety
+///
+/// `buffer` must be valid for writes of `length` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn camelol_key_name(key_id: i32, buffer: *mut c_char, length: usize) -> i32 {
+    let Some(scale) = key(key_id) else {
+        return -1;
+    };
+    let name = scale.to_string();
+    if buffer.is_null() || name.len() + 1 > length {
+        return -1;
+    }
+    std::ptr::copy_nonoverlapping(name.as_ptr().cast(), buffer, name.len());
+    *buffer.add(name.len()) = 0;
+    name.len() as i32
+}
+
+/// Number of transitions between two keys, or -1.
+#[no_mangle]
+pub extern "C" fn camelol_distance(from: i32, to: i32) -> i32 {
+    match (key(from), key(to)) {
+        (Some(from), Some(to)) => engine()
+            .distance(from, to)
+            .map_or(-1, |distance| distance as i32),
+        _ => -1,
+    }
+}
+
+/// Compatibility score between two keys from 0 to 1, or -1 for invalid keys.
+#[no_mangle]
+pub extern "C" fn camelol_compatibility(from: i32, to: i32) -> f64 {
+    match (key(from), key(to)) {
+        (Some(from), Some(to)) => engine().compatibility(from, to),
+        _ => -1.0,
+    }
+}
+
+/// The `n` shortest paths between two keys, or null for invalid keys or an `n` over
+/// [`MAX_PATH_COUNT`] (100).
+#[no_mangle]
+pub extern "C" fn camelol_paths(from: i32, to: i32, n: usize) -> *mut CamelolPaths {
+    let paths = match (key(from), key(to)) {
+        (Some(from), Some(to)) => engine().paths(from, to, n),
+        _ => return std::ptr::null_mut(),
+    };
+    match paths {
+        Ok(paths) => Box::into_raw(Box::new(CamelolPaths(paths))),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `paths` must come from [`camelol_paths`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn camelol_paths_count(paths: *const CamelolPaths) -> usize {
+    paths.as_ref().map_or(0, |paths| paths.0.len())
+}
+
+/// Number of keys on the path at `index`, including both ends.
+///
+/// # Safety
+///
+/// `paths` must come from [`camelol_paths`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn camelol_path_length(paths: *const CamelolPaths, index: usize) -> usize {
+    paths
+        .as_ref()
+        .and_then(|paths| paths.0.get(index))
+        .map_or(0, |path| path.keys.len())
+}
+
+/// The key at `step` on the path at `index`, or -1.
+///
+/// # Safety
+///
+/// `paths` must come from [`camelol_paths`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn camelol_path_key(
+    paths: *const CamelolPaths,
+    index: usize,
+    step: usize,
+) -> i32 {
+    paths
+        .as_ref()
+        .and_then(|paths| paths.0.get(index))
+        .and_then(|path| path.keys.get(step))
+        .map_or(-1, |scale| scale.position() as i32)
+}
+
+/// # Safety
+///
+/// `paths` must come from [`camelol_paths`] and not have been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn camelol_paths_free(paths: *mut CamelolPaths) {
+    if !paths.is_null() {
+        drop(Box::from_raw(paths));
+    }
+}
@@ -0,0 +1,462 @@
+use crate::color::{wheel_color, Rgb};
+use crate::engine::Engine;
+use crate::error::{Error, Result};
+use crate::library::{Track, TrackLibrary};
+use crate::matches::{track_match, TrackMatch};
+use crate::playlist::m3u_playlist;
+use crate::rekordbox::rekordbox_xml;
+use crate::render::Notation;
+use crate::scale::{make_nodes_on, Scale, ScaleKind};
+use crate::serato::serato_crate;
+use crate::traktor::traktor_nml;
+use eframe::egui::{
+    self, Align2, Color32, ComboBox, FontId, Id, Panel, Pos2, RichText, ScrollArea, Sense, Stroke,
+    Ui,
+};
+use std::f32::consts::PI;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Scores from which candidates are shown as good or fair mixes into the set, like the
+/// terminal set builder does.
+const GOOD_MIX: f64 = 0.5;
+const FAIR_MIX: f64 = 0.3;
+const GOOD_COLOR: Color32 = Color32::from_rgb(90, 200, 90);
+const FAIR_COLOR: Color32 = Color32::from_rgb(220, 190, 60);
+const POOR_COLOR: Color32 = Color32::from_rgb(220, 80, 70);
+/// Height left below the set for the export controls.
+const EXPORT_HEIGHT: f32 = 120.0;
+/// Radius of a key on the wheel, as a share of the wheel's.
+const KEY_RADIUS: f32 = 0.11;
+
+/// What the set is exported as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Export {
+    Library,
+    M3u,
+    Rekordbox,
+    Traktor,
+    Serato,
+}
+
+impl Export {
+    const ALL: [Self; 5] = [
+        Self::Library,
+        Self::M3u,
+        Self::Rekordbox,
+        Self::Traktor,
+        Self::Serato,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Library => "Track library",
+            Self::M3u => "M3U playlist",
+            Self::Rekordbox => "Rekordbox XML",
+            Self::Traktor => "Traktor NML",
+            Self::Serato => "Serato crate",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Library => "txt",
+            Self::M3u => "m3u",
+            Self::Rekordbox => "xml",
+            Self::Traktor => "nml",
+            Self::Serato => "crate",
+        }
+    }
+
+    /// `set` written in this format, as a playlist named `name` where the format names them.
+    fn contents(self, set: &[Track], name: &str) -> Vec<u8> {
+        match self {
+            Self::Library => set
+                .iter()
+                .map(|track| format!("{track}\n"))
+                .collect::<String>()
+                .into_bytes(),
+            Self::M3u => m3u_playlist(&set.iter().collect::<Vec<_>>()).into_bytes(),
+            Self::Rekordbox => rekordbox_xml(set, name).into_bytes(),
+            Self::Traktor => traktor_nml(set, name).into_bytes(),
+            Self::Serato => serato_crate(set),
+        }
+    }
+}
+
+struct SetBuilder {
+    engine: Engine,
+    notation: Notation,
+    pitch_range: f64,
+    /// The path typed into the library field, loaded from when asked to.
+    library_path: String,
+    tracks: Vec<Track>,
+    /// Indices of the tracks in the set, in order.
+    set: Vec<usize>,
+    /// Only library tracks whose name contains this, ignoring case, are listed.
+    filter: String,
+    export: Export,
+    export_path: String,
+    playlist: String,
+    /// What the last load or export did, and whether it failed.
+    status: Option<(String, bool)>,
+}
+
+impl SetBuilder {
+    fn new(engine: Engine, notation: Notation, library: Option<PathBuf>, pitch_range: f64) -> Self {
+        let mut builder = Self {
+            engine,
+            notation,
+            pitch_range,
+            library_path: String::new(),
+            tracks: vec![],
+            set: vec![],
+            filter: String::new(),
+            export: Export::M3u,
+            export_path: "set.m3u".to_string(),
+            playlist: "camelol".to_string(),
+            status: None,
+        };
+        if let Some(library) = library {
+            builder.load(&library);
+        }
+        builder
+    }
+
+    /// Replaces the library with the one at `path` and empties the set, or keeps both and
+    /// shows why it couldn't be read.
+    fn load(&mut self, path: &Path) {
+        self.library_path = path.display().to_string();
+        match TrackLibrary::load(path, &self.notation) {
+            Ok(library) => {
+                self.tracks = library.tracks().to_vec();
+                self.set.clear();
+                self.status = Some((format!("Loaded {} tracks", self.tracks.len()), false));
+            }
+            Err(error) => self.status = Some((error.to_string(), true)),
+        }
+    }
+
+    fn save(&mut self) {
+        let set: Vec<Track> = self
+            .set
+            .iter()
+            .map(|&track| self.tracks[track].clone())
+            .collect();
+        let path = PathBuf::from(&self.export_path);
+        self.status = Some(
+            match fs::write(&path, self.export.contents(&set, &self.playlist)) {
+                Ok(()) => (format!("Saved the set to {}", path.display()), false),
+                Err(error) => (format!("{}: {error}", path.display()), true),
+            },
+        );
+    }
+
+    /// How well the last track of the set mixes into `track`, or `None` if it doesn't or the
+    /// set is empty.
+    fn candidate_match(&self, track: usize) -> Option<TrackMatch> {
+        let last = &self.tracks[*self.set.last()?];
+        self.mixes(last, track)
+    }
+
+    fn mixes(&self, from: &Track, to: usize) -> Option<TrackMatch> {
+        track_match(
+            from,
+            &self.tracks[to],
+            to,
+            &self.engine.distances,
+            self.pitch_range,
+        )
+    }
+
+    fn add(&mut self, track: usize) {
+        if track < self.tracks.len() && !self.set.contains(&track) {
+            self.set.push(track);
+        }
+    }
+
+    fn track_label(&self, track: &Track) -> String {
+        let tempo = track
+            .tempo
+            .map_or("-".to_string(), |tempo| format!("{tempo:.1}"));
+        format!(
+            "{}  {tempo} BPM  {}",
+            self.notation.label(track.key),
+            track.name
+        )
+    }
+
+    fn library_bar(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Library");
+            ui.text_edit_singleline(&mut self.library_path);
+            if ui.button("Load").clicked() {
+                let path = PathBuf::from(&self.library_path);
+                self.load(&path);
+            }
+            match &self.status {
+                Some((status, true)) => ui.colored_label(POOR_COLOR, status),
+                Some((status, false)) => ui.label(status),
+                None => ui.weak("Type a library's path or drop one on the window"),
+            };
+        });
+    }
+
+    /// The library's tracks outside the set, colored by how well the set's last track mixes
+    /// into them, to be dragged onto the wheel.
+    fn candidates(&mut self, ui: &mut Ui) {
+        ui.heading(match self.set.last() {
+            Some(&last) => format!("Mixing out of {}", self.tracks[last].name),
+            None => "Drag a track onto the wheel".to_string(),
+        });
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        ui.separator();
+        let filter = self.filter.to_lowercase();
+        ScrollArea::vertical().show(ui, |ui| {
+            for (index, track) in self.tracks.iter().enumerate() {
+                if self.set.contains(&index) || !track.name.to_lowercase().contains(&filter) {
+                    continue;
+                }
+                let label = self.track_label(track);
+                let text = match (self.set.is_empty(), self.candidate_match(index)) {
+                    (true, _) => RichText::new(label),
+                    (false, None) => RichText::new(format!("   -  {label}")).weak(),
+                    (false, Some(found)) => RichText::new(format!("{:.2}  {label}", found.score))
+                        .color(score_color(found.score)),
+                };
+                ui.dnd_drag_source(Id::new(("candidate", index)), index, |ui| {
+                    ui.label(text);
+                });
+            }
+        });
+    }
+
+    /// The set in order, with how well every track mixes into the next, and where to export
+    /// it.
+    fn set_list(&mut self, ui: &mut Ui) {
+        ui.heading(format!("Set ({} tracks)", self.set.len()));
+        ui.separator();
+        let mut moved = None;
+        let mut removed = None;
+        ScrollArea::vertical()
+            .max_height((ui.available_height() - EXPORT_HEIGHT).max(0.0))
+            .show(ui, |ui| {
+                for (position, &track) in self.set.iter().enumerate() {
+                    if let Some(previous) =
+                        position.checked_sub(1).map(|previous| self.set[previous])
+                    {
+                        let text = match self.mixes(&self.tracks[previous], track) {
+                            Some(found) => {
+                                let pitch = found
+                                    .pitch
+                                    .map_or(String::new(), |pitch| format!(", pitch {pitch:+.1}%"));
+                                RichText::new(format!(
+                                    "    ↓ {:.2}, keys {:.2}{pitch}",
+                                    found.score, found.compatibility
+                                ))
+                                .color(score_color(found.score))
+                            }
+                            None => RichText::new("    ↓ doesn't mix").color(POOR_COLOR),
+                        };
+                        ui.label(text.small());
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "{:>3}  {}",
+                            position + 1,
+                            self.track_label(&self.tracks[track])
+                        ));
+                        if ui.small_button("Up").clicked() && position > 0 {
+                            moved = Some((position, position - 1));
+                        }
+                        if ui.small_button("Down").clicked() && position + 1 < self.set.len() {
+                            moved = Some((position, position + 1));
+                        }
+                        if ui.small_button("Remove").clicked() {
+                            removed = Some(position);
+                        }
+                    });
+                }
+            });
+        if let Some((from, to)) = moved {
+            self.set.swap(from, to);
+        }
+        if let Some(position) = removed {
+            self.set.remove(position);
+        }
+
+        ui.separator();
+        ComboBox::from_label("Format")
+            .selected_text(self.export.name())
+            .show_ui(ui, |ui| {
+                for export in Export::ALL {
+                    if ui
+                        .selectable_value(&mut self.export, export, export.name())
+                        .changed()
+                    {
+                        self.export_path = Path::new(&self.export_path)
+                            .with_extension(export.extension())
+                            .display()
+                            .to_string();
+                    }
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Playlist");
+            ui.text_edit_singleline(&mut self.playlist);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Save to");
+            ui.text_edit_singleline(&mut self.export_path);
+        });
+        if ui
+            .add_enabled(!self.set.is_empty(), egui::Button::new("Export"))
+            .clicked()
+        {
+            self.save();
+        }
+    }
+
+    /// The wheel, with the set's keys joined in order and every key shaded by how well the
+    /// set's last key mixes into it. Tracks dropped on it join the end of the set.
+    fn wheel(&mut self, ui: &mut Ui) {
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::hover());
+        let rect = response.rect;
+        let center = rect.center();
+        let radius = rect.width().min(rect.height()) * 0.42;
+        // Wheel numbers sit where the hours do on a clock, minor keys on the inner ring.
+        let position = |key: Scale| {
+            let angle = PI / 2.0 - (key.index + 1) as f32 * PI / 6.0;
+            let ring = match key.kind {
+                ScaleKind::Major => 1.0,
+                ScaleKind::Minor => 0.62,
+            };
+            center + egui::vec2(angle.cos(), -angle.sin()) * radius * ring
+        };
+
+        let dragged = response.dnd_hover_payload::<usize>().map(|track| *track);
+        let last = self
+            .set
+            .last()
+            .map(|&track| self.tracks[track].outgoing_key());
+        for key in make_nodes_on(self.engine.wheel.size()) {
+            let Rgb(r, g, b) = wheel_color(key);
+            let compatibility = last.map_or(1.0, |last| self.engine.compatibility(last, key));
+            let fill =
+                Color32::from_rgb(r, g, b).gamma_multiply(0.25 + 0.75 * compatibility as f32);
+            painter.circle_filled(position(key), radius * KEY_RADIUS, fill);
+            painter.text(
+                position(key),
+                Align2::CENTER_CENTER,
+                key.to_string(),
+                FontId::proportional(radius * KEY_RADIUS * 0.8),
+                Color32::BLACK,
+            );
+        }
+
+        let keys: Vec<Pos2> = self
+            .set
+            .iter()
+            .map(|&track| position(self.tracks[track].key))
+            .collect();
+        let line = Stroke::new(3.0, ui.visuals().strong_text_color());
+        for pair in keys.windows(2) {
+            painter.line_segment([pair[0], pair[1]], line);
+        }
+        if let Some(&last) = keys.last() {
+            painter.circle_stroke(last, radius * KEY_RADIUS * 1.2, line);
+        }
+
+        if let Some(track) = dragged.filter(|&track| track < self.tracks.len()) {
+            let key = self.tracks[track].incoming_key();
+            painter.circle_stroke(
+                position(key),
+                radius * KEY_RADIUS * 1.35,
+                Stroke::new(3.0, GOOD_COLOR),
+            );
+            let text = match self.candidate_match(track) {
+                Some(found) => format!("{:.2}", found.score),
+                None if self.set.is_empty() => "Start the set".to_string(),
+                None => "Doesn't mix".to_string(),
+            };
+            painter.text(
+                center,
+                Align2::CENTER_CENTER,
+                text,
+                FontId::proportional(radius * 0.12),
+                ui.visuals().strong_text_color(),
+            );
+        }
+        if let Some(track) = response.dnd_release_payload::<usize>() {
+            self.add(*track);
+        }
+    }
+}
+
+impl eframe::App for SetBuilder {
+    fn ui(&mut self, ui: &mut Ui, _frame: &mut eframe::Frame) {
+        let dropped = ui.ctx().input(|input| {
+            input
+                .raw
+                .dropped_files
+                .first()
+                .map(|file| file.path().to_path_buf())
+        });
+        if let Some(path) = dropped {
+            self.load(&path);
+        }
+        Panel::top("library").show(ui, |ui| self.library_bar(ui));
+        Panel::left("candidates")
+            .default_size(360.0)
+            .show(ui, |ui| self.candidates(ui));
+        Panel::right("set")
+            .default_size(420.0)
+            .show(ui, |ui| self.set_list(ui));
+        egui::CentralPanel::default().show(ui, |ui| self.wheel(ui));
+    }
+}
+
+fn score_color(score: f64) -> Color32 {
+    if score >= GOOD_MIX {
+        GOOD_COLOR
+    } else if score >= FAIR_MIX {
+        FAIR_COLOR
+    } else {
+        POOR_COLOR
+    }
+}
+
+/// Opens a window to build a set in by dragging tracks from a library onto the wheel, with
+/// every candidate colored by how well the set's last track mixes into it, scored like
+/// [`best_matches`](crate::best_matches) within `pitch_range` percent, and the set exported
+/// as a track library or a DJ software playlist. Starts with `library` loaded if given, and
+/// loads any library dropped onto the window. Keys are named with `notation`.
+pub fn build_set(
+    engine: Engine,
+    notation: Notation,
+    library: Option<PathBuf>,
+    pitch_range: f64,
+) -> Result<()> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("camelol")
+            .with_inner_size([1280.0, 760.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "camelol",
+        options,
+        Box::new(move |_| {
+            Ok(Box::new(SetBuilder::new(
+                engine,
+                notation,
+                library,
+                pitch_range,
+            )))
+        }),
+    )
+    .map_err(|error| Error::Gui(error.to_string()))
+}
@@ -0,0 +1,145 @@
+use crate::error::Result;
+use crate::library::{find_energy, find_key, Track};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+struct Library {
+    #[serde(rename = "Tracks", default)]
+    tracks: HashMap<String, LibraryTrack>,
+    #[serde(rename = "Playlists", default)]
+    playlists: Vec<Playlist>,
+}
+
+#[derive(Deserialize)]
+struct LibraryTrack {
+    #[serde(rename = "Name")]
+    name: Option<String>,
+    #[serde(rename = "Artist")]
+    artist: Option<String>,
+    #[serde(rename = "Comments")]
+    comments: Option<String>,
+    #[serde(rename = "Grouping")]
+    grouping: Option<String>,
+    #[serde(rename = "BPM")]
+    bpm: Option<u32>,
+    #[serde(rename = "Location")]
+    location: Option<String>,
+    /// In milliseconds.
+    #[serde(rename = "Total Time")]
+    total_time: Option<u64>,
+    #[serde(rename = "Genre")]
+    genre: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Playlist {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Playlist Items", default)]
+    items: Vec<PlaylistItem>,
+}
+
+#[derive(Deserialize)]
+struct PlaylistItem {
+    #[serde(rename = "Track ID")]
+    track_id: u64,
+}
+
+/// Decodes a `file://localhost/Users/...` URL like iTunes writes to a path.
+fn path_from_file_url(url: &str) -> Option<PathBuf> {
+    let path = url
+        .strip_prefix("file://localhost")
+        .or_else(|| url.strip_prefix("file://"))?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| std::str::from_utf8(after.get(..2)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                rest = &after[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = after;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // Windows libraries write drive paths like `/C:/Music`.
+    let path = match path.as_bytes() {
+        [b'/', _, b':', ..] => &path[1..],
+        _ => &path,
+    };
+    Some(PathBuf::from(path))
+}
+
+impl LibraryTrack {
+    /// Music.app has no key or energy fields, so they're taken from wherever tools like Mixed
+    /// In Key write them.
+    fn to_track(&self) -> Option<Track> {
+        let tags = || [&self.comments, &self.grouping].into_iter().flatten();
+        let key = tags().find_map(|tag| find_key(tag))?;
+        let name = match (&self.artist, &self.name) {
+            (Some(artist), Some(name)) => format!("{artist} - {name}"),
+            (None, Some(name)) => name.clone(),
+            _ => return None,
+        };
+        Some(Track {
+            name,
+            key,
+            confidence: None,
+            tempo: self.bpm.filter(|&bpm| bpm > 0).map(f64::from),
+            path: self.location.as_deref().and_then(path_from_file_url),
+            duration: self.total_time.map(|time| time as f64 / 1000.0),
+            energy: tags().find_map(|tag| find_energy(tag)),
+            genre: self.genre.clone().filter(|genre| !genre.is_empty()),
+            tags: vec![],
+            segments: vec![],
+            modified: None,
+        })
+    }
+}
+
+/// Reads the tracks of an iTunes or Music.app `Library.xml` whose comments or grouping name
+/// their key, in the library's order or, given a `playlist`, in the playlist's order.
+/// Returns `None` if there's no playlist with that name.
+pub fn read_itunes_library(path: &Path, playlist: Option<&str>) -> Result<Option<Vec<Track>>> {
+    let library: Library = plist::from_file(path)?;
+    let ids: Vec<u64> = match playlist {
+        Some(name) => {
+            let Some(playlist) = library
+                .playlists
+                .iter()
+                .find(|playlist| playlist.name == name)
+            else {
+                return Ok(None);
+            };
+            playlist.items.iter().map(|item| item.track_id).collect()
+        }
+        None => {
+            let mut ids: Vec<u64> = library
+                .tracks
+                .keys()
+                .filter_map(|id| id.parse().ok())
+                .collect();
+            ids.sort_unstable();
+            ids
+        }
+    };
+    let tracks: Vec<Track> = ids
+        .iter()
+        .filter_map(|id| library.tracks.get(&id.to_string())?.to_track())
+        .collect();
+    tracing::info!(
+        tracks = tracks.len(),
+        without_key = ids.len() - tracks.len(),
+        "read the iTunes library"
+    );
+    Ok(Some(tracks))
+}
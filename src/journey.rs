@@ -0,0 +1,108 @@
+use crate::error::Result;
+use crate::scale::Scale;
+use crate::search::Path;
+use crate::transition::ScaleTransition;
+use crate::wheel::Wheel;
+use petgraph::visit::EdgeRef;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+
+/// How likely a transition is to be picked on a random journey. Small, smooth moves are favoured
+/// over the more adventurous jumps.
+pub fn default_transition_weight(transition: ScaleTransition) -> f64 {
+    match transition {
+        ScaleTransition::Vertical => 3.0,
+        ScaleTransition::ChangeIndex(1 | -1) => 3.0,
+        ScaleTransition::Diagonal => 1.5,
+        ScaleTransition::ChangeIndex(2 | -2) => 1.0,
+        ScaleTransition::MajorToMinor => 1.0,
+        ScaleTransition::ChangeIndex(_) => 0.5,
+        ScaleTransition::FlatToMinor => 0.5,
+    }
+}
+
+/// Picks the key to play after `from` among its neighbors, weighing every transition by
+/// `weight` and how adventurous to be by `temperature`. At 1, transitions are picked with a
+/// probability proportional to their weight. Lower temperatures favour the heaviest ones more,
+/// down to always taking the heaviest at 0, and higher ones pick more evenly, up to any
+/// neighbor alike at infinity. Returns `None` if no transition out of `from` has any weight.
+pub fn sample_next_key<R: Rng>(
+    wheel: &Wheel,
+    from: Scale,
+    temperature: f64,
+    rng: &mut R,
+    weight: impl Fn(ScaleTransition) -> f64,
+) -> Result<Option<(ScaleTransition, Scale)>> {
+    let edges = wheel
+        .graph()
+        .edges(wheel.node(from)?)
+        .filter_map(|edge| Some((*edge.weight(), wheel.scale(edge.target())?)))
+        .filter(|(transition, _)| weight(*transition) > 0.0)
+        .collect::<Vec<_>>();
+    let heaviest = edges
+        .iter()
+        .map(|(transition, _)| weight(*transition))
+        .fold(0.0, f64::max);
+    if temperature <= 0.0 {
+        // Ties go to the transition listed first.
+        return Ok(edges
+            .into_iter()
+            .find(|(transition, _)| weight(*transition) == heaviest));
+    }
+    // Scaling down to the heaviest weight first keeps low temperatures from overflowing, and
+    // leaves weights untouched at 1 so seeded journeys pick what they always have.
+    let scale = if temperature < 1.0 { heaviest } else { 1.0 };
+    let Ok(distribution) = WeightedIndex::new(
+        edges
+            .iter()
+            .map(|(transition, _)| (weight(*transition) / scale).powf(temperature.recip())),
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some(edges[distribution.sample(rng)]))
+}
+
+/// A random walk of `length` transitions starting at `source`, where every step picks one of the
+/// outgoing transitions with a probability proportional to `weight`.
+pub fn random_journey<R: Rng>(
+    wheel: &Wheel,
+    source: Scale,
+    length: usize,
+    rng: &mut R,
+    weight: impl Fn(ScaleTransition) -> f64,
+) -> Result<Path> {
+    tempered_journey(wheel, source, length, 1.0, rng, weight)
+}
+
+/// Like [`random_journey`], picking every step like [`sample_next_key`] does at `temperature`.
+pub fn tempered_journey<R: Rng>(
+    wheel: &Wheel,
+    source: Scale,
+    length: usize,
+    temperature: f64,
+    rng: &mut R,
+    weight: impl Fn(ScaleTransition) -> f64,
+) -> Result<Path> {
+    let mut scale = source;
+    let mut path = vec![scale];
+    let mut transition_path = vec![];
+
+    for _ in 0..length {
+        let Some((transition, next)) = sample_next_key(wheel, scale, temperature, rng, &weight)?
+        else {
+            break;
+        };
+        scale = next;
+        path.push(scale);
+        transition_path.push(transition);
+    }
+
+    Ok(Path {
+        cost: transition_path.len() as u64,
+        node: scale,
+        transition: transition_path.last().copied(),
+        path,
+        transition_path,
+    })
+}
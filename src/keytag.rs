@@ -0,0 +1,281 @@
+use crate::error::{Error, Result};
+use crate::rekordbox::tonality;
+use crate::scale::Scale;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+/// Padding left after a rewritten ID3 tag, so the next edit of the tag fits.
+const ID3_PADDING: usize = 1024;
+/// The Vorbis comment field DJ software keeps the key in.
+const VORBIS_KEY_FIELD: &str = "INITIALKEY";
+
+fn tag_error(path: &Path, message: impl Into<String>) -> Error {
+    Error::KeyTag {
+        path: path.to_path_buf(),
+        message: message.into(),
+    }
+}
+
+/// `key` written the way `old` was, as a camelot code, an Open Key code, or else a key name
+/// like `Am`, which is how ID3 asks for it.
+fn key_text(key: Scale, old: Option<&str>) -> String {
+    match old.map(str::trim) {
+        Some(old) if old.parse::<Scale>().is_ok() => key.to_string(),
+        Some(old) if Scale::from_open_key(old).is_ok() => key.open_key().to_string(),
+        _ => tonality(key),
+    }
+}
+
+/// Writes `key` into the key tag of the audio file at `path`, replacing the key there: the
+/// `TKEY` frame of an MP3's ID3v2 tag, or the `INITIALKEY` comment of a FLAC file. The key is
+/// written like the one it replaces, and as a key name like `Am` in files without one. The
+/// file is rewritten whole next to the original and then moved over it, so it's never left
+/// half written.
+pub fn write_key_tag(path: &Path, key: Scale) -> Result<()> {
+    let data = fs::read(path)?;
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let rewritten = match extension.as_deref() {
+        Some("mp3") => with_id3_key(&data, key).map_err(|message| tag_error(path, message))?,
+        Some("flac") => with_flac_key(&data, key).map_err(|message| tag_error(path, message))?,
+        _ => return Err(tag_error(path, "only MP3 and FLAC key tags can be written")),
+    };
+
+    let mut partial = OsString::from(path.as_os_str());
+    partial.push(".partial");
+    fs::write(&partial, rewritten)?;
+    fs::set_permissions(&partial, fs::metadata(path)?.permissions())?;
+    fs::rename(&partial, path)?;
+    tracing::info!(path = %path.display(), %key, "rewrote the key tag");
+    Ok(())
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |size, &byte| (size << 7) | usize::from(byte & 0x7f))
+}
+
+fn to_syncsafe(size: usize) -> [u8; 4] {
+    [21, 14, 7, 0].map(|shift| ((size >> shift) & 0x7f) as u8)
+}
+
+/// The text of an ID3 text frame, in whichever of its four encodings.
+fn id3_text(data: &[u8]) -> Option<String> {
+    let (&encoding, text) = data.split_first()?;
+    let utf16 = |text: &[u8], big_endian: bool| {
+        let units: Vec<u16> = text
+            .chunks_exact(2)
+            .map(|pair| match big_endian {
+                true => u16::from_be_bytes([pair[0], pair[1]]),
+                false => u16::from_le_bytes([pair[0], pair[1]]),
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    let text = match encoding {
+        0 => text.iter().map(|&byte| char::from(byte)).collect(),
+        1 => match text {
+            [0xfe, 0xff, rest @ ..] => utf16(rest, true),
+            [0xff, 0xfe, rest @ ..] => utf16(rest, false),
+            _ => return None,
+        },
+        2 => utf16(text, true),
+        3 => String::from_utf8_lossy(text).into_owned(),
+        _ => return None,
+    };
+    Some(text.trim_end_matches('\0').to_string())
+}
+
+/// `data`, an MP3 file, with `key` in the `TKEY` frame of its ID3v2.3 or 2.4 tag, which is
+/// added if it has none.
+fn with_id3_key(data: &[u8], key: Scale) -> Result<Vec<u8>, String> {
+    // Frames as (id, flags, contents), and what follows the tag.
+    let (version, mut frames, audio) = match data {
+        [b'I', b'D', b'3', version, _, flags, size @ ..] if size.len() >= 4 => {
+            let (version, flags) = (*version, *flags);
+            if !matches!(version, 3 | 4) {
+                return Err(format!("ID3v2.{version} tags aren't supported"));
+            }
+            if flags & 0x80 != 0 {
+                return Err("unsynchronised ID3 tags aren't supported".into());
+            }
+            let end = 10 + syncsafe(&size[..4]);
+            let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+            let tag = data
+                .get(10..end)
+                .ok_or("the ID3 tag is longer than the file")?;
+            let mut offset = 0;
+            // The extended header is dropped with the rest of the old tag.
+            if flags & 0x40 != 0 {
+                let size = tag.get(..4).ok_or("the ID3 tag is cut short")?;
+                offset = match version {
+                    3 => 4 + u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize,
+                    _ => syncsafe(size),
+                };
+            }
+            let mut frames = vec![];
+            while let Some(header) = tag.get(offset..offset + 10) {
+                if header[0] == 0 {
+                    break;
+                }
+                let size = match version {
+                    3 => u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize,
+                    _ => syncsafe(&header[4..8]),
+                };
+                let contents = tag
+                    .get(offset + 10..offset + 10 + size)
+                    .ok_or("an ID3 frame runs past the end of the tag")?;
+                let id: [u8; 4] = [header[0], header[1], header[2], header[3]];
+                frames.push((id, [header[8], header[9]], contents.to_vec()));
+                offset += 10 + size;
+            }
+            (
+                version,
+                frames,
+                data.get(end + footer..).unwrap_or_default(),
+            )
+        }
+        _ => (4, vec![], data),
+    };
+
+    let old = frames
+        .iter()
+        .find(|(id, ..)| id == b"TKEY")
+        .and_then(|(_, _, contents)| id3_text(contents));
+    let mut contents = vec![0];
+    contents.extend(key_text(key, old.as_deref()).bytes());
+    match frames.iter().position(|(id, ..)| id == b"TKEY") {
+        Some(first) => {
+            frames[first] = (*b"TKEY", [0, 0], contents);
+            let mut seen = false;
+            frames.retain(|(id, ..)| id != b"TKEY" || !std::mem::replace(&mut seen, true));
+        }
+        None => frames.push((*b"TKEY", [0, 0], contents)),
+    }
+
+    let mut tag = vec![];
+    for (id, flags, contents) in &frames {
+        tag.extend(id);
+        match version {
+            3 => tag.extend((contents.len() as u32).to_be_bytes()),
+            _ => tag.extend(to_syncsafe(contents.len())),
+        }
+        tag.extend(flags);
+        tag.extend(contents);
+    }
+    tag.resize(tag.len() + ID3_PADDING, 0);
+    let mut file = vec![b'I', b'D', b'3', version, 0, 0];
+    file.extend(to_syncsafe(tag.len()));
+    file.extend(tag);
+    file.extend(audio);
+    Ok(file)
+}
+
+/// `data`, a FLAC file, with `key` as the `INITIALKEY` comment of its Vorbis comment block,
+/// which is added after the stream info if it has none.
+fn with_flac_key(data: &[u8], key: Scale) -> Result<Vec<u8>, String> {
+    if !data.starts_with(b"fLaC") {
+        return Err("not a FLAC file".into());
+    }
+    // Metadata blocks as (type, contents), and the audio frames after them.
+    let mut blocks: Vec<(u8, Vec<u8>)> = vec![];
+    let mut offset = 4;
+    loop {
+        let header = data
+            .get(offset..offset + 4)
+            .ok_or("the FLAC metadata is cut short")?;
+        let size =
+            usize::from(header[1]) << 16 | usize::from(header[2]) << 8 | usize::from(header[3]);
+        let contents = data
+            .get(offset + 4..offset + 4 + size)
+            .ok_or("a FLAC metadata block runs past the end of the file")?;
+        blocks.push((header[0] & 0x7f, contents.to_vec()));
+        offset += 4 + size;
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+    }
+    let audio = &data[offset..];
+
+    let position = match blocks.iter().position(|(kind, _)| *kind == 4) {
+        Some(position) => position,
+        None => {
+            // No vendor and no comments yet, after the stream info that has to come first.
+            blocks.insert(1, (4, [0; 8].to_vec()));
+            1
+        }
+    };
+    let block = &blocks[position].1;
+    let read_u32 = |at: usize| -> Result<usize, String> {
+        let bytes = block
+            .get(at..at + 4)
+            .ok_or("the FLAC Vorbis comments are cut short")?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    let vendor_end = 4 + read_u32(0)?;
+    let vendor = block
+        .get(..vendor_end)
+        .ok_or("the FLAC Vorbis comments are cut short")?
+        .to_vec();
+    let count = read_u32(vendor_end)?;
+    let mut at = vendor_end + 4;
+    let mut comments: Vec<String> = vec![];
+    for _ in 0..count {
+        let length = read_u32(at)?;
+        let comment = block
+            .get(at + 4..at + 4 + length)
+            .ok_or("a FLAC Vorbis comment runs past the end of its block")?;
+        comments.push(String::from_utf8_lossy(comment).into_owned());
+        at += 4 + length;
+    }
+
+    let is_key = |comment: &String| {
+        comment
+            .split_once('=')
+            .is_some_and(|(field, _)| field.eq_ignore_ascii_case(VORBIS_KEY_FIELD))
+    };
+    let old = comments
+        .iter()
+        .find(|comment| is_key(comment))
+        .and_then(|comment| comment.split_once('='))
+        .map(|(_, value)| value.to_string());
+    let value = format!("{VORBIS_KEY_FIELD}={}", key_text(key, old.as_deref()));
+    match comments.iter().position(is_key) {
+        Some(first) => {
+            comments[first] = value;
+            let mut seen = false;
+            comments.retain(|comment| !is_key(comment) || !std::mem::replace(&mut seen, true));
+        }
+        None => comments.push(value),
+    }
+
+    let mut block = vendor;
+    block.extend((comments.len() as u32).to_le_bytes());
+    for comment in &comments {
+        block.extend((comment.len() as u32).to_le_bytes());
+        block.extend(comment.as_bytes());
+    }
+    if block.len() >= 1 << 24 {
+        return Err("the FLAC Vorbis comments are too long".into());
+    }
+    blocks[position].1 = block;
+
+    let mut file = b"fLaC".to_vec();
+    let last = blocks.len() - 1;
+    for (index, (kind, contents)) in blocks.iter().enumerate() {
+        let size = contents.len();
+        let flag = if index == last { 0x80 } else { 0 };
+        file.extend([
+            flag | kind,
+            (size >> 16) as u8,
+            (size >> 8) as u8,
+            size as u8,
+        ]);
+        file.extend(contents);
+    }
+    file.extend(audio);
+    Ok(file)
+}
@@ -0,0 +1,211 @@
+#[cfg(any(feature = "spotify", feature = "beatport", feature = "musicbrainz"))]
+mod api;
+#[cfg(feature = "audio")]
+mod audio;
+#[cfg(feature = "audio")]
+mod audit;
+mod autodj;
+mod batch;
+#[cfg(feature = "beatport")]
+mod beatport;
+#[cfg(feature = "musicbrainz")]
+mod brainz;
+#[cfg(feature = "cache")]
+mod cache;
+mod chord;
+mod cluster;
+mod color;
+mod community;
+#[cfg(feature = "midi")]
+mod control;
+mod csv;
+mod cue;
+#[cfg(unix)]
+mod daemon;
+mod detect;
+mod distance;
+mod engine;
+#[cfg(feature = "engine")]
+mod enginedj;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(any(feature = "audio", feature = "musicbrainz"))]
+mod fingerprint;
+#[cfg(feature = "gui")]
+pub mod gui;
+#[cfg(feature = "itunes")]
+mod itunes;
+mod journey;
+#[cfg(feature = "audio")]
+mod keytag;
+mod library;
+#[cfg(feature = "link")]
+mod link;
+#[cfg(feature = "audio")]
+mod loudness;
+mod markov;
+mod matches;
+#[cfg(feature = "midi")]
+mod midi;
+mod mixable;
+mod modulation;
+#[cfg(any(target_os = "linux", windows))]
+mod nowplaying;
+mod osc;
+mod overlay;
+#[cfg(feature = "audio")]
+mod phrase;
+mod planner;
+mod playlist;
+#[cfg(feature = "plugin")]
+mod plugin;
+#[cfg(feature = "midi")]
+mod preview;
+mod project;
+mod quarter;
+mod quiz;
+mod rekordbox;
+pub mod render;
+mod report;
+mod rpc;
+mod rules;
+mod scale;
+mod script;
+mod search;
+mod serato;
+#[cfg(feature = "server")]
+pub mod server;
+mod session;
+#[cfg(feature = "spotify")]
+mod spotify;
+mod stats;
+#[cfg(any(feature = "audio", feature = "midi"))]
+mod stems;
+mod suggest;
+mod tour;
+mod traktor;
+mod transition;
+mod transpose;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "virtualdj")]
+mod virtualdj;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod wheel;
+
+#[cfg(feature = "audio")]
+pub use audio::{
+    analyze_audio, analyze_audio_with, analyzed_track, analyzed_track_with, audio_files, chroma,
+    decode_audio, estimate_tempo, key_segments, key_segments_with, read_tags, tagged_track, Audio,
+    AudioTags, AUDIO_EXTENSIONS,
+};
+#[cfg(feature = "audio")]
+pub use audit::{audit_key, KeyAudit};
+pub use autodj::AutoDj;
+pub use batch::batch_paths;
+#[cfg(feature = "beatport")]
+pub use beatport::BeatportClient;
+#[cfg(feature = "musicbrainz")]
+pub use brainz::BrainzClient;
+#[cfg(feature = "cache")]
+pub use cache::{file_hash, AnalysisCache, CachedAnalysis};
+pub use chord::{
+    diatonic_triads, layering_score, mashup, parse_progression, progression_keys, roman_numeral,
+    scale_notes, Chord, Mashup, ProgressionFit, Quality, SharedChord, SharedNote, Triad,
+};
+pub use cluster::{community_clusters, key_clusters, KeyCluster};
+pub use color::{wheel_color, Rgb};
+pub use community::{key_communities, modularity};
+#[cfg(feature = "midi")]
+pub use control::{KeyMapping, KeyMessage, KeyOutput};
+pub use csv::csv_row;
+pub use cue::cue_sheet;
+#[cfg(unix)]
+pub use daemon::run_daemon;
+pub use detect::{
+    detect_key, detect_key_with, key_consensus, rank_keys, rank_keys_with, KeyConsensus,
+    KeyEstimate, KeyProfile,
+};
+pub use distance::DistanceMatrix;
+pub use engine::{
+    Bridge, Engine, Neighbor, PathSummary, Reachable, DEFAULT_PATH_COUNT, DEFAULT_SEARCH_TIME,
+    DEFAULT_SUGGESTION_COUNT, MAX_PATH_COUNT,
+};
+#[cfg(feature = "engine")]
+pub use enginedj::{engine_key, read_engine_library};
+pub use error::{Error, Result};
+#[cfg(any(feature = "audio", feature = "musicbrainz"))]
+pub use fingerprint::{check_fpcalc, fingerprint, Fingerprint};
+#[cfg(feature = "itunes")]
+pub use itunes::read_itunes_library;
+pub use journey::{default_transition_weight, random_journey, sample_next_key, tempered_journey};
+#[cfg(feature = "audio")]
+pub use keytag::write_key_tag;
+pub use library::{
+    file_modified, find_energy, find_key, KeySegment, Track, TrackLibrary, TrackLookup,
+    LOW_CONFIDENCE,
+};
+#[cfg(feature = "link")]
+pub use link::{parse_link_tempo, LinkListener};
+#[cfg(feature = "audio")]
+pub use loudness::{loudness, Loudness};
+pub use markov::{parse_history, MarkovModel};
+pub use matches::{best_matches, TrackMatch, DEFAULT_PITCH_RANGE};
+#[cfg(feature = "midi")]
+pub use midi::{detect_midi_key, pitch_histogram, KeyboardKeys};
+pub use mixable::{key_paths, order_keys, MixPath, MixableKey, WheelKey};
+pub use modulation::{modulation_steps, ModulationStep};
+#[cfg(any(target_os = "linux", windows))]
+pub use nowplaying::NowPlaying;
+pub use osc::{osc_message, OscAddresses, OscArg, OscOutput};
+pub use overlay::{overlay_text, OverlayOutput};
+#[cfg(feature = "audio")]
+pub use phrase::{
+    mix_point, mix_point_sheet, phrase_boundaries, set_mix_points, MixPoint, PhraseBoundary,
+};
+pub use planner::{EnergyShape, PlanOptions, SetHalf, SetPlanner, TagPlacement};
+pub use playlist::{m3u_playlist, Playlist, PlaylistEntry, PlaylistFormat};
+#[cfg(feature = "plugin")]
+pub use plugin::RulePlugin;
+#[cfg(feature = "midi")]
+pub use preview::{play_preview, preview_notes, PreviewOptions};
+pub use project::{ProjectTrack, ProjectTransition, SetProject, SET_PROJECT_VERSION};
+pub use quarter::{quarter_tone_wheel, QuarterToneKey, QuarterToneRules, QUARTER_TONE_WHEEL_SIZE};
+pub use quiz::{ParseQuizError, QuizAnswer, QuizKind, QuizLog, QuizQuestion, QuizResult};
+pub use rekordbox::rekordbox_xml;
+pub use report::html_report;
+pub use rpc::{answer_lines, handle_request};
+pub use rules::{is_clash, Preset, TransitionRules};
+pub use scale::{
+    make_nodes, make_nodes_on, parse_key, scale, Accidental, KeyName, Locale, ModalKey, Mode,
+    OpenKey, ParseScaleError, Scale, ScaleKind, WheelNumbering,
+};
+pub use script::RuleScript;
+pub use search::{
+    format_path, format_path_colored, multi_path_dijkstra, multi_path_search, Path, PathCost,
+    SearchLimit,
+};
+pub use serato::serato_crate;
+pub use session::{ParseSessionError, Play, Session};
+#[cfg(feature = "spotify")]
+pub use spotify::{playlist_id, spotify_key, SpotifyClient};
+pub use stats::{
+    key_coverage, mixing_report, transition_usage, GraphStats, KeyCoverage, KeyHabit, MixingReport,
+};
+#[cfg(any(feature = "audio", feature = "midi"))]
+pub use stems::{analyze_stem, analyze_stems, match_stems, StemKey, StemMatch};
+pub use suggest::{
+    compatibility, modal_compatibility, mode_penalty, suggest, weigh_confidence, Suggestion,
+    TempoRange,
+};
+pub use tour::key_tour;
+pub use traktor::traktor_nml;
+pub use transition::{
+    make_transition, make_transition_on, possible_transitions, Interval, ScaleTransition,
+};
+pub use transpose::{stretches, transpose, transpositions, Stretch, Transposition};
+#[cfg(feature = "virtualdj")]
+pub use virtualdj::{read_virtualdj_database, virtualdj_key};
+pub use wheel::Wheel;
@@ -0,0 +1,432 @@
+use crate::error::{Error, Result};
+use crate::render::Notation;
+use crate::scale::{Locale, Scale};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Keys known with less confidence than this are flagged for review.
+pub const LOW_CONFIDENCE: f64 = 0.5;
+
+/// A stretch of a track in one key, lasting until the next segment starts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KeySegment {
+    /// Seconds into the track.
+    pub start: f64,
+    pub key: Scale,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    /// `Artist - Title`, or the file name when the tags don't say.
+    pub name: String,
+    /// The main key, used wherever a track has only one.
+    pub key: Scale,
+    /// How sure the key is, from 0 for a guess to 1 for certain, if the key's source says.
+    pub confidence: Option<f64>,
+    /// Beats per minute, if known.
+    pub tempo: Option<f64>,
+    /// The audio file, if known.
+    pub path: Option<PathBuf>,
+    /// Length in seconds, if known.
+    pub duration: Option<f64>,
+    /// Energy level from 1 to 10, as rated by Mixed In Key and similar tools, if known.
+    pub energy: Option<u8>,
+    pub genre: Option<String>,
+    /// Free-form labels like `vocal` or `warmup`.
+    pub tags: Vec<String>,
+    /// The keys of a track that modulates, in order. Empty for tracks in one key.
+    pub segments: Vec<KeySegment>,
+    /// When the audio file was last modified, in seconds since the unix epoch, as of when its
+    /// key was read, so scans can leave unchanged files alone.
+    pub modified: Option<u64>,
+}
+
+impl Track {
+    /// A track with nothing known about it but its name and key.
+    pub fn new(name: impl Into<String>, key: Scale) -> Self {
+        Self {
+            name: name.into(),
+            key,
+            confidence: None,
+            tempo: None,
+            path: None,
+            duration: None,
+            energy: None,
+            genre: None,
+            tags: vec![],
+            segments: vec![],
+            modified: None,
+        }
+    }
+
+    /// The key the track starts in, which the previous track is mixed into.
+    pub fn incoming_key(&self) -> Scale {
+        self.segments
+            .first()
+            .map_or(self.key, |segment| segment.key)
+    }
+
+    /// The key the track ends in, which the next track is mixed from.
+    pub fn outgoing_key(&self) -> Scale {
+        self.segments.last().map_or(self.key, |segment| segment.key)
+    }
+
+    /// Whether the key is too unsure to rely on without checking it by ear.
+    pub fn needs_review(&self) -> bool {
+        self.confidence
+            .is_some_and(|confidence| confidence < LOW_CONFIDENCE)
+    }
+
+    /// Percent to pitch `next` by to play it at this track's tempo, if both tempos are known.
+    pub fn pitch_into(&self, next: &Track) -> Option<f64> {
+        match (self.tempo, next.tempo) {
+            (Some(tempo), Some(next)) if next > 0.0 => Some((tempo / next - 1.0) * 100.0),
+            _ => None,
+        }
+    }
+
+    /// The artist and title in the track's name. Names without an artist are all title.
+    pub fn artist_and_title(&self) -> (Option<&str>, &str) {
+        match self.name.split_once(" - ") {
+            Some((artist, title)) => (Some(artist), title),
+            None => (None, &self.name),
+        }
+    }
+}
+
+/// When the file at `path` was last modified, in seconds since the unix epoch, if the file
+/// system says.
+pub fn file_modified(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// `path` made absolute for DJ software, which finds tracks by their full path: through any
+/// links if the file is there, and otherwise just from the current directory.
+pub(crate) fn absolute_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Formats a duration in seconds as `m:ss`.
+pub(crate) fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Parses a duration written as `m:ss`, `h:mm:ss` or plain seconds.
+pub(crate) fn parse_duration(duration: &str) -> Option<f64> {
+    duration
+        .split(':')
+        .try_fold(0.0, |total, part| {
+            Some(total * 60.0 + part.trim().parse::<f64>().ok()?)
+        })
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+}
+
+fn parse_energy(energy: &str) -> Option<u8> {
+    energy
+        .trim()
+        .parse()
+        .ok()
+        .filter(|energy| (1..=10).contains(energy))
+}
+
+/// Finds an energy level written like `Energy 6` in a free-form tag, the way Mixed In Key
+/// writes it into comments.
+pub fn find_energy(text: &str) -> Option<u8> {
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || ",;/|()[]{}:-".contains(c))
+        .filter(|word| !word.is_empty())
+        .collect();
+    words
+        .windows(2)
+        .find(|pair| pair[0].eq_ignore_ascii_case("energy"))
+        .and_then(|pair| parse_energy(pair[1]))
+}
+
+/// Formats the track as a library line.
+impl Display for Track {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}", self.key, self.name)?;
+        let attributes: Vec<String> = [
+            self.duration
+                .map(|duration| format!("duration={}", format_duration(duration))),
+            self.energy.map(|energy| format!("energy={energy}")),
+            self.confidence
+                .map(|confidence| format!("confidence={confidence:.2}")),
+            self.genre.as_ref().map(|genre| format!("genre={genre}")),
+            (!self.tags.is_empty()).then(|| format!("tags={}", self.tags.join(","))),
+            (!self.segments.is_empty()).then(|| {
+                let segments: Vec<String> = self
+                    .segments
+                    .iter()
+                    .map(|segment| format!("{}@{}", segment.key, format_duration(segment.start)))
+                    .collect();
+                format!("keys={}", segments.join(","))
+            }),
+            self.modified.map(|modified| format!("modified={modified}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if self.tempo.is_some() || self.path.is_some() || !attributes.is_empty() {
+            write!(f, "\t")?;
+        }
+        if let Some(tempo) = self.tempo {
+            write!(f, "{tempo:.1}")?;
+        }
+        if self.path.is_some() || !attributes.is_empty() {
+            write!(f, "\t")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "{}", path.display())?;
+        }
+        for attribute in attributes {
+            write!(f, "\t{attribute}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds a key written somewhere in a free-form tag like a comment, as a camelot code like
+/// `8A - Energy 6`, a short name like `F#m` or `Ebmaj`, or a full name like `A minor`. Bare
+/// note names are left alone, since a lone `A` is more likely a word than a key.
+pub fn find_key(text: &str) -> Option<Scale> {
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || ",;/|()[]{}".contains(c))
+        .filter(|word| !word.is_empty())
+        .collect();
+    let camelot = words.iter().find_map(|word| word.parse().ok());
+    let named = || {
+        words.windows(2).find_map(|pair| {
+            ["major", "minor"]
+                .iter()
+                .any(|mode| pair[1].eq_ignore_ascii_case(mode))
+                .then(|| Scale::from_key_name(&pair.join(" "), Locale::English).ok())
+                .flatten()
+        })
+    };
+    let short = || {
+        words.iter().find_map(|word| {
+            let lower = word.to_lowercase();
+            ["m", "min", "maj"]
+                .iter()
+                .any(|mode| lower.len() > mode.len() && lower.ends_with(mode))
+                .then(|| Scale::from_key_name(word, Locale::English).ok())
+                .flatten()
+        })
+    };
+    camelot.or_else(named).or_else(short)
+}
+
+/// An online source of keys and tempos for tracks known only by name.
+pub trait TrackLookup {
+    /// The request that would be made to look up `name`, an `Artist - Title`. Returns `None` if
+    /// the name doesn't have both.
+    fn request(&self, name: &str) -> Option<String>;
+
+    /// Looks up the key and tempo of `name`, an `Artist - Title`. Returns `None` if the source
+    /// doesn't know the track or its key.
+    fn lookup(&self, name: &str) -> Result<Option<Track>>;
+}
+
+/// The keys and tempos of known tracks, looked up by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackLibrary {
+    tracks: Vec<Track>,
+    by_name: HashMap<String, usize>,
+}
+
+/// Tracks are matched regardless of case and surrounding whitespace.
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+impl TrackLibrary {
+    /// Parses a library with one `<key>\t<artist> - <title>\t<bpm>\t<path>` entry per line,
+    /// where the tempo and path are optional and may be left empty. They can be followed by
+    /// `name=value` columns for everything else known about the track: its `duration=m:ss`,
+    /// `energy=1..10`, how sure its key is as `confidence=0..1`, `genre=...`, comma-separated
+    /// `tags=...` and, for tracks that modulate, `keys=8A@0:00,9A@2:30` with the key each
+    /// segment is in and when it starts. Without tabs the key is split from the name at the
+    /// first space. Blank lines and lines starting with `#` are skipped.
+    pub fn parse(library: &str, notation: &Notation) -> Result<Self> {
+        let mut tracks = Self::default();
+        for (number, line) in library.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: String| Error::InvalidLibrary {
+                line: number + 1,
+                message,
+            };
+            // The column `name` holds something it can't, `value`.
+            let invalid_value =
+                |name: &str, value: &str| invalid(format!("invalid {name} `{value}`"));
+            let mut fields = line.split('\t');
+            let (key, name, tempo, path) = match (fields.next(), fields.next()) {
+                (Some(key), Some(name)) => (key, name, fields.next(), fields.next()),
+                _ => line
+                    .split_once(char::is_whitespace)
+                    .map(|(key, name)| (key, name, None, None))
+                    .ok_or_else(|| invalid(format!("expected a key and a name, got `{line}`")))?,
+            };
+            let tempo = match tempo.map(str::trim).filter(|tempo| !tempo.is_empty()) {
+                // `nan`, `inf` and negative numbers parse, but aren't tempos.
+                Some(tempo) => Some(
+                    tempo
+                        .parse()
+                        .ok()
+                        .filter(|tempo: &f64| tempo.is_finite() && *tempo > 0.0)
+                        .ok_or_else(|| invalid_value("tempo", tempo))?,
+                ),
+                None => None,
+            };
+            let mut track = Track {
+                tempo,
+                path: path
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from),
+                ..Track::new(
+                    name.trim(),
+                    notation
+                        .parse(key)
+                        .map_err(|_| invalid_value("key", key.trim()))?,
+                )
+            };
+            for attribute in fields.map(str::trim).filter(|field| !field.is_empty()) {
+                let (column, value) = attribute.split_once('=').ok_or_else(|| {
+                    invalid(format!("expected a `name=value` column, got `{attribute}`"))
+                })?;
+                let invalid_column = || invalid_value(column, value);
+                match (column, value) {
+                    ("confidence", confidence) => {
+                        track.confidence = Some(
+                            confidence
+                                .trim()
+                                .parse()
+                                .ok()
+                                .filter(|confidence| (0.0..=1.0).contains(confidence))
+                                .ok_or_else(invalid_column)?,
+                        );
+                    }
+                    ("duration", duration) => {
+                        track.duration = Some(parse_duration(duration).ok_or_else(invalid_column)?);
+                    }
+                    ("energy", energy) => {
+                        track.energy = Some(parse_energy(energy).ok_or_else(invalid_column)?);
+                    }
+                    ("genre", genre) => {
+                        track.genre =
+                            Some(genre.trim().to_string()).filter(|genre| !genre.is_empty());
+                    }
+                    ("keys", segments) => {
+                        track.segments = segments
+                            .split(',')
+                            .map(|segment| {
+                                let (key, start) = segment.split_once('@')?;
+                                Some(KeySegment {
+                                    start: parse_duration(start)?,
+                                    key: notation.parse(key.trim()).ok()?,
+                                })
+                            })
+                            .collect::<Option<_>>()
+                            .ok_or_else(invalid_column)?;
+                    }
+                    ("modified", modified) => {
+                        track.modified =
+                            Some(modified.trim().parse().map_err(|_| invalid_column())?);
+                    }
+                    ("tags", tags) => {
+                        track.tags = tags
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                            .map(str::to_string)
+                            .collect();
+                    }
+                    _ => return Err(invalid(format!("unknown column `{column}`"))),
+                }
+            }
+            tracks.insert(track);
+        }
+        Ok(tracks)
+    }
+
+    /// Reads the library at `path`, which is empty if there's no file yet.
+    pub fn load(path: &Path, notation: &Notation) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(library) => {
+                let library = Self::parse(&library, notation)?;
+                tracing::debug!(
+                    path = %path.display(),
+                    tracks = library.tracks.len(),
+                    "read the track library"
+                );
+                Ok(library)
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Adds `track`, replacing any track with the same name.
+    pub fn insert(&mut self, track: Track) {
+        match self.by_name.get(&normalize(&track.name)) {
+            Some(&index) => self.tracks[index] = track,
+            None => {
+                self.by_name
+                    .insert(normalize(&track.name), self.tracks.len());
+                self.tracks.push(track);
+            }
+        }
+    }
+
+    /// Where the track named `name` is in [`tracks`](Self::tracks).
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.by_name.get(&normalize(name)).copied()
+    }
+
+    pub fn track(&self, name: &str) -> Option<&Track> {
+        self.by_name
+            .get(&normalize(name))
+            .map(|&index| &self.tracks[index])
+    }
+
+    /// The name to give the track of the audio file at `path`: `name`, unless a track of
+    /// another file or of none has it, and then `name (2)`, `name (3)` or the first after that
+    /// that's free or already this file's, so every file gets a track of its own.
+    pub fn name_for(&self, name: &str, path: &Path) -> String {
+        let is_free = |name: &str| {
+            self.track(name).is_none_or(|track| {
+                track.path.as_deref().is_some_and(|known| {
+                    known == path || fs::canonicalize(known).is_ok_and(|known| known == path)
+                })
+            })
+        };
+        iter::once(name.to_string())
+            .chain((2..).map(|number| format!("{name} ({number})")))
+            .find(|name| is_free(name))
+            .unwrap_or_default()
+    }
+
+    pub fn key(&self, name: &str) -> Option<Scale> {
+        self.track(name).map(|track| track.key)
+    }
+
+    /// Every track in the order it was added.
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+}
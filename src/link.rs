@@ -0,0 +1,86 @@
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+
+/// Multicast group Ableton Link peers announce themselves on.
+const LINK_GROUP: Ipv4Addr = Ipv4Addr::new(224, 76, 78, 75);
+const LINK_PORT: u16 = 20808;
+const PROTOCOL_HEADER: &[u8; 8] = b"_asdp_v\x01";
+/// Protocol header, message type, ttl, session group and node id.
+const MESSAGE_HEADER_LEN: usize = 8 + 1 + 1 + 2 + 8;
+const ALIVE: u8 = 1;
+const RESPONSE: u8 = 2;
+const TIMELINE: &[u8; 4] = b"tmln";
+
+/// Reads the session tempo from the state peers announce in a Link discovery message, in
+/// beats per minute. Returns `None` for goodbyes and anything that isn't a Link message.
+pub fn parse_link_tempo(message: &[u8]) -> Option<f64> {
+    let header = message.get(..MESSAGE_HEADER_LEN)?;
+    if !header.starts_with(PROTOCOL_HEADER) || ![ALIVE, RESPONSE].contains(&header[8]) {
+        return None;
+    }
+
+    let mut entries = &message[MESSAGE_HEADER_LEN..];
+    while entries.len() >= 8 {
+        let key = &entries[..4];
+        let size = u32::from_be_bytes(entries[4..8].try_into().ok()?) as usize;
+        let value = entries.get(8..8 + size)?;
+        if key == TIMELINE {
+            // The timeline starts with the tempo as microseconds per beat.
+            let micros_per_beat = i64::from_be_bytes(value.get(..8)?.try_into().ok()?);
+            return (micros_per_beat > 0).then(|| 60_000_000.0 / micros_per_beat as f64);
+        }
+        entries = &entries[8 + size..];
+    }
+    None
+}
+
+/// Listens to the tempo of an Ableton Link session on the local network.
+///
+/// This only listens, so it never joins the session as a peer and can't change its tempo.
+pub struct LinkListener {
+    socket: UdpSocket,
+}
+
+impl LinkListener {
+    pub fn join() -> io::Result<Self> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        // Link apps on the same machine listen on the same port.
+        socket.set_reuse_address(true)?;
+        #[cfg(unix)]
+        socket.set_reuse_port(true)?;
+        socket.bind(&SockAddr::from(SocketAddrV4::new(
+            Ipv4Addr::UNSPECIFIED,
+            LINK_PORT,
+        )))?;
+        socket.join_multicast_v4(&LINK_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+        Ok(Self {
+            socket: socket.into(),
+        })
+    }
+
+    /// Waits for the next tempo announced by a peer. Returns `None` if no peer announces one
+    /// within `timeout`.
+    pub fn next_tempo(&self, timeout: Option<Duration>) -> io::Result<Option<f64>> {
+        self.socket.set_read_timeout(timeout)?;
+        let mut buffer = [0; 512];
+        loop {
+            let len = match self.socket.recv(&mut buffer) {
+                Ok(len) => len,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None);
+                }
+                Err(error) => return Err(error),
+            };
+            if let Some(tempo) = parse_link_tempo(&buffer[..len]) {
+                return Ok(Some(tempo));
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::audio::Audio;
+use std::f64::consts::PI;
+
+/// Loudness is gated and averaged over blocks of this many seconds, overlapping by three
+/// quarters, as in ITU-R BS.1770.
+const BLOCK: f64 = 0.4;
+const BLOCK_HOP: f64 = 0.1;
+/// Short-term loudness is measured over windows of this many seconds, a second apart.
+const SHORT_TERM: f64 = 3.0;
+const SHORT_TERM_HOP: f64 = 1.0;
+/// Blocks quieter than this many LUFS are silence, and those more than [`RELATIVE_GATE`] LU
+/// below the loudness of the rest are left out as well.
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = 10.0;
+/// Loudness in LUFS rated energy 1 and 10. Quiet masters sit around -20 LUFS, and the loudest
+/// club masters around -5.
+const QUIETEST: f64 = -20.0;
+const LOUDEST: f64 = -5.0;
+
+/// How loud a track is, in LUFS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness over the whole track, gated as in ITU-R BS.1770 and EBU R 128.
+    pub integrated: f64,
+    /// Short-term loudness of the loudest three seconds.
+    pub short_term_max: f64,
+}
+
+/// A biquad filter, with the denominator's leading 1 left out.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn apply(&self, samples: &mut [f64]) {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for sample in samples {
+            let x = *sample;
+            let y =
+                self.b[0] * x + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+            (x2, x1, y2, y1) = (x1, x, y1, y);
+            *sample = y;
+        }
+    }
+}
+
+/// The two stages of the K-weighting filter at `rate`, a shelf boosting what's above about
+/// 1.5 kHz by 4 dB and a high-pass below about 38 Hz, derived for any rate the way libebur128
+/// does from the coefficients BS.1770 gives at 48 kHz.
+fn k_weighting(rate: f64) -> [Biquad; 2] {
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+    [shelf, high_pass]
+}
+
+/// Loudness in LUFS of a mean square of K-weighted samples.
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// The mean squares of the windows of `length` samples of `squares`, `hop` apart, from
+/// running sums so long tracks stay fast.
+fn window_means(squares: &[f64], length: usize, hop: usize) -> Vec<f64> {
+    let mut sums = Vec::with_capacity(squares.len() + 1);
+    sums.push(0.0);
+    for square in squares {
+        sums.push(sums.last().copied().unwrap_or_default() + square);
+    }
+    (0..=squares.len().saturating_sub(length))
+        .step_by(hop.max(1))
+        .map(|start| (sums[start + length] - sums[start]) / length as f64)
+        .collect()
+}
+
+/// How loud `audio` is, or `None` if it's silent or shorter than a block. The audio is mono,
+/// so its loudness is that of the channels mixed down.
+pub fn loudness(audio: &Audio) -> Option<Loudness> {
+    let rate = f64::from(audio.sample_rate);
+    let mut samples: Vec<f64> = audio
+        .samples
+        .iter()
+        .map(|&sample| f64::from(sample))
+        .collect();
+    for stage in k_weighting(rate) {
+        stage.apply(&mut samples);
+    }
+    let squares: Vec<f64> = samples.iter().map(|sample| sample * sample).collect();
+    let seconds = |seconds: f64| (seconds * rate).round() as usize;
+    if squares.len() < seconds(BLOCK) {
+        return None;
+    }
+
+    let blocks: Vec<f64> = window_means(&squares, seconds(BLOCK), seconds(BLOCK_HOP))
+        .into_iter()
+        .filter(|&block| block > 0.0 && lufs(block) > ABSOLUTE_GATE)
+        .collect();
+    if blocks.is_empty() {
+        return None;
+    }
+    let gate = lufs(blocks.iter().sum::<f64>() / blocks.len() as f64) - RELATIVE_GATE;
+    let gated: Vec<f64> = blocks
+        .into_iter()
+        .filter(|&block| lufs(block) > gate)
+        .collect();
+    let integrated = lufs(gated.iter().sum::<f64>() / gated.len() as f64);
+
+    let length = seconds(SHORT_TERM).min(squares.len());
+    let short_term_max = window_means(&squares, length, seconds(SHORT_TERM_HOP))
+        .into_iter()
+        .fold(0.0, f64::max);
+    Some(Loudness {
+        integrated,
+        short_term_max: lufs(short_term_max),
+    })
+}
+
+impl Loudness {
+    /// An energy level from 1 to 10 like Mixed In Key's, estimated from the loudness for
+    /// tracks without a rating. The loudest three seconds count as much as the whole track,
+    /// so tracks building to a loud drop rate above flat ones of the same average.
+    pub fn energy(&self) -> u8 {
+        let level = (self.integrated + self.short_term_max) / 2.0;
+        let energy = 1.0 + 9.0 * (level - QUIETEST) / (LOUDEST - QUIETEST);
+        energy.round().clamp(1.0, 10.0) as u8
+    }
+}
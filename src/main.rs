@@ -1,283 +1,4165 @@
-use itertools::Itertools;
-use petgraph::algo::dijkstra;
-use petgraph::prelude::NodeIndex;
-use petgraph::visit::EdgeRef;
-use petgraph::Graph;
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
-use std::fmt::{Display, Formatter};
-use std::iter;
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
-pub enum ScaleKind {
-    Major,
-    Minor,
-}
-
-impl ScaleKind {
-    fn swap(self) -> Self {
+use camelol::render::{
+    ascii_wheel, ascii_wheel_marked, explain_steps, graphml, mermaid_flowchart, paint, paint_scale,
+    svg_wheel, ColorSupport, KeyDisplay, Notation,
+};
+#[cfg(feature = "plugin")]
+use camelol::RulePlugin;
+use camelol::{
+    batch_paths, default_transition_weight, format_path_colored, mashup, modal_compatibility,
+    parse_history, suggest, tempered_journey, wheel_color, Accidental, DistanceMatrix, Engine,
+    GraphStats, Interval, Locale, MarkovModel, Path, PathCost, Preset, RuleScript, Scale,
+    ScaleTransition, SearchLimit, Session, TransitionRules, WheelNumbering,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use indicatif::ProgressBar;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "camelol",
+    about = "Find harmonic paths through the camelot wheel"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Instead of running a command, answer newline-delimited JSON-RPC requests on stdin with
+    /// one response per line on stdout, like the daemon does, for editors and scripts driving
+    /// camelol as a subprocess
+    #[arg(long)]
+    jsonl: bool,
+    /// Defaults for the options below and the track library, by default
+    /// `~/.config/camelol/config.toml`
+    #[arg(long, global = true, value_name = "FILE", env = "CAMELOL_CONFIG")]
+    config: Option<PathBuf>,
+    /// Color key names with the wheel colors [default: auto]
+    #[arg(long, value_enum, global = true, env = "CAMELOL_COLOR")]
+    color: Option<ColorChoice>,
+    /// Write only ASCII, without colors, box drawing or symbols, for screen readers and dumb
+    /// terminals
+    #[arg(long, global = true, env = "CAMELOL_PLAIN")]
+    plain: bool,
+    /// Write keys as camelot codes, musical key names or both [default: camelot]
+    #[arg(long, value_enum, global = true, env = "CAMELOL_KEYS")]
+    keys: Option<KeyChoice>,
+    /// Spell the black-key tonics of musical key names with flats, sharps, or as their key
+    /// signatures usually are [default: conventional]
+    #[arg(long, value_enum, global = true, env = "CAMELOL_SPELLING")]
+    spelling: Option<Spelling>,
+    /// Which musical keys the wheel numbers stand for: `camelot`, `open-key` for A minor on 1A
+    /// like Open Key numbers it, or the key one number stands for, like `1A=Am` [default:
+    /// camelot]
+    #[arg(
+        long,
+        global = true,
+        value_name = "NUMBERING",
+        env = "CAMELOL_NUMBERING",
+        value_parser = parse_numbering
+    )]
+    numbering: Option<WheelNumbering>,
+    /// Spell one key differently from the rest, like `2B=flat` for Gb major. Can be repeated
+    #[arg(long, global = true, value_name = "KEY=flat|sharp", value_parser = parse_spelling)]
+    spell: Vec<(String, Accidental)>,
+    /// Language of musical key names, for output as well as keys given as input [default:
+    /// english]
+    #[arg(long, value_enum, global = true, env = "CAMELOL_LOCALE")]
+    locale: Option<LocaleChoice>,
+    /// Which transitions the wheel has: one step and the relative key, those and the parallel
+    /// key, or every move including jumps and diagonals [default: extended]
+    #[arg(long, value_enum, global = true, env = "CAMELOL_PRESET")]
+    preset: Option<PresetChoice>,
+    /// Script of `valid = ...` and `cost = ...` rules deciding which transitions the wheel has
+    /// and what paths pay for them, on top of the preset
+    #[arg(long, global = true, value_name = "FILE", env = "CAMELOL_RULES")]
+    rules: Option<PathBuf>,
+    /// WebAssembly plugin deciding which transitions the wheel has and what paths pay for them,
+    /// on top of the preset, instead of a rule script. Plugins run sandboxed
+    #[cfg(feature = "plugin")]
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        env = "CAMELOL_PLUGIN",
+        conflicts_with = "rules"
+    )]
+    plugin: Option<PathBuf>,
+    /// Rule pack to take the preset, rules and weights from when they aren't given here, a
+    /// file or the name of one installed with `camelol pack import`
+    #[arg(long, global = true, value_name = "PACK", env = "CAMELOL_PACK")]
+    pack: Option<String>,
+    /// Scale what a transition costs paths for this run, like `Vertical=0.5` to favor it or
+    /// `ChangeIndex(7)=3` to avoid it, from 0 to 1000. Can be repeated
+    #[arg(long, global = true, value_name = "TRANSITION=WEIGHT", value_parser = parse_preference)]
+    prefer: Vec<(ScaleTransition, f64)>,
+    /// How far your decks' pitch faders go, like 8 or 16 percent, so plans and key shifts only
+    /// ask for tempo changes they can reach
+    #[arg(
+        long,
+        global = true,
+        value_name = "PERCENT",
+        env = "CAMELOL_PITCH_RANGE",
+        value_parser = parse_pitch_range
+    )]
+    pitch_range: Option<f64>,
+    /// Leave out every transition whose tonic moves by a semitone or a tritone, whatever the
+    /// preset or rules allow
+    #[arg(long, global = true, env = "CAMELOL_NO_CLASHES")]
+    no_clashes: bool,
+    /// Log what's going on to stderr: -v for progress, -vv for details and -vvv for every
+    /// search
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Only log errors, not warnings
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+/// Writes the library's log events at or above `level` to stderr, like
+/// `INFO analyzed path=/music/a.mp3 key=8A`.
+struct StderrLog {
+    level: tracing::Level,
+    next_span: std::sync::atomic::AtomicU64,
+}
+
+/// The message of an event and its other fields as `name=value` pairs.
+#[derive(Default)]
+struct EventLine {
+    message: String,
+    fields: String,
+}
+
+impl tracing::field::Visit for EventLine {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.record_debug(field, &format_args!("{value}"));
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields += &format!(" {}={value:?}", field.name());
+        }
+    }
+}
+
+impl tracing::Subscriber for StderrLog {
+    fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+        // Only the library's events, not those of the HTTP server's dependencies.
+        *metadata.level() <= self.level && metadata.target().starts_with("camelol")
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        Some(tracing::level_filters::LevelFilter::from_level(self.level))
+    }
+
+    fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let id = self
+            .next_span
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        tracing::span::Id::from_u64(id)
+    }
+
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        let mut line = EventLine::default();
+        event.record(&mut line);
+        eprintln!(
+            "{:>5} {}{}",
+            event.metadata().level(),
+            line.message,
+            line.fields
+        );
+    }
+
+    fn enter(&self, _: &tracing::span::Id) {}
+
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+impl Cli {
+    /// Logs warnings and errors, more with each `-v` and only errors with `-q`.
+    fn log(&self) -> StderrLog {
+        let level = match (self.quiet, self.verbose) {
+            (true, _) => tracing::Level::ERROR,
+            (false, 0) => tracing::Level::WARN,
+            (false, 1) => tracing::Level::INFO,
+            (false, 2) => tracing::Level::DEBUG,
+            (false, _) => tracing::Level::TRACE,
+        };
+        StderrLog {
+            level,
+            // Span ids can't be 0.
+            next_span: std::sync::atomic::AtomicU64::new(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn support(self) -> ColorSupport {
         match self {
-            ScaleKind::Minor => ScaleKind::Major,
-            ScaleKind::Major => ScaleKind::Minor,
+            ColorChoice::Auto => ColorSupport::detect(),
+            ColorChoice::Always => ColorSupport::depth(),
+            ColorChoice::Never => ColorSupport::None,
         }
     }
 }
 
-impl Display for ScaleKind {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+#[derive(Clone, Copy, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum KeyChoice {
+    #[default]
+    Camelot,
+    Musical,
+    Both,
+}
+
+impl KeyChoice {
+    fn display(self) -> KeyDisplay {
         match self {
-            Self::Minor => write!(f, "A"),
-            Self::Major => write!(f, "B"),
+            KeyChoice::Camelot => KeyDisplay::Camelot,
+            KeyChoice::Musical => KeyDisplay::Musical,
+            KeyChoice::Both => KeyDisplay::Both,
         }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
-struct Scale {
-    index: usize,
-    kind: ScaleKind,
+#[derive(Clone, Copy, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Spelling {
+    #[default]
+    Conventional,
+    Flats,
+    Sharps,
+}
+
+fn parse_accidental(input: &str) -> Result<Accidental, String> {
+    match input {
+        "flat" | "b" => Ok(Accidental::Flat),
+        "sharp" | "#" => Ok(Accidental::Sharp),
+        _ => Err(format!("expected `flat` or `sharp`, got `{input}`")),
+    }
+}
+
+/// Splits `KEY=flat`. The key is parsed later, once the locale is known.
+fn parse_spelling(input: &str) -> Result<(String, Accidental), String> {
+    let (key, accidental) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected `KEY=flat` or `KEY=sharp`, got `{input}`"))?;
+    Ok((key.to_string(), parse_accidental(accidental)?))
+}
+
+/// Parses a wheel numbering: `camelot`, `open-key`, or the key a wheel number stands for, like
+/// `1A=Am` or `8B=C major`.
+fn parse_numbering(input: &str) -> Result<WheelNumbering, String> {
+    match input.trim().to_lowercase().as_str() {
+        "camelot" => return Ok(WheelNumbering::CAMELOT),
+        "open-key" => return Ok(WheelNumbering::OPEN_KEY),
+        _ => {}
+    }
+    let error = || {
+        format!(
+            "expected `camelot`, `open-key` or the key a wheel number stands for like `1A=Am`, \
+             got `{input}`"
+        )
+    };
+    let (number, key) = input.split_once('=').ok_or_else(error)?;
+    let number: Scale = number.trim().parse().map_err(|_| error())?;
+    let key = Scale::from_key_name(key, Locale::English).map_err(|_| error())?;
+    WheelNumbering::aligning(number, key)
+        .ok_or_else(|| format!("`{input}`: a minor key's number stands for a minor key"))
+}
+
+/// The tempo of an Ableton Link session on the local network, waiting a few seconds for a peer
+/// to announce it.
+#[cfg(feature = "link")]
+fn link_tempo() -> Result<f64, Box<dyn Error>> {
+    let link = camelol::LinkListener::join()?;
+    Ok(link
+        .next_tempo(Some(std::time::Duration::from_secs(3)))?
+        .ok_or("no Ableton Link session announced its tempo within 3 seconds")?)
+}
+
+/// Parses a pitch range like `8`, `8%` or `±16%`.
+fn parse_pitch_range(input: &str) -> Result<f64, String> {
+    input
+        .trim()
+        .trim_start_matches('±')
+        .trim_end_matches('%')
+        .trim()
+        .parse()
+        .ok()
+        .filter(|range: &f64| range.is_finite() && *range > 0.0)
+        .ok_or_else(|| format!("expected a pitch range in percent like `8`, got `{input}`"))
 }
 
-fn mod_cyclic(num: isize, modulus: usize) -> isize {
-    let modulus = modulus as isize;
-    ((num % modulus) + modulus) % modulus
+/// Parses a positive number of seconds, like `2.5`.
+fn parse_seconds(input: &str) -> Result<std::time::Duration, String> {
+    input
+        .trim()
+        .parse()
+        .ok()
+        .filter(|seconds: &f64| *seconds > 0.0)
+        .and_then(|seconds| std::time::Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("expected a number of seconds over 0, got `{input}`"))
+}
+
+/// A transition written like `Vertical` or `ChangeIndex(-7)`, in any case.
+fn parse_transition(name: &str) -> Result<ScaleTransition, String> {
+    let name = name.trim().replace('+', "");
+    camelol::possible_transitions()
+        .into_iter()
+        .find(|transition| format!("{transition:?}").eq_ignore_ascii_case(&name))
+        .ok_or_else(|| {
+            let names: Vec<String> = camelol::possible_transitions()
+                .iter()
+                .map(|transition| format!("{transition:?}"))
+                .collect();
+            format!(
+                "unknown transition `{name}`, expected one of {}",
+                names.join(", ")
+            )
+        })
+}
+
+/// Splits `TRANSITION=WEIGHT`, where the transition is written like `Vertical` or
+/// `ChangeIndex(-7)`.
+fn parse_preference(input: &str) -> Result<(ScaleTransition, f64), String> {
+    let (name, weight) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected `TRANSITION=WEIGHT`, got `{input}`"))?;
+    let transition = parse_transition(name)?;
+    let weight = weight
+        .trim()
+        .parse()
+        .ok()
+        .filter(|weight: &f64| (0.0..=MAX_PREFERENCE).contains(weight))
+        .ok_or_else(|| format!("expected a weight from 0 to {MAX_PREFERENCE}, got `{weight}`"))?;
+    Ok((transition, weight))
+}
+
+/// Under `--prefer`, steps cost hundredths of what they would otherwise, so weights like 0.5
+/// still tell steps apart.
+const PREFERENCE_SCALE: f64 = 100.0;
+/// The heaviest `--prefer` weight. Any step cost, scaled up by it and [`PREFERENCE_SCALE`],
+/// still fits the `u32` a path search adds up, so weights never wrap around or saturate.
+const MAX_PREFERENCE: f64 = 1000.0;
+
+#[derive(Clone, Copy, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum LocaleChoice {
+    #[default]
+    English,
+    German,
 }
 
-impl Scale {
-    pub fn swap_kind(self) -> Self {
-        Self {
-            kind: self.kind.swap(),
-            ..self
+impl LocaleChoice {
+    fn locale(self) -> Locale {
+        match self {
+            LocaleChoice::English => Locale::English,
+            LocaleChoice::German => Locale::German,
         }
     }
+}
+
+#[cfg(feature = "audio")]
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum ProfileChoice {
+    /// Krumhansl and Kessler's, from listeners rating notes
+    #[default]
+    Krumhansl,
+    /// Temperley's, from classical scores
+    Temperley,
+    /// Sha'ath's from KeyFinder, tuned on electronic dance music
+    #[value(alias = "edmk")]
+    Shaath,
+}
 
-    pub fn change_index(self, amount: isize) -> Self {
-        let index = mod_cyclic((self.index as isize) + amount, 12);
-        Self {
-            index: index as usize,
-            ..self
+#[cfg(feature = "audio")]
+impl ProfileChoice {
+    fn profile(self) -> camelol::KeyProfile {
+        match self {
+            ProfileChoice::Krumhansl => camelol::KeyProfile::Krumhansl,
+            ProfileChoice::Temperley => camelol::KeyProfile::Temperley,
+            ProfileChoice::Shaath => camelol::KeyProfile::Shaath,
         }
     }
 }
 
-impl Display for Scale {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}", self.index + 1, self.kind)
+impl Cli {
+    fn notation(&self) -> camelol::Result<Notation> {
+        let mut notation = Notation::new(self.keys.unwrap_or_default().display());
+        notation.locale = self.locale.unwrap_or_default().locale();
+        notation.numbering = self.numbering.unwrap_or_default();
+        match self.spelling.unwrap_or_default() {
+            Spelling::Conventional => {}
+            Spelling::Flats => notation.spell_all(Accidental::Flat),
+            Spelling::Sharps => notation.spell_all(Accidental::Sharp),
+        }
+        for (key, accidental) in &self.spell {
+            notation.spell(notation.parse(key)?, *accidental);
+        }
+        Ok(notation)
     }
 }
 
-fn scale(index: usize, kind: ScaleKind) -> Scale {
-    Scale { index, kind }
+/// Defaults for options left off the command line, from a TOML file like
+///
+/// ```toml
+/// keys = "both"
+/// preset = "classic"
+/// paths = 5
+/// library = "~/Music/tracks.txt"
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    color: Option<ColorChoice>,
+    plain: bool,
+    keys: Option<KeyChoice>,
+    spelling: Option<Spelling>,
+    /// Which musical keys the wheel numbers stand for, like `numbering = "open-key"`.
+    numbering: Option<String>,
+    locale: Option<LocaleChoice>,
+    preset: Option<PresetChoice>,
+    rules: Option<PathBuf>,
+    pack: Option<String>,
+    pitch_range: Option<f64>,
+    no_clashes: bool,
+    /// Number of paths `paths` finds.
+    paths: Option<usize>,
+    /// Track library for the commands that read or add to one.
+    library: Option<PathBuf>,
 }
 
-fn make_nodes() -> Vec<Scale> {
-    (0..=11)
-        .flat_map(|i| [scale(i, ScaleKind::Minor), scale(i, ScaleKind::Major)])
-        .collect::<Vec<_>>()
+/// Expands a leading `~` to the home directory.
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path,
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-enum ScaleTransition {
-    Vertical,
-    Diagonal,
-    ChangeIndex(isize),
-    MajorToMinor,
-    FlatToMinor,
+impl Config {
+    /// `$XDG_CONFIG_HOME/camelol`, or `~/.config/camelol`.
+    fn dir() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|config| !config.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?;
+        Some(config.join("camelol"))
+    }
+
+    /// `config.toml` in [`Config::dir`].
+    fn default_path() -> Option<PathBuf> {
+        Some(Self::dir()?.join("config.toml"))
+    }
+
+    /// Reads the config file at `path`, or at the default path if there is one there.
+    fn load(path: Option<&std::path::Path>) -> Result<Self, Box<dyn Error>> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if !required && error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(error) => return Err(format!("{}: {error}", path.display()).into()),
+        };
+        let mut config: Self =
+            toml::from_str(&text).map_err(|error| format!("{}: {error}", path.display()))?;
+        config.rules = config.rules.map(expand_home);
+        config.library = config.library.map(expand_home);
+        Ok(config)
+    }
+
+    /// Overrides the settings that aren't options of their own with `CAMELOL_LIBRARY` and
+    /// `CAMELOL_PATHS`, the rest are read from the environment by clap.
+    fn with_environment(mut self) -> Result<Self, Box<dyn Error>> {
+        if let Some(library) = std::env::var_os("CAMELOL_LIBRARY").filter(|var| !var.is_empty()) {
+            self.library = Some(PathBuf::from(library));
+        }
+        if let Ok(paths) = std::env::var("CAMELOL_PATHS") {
+            self.paths = Some(
+                paths
+                    .parse()
+                    .map_err(|_| format!("CAMELOL_PATHS: expected a number, got `{paths}`"))?,
+            );
+        }
+        Ok(self)
+    }
+
+    /// The track library given on the command line, or else the configured one.
+    fn library(&self, library: Option<PathBuf>) -> Result<PathBuf, Box<dyn Error>> {
+        library
+            .or_else(|| self.library.clone())
+            .ok_or_else(|| "no track library given, and none set in the config file".into())
+    }
 }
 
-fn possible_transitions() -> Vec<ScaleTransition> {
-    vec![
-        ScaleTransition::Vertical,
-        ScaleTransition::Diagonal,
-        ScaleTransition::MajorToMinor,
-        ScaleTransition::FlatToMinor,
-        ScaleTransition::ChangeIndex(1),
-        ScaleTransition::ChangeIndex(2),
-        ScaleTransition::ChangeIndex(7),
-        ScaleTransition::ChangeIndex(-1),
-        ScaleTransition::ChangeIndex(-2),
-        ScaleTransition::ChangeIndex(-7),
-    ]
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Mermaid,
+    Graphml,
 }
 
-fn make_transition(scale: Scale, transition: ScaleTransition) -> Scale {
-    match transition {
-        ScaleTransition::Vertical => scale.swap_kind(),
-        ScaleTransition::ChangeIndex(amount) => scale.change_index(amount),
-        ScaleTransition::Diagonal if matches!(scale.kind, ScaleKind::Major) => {
-            scale.swap_kind().change_index(1)
+/// The rule pack format this version reads and writes.
+const RULE_PACK_VERSION: u32 = 1;
+
+/// A preset, transition rules and weights shared as one file, so harmonic rules can be passed
+/// around, like
+///
+/// ```toml
+/// version = 1
+/// name = "steady-mode"
+/// description = "Classic moves, and changing mode costs extra"
+/// preset = "classic"
+/// rules = "cost = 1 + (from_minor != to_minor) * 2"
+///
+/// [prefer]
+/// Vertical = 0.5
+/// ```
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RulePack {
+    version: u32,
+    /// What `camelol pack import` installs it as.
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preset: Option<PresetChoice>,
+    /// A rule script, like `--rules` reads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rules: Option<String>,
+    no_clashes: bool,
+    /// Weights like `--prefer` takes, by transition.
+    prefer: std::collections::BTreeMap<String, f64>,
+}
+
+impl RulePack {
+    /// Where `camelol pack import` installs packs.
+    fn dir() -> Option<PathBuf> {
+        Some(Config::dir()?.join("packs"))
+    }
+
+    /// Reads the pack at `pack`, or the installed pack named `pack` if there's no such file.
+    fn load(pack: &str) -> Result<Self, Box<dyn Error>> {
+        let mut path = PathBuf::from(pack);
+        if !path.exists() {
+            if let Some(dir) = Self::dir() {
+                path = dir.join(format!("{pack}.toml"));
+            }
         }
-        ScaleTransition::Diagonal if matches!(scale.kind, ScaleKind::Minor) => {
-            scale.swap_kind().change_index(-1)
+        let text = fs::read_to_string(&path).map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => format!("no rule pack file or installed pack `{pack}`"),
+            _ => format!("{}: {error}", path.display()),
+        })?;
+        Ok(Self::parse(&text).map_err(|error| format!("{}: {error}", path.display()))?)
+    }
+
+    /// Parses a pack, failing on packs newer than this version reads and on rules or weights
+    /// that wouldn't work.
+    fn parse(text: &str) -> Result<Self, String> {
+        let pack: Self = toml::from_str(text).map_err(|error| error.to_string())?;
+        match pack.version {
+            0 => return Err("missing `version`".to_string()),
+            version if version > RULE_PACK_VERSION => {
+                return Err(format!(
+                    "rule pack version {version} is newer than this camelol reads, \
+                     {RULE_PACK_VERSION}"
+                ));
+            }
+            _ => {}
         }
-        ScaleTransition::FlatToMinor if matches!(scale.kind, ScaleKind::Minor) => {
-            scale.swap_kind().change_index(-4)
+        if let Some(rules) = &pack.rules {
+            RuleScript::parse(rules).map_err(|error| format!("rules: {error}"))?;
         }
-        ScaleTransition::FlatToMinor if matches!(scale.kind, ScaleKind::Major) => {
-            scale.swap_kind().change_index(4)
+        pack.preferences()?;
+        Ok(pack)
+    }
+
+    fn preferences(&self) -> Result<Vec<(ScaleTransition, f64)>, String> {
+        self.prefer
+            .iter()
+            .map(|(transition, weight)| parse_preference(&format!("{transition}={weight}")))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum PresetChoice {
+    Strict,
+    Classic,
+    #[default]
+    Extended,
+}
+
+impl PresetChoice {
+    fn preset(self) -> Preset {
+        match self {
+            PresetChoice::Strict => Preset::Strict,
+            PresetChoice::Classic => Preset::Classic,
+            PresetChoice::Extended => Preset::Extended,
         }
-        ScaleTransition::MajorToMinor if matches!(scale.kind, ScaleKind::Minor) => {
-            scale.swap_kind().change_index(3)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CostChoice {
+    Hops,
+    Semitones,
+}
+
+impl CostChoice {
+    fn cost(self) -> PathCost {
+        match self {
+            CostChoice::Hops => PathCost::Hops,
+            CostChoice::Semitones => PathCost::Semitones,
         }
-        ScaleTransition::MajorToMinor if matches!(scale.kind, ScaleKind::Major) => {
-            scale.swap_kind().change_index(-3)
+    }
+}
+
+/// The notations `convert` can write a key in.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum KeyNotation {
+    Camelot,
+    OpenKey,
+    Musical,
+    German,
+}
+
+impl KeyNotation {
+    fn write(self, scale: Scale, mut notation: Notation) -> String {
+        match self {
+            KeyNotation::Camelot => notation.numbering.standard(scale).to_string(),
+            KeyNotation::OpenKey => notation.numbering.standard(scale).open_key().to_string(),
+            KeyNotation::Musical | KeyNotation::German => {
+                notation.locale = match self {
+                    KeyNotation::German => Locale::German,
+                    _ => Locale::English,
+                };
+                notation.key_name(scale)
+            }
         }
-        _ => unreachable!(),
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-struct NodeDistance {
-    node: NodeIndex,
-    distance: usize,
+#[derive(Subcommand)]
+enum Command {
+    /// Find the shortest paths from one key to another
+    Paths {
+        /// Key to start from, or several separated by commas to start from any of them
+        from: String,
+        /// Key to end on, or several separated by commas to end on any of them
+        to: String,
+        /// Number of paths to return, 10 unless the config file sets `paths`
+        #[arg(short)]
+        n: Option<usize>,
+        /// Rank paths by their number of transitions, or by how far the tonic moves in total.
+        /// Defaults to the cost rule of `--rules`, or to transitions
+        #[arg(long, value_enum)]
+        by: Option<CostChoice>,
+        /// Take at most this many energy boosts, `ChangeIndex(1)` or `ChangeIndex(2)`, in a row
+        #[arg(long, value_name = "N")]
+        max_boost_run: Option<usize>,
+        /// Explain every step of the paths in plain language
+        #[arg(long)]
+        explain: bool,
+        /// Draw the wheel with the shortest path overlaid
+        #[arg(long)]
+        wheel: bool,
+        /// Write an SVG image of the wheel with the shortest path overlaid
+        #[arg(long)]
+        svg: Option<PathBuf>,
+        /// Write the same image as a PNG, for apps that don't show SVG (png feature)
+        #[cfg(feature = "png")]
+        #[arg(long)]
+        png: Option<PathBuf>,
+        /// Width and height of the PNG in pixels
+        #[cfg(feature = "png")]
+        #[arg(long, value_name = "PIXELS", default_value_t = 520)]
+        png_size: u32,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, env = "CAMELOL_FORMAT")]
+        format: OutputFormat,
+        /// Play the shortest path as arpeggios on a raw MIDI output port, like /dev/snd/midiC1D0
+        #[cfg(feature = "midi")]
+        #[arg(long, value_name = "DEVICE")]
+        preview: Option<PathBuf>,
+    },
+    /// Find the shortest path for every `FROM TO` pair in a file, in parallel
+    Batch {
+        /// File with one pair of keys per line, `-` for stdin
+        pairs: PathBuf,
+        /// Number of paths to return per pair
+        #[arg(short, default_value_t = 1)]
+        n: usize,
+    },
+    /// Print the whole transition graph
+    Graph {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, env = "CAMELOL_FORMAT")]
+        format: OutputFormat,
+    },
+    /// Report on the configured transitions
+    Stats {
+        #[command(subcommand)]
+        report: StatsReport,
+    },
+    /// Share the preset, rules and weights in use as a rule pack file, or install one
+    Pack {
+        #[command(subcommand)]
+        action: PackAction,
+    },
+    /// Rank keys by how cheaply they can be reached from a key, with the cheapest path to each
+    Nearest {
+        from: String,
+        /// Keys to rank, like those of the tracks that could come next
+        #[arg(required = true)]
+        targets: Vec<String>,
+        /// Rank by number of transitions or by how far the tonic moves, see `paths --by`
+        #[arg(long, value_enum)]
+        by: Option<CostChoice>,
+    },
+    /// Find a cheap path visiting every one of a set of keys in any order, like those of the
+    /// tracks promised to be played
+    Tour {
+        /// Keys to visit
+        #[arg(required = true, value_name = "KEY")]
+        stops: Vec<String>,
+        /// Start at this key, instead of whichever of the keys makes the path cheapest
+        #[arg(long, value_name = "KEY")]
+        from: Option<String>,
+        /// Rank by number of transitions or by how far the tonic moves, see `paths --by`
+        #[arg(long, value_enum)]
+        by: Option<CostChoice>,
+    },
+    /// Print the wheel, marking a key and the keys one transition away from it
+    Wheel { key: Option<String> },
+    /// List the keys one transition before a key, to plan backwards from a track to end on
+    Predecessors { key: String },
+    /// List the keys reachable from a key within a number of transitions
+    Reach {
+        from: String,
+        /// Maximum number of transitions
+        steps: usize,
+    },
+    /// List the pivot keys between two keys, within a number of transitions of both, for
+    /// tracks that bridge a part of a set in one key and a part in the other
+    Bridges {
+        from: String,
+        to: String,
+        /// Maximum number of transitions from the first key and to the second
+        #[arg(default_value_t = 1)]
+        steps: usize,
+    },
+    /// Score how well two keys mix. Modal keys like `"D Dorian"` are scored from the nearest
+    /// wheel key, with a penalty for modes far from major and minor
+    Compat { from: String, to: String },
+    /// List the notes and chords two keys share, to judge whether an acapella in one will sit
+    /// over an instrumental in the other
+    Mashup { from: String, to: String },
+    /// List the tempos a track can be pitched to with key lock off, within the pitch range, and
+    /// the key it sounds in at each, to stretch it into the key of another
+    Stretch {
+        key: String,
+        /// Tempo of the track
+        bpm: f64,
+        /// Also print how many transitions each key is from this one, like the key playing
+        #[arg(long, value_name = "KEY")]
+        to: Option<String>,
+    },
+    /// Put keys in an order that mixes smoothly from the first, like the keys of tracks picked
+    /// for a set, and print the path between every two
+    Sequence {
+        #[arg(required = true, value_name = "KEYS")]
+        scales: Vec<String>,
+        /// Order the keys on the 24-position quarter-tone wheel instead, with its own moves,
+        /// where 8.5A is the key a neutral third above 8A
+        #[arg(long)]
+        quarter_tones: bool,
+    },
+    /// List the relative, parallel, dominant and subdominant keys of a key, by camelot code and
+    /// musical name
+    Relations { key: String },
+    /// List the notes of keys, like `A B C D E F G` for 8A
+    Notes {
+        #[arg(required = true, value_name = "KEYS")]
+        scales: Vec<String>,
+    },
+    /// List the keys a chord progression like `Am F C G` fits best, with each chord's roman
+    /// numeral in them, for tracks whose key label is ambiguous
+    Progression {
+        /// The chords, as one argument or several
+        #[arg(required = true)]
+        chords: Vec<String>,
+        /// Number of keys to list
+        #[arg(short, default_value_t = 3)]
+        n: usize,
+    },
+    /// Plan how a track modulates from one key to another, with the chords to pivot on and
+    /// the new key's dominant at every step, for producing rather than mixing
+    Modulate {
+        from: String,
+        to: String,
+        /// Number of routes to propose
+        #[arg(short, default_value_t = 3)]
+        n: usize,
+        /// Rank routes by number of key changes or by how far the tonic moves, see `paths --by`
+        #[arg(long, value_enum)]
+        by: Option<CostChoice>,
+    },
+    /// Write a key in every notation: camelot, Open Key, and English and German key names.
+    /// Reads any of them
+    Convert {
+        key: String,
+        /// Print only the key in this notation, for scripts
+        #[arg(long, value_enum)]
+        to: Option<KeyNotation>,
+    },
+    /// Generate a random harmonic journey through the wheel
+    Journey {
+        from: String,
+        /// Number of transitions to take
+        length: usize,
+        /// Seed for the random number generator, picked at random if omitted
+        #[arg(long)]
+        seed: Option<u64>,
+        /// How adventurous every step is: 0 always takes the smoothest transition, 1 picks
+        /// transitions by how smooth they are and higher picks them more evenly
+        #[arg(long, default_value_t = 1.0)]
+        temperature: f64,
+    },
+    /// Suggest the keys to play next. Keys or audio files piped in, one per line, are ranked
+    /// instead, like `ls *.mp3 | camelol suggest --from 8A`
+    Suggest {
+        #[arg(required_unless_present = "from_option")]
+        from: Option<String>,
+        /// The key to suggest from, for pipelines where `--from` reads better
+        #[arg(
+            long = "from",
+            id = "from_option",
+            value_name = "FROM",
+            conflicts_with = "from"
+        )]
+        from_option: Option<String>,
+        /// Number of suggestions to show, 5 by default or every piped key and file
+        #[arg(short)]
+        n: Option<usize>,
+        /// Track library to find the keys of piped files in, defaults to `library` in the config
+        /// file. Files missing from it are read from their tags with the audio feature
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Only rank piped tracks whose tempo falls in this range of BPM, like `122-128`, as is
+        /// or at half or double time. Tracks of unknown tempo and bare keys are left out
+        #[arg(long, value_name = "MIN-MAX")]
+        bpm_range: Option<camelol::TempoRange>,
+        /// Only rank piped tracks within the `--pitch-range` of the tempo an Ableton Link
+        /// session on the local network is playing at, like `--bpm-range` does (link feature)
+        #[cfg(feature = "link")]
+        #[arg(long, conflicts_with = "bpm_range")]
+        link: bool,
+        /// Rank piped tracks by their keys alone, without scoring tracks whose key is unsure
+        /// lower
+        #[arg(long)]
+        ignore_confidence: bool,
+        /// File of past sets, one set of keys per line, to learn mixing habits from
+        #[arg(long)]
+        history: Option<PathBuf>,
+        /// Share of the score given to the learned habits over wheel distance
+        #[arg(long, default_value_t = 0.5)]
+        blend: f64,
+    },
+    /// Record the tracks played tonight and get suggestions that account for them
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+        /// Session log to read and append to
+        #[arg(long, default_value = "camelol-session.log")]
+        file: PathBuf,
+        /// Number of suggestions to show
+        #[arg(short, default_value_t = 5)]
+        n: usize,
+        /// File of past sets, one set of keys per line, to learn mixing habits from
+        #[arg(long)]
+        history: Option<PathBuf>,
+        /// Share of the score given to the learned habits over wheel distance
+        #[arg(long, default_value_t = 0.5)]
+        blend: f64,
+        /// Stop suggesting the current key once this many tracks in a row were played in it
+        #[arg(long, value_name = "N")]
+        max_key_run: Option<usize>,
+        /// Score keys played in the last N tracks lower, the latest the most, so the set doesn't
+        /// go back and forth between two keys
+        #[arg(long, value_name = "N")]
+        avoid_recent: Option<usize>,
+        #[cfg(feature = "midi")]
+        #[command(flatten)]
+        midi: MidiOutArgs,
+        #[command(flatten)]
+        osc: OscArgs,
+    },
+    /// Serve the HTTP API
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:3000", env = "CAMELOL_ADDR")]
+        addr: std::net::SocketAddr,
+        /// Seconds a path query may search before it fails, 10 if omitted
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            env = "CAMELOL_SEARCH_TIMEOUT",
+            value_parser = parse_seconds
+        )]
+        search_timeout: Option<std::time::Duration>,
+        /// Send the `/live` session's keys to hardware
+        #[cfg(feature = "midi")]
+        #[command(flatten)]
+        midi: MidiOutArgs,
+        /// Send the `/live` session's keys and suggestions over OSC
+        #[command(flatten)]
+        osc: OscArgs,
+        #[command(flatten)]
+        overlay: OverlayArgs,
+    },
+    /// Answer JSON-RPC requests on a unix socket, keeping everything loaded between queries
+    #[cfg(unix)]
+    Daemon {
+        /// Socket to listen on
+        #[arg(long, default_value = "/tmp/camelol.sock", env = "CAMELOL_SOCKET")]
+        socket: PathBuf,
+        /// Seconds a path query may search before it fails, 10 if omitted
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            env = "CAMELOL_SEARCH_TIMEOUT",
+            value_parser = parse_seconds
+        )]
+        search_timeout: Option<std::time::Duration>,
+        /// Track library to keep loaded for the track queries, defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+    },
+    /// Estimate the key of MIDI files from the notes they play
+    #[cfg(feature = "midi")]
+    Detect {
+        /// Standard MIDI files to analyze
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Listen to a MIDI keyboard, estimate the key of what's played and suggest what to mix
+    /// into each time it moves to another key, for sets mixing live playing with tracks
+    #[cfg(feature = "midi")]
+    Keyboard {
+        /// Raw MIDI input port of the keyboard, like /dev/snd/midiC1D0
+        device: PathBuf,
+        /// Number of suggestions to show
+        #[arg(short, default_value_t = 5)]
+        n: usize,
+        #[command(flatten)]
+        midi: MidiOutArgs,
+        #[command(flatten)]
+        osc: OscArgs,
+        #[command(flatten)]
+        overlay: OverlayArgs,
+    },
+    /// Estimate the key of each stem of two tracks and show which stems layer well over each
+    /// other, for mashups
+    #[cfg(any(feature = "audio", feature = "midi"))]
+    Stems {
+        /// Folder with the stems of the first track, like a stem separator writes, or one stem
+        a: PathBuf,
+        /// Folder with the stems of the second track, or one stem
+        b: PathBuf,
+    },
+    /// Follow what a media player is playing and suggest what to play next: MPRIS players on
+    /// Linux, through `playerctl`, and the media sessions of the System Media Transport
+    /// Controls on Windows, through PowerShell
+    #[cfg(any(target_os = "linux", windows))]
+    Listen {
+        /// Track library with one `<key> <artist> - <title>` line per track, like `watch` writes.
+        /// Defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Player to follow, like `spotify` or `vlc`, instead of the active one. On Windows, part
+        /// of the playing app's id, like `Spotify`
+        #[arg(long)]
+        player: Option<String>,
+        /// Number of suggestions to show
+        #[arg(short, default_value_t = 5)]
+        n: usize,
+        /// Stop suggesting the current key once this many tracks in a row were played in it
+        #[arg(long, value_name = "N")]
+        max_key_run: Option<usize>,
+        /// Score keys played in the last N tracks lower, the latest the most, so the set doesn't
+        /// go back and forth between two keys
+        #[arg(long, value_name = "N")]
+        avoid_recent: Option<usize>,
+        #[cfg(feature = "midi")]
+        #[command(flatten)]
+        midi: MidiOutArgs,
+        #[command(flatten)]
+        osc: OscArgs,
+        #[command(flatten)]
+        overlay: OverlayArgs,
+    },
+    /// Read or detect the key and tempo of every audio file under a directory, on every core,
+    /// and print them as a track library
+    #[cfg(feature = "audio")]
+    Analyze {
+        /// Directory to analyze, including subdirectories
+        dir: PathBuf,
+        /// Track library to add the tracks to, created if missing, as each is analyzed. Files
+        /// already in it and not modified since are skipped, so an interrupted scan picks up
+        /// where it stopped, and modified ones are analyzed again. Defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Write a CSV report of every file, with where its key came from or why it failed
+        #[arg(long, value_name = "FILE")]
+        csv: Option<PathBuf>,
+        /// Detect keys and tempos even for files whose tags have them
+        #[arg(long)]
+        detect: bool,
+        /// Key profile to detect keys with, as some fit some genres better
+        #[arg(long, value_enum, default_value_t = ProfileChoice::default())]
+        profile: ProfileChoice,
+        /// Instead of a track library, print the key every profile detects in every file, and
+        /// how many of them agree, to find the keys worth checking by ear
+        #[arg(
+            long,
+            conflicts_with_all = ["library", "csv", "detect", "dedupe", "loudness", "profile"]
+        )]
+        compare_profiles: bool,
+        /// Leave out files that are another encoding of a file already found, judged by their
+        /// Chromaprint fingerprints, so duplicates stay out of the library. Takes `fpcalc`
+        #[arg(long)]
+        dedupe: bool,
+        /// Also decode files whose tags have a key but no Mixed In Key energy rating, to
+        /// estimate their energy from how loud they are, as analyzed files get
+        #[arg(long)]
+        loudness: bool,
+        /// SQLite database of past analyses, so unchanged files aren't analyzed again
+        #[cfg(feature = "cache")]
+        #[arg(long, value_name = "FILE")]
+        cache: Option<PathBuf>,
+    },
+    /// Watch a directory and add the key and tempo of new audio files to a track library
+    #[cfg(feature = "audio")]
+    Watch {
+        /// Directory to watch, including subdirectories
+        dir: PathBuf,
+        /// Track library to append to, created if missing. Defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Seconds between scans of the directory
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+        /// Also analyze the files already there when starting
+        #[arg(long)]
+        existing: bool,
+        /// Key profile to detect keys with, as some fit some genres better
+        #[arg(long, value_enum, default_value_t = ProfileChoice::default())]
+        profile: ProfileChoice,
+        /// SQLite database of past analyses, so unchanged files aren't analyzed again
+        #[cfg(feature = "cache")]
+        #[arg(long, value_name = "FILE")]
+        cache: Option<PathBuf>,
+    },
+    /// Detect the key of every audio file under a directory with a key tag, on every core, and
+    /// list the files whose tag disagrees, with how confident the detection is
+    #[cfg(feature = "audio")]
+    Audit {
+        /// Directory to audit, including subdirectories
+        dir: PathBuf,
+        /// Key profile to detect keys with, as some fit some genres better
+        #[arg(long, value_enum, default_value_t = ProfileChoice::default())]
+        profile: ProfileChoice,
+        /// Leave out mismatches detected with less confidence than this, from 0 to 1
+        #[arg(long, default_value_t = 0.0)]
+        min_confidence: f64,
+        /// Rewrite the key tag of every file listed with the detected key. Only MP3 and FLAC
+        /// tags can be written
+        #[arg(long, conflicts_with = "interactive")]
+        fix: bool,
+        /// Ask about every file listed: enter or `y` writes the detected key, `n` keeps the
+        /// tag, a key writes that key instead and `q` stops
+        #[arg(long)]
+        interactive: bool,
+    },
+    /// Print the tracks of a Spotify playlist with their keys and tempos as a track library
+    #[cfg(feature = "spotify")]
+    Spotify {
+        /// Playlist link, `spotify:playlist:` URI or id
+        playlist: String,
+        /// OAuth access token for the Spotify Web API
+        #[arg(long, env = "SPOTIFY_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+    /// Look up the keys and tempos of tracks on Beatport and add them to a track library
+    #[cfg(feature = "beatport")]
+    Beatport {
+        /// File with one `Artist - Title` line per track to look up
+        tracks: PathBuf,
+        /// Track library to add to, created if missing. Tracks already in it aren't looked up
+        /// again. Defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// OAuth access token for the Beatport API
+        #[arg(
+            long,
+            env = "BEATPORT_TOKEN",
+            hide_env_values = true,
+            required_unless_present = "dry_run"
+        )]
+        token: Option<String>,
+        /// Print what would be looked up instead of fetching it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Look up the keys and tempos of tracks on MusicBrainz and AcousticBrainz and add them to
+    /// a track library
+    #[cfg(feature = "musicbrainz")]
+    Musicbrainz {
+        /// File with one `Artist - Title` line per track to look up, or the path of an audio
+        /// file to look up by its fingerprint
+        tracks: PathBuf,
+        /// Track library to add to, created if missing. Tracks already in it aren't looked up
+        /// again. Defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// AcoustID API key to look up audio files by their Chromaprint fingerprint with, which
+        /// takes Chromaprint's `fpcalc`
+        #[arg(long, env = "ACOUSTID_KEY", hide_env_values = true)]
+        acoustid_key: Option<String>,
+        /// Print what would be looked up instead of fetching it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the tracks of an iTunes or Music.app library with keys in their comments or
+    /// grouping as a track library
+    #[cfg(feature = "itunes")]
+    Itunes {
+        /// `Library.xml` exported from iTunes or Music.app
+        library: PathBuf,
+        /// Only print the tracks of this playlist, in its order
+        #[arg(long)]
+        playlist: Option<String>,
+    },
+    /// Print the analyzed tracks of an Engine DJ library as a track library
+    #[cfg(feature = "engine")]
+    EngineDj {
+        /// The `Engine Library` folder or its `Database2/m.db`
+        library: PathBuf,
+        /// Only print the tracks of this playlist, in its order
+        #[arg(long)]
+        playlist: Option<String>,
+    },
+    /// Print the tracks of a VirtualDJ database that have a key as a track library
+    #[cfg(feature = "virtualdj")]
+    Virtualdj {
+        /// VirtualDJ's `database.xml`
+        database: PathBuf,
+    },
+    /// Order the tracks of a track library into a set that mixes as harmonically as possible
+    Plan {
+        /// Track library with the tracks to play, by default the config file's
+        library: Option<PathBuf>,
+        /// Open the set with this track
+        #[arg(long, value_name = "TRACK")]
+        first: Option<String>,
+        /// Close the set with this track
+        #[arg(long, value_name = "TRACK")]
+        last: Option<String>,
+        /// Only play as many tracks as fit in this many minutes, picking the ones that mix
+        /// best. Tracks without a duration are left out
+        #[arg(long)]
+        minutes: Option<f64>,
+        /// Keep the energy `rising`, or follow a curve of energy levels like `3,6,8,5`. Tracks
+        /// without an energy are left out
+        #[arg(long, value_name = "SHAPE")]
+        energy: Option<camelol::EnergyShape>,
+        /// Play at most this many tracks in the same key in a row
+        #[arg(long, value_name = "N")]
+        max_key_run: Option<usize>,
+        /// Play at most this many tracks of the same genre in a row
+        #[arg(long, value_name = "N")]
+        max_genre_run: Option<usize>,
+        /// Keep the tracks with a tag in one half of the set, like `vocal=second`. Can be
+        /// given more than once
+        #[arg(long = "tag", value_name = "TAG=HALF")]
+        tag_placements: Vec<camelol::TagPlacement>,
+        /// Also write the set as a Rekordbox XML playlist, for tracks with a file path
+        #[arg(long, value_name = "FILE")]
+        rekordbox: Option<PathBuf>,
+        /// Also write the set as a Serato crate, for tracks with a file path. Serato shows
+        /// crates saved in `_Serato_/Subcrates` on the drive the tracks are on
+        #[arg(long, value_name = "FILE")]
+        serato: Option<PathBuf>,
+        /// Also write the set as a Traktor NML playlist, for tracks with a file path, with
+        /// their keys in Open Key notation as Traktor shows them
+        #[arg(long, value_name = "FILE")]
+        traktor: Option<PathBuf>,
+        /// Also write a CUE sheet with where each track starts in a recording of the set, for
+        /// tracks with durations
+        #[arg(long, value_name = "FILE")]
+        cue: Option<PathBuf>,
+        /// Recording the CUE sheet refers to, by default the CUE sheet's name with `.wav`
+        #[arg(long, value_name = "FILE", requires = "cue")]
+        recording: Option<String>,
+        /// Seconds each transition overlaps the tracks mixed, for the CUE sheet
+        #[arg(long, default_value_t = 0.0)]
+        overlap: f64,
+        /// Also write the set, its transitions and the pitch changes to match tempos as JSON,
+        /// following `schema/set.schema.json`
+        #[arg(long, value_name = "FILE")]
+        json: Option<PathBuf>,
+        /// Also write a single HTML page with the tracks, the set's route on the wheel and notes
+        /// on every transition, to send to a co-DJ
+        #[arg(long, value_name = "FILE")]
+        html: Option<PathBuf>,
+        /// Also write a CSV sheet of where to mix every track into the next, on phrase
+        /// boundaries of 8, 16 or 32 bars. Decodes the tracks with a file path
+        #[cfg(feature = "audio")]
+        #[arg(long, value_name = "FILE")]
+        mix_points: Option<PathBuf>,
+        /// Name of the exported playlist, CUE sheet, project or report
+        #[arg(long, default_value = "camelol")]
+        name: String,
+    },
+    /// Reorder an M3U or CSV playlist so each mix is as harmonic as possible, and print the
+    /// transitions between its tracks
+    Order {
+        /// Playlist to reorder, read as CSV if it ends in `.csv` and as M3U otherwise
+        playlist: PathBuf,
+        /// Where to write the reordered playlist, by default next to it with `.ordered` added
+        /// to its name
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Track library to look up the keys, tempos and durations the playlist doesn't have,
+        /// by file path or name. Defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+    },
+    /// List the best tracks to mix into from every track of a library, by key compatibility and
+    /// how close the tempos are, at half or double time too
+    Matches {
+        /// Track library to match, defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Number of matches per track
+        #[arg(short, default_value_t = 5)]
+        k: usize,
+        /// Also write the matches as CSV
+        #[arg(long, value_name = "FILE")]
+        csv: Option<PathBuf>,
+    },
+    /// Keep a rolling queue of the next tracks to play from a library, by key, tempo and energy,
+    /// starting from one track. Reads `accept` or `reject` with a queue position, 1 by default,
+    /// line by line, and queues again after each. An empty line accepts the next track
+    Autodj {
+        /// Track to start from
+        start: String,
+        /// Track library to play from, defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Number of tracks to keep queued
+        #[arg(short, default_value_t = 5)]
+        n: usize,
+    },
+    /// Practice the wheel: answer questions like which keys are one transition from a key, or
+    /// which transition takes one key to another, checked against the configured wheel, with
+    /// the score kept over every quiz
+    Quiz {
+        /// Number of questions to ask
+        #[arg(short, default_value_t = 10)]
+        n: usize,
+        /// Seed for the random number generator, picked at random if omitted
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Log of every question answered, read and appended to, to keep the score over time
+        #[arg(long, default_value = "camelol-quiz.log")]
+        file: PathBuf,
+    },
+    /// Group the wheel's keys into communities of keys more strongly connected to each other by
+    /// the configured transitions than to the rest of the wheel
+    Communities,
+    /// Split a library into M3U playlists of neighbouring wheel numbers, as pools of tracks that
+    /// mix into each other for DJ software to show as crates
+    Crates {
+        /// Track library to split, defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Number of playlists to split into, at most one per wheel number
+        #[arg(short, default_value_t = 4)]
+        n: usize,
+        /// Directory to write the playlists to, named after their wheel numbers like `11-2.m3u`
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        /// Split by the communities of the configured transitions, see `communities`, instead
+        /// of stretches of the wheel balanced by track count
+        #[arg(long, conflicts_with = "n")]
+        communities: bool,
+    },
+    /// Follow the tempo of an Ableton Link session on the local network
+    #[cfg(feature = "link")]
+    Tempo {
+        /// Print the first tempo heard and exit instead of following changes
+        #[arg(long)]
+        once: bool,
+    },
+    /// Read `path`, `neighbors`, `suggest` and other commands line by line, keeping the wheel
+    /// and track library loaded between them. `help` lists the commands
+    Repl {
+        /// Track library whose tracks `suggest` and `tracks` list, defaults to `library` in the
+        /// config file
+        #[arg(long)]
+        library: Option<PathBuf>,
+    },
+    /// Explore the wheel interactively
+    #[cfg(feature = "tui")]
+    Explore {
+        /// Key to start on
+        #[arg(default_value = "8A")]
+        from: String,
+    },
+    /// Show the key playing big with the best next keys ranked below it, for glancing at
+    /// mid-set. Typing a wheel number and `a` or `b` sets the key
+    #[cfg(feature = "tui")]
+    Live {
+        /// Key to start on
+        #[arg(default_value = "8A")]
+        from: String,
+    },
+    /// Build a set interactively from a library, with the tracks left to pick beside the set and
+    /// colored by how well the set's last track mixes into them, and `u` and `r` to undo and redo
+    /// edits. Prints the set as a track library when done
+    #[cfg(feature = "tui")]
+    Build {
+        /// Tracks to start the set with, in order
+        start: Vec<String>,
+        /// Track library to pick from, defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+        /// Write the set to this file instead
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Build a set in a desktop window by dragging tracks from a library onto the wheel, with
+    /// the tracks left colored by how well the set's last track mixes into them, and export it
+    /// as a track library or a playlist for DJ software
+    #[cfg(feature = "gui")]
+    Gui {
+        /// Track library to start with, defaults to the config file's. Libraries can also be
+        /// opened or dropped onto the window
+        #[arg(long)]
+        library: Option<PathBuf>,
+    },
+}
+
+/// Sends the current and suggested key to hardware on a raw MIDI output port.
+#[cfg(feature = "midi")]
+#[derive(clap::Args)]
+struct MidiOutArgs {
+    /// Raw MIDI output port to send keys to, like /dev/snd/midiC1D0
+    #[arg(long, value_name = "DEVICE")]
+    midi_out: Option<PathBuf>,
+    /// MIDI channel to send on, from 0 to 15
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..16))]
+    midi_channel: u8,
+    /// Send the current key as `program` or `cc=N`
+    #[arg(long, value_name = "MESSAGE", default_value = "program")]
+    send_current: camelol::KeyMessage,
+    /// Also send the top suggestion as `program` or `cc=N`
+    #[arg(long, value_name = "MESSAGE")]
+    send_suggested: Option<camelol::KeyMessage>,
+    /// File of `<key> <value>` lines overriding the value sent for each key, which is
+    /// otherwise its position on the wheel (1A is 0, 1B is 1, ..., 12B is 23)
+    #[arg(long, value_name = "FILE")]
+    midi_map: Option<PathBuf>,
 }
 
-impl Ord for NodeDistance {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.distance.cmp(&self.distance)
+#[cfg(feature = "midi")]
+impl MidiOutArgs {
+    fn open(&self, notation: &Notation) -> Result<Option<camelol::KeyOutput>, Box<dyn Error>> {
+        let Some(device) = &self.midi_out else {
+            return Ok(None);
+        };
+        let map = match &self.midi_map {
+            Some(map) => Some(fs::read_to_string(map)?),
+            None => None,
+        };
+        let mapping = |message| -> camelol::Result<camelol::KeyMapping> {
+            let mut mapping = camelol::KeyMapping::new(self.midi_channel, message);
+            if let Some(map) = &map {
+                mapping.load_values(map, notation)?;
+            }
+            Ok(mapping)
+        };
+        let output = OpenOptions::new().write(true).open(device)?;
+        Ok(Some(camelol::KeyOutput::new(
+            Box::new(output),
+            Some(mapping(self.send_current)?),
+            self.send_suggested.map(mapping).transpose()?,
+        )))
     }
 }
 
-impl PartialOrd for NodeDistance {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+/// Adds the tracks of a file of `Artist - Title` lines to a track library, looking up their
+/// keys and tempos online.
+#[cfg(any(feature = "beatport", feature = "musicbrainz"))]
+struct LibraryLookup {
+    tracks: PathBuf,
+    library: PathBuf,
+    dry_run: bool,
+}
+
+#[cfg(any(feature = "beatport", feature = "musicbrainz"))]
+impl LibraryLookup {
+    fn run(
+        self,
+        source: &impl camelol::TrackLookup,
+        notation: &Notation,
+        colors: ColorSupport,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut out = std::io::stdout().lock();
+        let library = camelol::TrackLibrary::load(&self.library, notation)?;
+        let tracks = fs::read_to_string(self.tracks)?;
+        // Audio files looked up before are in the library by their path.
+        let known_file = |name: &str| {
+            fs::canonicalize(name).is_ok_and(|file| {
+                library
+                    .tracks()
+                    .iter()
+                    .any(|track| track.path.as_ref() == Some(&file))
+            })
+        };
+        let missing = tracks
+            .lines()
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && library.track(name).is_none() && !known_file(name));
+        for name in missing {
+            if self.dry_run {
+                match source.request(name) {
+                    Some(request) => writeln!(out, "{name}\t{request}")?,
+                    None => writeln!(out, "{name}\t-")?,
+                }
+                continue;
+            }
+            let Some(track) = source.lookup(name)? else {
+                writeln!(out, "{name}\t-")?;
+                continue;
+            };
+            let mut log = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.library)?;
+            writeln!(log, "{track}")?;
+            writeln!(
+                out,
+                "{name}\t{}\t{}",
+                paint_scale(track.key, *notation, colors),
+                track
+                    .tempo
+                    .map_or("-".to_string(), |tempo| format!("{tempo:.1}"))
+            )?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
-struct Path {
-    cost: i32,
-    node: NodeIndex<u32>,
-    transition: Option<ScaleTransition>,
-    path: Vec<NodeIndex<u32>>,
-    transition_path: Vec<ScaleTransition>,
+/// Sends the current key and suggestions to an OSC receiver.
+#[derive(clap::Args)]
+struct OscArgs {
+    /// Host and port to send OSC messages to over UDP, like 192.168.1.20:8000
+    #[arg(long, value_name = "HOST:PORT")]
+    osc: Option<String>,
+    /// OSC address for the current key
+    #[arg(long, value_name = "ADDRESS", default_value = "/camelol/key")]
+    osc_key_address: String,
+    /// OSC address for the suggested keys and their scores
+    #[arg(long, value_name = "ADDRESS", default_value = "/camelol/suggestions")]
+    osc_suggestions_address: String,
 }
 
-impl Eq for Path {}
+impl OscArgs {
+    fn open(self) -> std::io::Result<Option<camelol::OscOutput>> {
+        let Some(target) = self.osc else {
+            return Ok(None);
+        };
+        let addresses = camelol::OscAddresses {
+            current: self.osc_key_address,
+            suggestions: self.osc_suggestions_address,
+        };
+        camelol::OscOutput::new(target, addresses).map(Some)
+    }
+}
 
-impl PartialEq for Path {
-    fn eq(&self, other: &Path) -> bool {
-        self.cost == other.cost
+/// Keeps a file or named pipe up to date for stream overlays.
+#[derive(clap::Args)]
+struct OverlayArgs {
+    /// Text file or named pipe to write the current key, the suggested keys and the current
+    /// key's neighbors on the wheel to, for OBS text sources and other stream overlays
+    #[arg(long, value_name = "FILE")]
+    overlay: Option<PathBuf>,
+}
+
+impl OverlayArgs {
+    fn open(self, notation: &Notation) -> std::io::Result<Option<camelol::OverlayOutput>> {
+        self.overlay
+            .map(|path| camelol::OverlayOutput::new(path, *notation))
+            .transpose()
     }
 }
 
-impl Ord for Path {
-    fn cmp(&self, other: &Path) -> Ordering {
-        other.cost.cmp(&self.cost)
+#[derive(Subcommand)]
+enum SessionAction {
+    /// Record a key as played now and suggest what to play next
+    Play { key: String },
+    /// Suggest what to play next
+    Suggest,
+    /// List everything played in the session so far
+    Show,
+}
+
+/// A line of `camelol repl`.
+#[derive(Parser)]
+#[command(
+    name = "camelol repl",
+    about = "Explore the wheel a command at a time",
+    no_binary_name = true
+)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: ReplCommand,
+}
+
+/// The commands of `camelol repl`. Those taking a key use the last key used when it's left off.
+#[derive(Subcommand)]
+enum ReplCommand {
+    /// Find the shortest paths from one key to another
+    Path {
+        from: String,
+        to: String,
+        /// Number of paths to return, 10 unless the config file sets `paths`
+        #[arg(short)]
+        n: Option<usize>,
+        /// Rank paths by their number of transitions, or by how far the tonic moves in total
+        #[arg(long, value_enum)]
+        by: Option<CostChoice>,
+    },
+    /// List the keys one transition away from a key
+    Neighbors { key: Option<String> },
+    /// List the keys one transition before a key
+    Predecessors { key: Option<String> },
+    /// Suggest the keys to play next, with the library's tracks in each
+    Suggest {
+        key: Option<String>,
+        /// Number of suggestions to show
+        #[arg(short, default_value_t = 5)]
+        n: usize,
+    },
+    /// List the library's tracks starting in a key
+    Tracks { key: Option<String> },
+    /// Leave the REPL
+    #[command(alias = "exit")]
+    Quit,
+}
+
+/// The words of a REPL line, where double quotes keep a key name like `"A minor"` together.
+fn repl_words(line: &str) -> Result<Vec<String>, String> {
+    if line.matches('"').count() % 2 == 1 {
+        return Err("unclosed `\"`".to_string());
+    }
+    let mut words = Vec::new();
+    for (i, part) in line.split('"').enumerate() {
+        if i % 2 == 1 {
+            words.push(part.to_string());
+        } else {
+            words.extend(part.split_whitespace().map(str::to_string));
+        }
     }
+    Ok(words)
 }
 
-impl PartialOrd for Path {
-    fn partial_cmp(&self, other: &Path) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// Prints the track `dj` is playing and its queue, then reads `accept`, `reject` and `quit`
+/// line by line until stdin ends.
+fn run_auto_dj(
+    dj: &mut camelol::AutoDj,
+    tracks: &[camelol::Track],
+    notation: Notation,
+    colors: ColorSupport,
+) -> Result<(), Box<dyn Error>> {
+    let mut out = std::io::stdout().lock();
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut line = String::new();
+    loop {
+        let current = &tracks[dj.current()];
+        writeln!(
+            out,
+            "playing\t{}\t{}",
+            current.name,
+            paint_scale(current.outgoing_key(), notation, colors)
+        )?;
+        for (position, found) in dj.queue().iter().enumerate() {
+            let track = &tracks[found.to];
+            writeln!(
+                out,
+                "  {}\t{:.3}\t{}\t{}\t{}",
+                position + 1,
+                found.score,
+                track.name,
+                paint_scale(track.incoming_key(), notation, colors),
+                found
+                    .pitch
+                    .map_or("-".to_string(), |pitch| format!("{pitch:+.1}%"))
+            )?;
+        }
+        if dj.queue().is_empty() {
+            writeln!(out, "  nothing left that mixes")?;
+        }
+        if interactive {
+            write!(out, "autodj> ")?;
+            out.flush()?;
+        }
+        line.clear();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("accept");
+        let position = match words.next().map(str::parse::<usize>) {
+            None => Ok(0),
+            Some(Ok(position)) if position > 0 => Ok(position - 1),
+            Some(_) => Err("queue positions start at 1"),
+        };
+        let done = match (command, position) {
+            ("q" | "quit" | "exit", _) => return Ok(()),
+            (_, Err(error)) => Err(error),
+            ("a" | "accept", Ok(position)) => dj.accept(position).ok_or("no such queued track"),
+            ("r" | "reject", Ok(position)) => dj.reject(position).ok_or("no such queued track"),
+            _ => Err("unknown command, use `accept`, `reject` or `quit`"),
+        };
+        if let Err(error) = done {
+            eprintln!("error: {error}");
+        }
     }
 }
 
-fn multi_path_dijkstra(
-    graph: &Graph<Scale, ScaleTransition>,
-    source: NodeIndex<u32>,
-    target: NodeIndex<u32>,
+/// Asks `n` questions about `wheel` on stdin, appending every answer to `file`, then prints how
+/// many were right this time and over every quiz in the log.
+fn run_quiz(
+    wheel: &camelol::Wheel,
     n: usize,
-) -> Vec<Path> {
-    let mut min_heap = BinaryHeap::new();
-    let mut paths = Vec::new();
-
-    min_heap.push(Path {
-        cost: 0,
-        node: source,
-        transition: None,
-        path: vec![],
-        transition_path: vec![],
-    });
+    rng: &mut ChaCha8Rng,
+    file: &std::path::Path,
+    notation: Notation,
+    colors: ColorSupport,
+) -> Result<(), Box<dyn Error>> {
+    let mut out = std::io::stdout().lock();
+    /// Answers every score is also given over, to show recent progress.
+    const RECENT_ANSWERS: usize = 20;
 
-    while let Some(mut path) = min_heap.pop() {
-        path.path.push(path.node);
+    let mut log = match fs::read_to_string(file) {
+        Ok(log) => camelol::QuizLog::parse(&log)?,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => camelol::QuizLog::default(),
+        Err(error) => return Err(error.into()),
+    };
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let keys = |keys: &[Scale]| -> String {
+        keys.iter()
+            .map(|key| paint_scale(*key, notation, colors))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let (mut right, mut asked) = (0, 0);
+    let mut line = String::new();
+    'questions: while asked < n {
+        let question =
+            camelol::QuizQuestion::random(wheel, rng).ok_or("the wheel has no transitions")?;
+        let answer = question.answer(wheel)?;
+        match question {
+            camelol::QuizQuestion::Neighbors(key) => writeln!(
+                out,
+                "which keys are one transition from {}?",
+                paint_scale(key, notation, colors)
+            )?,
+            camelol::QuizQuestion::Transition(from, to) => writeln!(
+                out,
+                "what transition takes {} to {}?",
+                paint_scale(from, notation, colors),
+                paint_scale(to, notation, colors)
+            )?,
+        }
+        let correct = loop {
+            if interactive {
+                write!(out, "quiz> ")?;
+                out.flush()?;
+            }
+            line.clear();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                break 'questions;
+            }
+            let reply = line.trim();
+            if matches!(reply, "q" | "quit" | "exit") {
+                break 'questions;
+            }
+            let checked = match &answer {
+                camelol::QuizAnswer::Keys(_) => reply
+                    // Commas separate musical key names, which have spaces of their own.
+                    .split(if reply.contains(',') { ',' } else { ' ' })
+                    .filter(|key| !key.trim().is_empty())
+                    .map(|key| notation.parse(key))
+                    .collect::<camelol::Result<Vec<_>>>()
+                    .map(|given| answer.accepts_keys(&given))
+                    .map_err(|error| error.to_string()),
+                camelol::QuizAnswer::Transitions(_) => {
+                    parse_transition(reply).map(|given| answer.accepts_transition(given))
+                }
+            };
+            match checked {
+                Ok(correct) => break correct,
+                Err(error) => eprintln!("error: {error}"),
+            }
+        };
+        asked += 1;
+        if correct {
+            right += 1;
+            writeln!(out, "right")?;
+        } else {
+            match &answer {
+                camelol::QuizAnswer::Keys(expected) => {
+                    writeln!(out, "wrong, it's {}", keys(expected))?
+                }
+                camelol::QuizAnswer::Transitions(expected) => {
+                    let names: Vec<String> = expected
+                        .iter()
+                        .map(|transition| format!("{transition:?}"))
+                        .collect();
+                    writeln!(out, "wrong, it's {}", names.join(" or "))?;
+                }
+            }
+        }
+        let result = log.record(question.kind(), correct);
+        let mut file = OpenOptions::new().create(true).append(true).open(file)?;
+        writeln!(file, "{result}")?;
+    }
 
-        if let Some(transition) = path.transition {
-            path.transition_path.push(transition);
+    writeln!(out, "{right}/{asked} right")?;
+    for kind in camelol::QuizKind::ALL {
+        let (right, total) = log.score(kind, None);
+        if total == 0 {
+            continue;
         }
+        let (recent, recent_total) = log.score(kind, Some(RECENT_ANSWERS));
+        writeln!(
+            out,
+            "{kind}\t{right}/{total}\t{:.0}%\tlast {recent_total}: {:.0}%",
+            100.0 * right as f64 / total as f64,
+            100.0 * recent as f64 / recent_total as f64
+        )?;
+    }
+    Ok(())
+}
 
-        if path.node == target {
-            paths.push(path.clone());
-            if paths.len() >= n {
-                break;
+/// What `camelol repl` keeps loaded between commands.
+struct Repl<'a> {
+    wheel: &'a camelol::Wheel,
+    distances: DistanceMatrix,
+    script: Option<&'a dyn TransitionRules>,
+    prefer: &'a [(ScaleTransition, f64)],
+    notation: Notation,
+    colors: ColorSupport,
+    library: Option<camelol::TrackLibrary>,
+    paths: Option<usize>,
+    /// The last key used, for commands given without one.
+    key: Option<Scale>,
+}
+
+impl Repl<'_> {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut out = std::io::stdout().lock();
+        let stdin = std::io::stdin();
+        let interactive = stdin.is_terminal();
+        let mut line = String::new();
+        loop {
+            if interactive {
+                write!(out, "camelol> ")?;
+                out.flush()?;
+            }
+            line.clear();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let words = match repl_words(&line) {
+                Ok(words) if words.is_empty() => continue,
+                Ok(words) => words,
+                Err(error) => {
+                    eprintln!("error: {error}");
+                    continue;
+                }
+            };
+            match ReplLine::try_parse_from(words) {
+                Ok(ReplLine {
+                    command: ReplCommand::Quit,
+                }) => return Ok(()),
+                Ok(ReplLine { command }) => {
+                    if let Err(error) = self.execute(command) {
+                        eprintln!("error: {error}");
+                    }
+                }
+                // Also prints `help`.
+                Err(error) => error.print()?,
             }
         }
+    }
 
-        for edge in graph.edges(path.node) {
-            let neighbor = edge.target();
-            let weight = graph.edge_weight(edge.id()).unwrap();
-            min_heap.push(Path {
-                cost: path.cost + 1,
-                node: neighbor,
-                transition: Some(*weight),
-                transition_path: path.transition_path.clone(),
-                path: path.path.clone(),
-            });
+    /// `key`, or the last key used without it.
+    fn key(&mut self, key: Option<String>) -> Result<Scale, Box<dyn Error>> {
+        let key = match key {
+            Some(key) => self.notation.parse(&key)?,
+            None => self
+                .key
+                .ok_or("no key used yet, give one like `suggest 8A`")?,
+        };
+        self.key = Some(key);
+        Ok(key)
+    }
+
+    fn tracks(&self, key: Scale) -> impl Iterator<Item = &camelol::Track> {
+        self.library
+            .iter()
+            .flat_map(camelol::TrackLibrary::tracks)
+            .filter(move |track| track.incoming_key() == key)
+    }
+
+    fn execute(&mut self, command: ReplCommand) -> Result<(), Box<dyn Error>> {
+        let mut out = std::io::stdout().lock();
+        let (notation, colors) = (self.notation, self.colors);
+        match command {
+            ReplCommand::Path { from, to, n, by } => {
+                let from = self.notation.parse(&from)?;
+                let to = self.key(Some(to))?;
+                let n = n.or(self.paths).unwrap_or(10);
+                let cost = step_cost(by, self.script, self.prefer);
+                for path in
+                    self.wheel
+                        .paths_between(&[from], &[to], n, cost, &SearchLimit::default())?
+                {
+                    writeln!(out, "{}", format_path_colored(&path, notation, colors))?;
+                }
+            }
+            ReplCommand::Neighbors { key } => {
+                let key = self.key(key)?;
+                for (transition, to) in self.wheel.neighbors(key)? {
+                    writeln!(
+                        out,
+                        "{}\t{transition:?}\t{}",
+                        paint_scale(to, notation, colors),
+                        Interval::between(key, to)
+                    )?;
+                }
+            }
+            ReplCommand::Predecessors { key } => {
+                let key = self.key(key)?;
+                for (transition, from) in self.wheel.predecessors(key)? {
+                    writeln!(
+                        out,
+                        "{}\t{transition:?}\t{}",
+                        paint_scale(from, notation, colors),
+                        Interval::between(from, key)
+                    )?;
+                }
+            }
+            ReplCommand::Suggest { key, n } => {
+                let key = self.key(key)?;
+                for suggestion in suggest(&self.distances, key, None, 0.0).into_iter().take(n) {
+                    writeln!(
+                        out,
+                        "{}\t{:.3}",
+                        paint_scale(suggestion.scale, notation, colors),
+                        suggestion.score
+                    )?;
+                    for track in self.tracks(suggestion.scale) {
+                        writeln!(out, "  {}", track.name)?;
+                    }
+                }
+            }
+            ReplCommand::Tracks { key } => {
+                let key = self.key(key)?;
+                if self.library.is_none() {
+                    return Err("no track library, start the REPL with `--library`".into());
+                }
+                for track in self.tracks(key) {
+                    writeln!(out, "{}", track.name)?;
+                }
+            }
+            ReplCommand::Quit => {}
         }
+        Ok(())
     }
+}
+
+#[derive(Subcommand)]
+enum StatsReport {
+    /// Diameter, average path length, degree distribution and unreachable pairs of the graph
+    Graph,
+    /// How often each transition is taken across the shortest paths between all keys
+    Transitions,
+    /// How many tracks of a library are in each key and which keys have too few, to know
+    /// which to look for when buying music
+    Keys {
+        /// Track library to count, defaults to the config file's
+        #[arg(long)]
+        library: Option<PathBuf>,
+    },
+    /// How you actually mix, from sets you played: the transitions you take most, how far
+    /// apart the keys of a mix are on average, and the keys you never leave or never play
+    Mixing {
+        /// File of past sets, one set of keys per line
+        #[arg(long, required_unless_present = "session")]
+        history: Option<PathBuf>,
+        /// Session log of a set, as `camelol session` records. Can be repeated
+        #[arg(long, value_name = "FILE")]
+        session: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PackAction {
+    /// Write the preset, `--rules` script, `--prefer` weights and `--no-clashes` in use, from
+    /// the command line, the config file or `--pack`, to a rule pack file
+    Export {
+        file: PathBuf,
+        /// Name to install the pack as, by default the file's
+        #[arg(long)]
+        name: Option<String>,
+        /// What the rules are for, shown by `camelol pack list`
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// Check a rule pack and install it, so `--pack NAME` uses it
+    Import {
+        file: PathBuf,
+        /// Replace an installed pack of the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// List the installed rule packs
+    List,
+}
+
+/// What a step costs a path: `--by` if given, otherwise the cost rule of `--rules`, and one per
+/// transition without either, weighted by `--prefer`.
+fn step_cost<'a>(
+    by: Option<CostChoice>,
+    script: Option<&'a dyn TransitionRules>,
+    prefer: &'a [(ScaleTransition, f64)],
+) -> impl Fn(Scale, ScaleTransition, Scale) -> u32 + Copy + 'a {
+    move |from, transition, to| {
+        let cost = match (by, script) {
+            (None, Some(script)) => script.cost(from, transition, to),
+            (by, _) => by
+                .map_or(PathCost::Hops, CostChoice::cost)
+                .step(from, transition, to),
+        };
+        if prefer.is_empty() {
+            return cost;
+        }
+        // The last weight given for a transition wins.
+        let weight = prefer
+            .iter()
+            .rev()
+            .find(|(preferred, _)| *preferred == transition)
+            .map_or(1.0, |(_, weight)| *weight);
+        (cost as f64 * weight * PREFERENCE_SCALE).round() as u32
+    }
+}
+
+/// Prints `keys` in a smooth mixing order from the first, with the path between every two.
+fn print_sequence<K: camelol::MixableKey>(keys: &[K]) -> camelol::Result<()> {
+    let mut out = std::io::stdout().lock();
+    let order = camelol::order_keys(keys);
+    for pair in order.windows(2) {
+        let (from, to) = (&keys[pair[0]], &keys[pair[1]]);
+        let path = camelol::key_paths(
+            std::slice::from_ref(from),
+            std::slice::from_ref(to),
+            1,
+            &SearchLimit::default(),
+        )?;
+        match path.first() {
+            Some(_) if from == to => {
+                writeln!(out, "{} -> {}\tsame key", from.display(), to.display())?
+            }
+            Some(path) => writeln!(out, "{}", path.display())?,
+            None => writeln!(out, "{} -> {}\tnot connected", from.display(), to.display())?,
+        }
+    }
+    Ok(())
+}
+
+/// A path's cost in the units of [`step_cost`], back in whole steps under `--prefer`.
+fn format_cost(cost: u64, prefer: &[(ScaleTransition, f64)]) -> String {
+    if prefer.is_empty() {
+        cost.to_string()
+    } else {
+        format!("{:.2}", cost as f64 / PREFERENCE_SCALE)
+    }
+}
 
-    paths
+/// Like `shift +1 semitone to 9A`, for a track that fits once transposed.
+fn format_shift(
+    transposition: &camelol::Transposition,
+    notation: Notation,
+    colors: ColorSupport,
+) -> String {
+    format!(
+        "shift {:+} semitone{} to {}",
+        transposition.semitones,
+        if transposition.semitones.abs() == 1 {
+            ""
+        } else {
+            "s"
+        },
+        paint_scale(transposition.key, notation, colors)
+    )
 }
 
-fn main() {
-    let mut graph = petgraph::Graph::new();
+/// The track a line piped into `suggest` stands for: a bare key, or an audio file found in the
+/// track library or, with the audio feature, tagged with its key.
+fn candidate_track(
+    line: &str,
+    library: &camelol::TrackLibrary,
+    notation: &Notation,
+) -> Option<camelol::Track> {
+    if let Ok(key) = notation.parse(line) {
+        return Some(camelol::Track::new(line, key));
+    }
+    let file = std::path::Path::new(line);
+    let absolute = fs::canonicalize(file).ok();
+    let known = library.tracks().iter().find(|track| {
+        track.path.as_deref() == Some(file)
+            || (track.path.is_some() && track.path.as_deref() == absolute.as_deref())
+    });
+    if let Some(track) = known {
+        return Some(track.clone());
+    }
+    #[cfg(feature = "audio")]
+    if let Ok(Some(track)) = camelol::tagged_track(file) {
+        return Some(track);
+    }
+    None
+}
 
-    let nodes = make_nodes();
-    let transitions = possible_transitions();
+fn load_model(
+    history: Option<PathBuf>,
+    notation: &Notation,
+) -> Result<Option<MarkovModel>, Box<dyn Error>> {
+    match history {
+        Some(history) => Ok(Some(MarkovModel::train(parse_history(
+            &fs::read_to_string(history)?,
+            notation,
+        )?))),
+        None => Ok(None),
+    }
+}
 
-    let scale_to_index = nodes
-        .iter()
-        .map(|scale| (*scale, graph.add_node(*scale)))
-        .collect::<HashMap<_, _>>();
+/// Whether `error` is from writing to a pipe whose reader has gone.
+fn is_broken_pipe(error: &(dyn Error + 'static)) -> bool {
+    let io = match error.downcast_ref::<camelol::Error>() {
+        Some(camelol::Error::Io(error)) => Some(error),
+        _ => error.downcast_ref::<std::io::Error>(),
+    };
+    io.is_some_and(|error| error.kind() == std::io::ErrorKind::BrokenPipe)
+}
 
-    for scale in &nodes {
-        let source_scale_node = *scale_to_index.get(scale).unwrap();
-        for transition in &transitions {
-            let target_scale = make_transition(*scale, *transition);
-            let target_scale_node = *scale_to_index.get(&target_scale).unwrap();
-            graph.add_edge(source_scale_node, target_scale_node, *transition);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // Only fails if a logger is already set, which nothing else does.
+    let _ = tracing::subscriber::set_global_default(cli.log());
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        // The output was piped into something like `head` that has read all it wants.
+        Err(error) if is_broken_pipe(&*error) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
         }
     }
+}
 
-    let a_minor = *scale_to_index
-        .get(&Scale {
-            index: 11,
-            kind: ScaleKind::Minor,
-        })
-        .unwrap();
-    let d_flat_major = *scale_to_index
-        .get(&Scale {
-            index: 0,
-            kind: ScaleKind::Major,
+fn run(mut cli: Cli) -> Result<(), Box<dyn Error>> {
+    let mut out = std::io::stdout().lock();
+    let config = Config::load(cli.config.as_deref())?.with_environment()?;
+    cli.color = cli.color.or(config.color);
+    cli.plain |= config.plain;
+    cli.keys = cli.keys.or(config.keys);
+    cli.spelling = cli.spelling.or(config.spelling);
+    if cli.numbering.is_none() {
+        cli.numbering = config
+            .numbering
+            .as_deref()
+            .map(parse_numbering)
+            .transpose()?;
+    }
+    cli.locale = cli.locale.or(config.locale);
+    // A pack's rules sit between the command line and the config file.
+    let pack = match cli.pack.as_ref().or(config.pack.as_ref()) {
+        Some(pack) => RulePack::load(pack)?,
+        None => RulePack::default(),
+    };
+    cli.preset = cli.preset.or(pack.preset).or(config.preset);
+    if pack.rules.is_none() {
+        cli.rules = cli.rules.or_else(|| config.rules.clone());
+    }
+    if cli.prefer.is_empty() {
+        cli.prefer = pack.preferences()?;
+    }
+    cli.pitch_range = cli.pitch_range.or(config.pitch_range);
+    cli.no_clashes |= pack.no_clashes || config.no_clashes;
+
+    let rules = match &cli.rules {
+        Some(rules) => Some(fs::read_to_string(rules)?),
+        None => pack.rules.clone(),
+    };
+    let script = match &rules {
+        Some(rules) => Some(RuleScript::parse(rules)?),
+        None => None,
+    };
+    let preset = cli.preset.unwrap_or_default().preset();
+    let mut wheel = match &script {
+        Some(script) => script.wheel(preset),
+        None => preset.wheel(),
+    };
+    #[cfg(feature = "plugin")]
+    let plugin = cli
+        .plugin
+        .as_ref()
+        .map(|plugin| {
+            RulePlugin::load(plugin).map_err(|error| format!("{}: {error}", plugin.display()))
         })
-        .unwrap();
-
-    let paths = multi_path_dijkstra(&graph, a_minor, d_flat_major, 10);
-
-    for path in paths {
-        let transitions = path
-            .transition_path
-            .into_iter()
-            .map(Some)
-            .chain(iter::repeat(None));
-
-        let path = path
-            .path
-            .into_iter()
-            .map(|node| graph.node_weight(node).unwrap())
-            .map(|scale| scale.to_string())
-            .zip(transitions)
-            .flat_map(|(scale, transition)| match transition {
-                Some(transition) => vec![scale.to_string(), format!("{transition:?}")],
-                None => vec![scale.to_string()],
-            })
-            .collect::<Vec<_>>();
+        .transpose()?;
+    // A plugin replaces the rules of the pack or the config file.
+    #[cfg(feature = "plugin")]
+    if let Some(plugin) = &plugin {
+        wheel = plugin.wheel(preset);
+    }
+    let script = script.as_ref().map(|script| script as &dyn TransitionRules);
+    #[cfg(feature = "plugin")]
+    let script = plugin
+        .as_ref()
+        .map(|plugin| plugin as &dyn TransitionRules)
+        .or(script);
+    if cli.no_clashes {
+        wheel = wheel.without_transitions_where(|from, _, to| camelol::is_clash(from, to));
+    }
+    let colors = if cli.plain {
+        ColorSupport::None
+    } else {
+        cli.color.unwrap_or_default().support()
+    };
+    let symbols = |text: &str| {
+        if cli.plain {
+            camelol::render::ascii_symbols(text)
+        } else {
+            text.to_string()
+        }
+    };
+    let notation = cli.notation()?;
+
+    let command = match (cli.command, cli.jsonl) {
+        (Some(command), false) => command,
+        (None, true) => {
+            let mut engine = Engine::new(wheel);
+            engine.search_time = Some(camelol::DEFAULT_SEARCH_TIME);
+            let library = config
+                .library
+                .map(|library| camelol::TrackLibrary::load(&library, &notation))
+                .transpose()?
+                .unwrap_or_default();
+            camelol::answer_lines(
+                &engine,
+                &library,
+                std::io::stdin().lock(),
+                std::io::stdout().lock(),
+            )?;
+            return Ok(());
+        }
+        (Some(_), true) => return Err("--jsonl runs without a command".into()),
+        (None, false) => return Err("no command given, see `camelol help`".into()),
+    };
+    match command {
+        Command::Paths {
+            from,
+            to,
+            n,
+            by,
+            max_boost_run,
+            explain,
+            wheel: draw_wheel,
+            svg,
+            #[cfg(feature = "png")]
+            png,
+            #[cfg(feature = "png")]
+            png_size,
+            format,
+            #[cfg(feature = "midi")]
+            preview,
+        } => {
+            let keys = |keys: &str| {
+                keys.split(',')
+                    .map(|key| notation.parse(key))
+                    .collect::<camelol::Result<Vec<_>>>()
+            };
+            let (sources, targets) = (keys(&from)?, keys(&to)?);
+            let n = n.or(config.paths).unwrap_or(10);
+            let cost = step_cost(by, script, &cli.prefer);
+            let limit = SearchLimit {
+                max_boost_run,
+                ..SearchLimit::default()
+            };
+            let paths = wheel.paths_between(&sources, &targets, n, cost, &limit)?;
+            let best = paths.first().map(Path::scales).unwrap_or_default();
+            let from = best.first().or(sources.first()).copied();
+            if draw_wheel {
+                writeln!(out, "{}\n", ascii_wheel(from, best))?;
+            }
+            if let Some(svg) = svg {
+                fs::write(svg, svg_wheel(from, best))?;
+            }
+            #[cfg(feature = "png")]
+            if let Some(png) = png {
+                fs::write(png, camelol::render::png_wheel(from, best, png_size))?;
+            }
+            match format {
+                OutputFormat::Text => {
+                    for path in &paths {
+                        writeln!(out, "{}", format_path_colored(path, notation, colors))?;
+                        if explain {
+                            let steps = explain_steps(path.steps(), &notation);
+                            if steps.is_empty() {
+                                writeln!(out, "  Stay in {}.", notation.label(path.node))?;
+                            }
+                            for step in steps {
+                                writeln!(out, "  {step}")?;
+                            }
+                        }
+                    }
+                }
+                OutputFormat::Mermaid => write!(
+                    out,
+                    "{}",
+                    mermaid_flowchart(paths.iter().flat_map(Path::steps))
+                )?,
+                OutputFormat::Graphml => write!(
+                    out,
+                    "{}",
+                    graphml(
+                        paths.iter().flat_map(|path| path.path.iter().copied()),
+                        paths.iter().flat_map(Path::steps),
+                        &notation,
+                        cost
+                    )
+                )?,
+            }
+            #[cfg(feature = "midi")]
+            if let Some(device) = preview {
+                let mut output = OpenOptions::new().write(true).open(device)?;
+                let options = camelol::PreviewOptions {
+                    numbering: notation.numbering,
+                    ..Default::default()
+                };
+                camelol::play_preview(&mut output, best, &options)?;
+            }
+        }
+        Command::Batch { pairs, n } => {
+            let pairs = if pairs.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                fs::read_to_string(pairs)?
+            };
+            let queries = pairs
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(
+                    |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                        [from, to] => Ok((notation.parse(from)?, notation.parse(to)?)),
+                        _ => Err(format!("expected `FROM TO`, got `{line}`").into()),
+                    },
+                )
+                .collect::<Result<Vec<(Scale, Scale)>, Box<dyn Error>>>()?;
+
+            let progress = ProgressBar::new(queries.len() as u64);
+            let results = batch_paths(&wheel, &queries, n, || progress.inc(1))?;
+            progress.finish_and_clear();
+
+            for ((from, to), paths) in queries.iter().zip(results) {
+                writeln!(
+                    out,
+                    "{} {}",
+                    paint_scale(*from, notation, colors),
+                    paint_scale(*to, notation, colors)
+                )?;
+                for path in paths {
+                    writeln!(out, "  {}", format_path_colored(&path, notation, colors))?;
+                }
+            }
+        }
+        Command::Graph { format } => match format {
+            OutputFormat::Text => {
+                for (from, transition, to) in wheel.transitions() {
+                    writeln!(
+                        out,
+                        "{} -> {}\t{transition:?}\t{}",
+                        paint_scale(from, notation, colors),
+                        paint_scale(to, notation, colors),
+                        Interval::between(from, to)
+                    )?;
+                }
+            }
+            OutputFormat::Mermaid => write!(out, "{}", mermaid_flowchart(wheel.transitions()))?,
+            OutputFormat::Graphml => write!(
+                out,
+                "{}",
+                graphml(
+                    wheel.scales(),
+                    wheel.transitions(),
+                    &notation,
+                    |from, transition, to| script
+                        .map_or(1, |script| script.cost(from, transition, to))
+                )
+            )?,
+        },
+        Command::Pack {
+            action:
+                PackAction::Export {
+                    file,
+                    name,
+                    description,
+                },
+        } => {
+            #[cfg(feature = "plugin")]
+            if cli.plugin.is_some() {
+                return Err("a plugin can't go in a rule pack, only a `--rules` script".into());
+            }
+            let name = name
+                .or_else(|| Some(file.file_stem()?.to_string_lossy().into_owned()))
+                .unwrap_or_default();
+            let pack = RulePack {
+                version: RULE_PACK_VERSION,
+                name,
+                description,
+                preset: cli.preset,
+                rules,
+                no_clashes: cli.no_clashes,
+                prefer: cli
+                    .prefer
+                    .iter()
+                    .map(|(transition, weight)| (format!("{transition:?}"), *weight))
+                    .collect(),
+            };
+            fs::write(file, toml::to_string(&pack)?)?;
+        }
+        Command::Pack {
+            action: PackAction::Import { file, force },
+        } => {
+            let pack = RulePack::load(&file.to_string_lossy())?;
+            let valid = !pack.name.is_empty()
+                && pack
+                    .name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                && !pack.name.starts_with('.');
+            if !valid {
+                return Err(format!(
+                    "can't install a pack named `{}`, names are letters, digits, `-`, `_` and `.`",
+                    pack.name
+                )
+                .into());
+            }
+            let dir = RulePack::dir().ok_or("no config directory to install packs in")?;
+            let installed = dir.join(format!("{}.toml", pack.name));
+            if installed.exists() && !force {
+                return Err(format!(
+                    "a pack named `{}` is already installed, use --force to replace it",
+                    pack.name
+                )
+                .into());
+            }
+            fs::create_dir_all(&dir)?;
+            fs::copy(&file, &installed)?;
+            writeln!(
+                out,
+                "installed `{}`, use it with --pack {}",
+                pack.name, pack.name
+            )?;
+        }
+        Command::Pack {
+            action: PackAction::List,
+        } => {
+            let Some(dir) = RulePack::dir().filter(|dir| dir.is_dir()) else {
+                return Ok(());
+            };
+            let mut packs: Vec<_> = fs::read_dir(dir)?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "toml")
+                })
+                .collect();
+            packs.sort();
+            for path in packs {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                match RulePack::load(&path.to_string_lossy()) {
+                    Ok(pack) => writeln!(out, "{name}\t{}", pack.description.unwrap_or_default())?,
+                    Err(error) => tracing::warn!(%error, "can't read the rule pack"),
+                }
+            }
+        }
+        Command::Stats {
+            report: StatsReport::Graph,
+        } => {
+            let stats = GraphStats::new(&wheel);
+            writeln!(out, "keys\t{}", stats.keys)?;
+            writeln!(out, "transitions\t{}", stats.transitions)?;
+            writeln!(out, "diameter\t{}", stats.diameter)?;
+            writeln!(out, "average distance\t{:.3}", stats.average_distance)?;
+            for (degree, keys) in &stats.degrees {
+                writeln!(out, "degree {degree}\t{keys} keys")?;
+            }
+            writeln!(out, "unreachable pairs\t{}", stats.unreachable.len())?;
+            for (from, to) in &stats.unreachable {
+                writeln!(
+                    out,
+                    "  {} -> {}",
+                    paint_scale(*from, notation, colors),
+                    paint_scale(*to, notation, colors)
+                )?;
+            }
+        }
+        Command::Stats {
+            report: StatsReport::Transitions,
+        } => {
+            let usage = camelol::transition_usage(&wheel);
+            let most = usage.iter().map(|(_, count)| *count).max().unwrap_or(0);
+            for (transition, count) in usage {
+                let bar = (count * 40).checked_div(most).unwrap_or(0);
+                writeln!(
+                    out,
+                    "{:<18}{count:>8}  {}",
+                    format!("{transition:?}"),
+                    "#".repeat(bar)
+                )?;
+            }
+        }
+        Command::Stats {
+            report: StatsReport::Keys { library },
+        } => {
+            let library = config.library(library)?;
+            let library = camelol::TrackLibrary::parse(&fs::read_to_string(library)?, &notation)?;
+            let coverage = camelol::key_coverage(&wheel, library.tracks());
+            let most = coverage.iter().map(|key| key.tracks).max().unwrap_or(0);
+            for key in &coverage {
+                let bar = (key.tracks * 40).checked_div(most).unwrap_or(0);
+                writeln!(
+                    out,
+                    "{}\t{:>5}  {:<40}  {} nearby{}",
+                    paint_scale(key.key, notation, colors),
+                    key.tracks,
+                    "#".repeat(bar),
+                    key.nearby,
+                    if key.gap { "\tgap" } else { "" }
+                )?;
+            }
+            // Gaps with the fewest tracks around them limit the most mixes.
+            let mut gaps: Vec<&camelol::KeyCoverage> =
+                coverage.iter().filter(|key| key.gap).collect();
+            gaps.sort_by_key(|key| (key.tracks, key.nearby));
+            let gaps: Vec<String> = gaps
+                .iter()
+                .map(|key| paint_scale(key.key, notation, colors))
+                .collect();
+            if !gaps.is_empty() {
+                writeln!(out, "look for tracks in {}", gaps.join(", "))?;
+            }
+        }
+        Command::Stats {
+            report: StatsReport::Mixing { history, session },
+        } => {
+            let mut sets = match history {
+                Some(history) => parse_history(&fs::read_to_string(history)?, &notation)?,
+                None => vec![],
+            };
+            for log in session {
+                let session = camelol::Session::parse(&fs::read_to_string(&log)?)
+                    .map_err(|error| format!("{}: {error}", log.display()))?;
+                sets.push(session.plays.iter().map(|play| play.scale).collect());
+            }
+            let report = camelol::mixing_report(&wheel, &sets);
+            let share = |count: usize| 100.0 * count as f64 / report.mixes.max(1) as f64;
+            let keys = |keys: Vec<Scale>| -> String {
+                if keys.is_empty() {
+                    return "none".to_string();
+                }
+                keys.into_iter()
+                    .map(|key| paint_scale(key, notation, colors))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            writeln!(out, "sets\t{}", report.sets)?;
+            writeln!(out, "mixes\t{}", report.mixes)?;
+            writeln!(out, "average distance\t{:.2}", report.average_distance)?;
+            writeln!(
+                out,
+                "same key\t{}\t{:.0}%",
+                report.same_key,
+                share(report.same_key)
+            )?;
+            for (transition, count) in &report.transitions {
+                writeln!(
+                    out,
+                    "{:<18}{count:>6}\t{:.0}%",
+                    format!("{transition:?}"),
+                    share(*count)
+                )?;
+            }
+            writeln!(out, "jumps\t{}\t{:.0}%", report.jumps, share(report.jumps))?;
+            writeln!(
+                out,
+                "unconnected\t{}\t{:.0}%",
+                report.unconnected,
+                share(report.unconnected)
+            )?;
+            for habit in &report.keys {
+                writeln!(
+                    out,
+                    "{}\tplayed {}\tstayed in key for {} of {} mixes out",
+                    paint_scale(habit.key, notation, colors),
+                    habit.plays,
+                    habit.stays,
+                    habit.mixes_out
+                )?;
+            }
+            writeln!(out, "never left\t{}", keys(report.never_left().collect()))?;
+            writeln!(out, "never played\t{}", keys(report.unplayed.clone()))?;
+        }
+        Command::Nearest { from, targets, by } => {
+            let targets = targets
+                .iter()
+                .map(|target| notation.parse(target))
+                .collect::<camelol::Result<Vec<_>>>()?;
+            let nearest = wheel.nearest(
+                notation.parse(&from)?,
+                &targets,
+                step_cost(by, script, &cli.prefer),
+            )?;
+            for (target, path) in nearest {
+                let target = paint_scale(target, notation, colors);
+                match path {
+                    Some(path) => writeln!(
+                        out,
+                        "{target}\t{}\t{}",
+                        format_cost(path.cost, &cli.prefer),
+                        format_path_colored(&path, notation, colors)
+                    )?,
+                    None => writeln!(out, "{target}\t-\tunreachable")?,
+                }
+            }
+        }
+        Command::Tour { stops, from, by } => {
+            let stops = stops
+                .iter()
+                .map(|key| notation.parse(key))
+                .collect::<camelol::Result<Vec<_>>>()?;
+            let from = from.map(|from| notation.parse(&from)).transpose()?;
+            let cost = step_cost(by, script, &cli.prefer);
+            let tour = camelol::key_tour(&wheel, from, &stops, cost, &SearchLimit::default())?
+                .ok_or("no path visits all of the keys")?;
+            writeln!(
+                out,
+                "{}\t{}",
+                format_cost(tour.cost, &cli.prefer),
+                format_path_colored(&tour, notation, colors)
+            )?;
+        }
+        Command::Wheel { key } => {
+            let key = key.map(|key| notation.parse(&key)).transpose()?;
+            let neighbors = match key {
+                Some(key) => wheel
+                    .neighbors(key)?
+                    .into_iter()
+                    .map(|(transition, to)| (transition, to, Interval::between(key, to)))
+                    .collect(),
+                None => vec![],
+            };
+            let marked = neighbors.iter().map(|(_, to, _)| *to).collect::<Vec<_>>();
+            writeln!(out, "{}", ascii_wheel_marked(key, &marked))?;
+            if !neighbors.is_empty() {
+                writeln!(out)?;
+            }
+            for (transition, to, interval) in neighbors {
+                writeln!(
+                    out,
+                    "{}\t{transition:?}\t{interval}",
+                    paint_scale(to, notation, colors)
+                )?;
+            }
+        }
+        Command::Predecessors { key } => {
+            let key = notation.parse(&key)?;
+            for (transition, from) in wheel.predecessors(key)? {
+                writeln!(
+                    out,
+                    "{}\t{transition:?}\t{}",
+                    paint_scale(from, notation, colors),
+                    Interval::between(from, key)
+                )?;
+            }
+        }
+        Command::Reach { from, steps } => {
+            for (scale, distance) in wheel.reachable(notation.parse(&from)?, steps)? {
+                writeln!(out, "{}\t{distance}", paint_scale(scale, notation, colors))?;
+            }
+        }
+        Command::Bridges { from, to, steps } => {
+            let (from, to) = (notation.parse(&from)?, notation.parse(&to)?);
+            for (key, before, after) in wheel.bridges(from, to, steps)? {
+                writeln!(
+                    out,
+                    "{}\t{before}\t{after}",
+                    paint_scale(key, notation, colors)
+                )?;
+            }
+        }
+        Command::Convert { key, to } => {
+            let scale = notation.parse(&key).or_else(|error| {
+                Scale::from_open_key(&key)
+                    .or_else(|_| Scale::from_key_name(&key, Locale::English))
+                    .or_else(|_| Scale::from_key_name(&key, Locale::German))
+                    .map(|scale| notation.numbering.from_standard(scale))
+                    .map_err(|_| error)
+            })?;
+            match to {
+                Some(to) => writeln!(out, "{}", to.write(scale, notation))?,
+                None => {
+                    for (name, to) in [
+                        ("camelot", KeyNotation::Camelot),
+                        ("open key", KeyNotation::OpenKey),
+                        ("musical", KeyNotation::Musical),
+                        ("german", KeyNotation::German),
+                    ] {
+                        writeln!(out, "{name}\t{}", to.write(scale, notation))?;
+                    }
+                }
+            }
+        }
+        Command::Compat { from, to } => {
+            let (from, to) = (notation.parse_modal(&from)?, notation.parse_modal(&to)?);
+            let (from_key, to_key) = (notation.wheel_key(from), notation.wheel_key(to));
+            let distances = DistanceMatrix::new(&wheel);
+            let distance = distances
+                .distance(from_key, to_key)
+                .map_or("-".to_string(), |distance| distance.to_string());
+            writeln!(
+                out,
+                "{} -> {}	{distance}	{:.3}",
+                paint(&notation.modal_label(from), wheel_color(from_key), colors),
+                paint(&notation.modal_label(to), wheel_color(to_key), colors),
+                modal_compatibility(&distances, from, to)
+            )?;
+            for transposition in
+                camelol::transpositions(&distances, from_key, to_key, cli.pitch_range)
+            {
+                writeln!(
+                    out,
+                    "  {} with key lock, or pitch {:+.1}% without",
+                    format_shift(&transposition, notation, colors),
+                    transposition.pitch
+                )?;
+            }
+        }
+        Command::Stretch { key, bpm, to } => {
+            let to = to.map(|to| notation.parse(&to)).transpose()?;
+            let distances = DistanceMatrix::new(&wheel);
+            let range = cli.pitch_range.unwrap_or(camelol::DEFAULT_PITCH_RANGE);
+            for stretch in camelol::stretches(notation.parse(&key)?, bpm, range) {
+                let mut line = format!(
+                    "{:+}\t{:+.2}%\t{:.2}\t{}",
+                    stretch.semitones,
+                    stretch.pitch,
+                    stretch.tempo,
+                    paint_scale(stretch.key, notation, colors)
+                );
+                if let Some(to) = to {
+                    let distance = distances
+                        .distance(stretch.key, to)
+                        .map_or("-".to_string(), |distance| distance.to_string());
+                    line.push_str(&format!("\t{distance}"));
+                }
+                writeln!(out, "{line}")?;
+            }
+        }
+        Command::Mashup { from, to } => {
+            let numbering = notation.numbering;
+            let (from, to) = (notation.parse(&from)?, notation.parse(&to)?);
+            let mashup = mashup(numbering.standard(from), numbering.standard(to));
+            writeln!(
+                out,
+                "{} -> {}: {}/7 notes shared, layering score {:.3}",
+                paint_scale(from, notation, colors),
+                paint_scale(to, notation, colors),
+                mashup.notes.len(),
+                mashup.score
+            )?;
+            for note in &mashup.notes {
+                writeln!(
+                    out,
+                    "  {}	degree {} -> {}",
+                    note.note, note.from_degree, note.to_degree
+                )?;
+            }
+            for chord in &mashup.chords {
+                writeln!(
+                    out,
+                    "  {}	{} -> {}",
+                    chord.name,
+                    symbols(&chord.from_numeral),
+                    symbols(&chord.to_numeral)
+                )?;
+            }
+        }
+        Command::Sequence {
+            scales,
+            quarter_tones: true,
+        } => {
+            let keys = scales
+                .iter()
+                .map(|key| key.parse())
+                .collect::<camelol::Result<Vec<camelol::QuarterToneKey>>>()?;
+            print_sequence(&keys)?;
+        }
+        Command::Sequence { scales, .. } => {
+            let keys = scales
+                .iter()
+                .map(|key| {
+                    Ok(camelol::WheelKey {
+                        wheel: &wheel,
+                        scale: notation.parse(key)?,
+                    })
+                })
+                .collect::<camelol::Result<Vec<_>>>()?;
+            print_sequence(&keys)?;
+        }
+        Command::Relations { key } => {
+            let key = notation.parse(&key)?;
+            for (relation, related) in [
+                ("relative", key.relative()),
+                ("parallel", key.parallel()),
+                ("dominant", key.dominant()),
+                ("subdominant", key.subdominant()),
+            ] {
+                writeln!(
+                    out,
+                    "{relation}\t{}\t{}",
+                    paint_scale(related, Notation::new(KeyDisplay::Camelot), colors),
+                    notation.key_name(related)
+                )?;
+            }
+        }
+        Command::Notes { scales } => {
+            for key in scales {
+                let key = notation.parse(&key)?;
+                writeln!(
+                    out,
+                    "{}\t{}",
+                    paint_scale(key, notation, colors),
+                    notation.note_names(key).join(" ")
+                )?;
+            }
+        }
+        Command::Progression { chords, n } => {
+            let chords = camelol::parse_progression(&chords.join(" "))?;
+            for fit in camelol::progression_keys(&chords).iter().take(n) {
+                let numerals: Vec<String> = fit
+                    .numerals
+                    .iter()
+                    .map(|numeral| symbols(numeral.as_deref().unwrap_or("-")))
+                    .collect();
+                writeln!(
+                    out,
+                    "{}\t{}/{} chords\t{:.0}% of notes\t{}{}",
+                    paint_scale(notation.numbering.from_standard(fit.key), notation, colors),
+                    fit.fitting,
+                    chords.len(),
+                    fit.notes * 100.0,
+                    numerals.join(" "),
+                    if fit.tonic { "\ttonic" } else { "" }
+                )?;
+            }
+        }
+        Command::Modulate { from, to, n, by } => {
+            let (from, to) = (notation.parse(&from)?, notation.parse(&to)?);
+            let cost = step_cost(by, script, &cli.prefer);
+            let label = |key: Scale| notation.label(notation.numbering.from_standard(key));
+            for (number, path) in wheel.paths_with(from, to, n, cost)?.iter().enumerate() {
+                writeln!(
+                    out,
+                    "{}. {}",
+                    number + 1,
+                    format_path_colored(path, notation, colors)
+                )?;
+                // Chords are worked out on the keys the wheel numbers stand for.
+                let standard = Path {
+                    path: path
+                        .path
+                        .iter()
+                        .map(|&key| notation.numbering.standard(key))
+                        .collect(),
+                    ..path.clone()
+                };
+                for step in camelol::modulation_steps(&standard) {
+                    // The best few pivots are enough to go on, relative keys share all seven.
+                    let pivots: Vec<String> = step
+                        .pivots
+                        .iter()
+                        .take(3)
+                        .map(|chord| {
+                            format!(
+                                "{} ({} -> {})",
+                                chord.name,
+                                symbols(&chord.from_numeral),
+                                symbols(&chord.to_numeral)
+                            )
+                        })
+                        .collect();
+                    let pivots = if pivots.is_empty() {
+                        "no chord in common, go".to_string()
+                    } else {
+                        format!("pivot on {}, then", pivots.join(", "))
+                    };
+                    writeln!(
+                        out,
+                        "   {} -> {}\t{pivots} through {} (V) into {}",
+                        label(step.from),
+                        label(step.to),
+                        step.dominant,
+                        label(step.to)
+                    )?;
+                }
+            }
+        }
+        Command::Journey {
+            from,
+            length,
+            seed,
+            temperature,
+        } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            eprintln!("seed: {seed}");
+
+            let from = notation.parse(&from)?;
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let path = tempered_journey(
+                &wheel,
+                from,
+                length,
+                temperature,
+                &mut rng,
+                default_transition_weight,
+            )?;
+            writeln!(out, "{}", format_path_colored(&path, notation, colors))?;
+        }
+        Command::Suggest {
+            from,
+            from_option,
+            n,
+            library,
+            bpm_range,
+            #[cfg(feature = "link")]
+            link,
+            ignore_confidence,
+            history,
+            blend,
+        } => {
+            #[cfg(feature = "link")]
+            let bpm_range = if link {
+                let range = cli.pitch_range.unwrap_or(camelol::DEFAULT_PITCH_RANGE);
+                Some(camelol::TempoRange::around(link_tempo()?, range))
+            } else {
+                bpm_range
+            };
+            let from = notation.parse(&from.or(from_option).unwrap_or_default())?;
+            let model = load_model(history, &notation)?;
+
+            let distances = DistanceMatrix::new(&wheel);
+            let suggestions = suggest(&distances, from, model.as_ref(), blend);
+            let stdin = std::io::stdin();
+            let candidates = if stdin.is_terminal() {
+                String::new()
+            } else {
+                std::io::read_to_string(stdin)?
+            };
+            let candidates: Vec<&str> = candidates
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect();
+            if candidates.is_empty() {
+                for suggestion in suggestions.into_iter().take(n.unwrap_or(5)) {
+                    writeln!(
+                        out,
+                        "{}\t{:.3}",
+                        paint_scale(suggestion.scale, notation, colors),
+                        suggestion.score
+                    )?;
+                }
+                return Ok(());
+            }
+
+            let library = library
+                .or(config.library)
+                .map(|library| camelol::TrackLibrary::load(&library, &notation))
+                .transpose()?
+                .unwrap_or_default();
+            // Candidates with their key and score, and whether the key needs checking.
+            let mut ranked: Vec<_> = candidates
+                .into_iter()
+                .filter_map(|candidate| {
+                    let track = candidate_track(candidate, &library, &notation);
+                    if let Some(range) = bpm_range {
+                        track
+                            .as_ref()
+                            .and_then(|track| track.tempo)
+                            .and_then(|tempo| range.matching_tempo(tempo))?;
+                    }
+                    let Some(track) = track else {
+                        return Some((candidate, None, None, false));
+                    };
+                    let (key, confidence) = (track.incoming_key(), track.confidence);
+                    let weight = if ignore_confidence { None } else { confidence };
+                    let score = suggestions
+                        .iter()
+                        .find(|suggestion| suggestion.scale == key)
+                        .map(|suggestion| camelol::weigh_confidence(suggestion.score, weight));
+                    let unsure =
+                        confidence.is_some_and(|confidence| confidence < camelol::LOW_CONFIDENCE);
+                    let shift = camelol::transpositions(&distances, from, key, cli.pitch_range)
+                        .first()
+                        .copied();
+                    Some((candidate, score.map(|score| (key, score)), shift, unsure))
+                })
+                .collect();
+            // Best first, keeping the piped order between equals and leaving unknown keys last.
+            ranked.sort_by(|(_, a, ..), (_, b, ..)| {
+                let score = |ranking: &Option<(Scale, f64)>| {
+                    ranking.map_or(f64::NEG_INFINITY, |(_, score)| score)
+                };
+                score(b).total_cmp(&score(a))
+            });
+            for (candidate, ranking, shift, unsure) in
+                ranked.into_iter().take(n.unwrap_or(usize::MAX))
+            {
+                match ranking {
+                    Some((key, score)) => writeln!(
+                        out,
+                        "{candidate}\t{}\t{score:.3}{}{}",
+                        paint_scale(key, notation, colors),
+                        shift.map_or(String::new(), |shift| format!(
+                            "\t{}",
+                            format_shift(&shift, notation, colors)
+                        )),
+                        if unsure { "\tunsure key" } else { "" }
+                    )?,
+                    None => writeln!(out, "{candidate}\t-\t-")?,
+                }
+            }
+        }
+        Command::Session {
+            action,
+            file,
+            n,
+            history,
+            blend,
+            max_key_run,
+            avoid_recent,
+            #[cfg(feature = "midi")]
+            midi,
+            osc,
+        } => {
+            let mut session = match fs::read_to_string(&file) {
+                Ok(log) => Session::parse(&log)?,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Session::default(),
+                Err(error) => return Err(error.into()),
+            };
+
+            match action {
+                SessionAction::Play { key } => {
+                    let play = session.record(notation.parse(&key)?);
+                    let mut log = OpenOptions::new().create(true).append(true).open(&file)?;
+                    writeln!(log, "{play}")?;
+                }
+                SessionAction::Suggest => {}
+                SessionAction::Show => {
+                    for play in &session.plays {
+                        writeln!(
+                            out,
+                            "{}\t{}",
+                            play.time,
+                            paint_scale(play.scale, notation, colors)
+                        )?;
+                    }
+                    return Ok(());
+                }
+            }
+
+            let model = load_model(history, &notation)?;
+            let distances = DistanceMatrix::new(&wheel);
+            let mut suggestions = session.suggest(&distances, model.as_ref(), blend);
+            if let Some(window) = avoid_recent {
+                session.avoid_recent(&mut suggestions, window);
+            }
+            if let Some(max_run) = max_key_run {
+                session.limit_key_run(&mut suggestions, max_run);
+            }
+            suggestions.truncate(n);
+            if let Some(play) = session.plays.last() {
+                #[cfg(feature = "midi")]
+                if let Some(mut output) = midi.open(&notation)? {
+                    let suggested = suggestions.first().map(|suggestion| suggestion.scale);
+                    output.update(play.scale, suggested)?;
+                }
+                if let Some(output) = osc.open()? {
+                    output.update(play.scale, &suggestions)?;
+                }
+            }
+            for suggestion in suggestions {
+                writeln!(
+                    out,
+                    "{}\t{:.3}",
+                    paint_scale(suggestion.scale, notation, colors),
+                    suggestion.score
+                )?;
+            }
+        }
+        #[cfg(feature = "server")]
+        Command::Serve {
+            addr,
+            search_timeout,
+            #[cfg(feature = "midi")]
+            midi,
+            osc,
+            overlay,
+        } => {
+            let outputs = camelol::server::LiveOutputs {
+                #[cfg(feature = "midi")]
+                midi: midi.open(&notation)?,
+                osc: osc.open()?,
+                overlay: overlay.open(&notation)?,
+            };
+            let mut engine = Engine::new(wheel);
+            engine.search_time = search_timeout;
+            let router = camelol::server::router_with_outputs(engine, outputs);
+            eprintln!("listening on http://{addr}");
+            let serve = camelol::server::serve(router, addr);
+            tokio::runtime::Runtime::new()?.block_on(serve)?
+        }
+        #[cfg(unix)]
+        Command::Daemon {
+            socket,
+            search_timeout,
+            library,
+        } => {
+            let mut engine = Engine::new(wheel);
+            engine.search_time = search_timeout;
+            let library = library
+                .or(config.library)
+                .map(|library| camelol::TrackLibrary::load(&library, &notation))
+                .transpose()?
+                .unwrap_or_default();
+            eprintln!("listening on {}", socket.display());
+            camelol::run_daemon(engine, library, &socket)?
+        }
+        #[cfg(feature = "midi")]
+        Command::Detect { files } => {
+            for file in files {
+                match camelol::detect_midi_key(&fs::read(&file)?)? {
+                    Some(estimate) => writeln!(
+                        out,
+                        "{}\t{}\t{:.3}",
+                        file.display(),
+                        paint_scale(estimate.key, notation, colors),
+                        estimate.correlation
+                    )?,
+                    None => writeln!(out, "{}\t-", file.display())?,
+                }
+            }
+        }
+        #[cfg(feature = "midi")]
+        Command::Keyboard {
+            device,
+            n,
+            midi,
+            osc,
+            overlay,
+        } => {
+            let mut midi = midi.open(&notation)?;
+            let osc = osc.open()?;
+            let overlay = overlay.open(&notation)?;
+            let distances = DistanceMatrix::new(&wheel);
+            let mut session = Session::default();
+            for estimate in camelol::KeyboardKeys::open(&device)? {
+                let estimate = estimate?;
+                session.record(estimate.key);
+                let mut suggestions = session.suggest(&distances, None, 0.0);
+                suggestions.truncate(n);
+                writeln!(
+                    out,
+                    "{}\t{:.3}",
+                    paint_scale(estimate.key, notation, colors),
+                    estimate.correlation
+                )?;
+                for suggestion in &suggestions {
+                    writeln!(
+                        out,
+                        "\t{}\t{:.3}",
+                        paint_scale(suggestion.scale, notation, colors),
+                        suggestion.score
+                    )?;
+                }
+                if let Some(midi) = &mut midi {
+                    let suggested = suggestions.first().map(|suggestion| suggestion.scale);
+                    midi.update(estimate.key, suggested)?;
+                }
+                if let Some(osc) = &osc {
+                    osc.update(estimate.key, &suggestions)?;
+                }
+                if let Some(overlay) = &overlay {
+                    overlay.update(estimate.key, &suggestions)?;
+                }
+            }
+        }
+        #[cfg(any(feature = "audio", feature = "midi"))]
+        Command::Stems { a, b } => {
+            let (a, b) = (camelol::analyze_stems(&a)?, camelol::analyze_stems(&b)?);
+            for stem in a.iter().chain(&b) {
+                match stem.estimate {
+                    Some(estimate) => writeln!(
+                        out,
+                        "{}\t{}\t{:.3}",
+                        stem.path.display(),
+                        paint_scale(estimate.key, notation, colors),
+                        estimate.correlation
+                    )?,
+                    None => writeln!(out, "{}\t-", stem.path.display())?,
+                }
+            }
+            for stem_match in camelol::match_stems(&a, &b) {
+                writeln!(
+                    out,
+                    "{}\t{}\t{:.3}",
+                    stem_match.a.path.display(),
+                    stem_match.b.path.display(),
+                    stem_match.score
+                )?;
+            }
+        }
+        #[cfg(any(target_os = "linux", windows))]
+        Command::Listen {
+            library,
+            player,
+            n,
+            max_key_run,
+            avoid_recent,
+            #[cfg(feature = "midi")]
+            midi,
+            osc,
+            overlay,
+        } => {
+            let library = config.library(library)?;
+            let library = camelol::TrackLibrary::parse(&fs::read_to_string(library)?, &notation)?;
+            #[cfg(feature = "midi")]
+            let mut midi = midi.open(&notation)?;
+            let osc = osc.open()?;
+            let overlay = overlay.open(&notation)?;
+            let distances = DistanceMatrix::new(&wheel);
+            let mut session = Session::default();
+            for track in camelol::NowPlaying::follow(player.as_deref())? {
+                let track = track?;
+                // The next track is mixed in from wherever this one ends up.
+                let Some(key) = library.track(&track).map(camelol::Track::outgoing_key) else {
+                    writeln!(out, "{track}\t-")?;
+                    continue;
+                };
+                session.record(key);
+                let mut suggestions = session.suggest(&distances, None, 0.0);
+                if let Some(window) = avoid_recent {
+                    session.avoid_recent(&mut suggestions, window);
+                }
+                if let Some(max_run) = max_key_run {
+                    session.limit_key_run(&mut suggestions, max_run);
+                }
+                suggestions.truncate(n);
+                writeln!(out, "{track}\t{}", paint_scale(key, notation, colors))?;
+                for suggestion in &suggestions {
+                    writeln!(
+                        out,
+                        "\t{}\t{:.3}",
+                        paint_scale(suggestion.scale, notation, colors),
+                        suggestion.score
+                    )?;
+                }
+                #[cfg(feature = "midi")]
+                if let Some(midi) = &mut midi {
+                    let suggested = suggestions.first().map(|suggestion| suggestion.scale);
+                    midi.update(key, suggested)?;
+                }
+                if let Some(osc) = &osc {
+                    osc.update(key, &suggestions)?;
+                }
+                if let Some(overlay) = &overlay {
+                    overlay.update(key, &suggestions)?;
+                }
+            }
+        }
+        #[cfg(feature = "audio")]
+        Command::Analyze {
+            dir,
+            library: library_file,
+            csv,
+            detect,
+            profile,
+            compare_profiles,
+            dedupe,
+            loudness,
+            #[cfg(feature = "cache")]
+            cache,
+        } => {
+            use rayon::prelude::*;
+
+            if compare_profiles {
+                let files = camelol::audio_files(&dir)?;
+                let progress = ProgressBar::new(files.len() as u64);
+                let consensus: Vec<_> = files
+                    .par_iter()
+                    .map(|file| {
+                        let consensus = camelol::decode_audio(file)
+                            .map(|audio| camelol::key_consensus(&camelol::chroma(&audio)));
+                        progress.inc(1);
+                        consensus
+                    })
+                    .collect();
+                progress.finish_and_clear();
+                for (file, consensus) in files.iter().zip(consensus) {
+                    match consensus {
+                        Ok(Some(consensus)) => {
+                            let estimates: Vec<String> = consensus
+                                .estimates
+                                .iter()
+                                .map(|(profile, estimate)| {
+                                    format!(
+                                        "{profile} {} {:.2}",
+                                        notation.label(estimate.key),
+                                        estimate.correlation
+                                    )
+                                })
+                                .collect();
+                            writeln!(
+                                out,
+                                "{}\t{:.0}% agree\t{}\t{}",
+                                paint_scale(consensus.key, notation, colors),
+                                consensus.agreement * 100.0,
+                                estimates.join(", "),
+                                file.display()
+                            )?;
+                        }
+                        Ok(None) => tracing::warn!(path = %file.display(), "silent, no key found"),
+                        Err(error) => eprintln!("{}: {error}", file.display()),
+                    }
+                }
+                return Ok(());
+            }
+            let profile = profile.profile();
+
+            #[cfg(feature = "cache")]
+            let cache = cache
+                .map(|cache| camelol::AnalysisCache::open(&cache))
+                .transpose()?;
+            // The fingerprints of the files kept so far.
+            let mut fingerprints: Vec<(camelol::Fingerprint, PathBuf)> = vec![];
+            let library_file = library_file.or(config.library);
+            let mut library = library_file
+                .as_ref()
+                .map(|file| camelol::TrackLibrary::load(file, &notation))
+                .transpose()?;
+            let mut report = vec![camelol::csv_row(&[
+                "path",
+                "name",
+                "key",
+                "color",
+                "confidence",
+                "tempo",
+                "duration",
+                "source",
+                "error",
+            ])];
+            // Paths are kept absolute, so scans from anywhere find the same files in the library.
+            let mut files = camelol::audio_files(&fs::canonicalize(&dir)?)?;
+            if let Some(library) = &library {
+                // When the files in the library were last modified as of their analysis.
+                let known: std::collections::HashMap<PathBuf, Option<u64>> = library
+                    .tracks()
+                    .iter()
+                    .filter_map(|track| {
+                        let path = track.path.as_deref()?;
+                        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+                        Some((path, track.modified))
+                    })
+                    .collect();
+                let found = files.len();
+                files.retain(|file| {
+                    !known.get(file).is_some_and(|&modified| {
+                        modified.is_some() && modified == camelol::file_modified(file)
+                    })
+                });
+                tracing::info!(
+                    skipped = found - files.len(),
+                    "skipping files unchanged since they were added to the library"
+                );
+            }
+            // Without fpcalc every file would fail, so that's found out once up front.
+            if dedupe {
+                camelol::check_fpcalc()?;
+            }
+            let progress = ProgressBar::new(files.len() as u64);
+            let mut failed = 0;
+            // Files are decoded and analyzed on every core a batch at a time, then added in the
+            // order found, so little is lost when a scan is interrupted.
+            for batch in files.chunks(4 * rayon::current_num_threads()) {
+                let analyses: Vec<_> = batch
+                    .par_iter()
+                    .map(|file| {
+                        let tagged = if detect {
+                            Ok(None)
+                        } else {
+                            camelol::tagged_track(file)
+                        };
+                        let analysis = tagged.and_then(|track| match track {
+                            Some(mut track) => {
+                                if loudness && track.energy.is_none() {
+                                    track.energy = camelol::loudness(&camelol::decode_audio(file)?)
+                                        .map(|loudness| loudness.energy());
+                                }
+                                Ok(Some((track, "tags")))
+                            }
+                            None => {
+                                #[cfg(feature = "cache")]
+                                let analysis = match &cache {
+                                    Some(cache) => cache.analyze_with(file, profile),
+                                    None => camelol::analyze_audio_with(file, profile),
+                                };
+                                #[cfg(not(feature = "cache"))]
+                                let analysis = camelol::analyze_audio_with(file, profile);
+                                Ok(analysis?.map(|track| (track, "analysis")))
+                            }
+                        });
+                        let (analysis, fingerprint) = match analysis.and_then(|analysis| {
+                            let fingerprint = (dedupe && analysis.is_some())
+                                .then(|| camelol::fingerprint(file))
+                                .transpose()?;
+                            Ok((analysis, fingerprint))
+                        }) {
+                            Ok((analysis, fingerprint)) => (Ok(analysis), fingerprint),
+                            Err(error) => (Err(error), None),
+                        };
+                        if let Err(error) = &analysis {
+                            progress.suspend(|| eprintln!("{}: {error}", file.display()));
+                        }
+                        progress.inc(1);
+                        (analysis, fingerprint)
+                    })
+                    .collect();
+                for (file, (analysis, fingerprint)) in batch.iter().zip(analyses) {
+                    let path = file.display().to_string();
+                    let (mut track, source) = match analysis {
+                        Ok(Some(analysis)) => analysis,
+                        Ok(None) => {
+                            report.push(camelol::csv_row(&[
+                                &path, "", "", "", "", "", "", "", "silent",
+                            ]));
+                            continue;
+                        }
+                        Err(error) => {
+                            failed += 1;
+                            let error = error.to_string();
+                            report.push(camelol::csv_row(&[
+                                &path, "", "", "", "", "", "", "", &error,
+                            ]));
+                            continue;
+                        }
+                    };
+                    if let Some(library) = &library {
+                        track.name = library.name_for(&track.name, file);
+                        // What was added by hand to the track of a file analyzed again stays.
+                        if let Some(known) = library.track(&track.name) {
+                            if track.genre.is_none() {
+                                track.genre.clone_from(&known.genre);
+                            }
+                            if track.tags.is_empty() {
+                                track.tags.clone_from(&known.tags);
+                            }
+                        }
+                    }
+                    let original = fingerprint.and_then(|fingerprint| {
+                        let original = fingerprints
+                            .iter()
+                            .find(|(kept, _)| kept.is_duplicate(&fingerprint))
+                            .map(|(_, original)| original.display().to_string());
+                        if original.is_none() {
+                            fingerprints.push((fingerprint, file.clone()));
+                        }
+                        original
+                    });
+                    report.push(camelol::csv_row(&[
+                        path,
+                        track.name.clone(),
+                        track.key.to_string(),
+                        wheel_color(track.key).to_string(),
+                        track
+                            .confidence
+                            .map_or(String::new(), |confidence| format!("{confidence:.2}")),
+                        track
+                            .tempo
+                            .map_or(String::new(), |tempo| format!("{tempo:.1}")),
+                        track
+                            .duration
+                            .map_or(String::new(), |duration| format!("{duration:.1}")),
+                        source.to_string(),
+                        original
+                            .as_ref()
+                            .map_or(String::new(), |original| format!("duplicate of {original}")),
+                    ]));
+                    if let Some(original) = original {
+                        progress.suspend(|| {
+                            tracing::warn!(
+                                path = %file.display(),
+                                %original,
+                                "left out as a duplicate"
+                            );
+                        });
+                        continue;
+                    }
+                    progress.suspend(|| -> std::io::Result<()> {
+                        writeln!(out, "{track}")?;
+                        if track.needs_review() {
+                            tracing::warn!(
+                                path = %file.display(),
+                                confidence = track.confidence,
+                                "unsure of the key, check it by ear"
+                            );
+                        }
+                        Ok(())
+                    })?;
+                    if let (Some(library), Some(file)) = (&mut library, &library_file) {
+                        // A later line for the same track replaces the one before.
+                        let mut log = OpenOptions::new().create(true).append(true).open(file)?;
+                        writeln!(log, "{track}")?;
+                        library.insert(track);
+                    }
+                }
+            }
+            progress.finish_and_clear();
+            if let Some(csv) = csv {
+                report.push(String::new());
+                fs::write(csv, report.join("\n"))?;
+            }
+            if failed > 0 {
+                let message = format!(
+                    "{failed} of {} files couldn't be analyzed, see the errors above",
+                    files.len()
+                );
+                return Err(message.into());
+            }
+        }
+        #[cfg(feature = "audio")]
+        Command::Watch {
+            dir,
+            library: library_file,
+            interval,
+            existing,
+            profile,
+            #[cfg(feature = "cache")]
+            cache,
+        } => {
+            let profile = profile.profile();
+            let dir = fs::canonicalize(&dir)?;
+            #[cfg(feature = "cache")]
+            let cache = cache
+                .map(|cache| camelol::AnalysisCache::open(&cache))
+                .transpose()?;
+            let library_file = config.library(library_file)?;
+            let mut library = camelol::TrackLibrary::load(&library_file, &notation)?;
+            let mut seen = std::collections::HashSet::new();
+            if !existing {
+                seen.extend(camelol::audio_files(&dir)?);
+            }
+            // Files still being downloaded or copied are left until their size stops changing.
+            let mut sizes = std::collections::HashMap::new();
+            loop {
+                for file in camelol::audio_files(&dir)? {
+                    if seen.contains(&file) {
+                        continue;
+                    }
+                    let size = fs::metadata(&file)?.len();
+                    if sizes.insert(file.clone(), size) != Some(size) {
+                        continue;
+                    }
+                    seen.insert(file.clone());
+                    #[cfg(feature = "cache")]
+                    let analysis = match &cache {
+                        Some(cache) => cache.analyze_with(&file, profile),
+                        None => camelol::analyze_audio_with(&file, profile),
+                    };
+                    #[cfg(not(feature = "cache"))]
+                    let analysis = camelol::analyze_audio_with(&file, profile);
+                    let mut track = match analysis {
+                        Ok(Some(track)) => track,
+                        Ok(None) => continue,
+                        Err(error) => {
+                            eprintln!("{}: {error}", file.display());
+                            continue;
+                        }
+                    };
+                    track.name = library.name_for(&track.name, &file);
+                    if library.track(&track.name).is_some() {
+                        continue;
+                    }
+                    let mut log = OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&library_file)?;
+                    writeln!(log, "{track}")?;
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}",
+                        track.name,
+                        paint_scale(track.key, notation, colors),
+                        track
+                            .tempo
+                            .map_or("-".to_string(), |tempo| format!("{tempo:.1}"))
+                    )?;
+                    library.insert(track);
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+        }
+        #[cfg(feature = "audio")]
+        Command::Audit {
+            dir,
+            profile,
+            min_confidence,
+            fix,
+            interactive,
+        } => {
+            use rayon::prelude::*;
+
+            let profile = profile.profile();
+            let files = camelol::audio_files(&dir)?;
+            let progress = ProgressBar::new(files.len() as u64);
+            let audits: Vec<_> = files
+                .par_iter()
+                .map(|file| {
+                    let audit = camelol::audit_key(file, profile);
+                    progress.inc(1);
+                    audit
+                })
+                .collect();
+            progress.finish_and_clear();
+            let label = |key: Scale| notation.label(notation.numbering.from_standard(key));
+            let paint =
+                |key: Scale| paint_scale(notation.numbering.from_standard(key), notation, colors);
+
+            let stdin = std::io::stdin();
+            let prompt = stdin.is_terminal();
+            let mut line = String::new();
+            let (mut audited, mut mismatched, mut rewritten) = (0, 0, 0);
+            'files: for (file, audit) in files.iter().zip(audits) {
+                let audit = match audit {
+                    Ok(Some(audit)) => audit,
+                    Ok(None) => continue,
+                    Err(error) => {
+                        eprintln!("{}: {error}", file.display());
+                        continue;
+                    }
+                };
+                audited += 1;
+                if audit.agrees() || audit.confidence < min_confidence {
+                    continue;
+                }
+                mismatched += 1;
+                writeln!(
+                    out,
+                    "{} -> {}\t{:.2}\t{}",
+                    paint(audit.tagged),
+                    paint(audit.detected),
+                    audit.confidence,
+                    file.display()
+                )?;
+                let key = if interactive {
+                    // Asks again until the answer is one of the choices.
+                    loop {
+                        if prompt {
+                            write!(out, "write {}? [Y/n/key/q] ", label(audit.detected))?;
+                            out.flush()?;
+                        }
+                        line.clear();
+                        if stdin.lock().read_line(&mut line)? == 0 {
+                            break 'files;
+                        }
+                        match line.trim() {
+                            "" | "y" | "yes" => break Some(audit.detected),
+                            "n" | "no" => break None,
+                            "q" | "quit" | "exit" => break 'files,
+                            answer => match notation.parse(answer) {
+                                Ok(key) => break Some(notation.numbering.standard(key)),
+                                Err(error) => eprintln!("{error}"),
+                            },
+                        }
+                    }
+                } else {
+                    fix.then_some(audit.detected)
+                };
+                if let Some(key) = key {
+                    match camelol::write_key_tag(file, key) {
+                        Ok(()) => rewritten += 1,
+                        Err(error) => eprintln!("{error}"),
+                    }
+                }
+            }
+            tracing::info!(audited, mismatched, rewritten, "audited the key tags");
+        }
+        #[cfg(feature = "spotify")]
+        Command::Spotify { playlist, token } => {
+            for track in camelol::SpotifyClient::new(token).playlist_tracks(&playlist)? {
+                writeln!(out, "{track}")?;
+            }
+        }
+        #[cfg(feature = "beatport")]
+        Command::Beatport {
+            tracks,
+            library,
+            token,
+            dry_run,
+        } => {
+            let client = camelol::BeatportClient::new(token.unwrap_or_default());
+            let lookup = LibraryLookup {
+                tracks,
+                library: config.library(library)?,
+                dry_run,
+            };
+            lookup.run(&client, &notation, colors)?
+        }
+        #[cfg(feature = "musicbrainz")]
+        Command::Musicbrainz {
+            tracks,
+            library,
+            acoustid_key,
+            dry_run,
+        } => {
+            let mut client = camelol::BrainzClient::default();
+            if let Some(key) = acoustid_key {
+                client = client.with_acoustid_key(key);
+            }
+            let lookup = LibraryLookup {
+                tracks,
+                library: config.library(library)?,
+                dry_run,
+            };
+            lookup.run(&client, &notation, colors)?
+        }
+        #[cfg(feature = "itunes")]
+        Command::Itunes { library, playlist } => {
+            let Some(tracks) = camelol::read_itunes_library(&library, playlist.as_deref())? else {
+                return Err(format!("no playlist named `{}`", playlist.unwrap_or_default()).into());
+            };
+            for track in tracks {
+                writeln!(out, "{track}")?;
+            }
+        }
+        #[cfg(feature = "engine")]
+        Command::EngineDj { library, playlist } => {
+            let Some(tracks) = camelol::read_engine_library(&library, playlist.as_deref())? else {
+                return Err(format!("no playlist named `{}`", playlist.unwrap_or_default()).into());
+            };
+            for track in tracks {
+                writeln!(out, "{track}")?;
+            }
+        }
+        #[cfg(feature = "virtualdj")]
+        Command::Virtualdj { database } => {
+            for track in camelol::read_virtualdj_database(&database)? {
+                writeln!(out, "{track}")?;
+            }
+        }
+        Command::Plan {
+            library,
+            first,
+            last,
+            minutes,
+            energy,
+            max_key_run,
+            max_genre_run,
+            tag_placements,
+            rekordbox,
+            serato,
+            traktor,
+            cue,
+            recording,
+            overlap,
+            json,
+            html,
+            #[cfg(feature = "audio")]
+            mix_points,
+            name,
+        } => {
+            let library = config.library(library)?;
+            let library = camelol::TrackLibrary::parse(&fs::read_to_string(library)?, &notation)?;
+            let pinned = |name: Option<String>| -> Result<Option<usize>, Box<dyn Error>> {
+                name.map(|name| {
+                    library
+                        .position(&name)
+                        .ok_or_else(|| format!("no track named `{name}` in the library").into())
+                })
+                .transpose()
+            };
+            let options = camelol::PlanOptions {
+                first: pinned(first)?,
+                last: pinned(last)?,
+                length: minutes.map(|minutes| minutes * 60.0),
+                energy,
+                max_key_run,
+                max_genre_run,
+                tag_placements,
+                pitch_range: cli.pitch_range,
+            };
+            if options.first.is_some() && options.first == options.last {
+                return Err("the set can't open and close with the same track".into());
+            }
+            let distances = DistanceMatrix::new(&wheel);
+            let set = camelol::SetPlanner::new(&distances).plan(library.tracks(), &options);
+            if let (Some(minutes), true) = (minutes, set.is_empty()) {
+                return Err(format!("no track fits in {minutes} minutes").into());
+            }
+            if let Some(range) = cli.pitch_range {
+                for pair in set.windows(2) {
+                    if let Some(pitch) = pair[0]
+                        .pitch_into(&pair[1])
+                        .filter(|pitch| pitch.abs() > range)
+                    {
+                        return Err(format!(
+                            "`{}` into `{}` needs pitch {pitch:+.1}%, beyond the {range}% the pitch faders go",
+                            pair[0].name, pair[1].name
+                        )
+                        .into());
+                    }
+                }
+            }
+            for track in &set {
+                writeln!(out, "{track}")?;
+            }
+            for track in set.iter().filter(|track| track.needs_review()) {
+                tracing::warn!(
+                    track = %track.name,
+                    confidence = track.confidence,
+                    "unsure of the key, check it by ear"
+                );
+            }
+            if let Some(rekordbox) = rekordbox {
+                let missing = set.iter().filter(|track| track.path.is_none()).count();
+                if missing > 0 {
+                    eprintln!("left {missing} tracks without a file path out of the playlist");
+                }
+                fs::write(rekordbox, camelol::rekordbox_xml(&set, &name))?;
+            }
+            if let Some(serato) = serato {
+                let missing = set.iter().filter(|track| track.path.is_none()).count();
+                if missing > 0 {
+                    eprintln!("left {missing} tracks without a file path out of the crate");
+                }
+                fs::write(serato, camelol::serato_crate(&set))?;
+            }
+            if let Some(traktor) = traktor {
+                let missing = set.iter().filter(|track| track.path.is_none()).count();
+                if missing > 0 {
+                    eprintln!("left {missing} tracks without a file path out of the playlist");
+                }
+                fs::write(traktor, camelol::traktor_nml(&set, &name))?;
+            }
+            if let Some(cue) = cue {
+                let recording = recording.unwrap_or_else(|| {
+                    let stem = cue.file_stem().unwrap_or_default().to_string_lossy();
+                    format!("{stem}.wav")
+                });
+                let Some(sheet) = camelol::cue_sheet(&set, &name, &recording, overlap) else {
+                    return Err("every track but the last needs a duration for a CUE sheet".into());
+                };
+                fs::write(cue, sheet)?;
+            }
+            if let Some(json) = json {
+                let project = camelol::SetProject::new(&name, &set, &distances);
+                fs::write(json, serde_json::to_string_pretty(&project)?)?;
+            }
+            if let Some(html) = html {
+                fs::write(
+                    html,
+                    camelol::html_report(&name, &set, &distances, &notation, cli.pitch_range),
+                )?;
+            }
+            #[cfg(feature = "audio")]
+            if let Some(mix_points) = mix_points {
+                let points = camelol::set_mix_points(&set);
+                fs::write(mix_points, camelol::mix_point_sheet(&set, &points))?;
+            }
+        }
+        Command::Matches { library, k, csv } => {
+            let library = config.library(library)?;
+            let library = camelol::TrackLibrary::parse(&fs::read_to_string(library)?, &notation)?;
+            let tracks = library.tracks();
+            let distances = DistanceMatrix::new(&wheel);
+            let matches = camelol::best_matches(
+                tracks,
+                &distances,
+                k,
+                cli.pitch_range.unwrap_or(camelol::DEFAULT_PITCH_RANGE),
+            );
+            let format_pitch =
+                |pitch: Option<f64>| pitch.map_or("-".to_string(), |pitch| format!("{pitch:+.1}%"));
+            let mut report = vec![camelol::csv_row(&[
+                "track",
+                "key",
+                "match",
+                "match_key",
+                "compatibility",
+                "pitch",
+                "score",
+            ])];
+            for (track, matches) in tracks.iter().zip(&matches) {
+                writeln!(
+                    out,
+                    "{}\t{}",
+                    track.name,
+                    paint_scale(track.outgoing_key(), notation, colors)
+                )?;
+                for found in matches {
+                    let other = &tracks[found.to];
+                    writeln!(
+                        out,
+                        "  {:.3}\t{}\t{}\t{}",
+                        found.score,
+                        other.name,
+                        paint_scale(other.incoming_key(), notation, colors),
+                        format_pitch(found.pitch)
+                    )?;
+                    report.push(camelol::csv_row(&[
+                        track.name.clone(),
+                        track.outgoing_key().to_string(),
+                        other.name.clone(),
+                        other.incoming_key().to_string(),
+                        format!("{:.3}", found.compatibility),
+                        found
+                            .pitch
+                            .map_or(String::new(), |pitch| format!("{pitch:.2}")),
+                        format!("{:.3}", found.score),
+                    ]));
+                }
+            }
+            if let Some(csv) = csv {
+                report.push(String::new());
+                fs::write(csv, report.join("\n"))?;
+            }
+        }
+        Command::Communities => {
+            let communities = camelol::key_communities(&wheel, default_transition_weight);
+            for keys in &communities {
+                let keys: Vec<String> = keys
+                    .iter()
+                    .map(|&key| paint_scale(key, notation, colors).to_string())
+                    .collect();
+                writeln!(out, "{}", keys.join(" "))?;
+            }
+            writeln!(
+                out,
+                "modularity\t{:.3}",
+                camelol::modularity(&wheel, &communities, default_transition_weight)
+            )?;
+        }
+        Command::Crates {
+            library,
+            n,
+            dir,
+            communities,
+        } => {
+            let library = config.library(library)?;
+            let library = camelol::TrackLibrary::parse(&fs::read_to_string(library)?, &notation)?;
+            let tracks = library.tracks();
+            let clusters = if communities {
+                let communities = camelol::key_communities(&wheel, default_transition_weight);
+                camelol::community_clusters(tracks, &communities)
+            } else {
+                camelol::key_clusters(tracks, n)
+            };
+            fs::create_dir_all(&dir)?;
+            for cluster in clusters {
+                let members: Vec<&camelol::Track> =
+                    cluster.tracks.iter().map(|&i| &tracks[i]).collect();
+                let file = dir.join(format!("{}.m3u", cluster.name()));
+                let missing = members.iter().filter(|track| track.path.is_none()).count();
+                if missing > 0 {
+                    tracing::warn!(
+                        playlist = %file.display(),
+                        tracks = missing,
+                        "tracks left out, the library doesn't say where their files are"
+                    );
+                }
+                fs::write(&file, camelol::m3u_playlist(&members))?;
+                // Members come in wheel order, so each key's tracks are next to each other.
+                let mut keys: Vec<_> = members.iter().map(|track| track.key).collect();
+                keys.dedup();
+                let keys: Vec<String> = keys
+                    .into_iter()
+                    .map(|key| paint_scale(key, notation, colors).to_string())
+                    .collect();
+                writeln!(
+                    out,
+                    "{}\t{} tracks\t{}",
+                    file.display(),
+                    members.len(),
+                    keys.join(" ")
+                )?;
+            }
+        }
+        Command::Order {
+            playlist: playlist_file,
+            output,
+            library,
+        } => {
+            let format = camelol::PlaylistFormat::of(&playlist_file);
+            let mut playlist =
+                camelol::Playlist::parse(&fs::read_to_string(&playlist_file)?, format, &notation)?;
+            let library = library
+                .or(config.library)
+                .map(|library| camelol::TrackLibrary::load(&library, &notation))
+                .transpose()?
+                .unwrap_or_default();
+            let directory = playlist_file.parent().unwrap_or(std::path::Path::new(""));
+            for entry in &mut playlist.entries {
+                let file = entry.path.as_ref().map(|path| directory.join(path));
+                let known = library
+                    .tracks()
+                    .iter()
+                    .find(|track| {
+                        track.path.is_some() && (track.path == entry.path || track.path == file)
+                    })
+                    .or_else(|| library.track(&entry.name));
+                if let Some(track) = known {
+                    entry.fill(track);
+                }
+                #[cfg(feature = "audio")]
+                if let (None, Some(file)) = (entry.key, &file) {
+                    if let Ok(Some(track)) = camelol::tagged_track(file) {
+                        entry.fill(&track);
+                    }
+                }
+            }
+
+            let (known, unknown): (Vec<usize>, Vec<usize>) =
+                (0..playlist.entries.len()).partition(|&i| playlist.entries[i].key.is_some());
+            let tracks: Vec<camelol::Track> = known
+                .iter()
+                .filter_map(|&i| playlist.entries[i].track())
+                .collect();
+            let distances = DistanceMatrix::new(&wheel);
+            let planner = camelol::SetPlanner::new(&distances);
+            let order = planner.plan_order(&tracks, &camelol::PlanOptions::default());
+            let set: Vec<camelol::Track> = order.iter().map(|&i| tracks[i].clone()).collect();
 
-        let path = path
-            .into_iter()
-            .intersperse(" -> ".into())
-            .collect::<String>();
-        dbg!(path);
+            for (number, track) in set.iter().enumerate() {
+                if let Some(previous) = number.checked_sub(1).map(|previous| &set[previous]) {
+                    let (from, to) = (previous.outgoing_key(), track.incoming_key());
+                    let transition = if from == to {
+                        "same key".to_string()
+                    } else {
+                        match wheel.paths(from, to, 1)?.first() {
+                            Some(path) => format_path_colored(path, notation, colors),
+                            None => "no harmonic path".to_string(),
+                        }
+                    };
+                    writeln!(out, "     {transition}")?;
+                }
+                writeln!(
+                    out,
+                    "{:>3}  {}  {}",
+                    number + 1,
+                    paint_scale(track.key, notation, colors),
+                    track.name
+                )?;
+            }
+            for &i in &unknown {
+                eprintln!("no key for `{}`, left at the end", playlist.entries[i].name);
+            }
+            writeln!(
+                out,
+                "{} transitions in total, {} in the original order",
+                planner.cost(&set),
+                planner.cost(&tracks)
+            )?;
+
+            let output = output.unwrap_or_else(|| {
+                let stem = playlist_file
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                let extension = playlist_file
+                    .extension()
+                    .map_or(String::new(), |extension| {
+                        format!(".{}", extension.to_string_lossy())
+                    });
+                playlist_file.with_file_name(format!("{stem}.ordered{extension}"))
+            });
+            let order: Vec<usize> = order
+                .iter()
+                .map(|&i| known[i])
+                .chain(unknown.iter().copied())
+                .collect();
+            fs::write(output, playlist.write(&order))?;
+        }
+        #[cfg(feature = "link")]
+        Command::Tempo { once } => {
+            let link = camelol::LinkListener::join()?;
+            let mut last = None;
+            while let Some(tempo) = link.next_tempo(None)? {
+                // Peers keep announcing the same tempo, so only print changes.
+                if last != Some(tempo) {
+                    writeln!(out, "{tempo:.2}")?;
+                    last = Some(tempo);
+                }
+                if once {
+                    break;
+                }
+            }
+        }
+        Command::Quiz { n, seed, file } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            run_quiz(&wheel, n, &mut rng, &file, notation, colors)?;
+        }
+        Command::Autodj { start, library, n } => {
+            let library = config.library(library)?;
+            let library = camelol::TrackLibrary::parse(&fs::read_to_string(library)?, &notation)?;
+            let start = library
+                .position(&start)
+                .ok_or_else(|| format!("no track named `{start}` in the library"))?;
+            let distances = DistanceMatrix::new(&wheel);
+            let mut dj = camelol::AutoDj::new(
+                library.tracks(),
+                &distances,
+                start,
+                n,
+                cli.pitch_range.unwrap_or(camelol::DEFAULT_PITCH_RANGE),
+            );
+            run_auto_dj(&mut dj, library.tracks(), notation, colors)?;
+        }
+        Command::Repl { library } => {
+            let library = library
+                .or(config.library)
+                .map(|library| camelol::TrackLibrary::load(&library, &notation))
+                .transpose()?;
+            Repl {
+                wheel: &wheel,
+                distances: DistanceMatrix::new(&wheel),
+                script,
+                prefer: &cli.prefer,
+                notation,
+                colors,
+                library,
+                paths: config.paths,
+                key: None,
+            }
+            .run()?;
+        }
+        #[cfg(feature = "tui")]
+        Command::Explore { from } => {
+            camelol::tui::explore(&wheel, notation.parse(&from)?, cli.plain)?
+        }
+        #[cfg(feature = "tui")]
+        Command::Live { from } => camelol::tui::live(&wheel, notation.parse(&from)?, cli.plain)?,
+        #[cfg(feature = "tui")]
+        Command::Build {
+            start,
+            library,
+            output,
+        } => {
+            let library = config.library(library)?;
+            let library = camelol::TrackLibrary::parse(&fs::read_to_string(library)?, &notation)?;
+            let start = start
+                .iter()
+                .map(|name| {
+                    library
+                        .position(name)
+                        .ok_or_else(|| format!("no track named `{name}` in the library"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let distances = DistanceMatrix::new(&wheel);
+            let set = camelol::tui::build_set(
+                library.tracks(),
+                &distances,
+                cli.pitch_range.unwrap_or(camelol::DEFAULT_PITCH_RANGE),
+                start,
+                cli.plain,
+            )?;
+            let set: String = set
+                .into_iter()
+                .map(|track| format!("{}\n", library.tracks()[track]))
+                .collect();
+            match output {
+                Some(output) => fs::write(output, set)?,
+                None => write!(out, "{set}")?,
+            }
+        }
+        #[cfg(feature = "gui")]
+        Command::Gui { library } => camelol::gui::build_set(
+            Engine::new(wheel),
+            notation,
+            library.or(config.library),
+            cli.pitch_range.unwrap_or(camelol::DEFAULT_PITCH_RANGE),
+        )?,
     }
+
+    Ok(())
 }
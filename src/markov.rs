@@ -0,0 +1,58 @@
+use crate::error::{Error, Result};
+use crate::render::Notation;
+use crate::scale::Scale;
+use std::collections::HashMap;
+
+/// Parses a set history: one set per line, keys separated by whitespace. Blank lines and lines
+/// starting with `#` are skipped. Keys are camelot codes or key names in `notation`'s locale.
+/// Errors name the line of the first invalid key.
+pub fn parse_history(history: &str, notation: &Notation) -> Result<Vec<Vec<Scale>>> {
+    history
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            line.split_whitespace()
+                .map(|key| {
+                    notation.parse(key).map_err(|_| Error::InvalidHistory {
+                        line: number + 1,
+                        key: key.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// First-order Markov model of key changes, learned from sequences of played keys.
+#[derive(Debug, Clone, Default)]
+pub struct MarkovModel {
+    counts: HashMap<Scale, HashMap<Scale, usize>>,
+}
+
+impl MarkovModel {
+    pub fn train<S: AsRef<[Scale]>>(sets: impl IntoIterator<Item = S>) -> Self {
+        let mut model = Self::default();
+        for set in sets {
+            for pair in set.as_ref().windows(2) {
+                *model
+                    .counts
+                    .entry(pair[0])
+                    .or_default()
+                    .entry(pair[1])
+                    .or_default() += 1;
+            }
+        }
+        model
+    }
+
+    /// Observed probability of moving from `from` to `to`, or 0 if `from` was never left.
+    pub fn probability(&self, from: Scale, to: Scale) -> f64 {
+        let Some(next) = self.counts.get(&from) else {
+            return 0.0;
+        };
+        let total = next.values().sum::<usize>();
+        next.get(&to).copied().unwrap_or(0) as f64 / total as f64
+    }
+}
@@ -0,0 +1,93 @@
+use crate::distance::DistanceMatrix;
+use crate::library::Track;
+use crate::suggest::compatibility;
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// How far tempos may be apart, in percent, when no pitch range is given.
+pub const DEFAULT_PITCH_RANGE: f64 = 8.0;
+
+/// How well one track of a library mixes into another.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TrackMatch {
+    /// Index of the track mixed into.
+    pub to: usize,
+    /// Of the key the first track ends in and the key the other starts in, from 1 for the same
+    /// key down towards 0.
+    pub compatibility: f64,
+    /// Percent to pitch the other track by to match tempos, at half or double time if that's
+    /// closer, if both tempos are known.
+    pub pitch: Option<f64>,
+    /// The compatibility, lowered the further apart the tempos are.
+    pub score: f64,
+}
+
+/// Percent to pitch a track at `to` BPM by to play it at `from`, at half or double time if
+/// that needs less.
+fn closest_pitch(from: f64, to: f64) -> f64 {
+    [to, to * 2.0, to / 2.0]
+        .into_iter()
+        .map(|to| (from / to - 1.0) * 100.0)
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .unwrap_or_default()
+}
+
+/// How well `track` mixes into `other`, found at index `to`, scored like [`best_matches`]
+/// does, or `None` if it doesn't.
+pub(crate) fn track_match(
+    track: &Track,
+    other: &Track,
+    to: usize,
+    distances: &DistanceMatrix,
+    pitch_range: f64,
+) -> Option<TrackMatch> {
+    let compatibility =
+        compatibility(distances.distance(track.outgoing_key(), other.incoming_key()));
+    let pitch = match (track.tempo, other.tempo) {
+        (Some(tempo), Some(other)) if other > 0.0 => Some(closest_pitch(tempo, other)),
+        _ => None,
+    };
+    let fit = pitch.map_or(1.0, |pitch| 1.0 - pitch.abs() / pitch_range);
+    let score = compatibility * fit;
+    (score > 0.0).then_some(TrackMatch {
+        to,
+        compatibility,
+        pitch,
+        score,
+    })
+}
+
+/// The `k` best tracks to mix into from every track of `tracks`, best first, found in
+/// parallel. Keys are compared with `distances`, and the score falls linearly with the pitch
+/// it takes to match tempos, leaving out pairs further apart than `pitch_range` percent.
+/// Tracks of unknown tempo are judged by key alone, and pairs whose keys can't reach each
+/// other are left out.
+pub fn best_matches(
+    tracks: &[Track],
+    distances: &DistanceMatrix,
+    k: usize,
+    pitch_range: f64,
+) -> Vec<Vec<TrackMatch>> {
+    tracing::info!(tracks = tracks.len(), k, "matching every pair of tracks");
+    tracks
+        .par_iter()
+        .enumerate()
+        .map(|(from, track)| {
+            let mut matches: Vec<TrackMatch> = tracks
+                .iter()
+                .enumerate()
+                .filter(|&(to, _)| to != from)
+                .filter_map(|(to, other)| track_match(track, other, to, distances, pitch_range))
+                .collect();
+            let by_score =
+                |a: &TrackMatch, b: &TrackMatch| b.score.total_cmp(&a.score).then(a.to.cmp(&b.to));
+            // Only the best k need sorting, which keeps large libraries fast.
+            if matches.len() > k && k > 0 {
+                matches.select_nth_unstable_by(k - 1, by_score);
+            }
+            matches.truncate(k);
+            matches.sort_by(by_score);
+            matches
+        })
+        .collect()
+}
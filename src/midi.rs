@@ -0,0 +1,200 @@
+use crate::detect::{detect_key, rank_keys, KeyEstimate};
+use crate::error::Result;
+use crate::scale::Scale;
+use midly::live::LiveEvent;
+use midly::num::u4;
+use midly::{MidiMessage, Smf, TrackEventKind};
+use std::fs::File;
+use std::io::{self, BufReader, Bytes, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// The channel General MIDI reserves for drums, which carry no pitch.
+const DRUM_CHANNEL: u8 = 9;
+/// Seconds after which a note played on a keyboard counts half as much toward its key, so the
+/// key follows what's played now.
+const NOTE_HALF_LIFE: f64 = 8.0;
+/// Fewest pitch classes heard before a keyboard's key is estimated, as in a triad.
+const MIN_PITCH_CLASSES: usize = 3;
+/// How much better in correlation another key must fit than the current one before the key
+/// changes, so it doesn't flicker between keys sharing most notes.
+const KEY_CHANGE_MARGIN: f64 = 0.05;
+
+/// Total sounding time of each pitch class from C up, in ticks, over every track of a standard
+/// MIDI file. Drums are left out.
+pub fn pitch_histogram(bytes: &[u8]) -> Result<[f64; 12]> {
+    let smf = Smf::parse(bytes)?;
+    let mut histogram = [0.0; 12];
+
+    for track in &smf.tracks {
+        let mut time = 0u64;
+        // When each note of each channel started sounding.
+        let mut started = [[None::<u64>; 128]; 16];
+        for event in track {
+            time += u64::from(event.delta.as_int());
+            let TrackEventKind::Midi { channel, message } = event.kind else {
+                continue;
+            };
+            let channel = channel.as_int();
+            if channel == DRUM_CHANNEL {
+                continue;
+            }
+            let (key, on) = match message {
+                MidiMessage::NoteOn { key, vel } => (key.as_int(), vel.as_int() > 0),
+                MidiMessage::NoteOff { key, .. } => (key.as_int(), false),
+                _ => continue,
+            };
+            let start = &mut started[channel as usize][key as usize];
+            if let Some(start) = start.take() {
+                histogram[key as usize % 12] += (time - start) as f64;
+            }
+            if on {
+                *start = Some(time);
+            }
+        }
+    }
+
+    Ok(histogram)
+}
+
+/// Writes one channel message to a raw MIDI output, such as a `/dev/snd/midiC1D0` port.
+pub(crate) fn send(output: &mut impl Write, channel: u8, message: MidiMessage) -> io::Result<()> {
+    let event = LiveEvent::Midi {
+        channel: u4::new(channel & 0x0F),
+        message,
+    };
+    event.write_std(&mut *output)?;
+    output.flush()
+}
+
+/// Estimates the key of a standard MIDI file, or `None` if it has no pitched notes.
+pub fn detect_midi_key(bytes: &[u8]) -> Result<Option<KeyEstimate>> {
+    Ok(detect_key(&pitch_histogram(bytes)?))
+}
+
+/// The key of what's played on a MIDI keyboard, read from a raw MIDI input such as a
+/// `/dev/snd/midiC1D0` port and estimated from the notes played lately, louder ones counting
+/// more.
+pub struct KeyboardKeys<R: Read> {
+    input: Bytes<BufReader<R>>,
+    /// The status byte of the message being read, kept for running status.
+    status: Option<u8>,
+    histogram: [f64; 12],
+    updated: Instant,
+    key: Option<Scale>,
+}
+
+impl KeyboardKeys<File> {
+    /// Listens on the raw MIDI input port `device`.
+    pub fn open(device: &Path) -> io::Result<Self> {
+        Ok(Self::new(File::open(device)?))
+    }
+}
+
+impl<R: Read> KeyboardKeys<R> {
+    /// Listens to the MIDI bytes of `input`.
+    pub fn new(input: R) -> Self {
+        Self {
+            input: BufReader::new(input).bytes(),
+            status: None,
+            histogram: [0.0; 12],
+            updated: Instant::now(),
+            key: None,
+        }
+    }
+
+    /// The next channel message of the input, or `None` once it ends. System messages are
+    /// skipped.
+    fn message(&mut self) -> io::Result<Option<MidiMessage>> {
+        let mut data = vec![];
+        loop {
+            let Some(byte) = self.input.next().transpose()? else {
+                return Ok(None);
+            };
+            match byte {
+                // Real-time messages can come between the bytes of any other.
+                0xF8.. => continue,
+                0x80..=0xEF => {
+                    self.status = Some(byte);
+                    data.clear();
+                    continue;
+                }
+                0xF0..=0xF7 => {
+                    // System messages end running status, and their data is skipped.
+                    self.status = None;
+                    continue;
+                }
+                _ => data.push(byte),
+            }
+            let Some(status) = self.status else {
+                continue;
+            };
+            let length = match status >> 4 {
+                0xC | 0xD => 1,
+                _ => 2,
+            };
+            if data.len() < length {
+                continue;
+            }
+            let mut bytes = vec![status];
+            bytes.append(&mut data);
+            if let Ok(LiveEvent::Midi { channel, message }) = LiveEvent::parse(&bytes) {
+                if channel.as_int() != DRUM_CHANNEL {
+                    return Ok(Some(message));
+                }
+            }
+        }
+    }
+
+    /// Counts a note played at `time` toward the key, returning the new key if it changed.
+    fn hear(&mut self, key: u8, velocity: u8, time: Instant) -> Option<KeyEstimate> {
+        let elapsed = time.duration_since(self.updated).as_secs_f64();
+        let decay = 0.5f64.powf(elapsed / NOTE_HALF_LIFE);
+        for weight in &mut self.histogram {
+            *weight *= decay;
+        }
+        self.updated = time;
+        self.histogram[key as usize % 12] += f64::from(velocity) / 127.0;
+
+        let heard = self
+            .histogram
+            .iter()
+            .filter(|&&weight| weight > 0.0)
+            .count();
+        if heard < MIN_PITCH_CLASSES {
+            return None;
+        }
+        let ranked = rank_keys(&self.histogram);
+        let best = *ranked.first()?;
+        let current = ranked
+            .iter()
+            .find(|estimate| Some(estimate.key) == self.key)
+            .map_or(f64::NEG_INFINITY, |estimate| estimate.correlation);
+        if Some(best.key) == self.key || best.correlation < current + KEY_CHANGE_MARGIN {
+            return None;
+        }
+        self.key = Some(best.key);
+        Some(best)
+    }
+}
+
+/// Yields the key each time what's played moves into another one.
+impl<R: Read> Iterator for KeyboardKeys<R> {
+    type Item = io::Result<KeyEstimate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let message = match self.message() {
+                Ok(message) => message?,
+                Err(error) => return Some(Err(error)),
+            };
+            if let MidiMessage::NoteOn { key, vel } = message {
+                if vel.as_int() > 0 {
+                    if let Some(estimate) = self.hear(key.as_int(), vel.as_int(), Instant::now()) {
+                        return Some(Ok(estimate));
+                    }
+                }
+            }
+        }
+    }
+}
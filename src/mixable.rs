@@ -0,0 +1,432 @@
+//! Path search and ordering over any system of keys that says which keys mix into which, so
+//! Open Key, modal or entirely custom wheels can reuse the machinery the camelot wheel uses.
+
+use crate::error::{Error, Result};
+use crate::scale::{OpenKey, Scale};
+use crate::search::SearchLimit;
+use crate::transition::ScaleTransition;
+use crate::wheel::Wheel;
+use camelol_core::{neighbors, TRANSITIONS};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::iter;
+use std::rc::Rc;
+
+/// Cost of ordering two keys next to each other that can't reach each other, like
+/// [`SetPlanner::transition_cost`](crate::SetPlanner::transition_cost) charges.
+const UNREACHABLE_COST: u32 = 100;
+
+/// A key of some key system, which knows the keys it mixes into and what each move costs.
+/// The system has to have finitely many keys, since searches explore every key they reach.
+pub trait MixableKey: Clone + Eq + Hash {
+    /// What a move from one key to another is called, like a [`ScaleTransition`].
+    type Transition: Clone + Debug;
+
+    /// The keys one move away, with the move that reaches them, in a stable order.
+    fn neighbors(&self) -> Vec<(Self::Transition, Self)>;
+
+    /// What a path pays for `transition` from this key to `to`, 1 by default so paths are
+    /// ranked by their number of moves.
+    fn transition_cost(&self, _transition: &Self::Transition, _to: &Self) -> u32 {
+        1
+    }
+
+    /// The key as written to people, like `8A` or `1m`.
+    fn display(&self) -> String;
+}
+
+/// The extended camelot wheel, with every transition.
+impl MixableKey for Scale {
+    type Transition = ScaleTransition;
+
+    fn neighbors(&self) -> Vec<(ScaleTransition, Scale)> {
+        neighbors(*self).map_or_else(Vec::new, |neighbors| {
+            TRANSITIONS
+                .into_iter()
+                .zip(neighbors.iter().copied())
+                .collect()
+        })
+    }
+
+    fn display(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// The same circle of fifths written in Open Key notation.
+impl MixableKey for OpenKey {
+    type Transition = ScaleTransition;
+
+    fn neighbors(&self) -> Vec<(ScaleTransition, OpenKey)> {
+        self.0
+            .neighbors()
+            .into_iter()
+            .map(|(transition, scale)| (transition, scale.open_key()))
+            .collect()
+    }
+
+    fn display(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// A key on a [`Wheel`], which mixes only as the wheel's preset or rules allow.
+#[derive(Debug, Clone, Copy)]
+pub struct WheelKey<'a> {
+    pub wheel: &'a Wheel,
+    pub scale: Scale,
+}
+
+impl PartialEq for WheelKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.scale == other.scale
+    }
+}
+
+impl Eq for WheelKey<'_> {}
+
+impl Hash for WheelKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.scale.hash(state);
+    }
+}
+
+impl MixableKey for WheelKey<'_> {
+    type Transition = ScaleTransition;
+
+    fn neighbors(&self) -> Vec<(ScaleTransition, Self)> {
+        self.wheel
+            .neighbors(self.scale)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(transition, scale)| {
+                (
+                    transition,
+                    WheelKey {
+                        wheel: self.wheel,
+                        scale,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn display(&self) -> String {
+        self.scale.to_string()
+    }
+}
+
+/// A path through any key system, see [`key_paths`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixPath<K: MixableKey> {
+    pub cost: u32,
+    pub keys: Vec<K>,
+    pub transitions: Vec<K::Transition>,
+}
+
+impl<K: MixableKey> MixPath<K> {
+    /// The path like `8A -> ChangeIndex(1) -> 9A`.
+    pub fn display(&self) -> String {
+        let mut text = self.keys.first().map(K::display).unwrap_or_default();
+        for (transition, key) in self.transitions.iter().zip(self.keys.iter().skip(1)) {
+            text.push_str(&format!(" -> {transition:?} -> {}", key.display()));
+        }
+        text
+    }
+}
+
+/// The keys reachable from `sources` numbered in the order they were reached, with the moves
+/// out of each as `(transition, key, cost)`.
+struct KeyGraph<K: MixableKey> {
+    keys: Vec<K>,
+    numbers: HashMap<K, usize>,
+    edges: Vec<Vec<(K::Transition, usize, u32)>>,
+}
+
+impl<K: MixableKey> KeyGraph<K> {
+    fn explore(sources: &[K]) -> Self {
+        let mut graph = Self {
+            keys: vec![],
+            numbers: HashMap::new(),
+            edges: vec![],
+        };
+        let mut queue = VecDeque::new();
+        for source in sources {
+            if !graph.numbers.contains_key(source) {
+                graph.add(source.clone());
+                queue.push_back(graph.keys.len() - 1);
+            }
+        }
+        while let Some(number) = queue.pop_front() {
+            let key = graph.keys[number].clone();
+            let mut edges = vec![];
+            for (transition, neighbor) in key.neighbors() {
+                let cost = key.transition_cost(&transition, &neighbor);
+                let next = match graph.numbers.get(&neighbor) {
+                    Some(&next) => next,
+                    None => {
+                        graph.add(neighbor);
+                        queue.push_back(graph.keys.len() - 1);
+                        graph.keys.len() - 1
+                    }
+                };
+                edges.push((transition, next, cost));
+            }
+            graph.edges[number] = edges;
+        }
+        graph
+    }
+
+    fn add(&mut self, key: K) {
+        self.numbers.insert(key.clone(), self.keys.len());
+        self.keys.push(key);
+        self.edges.push(vec![]);
+    }
+
+    /// Moves needed from every key to the nearest of `targets`, `None` where there's no way.
+    fn moves_to(&self, targets: &[usize]) -> Vec<Option<u32>> {
+        let mut incoming = vec![vec![]; self.keys.len()];
+        for (from, edges) in self.edges.iter().enumerate() {
+            for &(_, to, _) in edges {
+                incoming[to].push(from);
+            }
+        }
+        let mut remaining = vec![None; self.keys.len()];
+        let mut queue = VecDeque::new();
+        for &target in targets {
+            remaining[target] = Some(0);
+            queue.push_back(target);
+        }
+        while let Some(key) = queue.pop_front() {
+            let left = remaining[key].unwrap_or(0);
+            for &previous in &incoming[key] {
+                if remaining[previous].is_none() {
+                    remaining[previous] = Some(left + 1);
+                    queue.push_back(previous);
+                }
+            }
+        }
+        remaining
+    }
+}
+
+/// The last key of a path on the search frontier, linked back to the one before.
+struct Step<T> {
+    key: usize,
+    transition: Option<T>,
+    previous: Option<Rc<Step<T>>>,
+}
+
+impl<T> Step<T> {
+    fn back(&self) -> impl Iterator<Item = &Step<T>> {
+        iter::successors(Some(self), |step| step.previous.as_deref())
+    }
+}
+
+struct Candidate<T> {
+    step: Rc<Step<T>>,
+    cost: u32,
+    estimate: u32,
+    length: usize,
+    /// When the candidate was found, so equal paths come out in the order the keys list their
+    /// neighbors.
+    found: usize,
+}
+
+impl<T> PartialEq for Candidate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Candidate<T> {}
+
+/// Reversed, so the lowest estimate pops first.
+impl<T> Ord for Candidate<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| other.cost.cmp(&self.cost))
+            .then_with(|| other.length.cmp(&self.length))
+            .then_with(|| other.found.cmp(&self.found))
+    }
+}
+
+impl<T> PartialOrd for Candidate<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The `n` cheapest paths from any of `sources` to any of `targets` in any key system, priced
+/// with [`MixableKey::transition_cost`], cheapest first. Like
+/// [`Wheel::paths_between`] it's an A* search where steps that cost nothing never lead back to
+/// a key already on the path, and it stops with [`Error::SearchStopped`] at the `limit`'s
+/// deadline or cancellation. Equal paths come in the order the keys list their neighbors.
+pub fn key_paths<K: MixableKey>(
+    sources: &[K],
+    targets: &[K],
+    n: usize,
+    limit: &SearchLimit,
+) -> Result<Vec<MixPath<K>>> {
+    let graph = KeyGraph::explore(sources);
+    let targets: Vec<usize> = targets
+        .iter()
+        .filter_map(|target| graph.numbers.get(target).copied())
+        .collect();
+    let remaining = graph.moves_to(&targets);
+    let cheapest = graph
+        .edges
+        .iter()
+        .flatten()
+        .map(|&(_, _, cost)| cost)
+        .min()
+        .unwrap_or(0);
+
+    let mut heap = BinaryHeap::new();
+    let mut found = 0;
+    for source in sources {
+        let number = graph.numbers[source];
+        let Some(moves) = remaining[number] else {
+            continue;
+        };
+        if heap
+            .iter()
+            .any(|candidate: &Candidate<K::Transition>| candidate.step.key == number)
+        {
+            continue;
+        }
+        heap.push(Candidate {
+            step: Rc::new(Step {
+                key: number,
+                transition: None,
+                previous: None,
+            }),
+            cost: 0,
+            estimate: moves * cheapest,
+            length: 1,
+            found,
+        });
+        found += 1;
+    }
+
+    let mut paths = vec![];
+    while let Some(candidate) = heap.pop() {
+        if limit.reached() {
+            return Err(Error::SearchStopped);
+        }
+        let step = &candidate.step;
+        if targets.contains(&step.key) {
+            let mut keys: Vec<K> = step
+                .back()
+                .map(|step| graph.keys[step.key].clone())
+                .collect();
+            let mut transitions: Vec<K::Transition> = step
+                .back()
+                .filter_map(|step| step.transition.clone())
+                .collect();
+            keys.reverse();
+            transitions.reverse();
+            paths.push(MixPath {
+                cost: candidate.cost,
+                keys,
+                transitions,
+            });
+            if paths.len() >= n {
+                break;
+            }
+        }
+        for (transition, next, cost) in &graph.edges[step.key] {
+            let Some(moves) = remaining[*next] else {
+                continue;
+            };
+            if *cost == 0 && step.back().any(|step| step.key == *next) {
+                continue;
+            }
+            let path_cost = candidate.cost + cost;
+            heap.push(Candidate {
+                step: Rc::new(Step {
+                    key: *next,
+                    transition: Some(transition.clone()),
+                    previous: Some(Rc::clone(step)),
+                }),
+                cost: path_cost,
+                estimate: path_cost + moves * cheapest,
+                length: candidate.length + 1,
+                found,
+            });
+            found += 1;
+        }
+    }
+    Ok(paths)
+}
+
+/// What the cheapest path from `from` to every key it reaches costs.
+fn costs_from<K: MixableKey>(from: &K) -> HashMap<K, u32> {
+    let graph = KeyGraph::explore(std::slice::from_ref(from));
+    let mut costs = vec![None; graph.keys.len()];
+    let mut heap = BinaryHeap::new();
+    heap.push((std::cmp::Reverse(0), 0));
+    while let Some((std::cmp::Reverse(cost), key)) = heap.pop() {
+        if costs[key].is_some() {
+            continue;
+        }
+        costs[key] = Some(cost);
+        for &(_, next, step) in &graph.edges[key] {
+            if costs[next].is_none() {
+                heap.push((std::cmp::Reverse(cost + step), next));
+            }
+        }
+    }
+    graph
+        .keys
+        .into_iter()
+        .zip(costs)
+        .filter_map(|(key, cost)| Some((key, cost?)))
+        .collect()
+}
+
+/// An order to play `keys` in, as indices into them, that keeps the moves between neighbours
+/// cheap: starting from the first key, the cheapest next key is taken greedily and stretches
+/// are then reversed while that lowers the total, like the set planner does for tracks.
+pub fn order_keys<K: MixableKey>(keys: &[K]) -> Vec<usize> {
+    let costs: Vec<HashMap<K, u32>> = keys.iter().map(costs_from).collect();
+    let cost = |a: usize, b: usize| costs[a].get(&keys[b]).copied().unwrap_or(UNREACHABLE_COST);
+    let total =
+        |order: &[usize]| -> u32 { order.windows(2).map(|pair| cost(pair[0], pair[1])).sum() };
+
+    let mut order: Vec<usize> = keys.first().map(|_| 0).into_iter().collect();
+    let mut unplayed: Vec<usize> = (1..keys.len()).collect();
+    while let Some(&current) = order.last() {
+        let Some((next, _)) = unplayed
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &key)| cost(current, key))
+        else {
+            break;
+        };
+        order.push(unplayed.remove(next));
+    }
+
+    let mut best = total(&order);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for start in 1..order.len() {
+            for end in start + 1..order.len() {
+                order[start..=end].reverse();
+                let reversed = total(&order);
+                if reversed < best {
+                    best = reversed;
+                    improved = true;
+                } else {
+                    order[start..=end].reverse();
+                }
+            }
+        }
+    }
+    order
+}
@@ -0,0 +1,69 @@
+use crate::chord::{diatonic_triads, roman_numeral, Quality, SharedChord, Triad};
+use crate::scale::Scale;
+use crate::search::Path;
+use crate::transition::ScaleTransition;
+use serde::Serialize;
+
+/// One key change of a modulation within a track, with the chords that carry it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModulationStep {
+    pub from: Scale,
+    pub transition: ScaleTransition,
+    pub to: Scale,
+    /// Triads diatonic to both keys, to play before turning towards the new one, the smoothest
+    /// pivots first: those on the new key's second and fourth degrees, which lead to its
+    /// dominant, then its sixth, then the rest, with diminished chords last.
+    pub pivots: Vec<SharedChord>,
+    /// The new key's dominant, a major triad even in minor keys, which confirms the arrival
+    /// on its way to the new tonic.
+    pub dominant: String,
+}
+
+/// How good a chord on `degree` of the new key is as a pivot, lower first.
+fn pivot_rank(degree: usize, quality: Quality) -> usize {
+    match (degree, quality) {
+        (_, Quality::Diminished) => 4,
+        (1 | 3, _) => 0,
+        (5, _) => 1,
+        // The new tonic as a pivot gives the change away before it happens.
+        (0, _) => 3,
+        _ => 2,
+    }
+}
+
+/// The key changes of `path`, planned as a modulation in a single track rather than a mix
+/// between two, with pivot chord hints at every step.
+pub fn modulation_steps(path: &Path) -> Vec<ModulationStep> {
+    path.steps()
+        .into_iter()
+        .map(|(from, transition, to)| {
+            let to_triads = diatonic_triads(to);
+            let mut pivots: Vec<(usize, SharedChord)> = diatonic_triads(from)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(from_degree, triad)| {
+                    let to_degree = to_triads.iter().position(|other| *other == triad)?;
+                    let chord = SharedChord {
+                        name: triad.name(from),
+                        quality: triad.quality,
+                        from_numeral: roman_numeral(from_degree, triad.quality),
+                        to_numeral: roman_numeral(to_degree, triad.quality),
+                    };
+                    Some((pivot_rank(to_degree, triad.quality), chord))
+                })
+                .collect();
+            pivots.sort_by_key(|(rank, _)| *rank);
+            let dominant = Triad {
+                root: (to.tonic() + 7) % 12,
+                quality: Quality::Major,
+            };
+            ModulationStep {
+                from,
+                transition,
+                to,
+                pivots: pivots.into_iter().map(|(_, chord)| chord).collect(),
+                dominant: dominant.name(to),
+            }
+        })
+        .collect()
+}
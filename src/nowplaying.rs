@@ -0,0 +1,124 @@
+use std::io::{self, BufRead, BufReader};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// Follows the media session of the System Media Transport Controls, printing `Artist - Title`
+/// every second. `CAMELOL_PLAYER` picks the session of the app whose id contains it.
+#[cfg(windows)]
+const SMTC_SCRIPT: &str = r#"
+[Console]::OutputEncoding = [Text.Encoding]::UTF8
+Add-Type -AssemblyName System.Runtime.WindowsRuntime
+$asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {
+    $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and
+    $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1'
+} | Select-Object -First 1
+function Await($operation, $type) {
+    $task = $asTask.MakeGenericMethod($type).Invoke($null, @($operation))
+    $task.Wait(-1) | Out-Null
+    $task.Result
+}
+$managerType = [Windows.Media.Control.GlobalSystemMediaTransportControlsSessionManager, Windows.Media.Control, ContentType = WindowsRuntime]
+$propertiesType = [Windows.Media.Control.GlobalSystemMediaTransportControlsSessionMediaProperties, Windows.Media.Control, ContentType = WindowsRuntime]
+$manager = Await ($managerType::RequestAsync()) $managerType
+while ($true) {
+    $session = if ($env:CAMELOL_PLAYER) {
+        $manager.GetSessions() | Where-Object {
+            $_.SourceAppUserModelId -like "*$env:CAMELOL_PLAYER*"
+        } | Select-Object -First 1
+    } else {
+        $manager.GetCurrentSession()
+    }
+    if ($session) {
+        $properties = Await ($session.TryGetMediaPropertiesAsync()) $propertiesType
+        if ($properties.Title) {
+            [Console]::Out.WriteLine("$($properties.Artist) - $($properties.Title)")
+            [Console]::Out.Flush()
+        }
+    }
+    Start-Sleep -Seconds 1
+}
+"#;
+
+/// Tracks played by media players: MPRIS players on Linux, using `playerctl` to follow them,
+/// and media sessions of the System Media Transport Controls on Windows, using PowerShell.
+pub struct NowPlaying {
+    child: Child,
+    lines: io::Lines<BufReader<ChildStdout>>,
+    last: Option<String>,
+}
+
+impl NowPlaying {
+    /// Follows `player`, or whichever player is active when `None`. On Windows, `player` is
+    /// matched against the id of the app playing, like `Spotify`.
+    pub fn follow(player: Option<&str>) -> io::Result<Self> {
+        let (mut command, missing) = follow_command(player);
+        let mut child =
+            command
+                .stdout(Stdio::piped())
+                .spawn()
+                .map_err(|error| match error.kind() {
+                    io::ErrorKind::NotFound => io::Error::new(error.kind(), missing),
+                    _ => error,
+                })?;
+        let stdout = child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?;
+        Ok(Self {
+            child,
+            lines: BufReader::new(stdout).lines(),
+            last: None,
+        })
+    }
+}
+
+/// The command printing `Artist - Title` lines for what `player` plays, with the error for
+/// when it isn't installed.
+#[cfg(not(windows))]
+fn follow_command(player: Option<&str>) -> (Command, &'static str) {
+    let mut command = Command::new("playerctl");
+    if let Some(player) = player {
+        command.arg("--player").arg(player);
+    }
+    command.args(["metadata", "--follow", "--format", "{{artist}} - {{title}}"]);
+    (
+        command,
+        "playerctl is needed to follow MPRIS players but wasn't found",
+    )
+}
+
+#[cfg(windows)]
+fn follow_command(player: Option<&str>) -> (Command, &'static str) {
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", SMTC_SCRIPT]);
+    if let Some(player) = player {
+        command.env("CAMELOL_PLAYER", player);
+    }
+    (
+        command,
+        "PowerShell is needed to follow media sessions but wasn't found",
+    )
+}
+
+/// Yields `Artist - Title` each time the playing track changes.
+impl Iterator for NowPlaying {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            // playerctl repeats the metadata when only the playback status changes, and the
+            // media session is read every second.
+            if !line.trim().is_empty() && self.last.as_ref() != Some(&line) {
+                self.last = Some(line.clone());
+                return Some(Ok(line));
+            }
+        }
+    }
+}
+
+impl Drop for NowPlaying {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
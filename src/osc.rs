@@ -0,0 +1,113 @@
+use crate::scale::Scale;
+use crate::suggest::Suggestion;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// An OSC argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+/// Appends `s` null-terminated and padded to a multiple of four bytes.
+fn push_padded(packet: &mut Vec<u8>, s: &str) {
+    packet.extend_from_slice(s.as_bytes());
+    packet.resize((packet.len() / 4 + 1) * 4, 0);
+}
+
+/// Encodes an OSC message for `address` with `args`.
+pub fn osc_message(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    push_padded(&mut packet, address);
+    let tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+            OscArg::String(_) => 's',
+        }))
+        .collect();
+    push_padded(&mut packet, &tags);
+    for arg in args {
+        match arg {
+            OscArg::Int(value) => packet.extend_from_slice(&value.to_be_bytes()),
+            OscArg::Float(value) => packet.extend_from_slice(&value.to_be_bytes()),
+            OscArg::String(value) => push_padded(&mut packet, value),
+        }
+    }
+    packet
+}
+
+/// The OSC addresses keys are sent on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OscAddresses {
+    /// Receives the current key as a string and its position on the wheel.
+    pub current: String,
+    /// Receives the suggested next keys as alternating key strings and compatibility scores.
+    pub suggestions: String,
+}
+
+impl Default for OscAddresses {
+    fn default() -> Self {
+        Self {
+            current: "/camelol/key".to_string(),
+            suggestions: "/camelol/suggestions".to_string(),
+        }
+    }
+}
+
+/// Sends the current key and suggestions over UDP to an OSC receiver like a TouchOSC layout,
+/// lighting desk or VJ software.
+pub struct OscOutput {
+    socket: UdpSocket,
+    target: SocketAddr,
+    pub addresses: OscAddresses,
+}
+
+impl OscOutput {
+    pub fn new(target: impl ToSocketAddrs, addresses: OscAddresses) -> io::Result<Self> {
+        let target = target.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "OSC target has no address")
+        })?;
+        let local: SocketAddr = if target.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        // So targets like 255.255.255.255 reach every receiver on the network.
+        socket.set_broadcast(true)?;
+        Ok(Self {
+            socket,
+            target,
+            addresses,
+        })
+    }
+
+    pub fn send(&self, address: &str, args: &[OscArg]) -> io::Result<()> {
+        self.socket
+            .send_to(&osc_message(address, args), self.target)
+            .map(|_| ())
+    }
+
+    pub fn update(&self, current: Scale, suggestions: &[Suggestion]) -> io::Result<()> {
+        self.send(
+            &self.addresses.current,
+            &[
+                OscArg::String(current.to_string()),
+                OscArg::Int(current.position() as i32),
+            ],
+        )?;
+        let suggestions: Vec<OscArg> = suggestions
+            .iter()
+            .flat_map(|suggestion| {
+                [
+                    OscArg::String(suggestion.scale.to_string()),
+                    OscArg::Float(suggestion.score as f32),
+                ]
+            })
+            .collect();
+        self.send(&self.addresses.suggestions, &suggestions)
+    }
+}
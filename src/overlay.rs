@@ -0,0 +1,112 @@
+use crate::render::Notation;
+use crate::scale::{Scale, ScaleKind};
+use crate::suggest::Suggestion;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+/// The current key, the suggested next keys and the current key's neighbors on the wheel as a
+/// few lines of plain text for a stream overlay, like
+///
+/// ```text
+/// Now 8A
+/// Next 9A 8B 7A
+///  7B  8B  9B
+///  7A [8A] 9A
+/// ```
+pub fn overlay_text(current: Scale, suggestions: &[Suggestion], notation: &Notation) -> String {
+    let next: Vec<String> = suggestions
+        .iter()
+        .map(|suggestion| notation.label(suggestion.scale))
+        .collect();
+    let mut text = format!("Now {}\nNext {}\n", notation.label(current), next.join(" "));
+    for kind in [ScaleKind::Major, ScaleKind::Minor] {
+        let row: String = (-1..=1)
+            .map(|step| {
+                let key = Scale { kind, ..current }.change_index(step);
+                if key == current {
+                    format!("[{}]", notation.label(key))
+                } else {
+                    format!("{:^4}", notation.label(key))
+                }
+            })
+            .collect();
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Keeps a text file or named pipe up to date with [`overlay_text`], for OBS text sources and
+/// other stream overlays reading live harmonic info from a file.
+///
+/// Files are replaced whole on every update, so a source reading one never sees half of it. A
+/// named pipe is written on a thread of its own whenever something reads it, with the latest
+/// update, so a pipe nobody reads doesn't hold the set up.
+pub struct OverlayOutput {
+    path: PathBuf,
+    notation: Notation,
+    pipe: Option<Sender<String>>,
+}
+
+impl OverlayOutput {
+    pub fn new(path: impl Into<PathBuf>, notation: Notation) -> io::Result<Self> {
+        let path = path.into();
+        let pipe = is_pipe(&path)?.then(|| {
+            let (sender, receiver) = mpsc::channel::<String>();
+            let path = path.clone();
+            thread::spawn(move || {
+                while let Ok(mut text) = receiver.recv() {
+                    // Opening blocks until a reader turns up, by when newer updates may be in.
+                    let file = File::create(&path);
+                    while let Ok(newer) = receiver.try_recv() {
+                        text = newer;
+                    }
+                    if let Err(error) = file.and_then(|mut file| file.write_all(text.as_bytes())) {
+                        tracing::warn!(path = %path.display(), %error, "can't write the overlay");
+                    }
+                }
+            });
+            sender
+        });
+        Ok(Self {
+            path,
+            notation,
+            pipe,
+        })
+    }
+
+    pub fn update(&self, current: Scale, suggestions: &[Suggestion]) -> io::Result<()> {
+        let text = overlay_text(current, suggestions, &self.notation);
+        if let Some(pipe) = &self.pipe {
+            // The writer only stops with the output.
+            let _ = pipe.send(text);
+            return Ok(());
+        }
+        let mut partial = OsString::from(self.path.as_os_str());
+        partial.push(".partial");
+        fs::write(&partial, text)?;
+        fs::rename(&partial, &self.path)
+    }
+}
+
+/// Whether `path` is a named pipe. Files that don't exist yet aren't.
+fn is_pipe(path: &Path) -> io::Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        match fs::metadata(path) {
+            Ok(metadata) => Ok(metadata.file_type().is_fifo()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
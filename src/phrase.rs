@@ -0,0 +1,212 @@
+use crate::audio::{
+    decode_audio, estimate_tempo, frame_energies, frame_rate, onset_envelope, Audio,
+};
+use crate::csv::csv_row;
+use crate::library::Track;
+
+const BEATS_PER_BAR: usize = 4;
+/// Bars in the shortest phrase looked for. Phrases of 16 and 32 bars are two and four of them.
+const PHRASE_BARS: usize = 8;
+/// Bars two tracks play together at a mix when the outgoing track has that many left after
+/// one of its boundaries, and otherwise [`PHRASE_BARS`].
+const MIX_BARS: usize = 16;
+
+/// The start of a phrase of a track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhraseBoundary {
+    /// Seconds into the track.
+    pub time: f64,
+    /// The longest phrase structure the boundary starts a phrase of: 8, 16 or 32 bars.
+    pub bars: usize,
+}
+
+/// The mean of `values`, or 0 for none.
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+/// Where the phrases of `audio` at `tempo` beats per minute start. The beats are where onsets
+/// line up best at the tempo, and phrases start on the grid of 8 bars where the bars after
+/// grow loudest or quietest compared with the bars before, then of those, every other or
+/// every fourth, where the loudness changes most, starts a phrase of 16 or 32 bars. Audio
+/// shorter than two phrases has none.
+pub fn phrase_boundaries(audio: &Audio, tempo: f64) -> Vec<PhraseBoundary> {
+    let energies = frame_energies(audio);
+    let onsets = onset_envelope(&energies);
+    let rate = frame_rate(audio);
+    let period = 60.0 * rate / tempo;
+    let phrase = PHRASE_BARS * BEATS_PER_BAR;
+    if !period.is_finite() || period < 1.0 || (onsets.len() as f64) < period * (2 * phrase) as f64 {
+        return vec![];
+    }
+    // The earliest of equally strong candidates wins, so results don't flicker.
+    let best = |candidates: Vec<usize>, strength: &dyn Fn(usize) -> f64| {
+        candidates
+            .into_iter()
+            .max_by(|&a, &b| strength(a).total_cmp(&strength(b)).then(b.cmp(&a)))
+            .unwrap_or(0)
+    };
+
+    let beats_from = |phase: usize| {
+        (0..)
+            .map(move |beat| (phase as f64 + beat as f64 * period).round() as usize)
+            .take_while(|&frame| frame < onsets.len())
+    };
+    let phase = best((0..period.ceil() as usize).collect(), &|phase| {
+        beats_from(phase).map(|frame| onsets[frame]).sum()
+    });
+    let beats: Vec<usize> = beats_from(phase).collect();
+    let loudness: Vec<f64> = beats
+        .iter()
+        .map(|&frame| {
+            let end = (frame + period.round() as usize).min(energies.len());
+            mean(&energies[frame.min(end)..end])
+        })
+        .collect();
+    // How much louder or quieter the bar from every beat on is than the bar before it.
+    let novelty: Vec<f64> = (0..beats.len())
+        .map(|beat| {
+            if beat < BEATS_PER_BAR || beat + BEATS_PER_BAR > beats.len() {
+                return 0.0;
+            }
+            let after = mean(&loudness[beat..beat + BEATS_PER_BAR]);
+            let before = mean(&loudness[beat - BEATS_PER_BAR..beat]);
+            (after - before).abs()
+        })
+        .collect();
+    let on_grid =
+        |step: usize, offset: usize| -> f64 { novelty.iter().skip(offset).step_by(step).sum() };
+    let eight = best((0..phrase).collect(), &|offset| on_grid(phrase, offset));
+    let sixteen = best(vec![eight, eight + phrase], &|offset| {
+        on_grid(2 * phrase, offset)
+    });
+    let thirty_two = best(vec![sixteen, sixteen + 2 * phrase], &|offset| {
+        on_grid(4 * phrase, offset)
+    });
+
+    let starts = |beat: usize, offset: usize, step: usize| {
+        beat >= offset && (beat - offset).is_multiple_of(step)
+    };
+    (eight..beats.len())
+        .step_by(phrase)
+        .map(|beat| PhraseBoundary {
+            // Onsets are measured from the window after them.
+            time: (beats[beat] + 1) as f64 / rate,
+            bars: if starts(beat, thirty_two, 4 * phrase) {
+                4 * PHRASE_BARS
+            } else if starts(beat, sixteen, 2 * phrase) {
+                2 * PHRASE_BARS
+            } else {
+                PHRASE_BARS
+            },
+        })
+        .collect()
+}
+
+/// Where to mix one track into the next, on their phrase boundaries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MixPoint {
+    /// Seconds into the outgoing track to start the next one.
+    pub mix_out: f64,
+    /// Seconds into the next track to start it from.
+    pub mix_in: f64,
+    /// Bars the tracks play together.
+    pub bars: usize,
+}
+
+/// Where to mix out of a track lasting `duration` seconds at `tempo` with phrases starting at
+/// `from` into one with phrases starting at `into`: at the last boundary of a phrase of at
+/// least 16 bars that leaves 16 bars to play, or failing that the last boundary leaving 8,
+/// with the next track started from its first boundary so the phrases of both line up.
+pub fn mix_point(
+    from: &[PhraseBoundary],
+    duration: f64,
+    tempo: f64,
+    into: &[PhraseBoundary],
+) -> Option<MixPoint> {
+    let bar = 60.0 * BEATS_PER_BAR as f64 / tempo;
+    let mix_in = into.first()?.time;
+    [MIX_BARS, PHRASE_BARS].into_iter().find_map(|bars| {
+        from.iter()
+            .rev()
+            .find(|boundary| boundary.bars >= bars && boundary.time + bars as f64 * bar <= duration)
+            .map(|boundary| MixPoint {
+                mix_out: boundary.time,
+                mix_in,
+                bars,
+            })
+    })
+}
+
+/// What [`set_mix_points`] needs to know about a track of the set.
+struct TrackPhrases {
+    boundaries: Vec<PhraseBoundary>,
+    tempo: f64,
+    duration: f64,
+}
+
+/// The phrases of `track`, by decoding its audio file. Tracks without a file, or whose file
+/// can't be decoded or has no tempo to hear, have none.
+fn track_phrases(track: &Track) -> Option<TrackPhrases> {
+    let path = track.path.as_ref()?;
+    let audio = decode_audio(path)
+        .inspect_err(|error| {
+            tracing::warn!(path = %path.display(), %error, "can't decode, no mix points");
+        })
+        .ok()?;
+    let tempo = track.tempo.or_else(|| estimate_tempo(&audio))?;
+    Some(TrackPhrases {
+        boundaries: phrase_boundaries(&audio, tempo),
+        tempo,
+        duration: track.duration.unwrap_or_else(|| audio.duration()),
+    })
+}
+
+/// Where to mix every track of `set` into the next, see [`mix_point`], for the tracks whose
+/// audio files can be decoded.
+pub fn set_mix_points(set: &[Track]) -> Vec<Option<MixPoint>> {
+    let phrases: Vec<Option<TrackPhrases>> = set.iter().map(track_phrases).collect();
+    phrases
+        .windows(2)
+        .map(|pair| {
+            let (Some(from), Some(into)) = (&pair[0], &pair[1]) else {
+                return None;
+            };
+            mix_point(
+                &from.boundaries,
+                from.duration,
+                from.tempo,
+                &into.boundaries,
+            )
+        })
+        .collect()
+}
+
+/// `seconds` like `4:05.25`, precise enough to cue on a beat.
+fn timestamp(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).floor();
+    format!("{minutes}:{:05.2}", seconds - minutes * 60.0)
+}
+
+/// A CSV sheet of the `points` between the tracks of `set`, like [`set_mix_points`] finds,
+/// with a row for every transition and the times left empty where there's no mix point.
+pub fn mix_point_sheet(set: &[Track], points: &[Option<MixPoint>]) -> String {
+    let mut rows = vec![csv_row(&["from", "to", "mix_out", "mix_in", "bars"])];
+    for (tracks, point) in set.windows(2).zip(points) {
+        let (mix_out, mix_in, bars) = point.map_or_else(Default::default, |point| {
+            (
+                timestamp(point.mix_out),
+                timestamp(point.mix_in),
+                point.bars.to_string(),
+            )
+        });
+        rows.push(csv_row(&[
+            tracks[0].name.clone(),
+            tracks[1].name.clone(),
+            mix_out,
+            mix_in,
+            bars,
+        ]));
+    }
+    rows.join("\n") + "\n"
+}
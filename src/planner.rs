@@ -0,0 +1,568 @@
+use crate::distance::DistanceMatrix;
+use crate::library::Track;
+use std::str::FromStr;
+
+/// Cost of a move between keys that can't reach each other, which only happens on custom
+/// wheels.
+const UNREACHABLE_COST: usize = 100;
+
+/// Cost of each broken rule, more than any transition between reachable keys.
+const RULE_COST: usize = 20;
+
+/// How long a track counts for in a set of limited length. Only pinned tracks can lack a
+/// duration there, and they count for nothing.
+fn duration(track: &Track) -> f64 {
+    track.duration.unwrap_or(0.0)
+}
+
+/// How the energy of a set should develop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnergyShape {
+    /// Never dropping.
+    Rising,
+    /// Following a curve through these energy levels, spread evenly over the set, like
+    /// `3,6,8,5` for a set that peaks late and winds down. Only the shape of the curve
+    /// matters: the highest energy tracks go where it peaks and the lowest where it dips.
+    Curve(Vec<f64>),
+}
+
+impl EnergyShape {
+    /// Where position `position` of `count` should be on the shape, higher for more energy.
+    fn target(&self, position: usize, count: usize) -> f64 {
+        match self {
+            EnergyShape::Rising => position as f64,
+            EnergyShape::Curve(levels) => {
+                let along = if count > 1 {
+                    position as f64 / (count - 1) as f64 * (levels.len() - 1) as f64
+                } else {
+                    0.0
+                };
+                let before = along.floor() as usize;
+                let after = (before + 1).min(levels.len() - 1);
+                let weight = along - before as f64;
+                levels[before] * (1.0 - weight) + levels[after] * weight
+            }
+        }
+    }
+}
+
+impl FromStr for EnergyShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "rising" {
+            return Ok(EnergyShape::Rising);
+        }
+        s.split(',')
+            .map(|level| {
+                level
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|level| level.is_finite())
+            })
+            .collect::<Option<Vec<f64>>>()
+            .filter(|levels| !levels.is_empty())
+            .map(EnergyShape::Curve)
+            .ok_or_else(|| format!("expected `rising` or energy levels like `3,6,8,5`, got `{s}`"))
+    }
+}
+
+/// A half of a set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetHalf {
+    First,
+    Second,
+}
+
+/// Keeps every track with a tag in one half of the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagPlacement {
+    /// Matched regardless of case.
+    pub tag: String,
+    pub half: SetHalf,
+}
+
+/// Parses `TAG=first` or `TAG=second`.
+impl FromStr for TagPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let half = match s.rsplit_once('=') {
+            Some((tag, "first")) if !tag.is_empty() => (tag, SetHalf::First),
+            Some((tag, "second")) if !tag.is_empty() => (tag, SetHalf::Second),
+            _ => return Err(format!("expected `TAG=first` or `TAG=second`, got `{s}`")),
+        };
+        Ok(TagPlacement {
+            tag: half.0.to_string(),
+            half: half.1,
+        })
+    }
+}
+
+/// Moves the element at `from` to `to`, shifting the ones between. `element` is the one at
+/// `from`.
+fn move_within(order: &mut [usize], from: usize, to: usize, element: usize) {
+    if from < to {
+        order.copy_within(from + 1..=to, from);
+    } else {
+        order.copy_within(to..from, to + 1);
+    }
+    order[to] = element;
+}
+
+/// The number of tracks in `order` played after `max_run` others in a row that are `same` as
+/// them.
+fn beyond_runs(order: &[usize], max_run: usize, same: impl Fn(usize, usize) -> bool) -> usize {
+    let mut beyond = 0;
+    let mut run = 0;
+    for (position, &i) in order.iter().enumerate() {
+        run = match position.checked_sub(1) {
+            Some(previous) if same(order[previous], i) => run + 1,
+            _ => 1,
+        };
+        beyond += usize::from(run > max_run);
+    }
+    beyond
+}
+
+/// Whether `options` has rules that [`broken_rules`] checks.
+fn has_rules(options: &PlanOptions) -> bool {
+    options.max_key_run.is_some()
+        || options.max_genre_run.is_some()
+        || options.pitch_range.is_some()
+        || !options.tag_placements.is_empty()
+}
+
+/// The number of times playing `tracks` in `order` breaks the rules of `options`: once for
+/// every track after the allowed run of a key or genre, once for every tagged track in the
+/// wrong half and once for every mix needing more pitch than the faders have.
+fn broken_rules(tracks: &[Track], order: &[usize], options: &PlanOptions) -> usize {
+    let mut broken = 0;
+    if let Some(max_run) = options.max_key_run {
+        broken += beyond_runs(order, max_run, |a, b| tracks[a].key == tracks[b].key);
+    }
+    if let Some(max_run) = options.max_genre_run {
+        broken += beyond_runs(order, max_run, |a, b| {
+            matches!(
+                (&tracks[a].genre, &tracks[b].genre),
+                (Some(a), Some(b)) if a.eq_ignore_ascii_case(b)
+            )
+        });
+    }
+    if let Some(range) = options.pitch_range {
+        broken += order
+            .windows(2)
+            .filter(|pair| {
+                tracks[pair[0]]
+                    .pitch_into(&tracks[pair[1]])
+                    .is_some_and(|pitch| pitch.abs() > range)
+            })
+            .count();
+    }
+    for placement in &options.tag_placements {
+        for (position, &i) in order.iter().enumerate() {
+            let tagged = tracks[i]
+                .tags
+                .iter()
+                .any(|tag| tag.eq_ignore_ascii_case(&placement.tag));
+            let half = if position * 2 < order.len() {
+                SetHalf::First
+            } else {
+                SetHalf::Second
+            };
+            broken += usize::from(tagged && half != placement.half);
+        }
+    }
+    broken
+}
+
+/// Restrictions on the order of a set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlanOptions {
+    /// Index of the track to open the set with.
+    pub first: Option<usize>,
+    /// Index of the track to close the set with, other than the first.
+    pub last: Option<usize>,
+    /// Longest the set may run, in seconds. With a length, only as many tracks as fit are
+    /// played and tracks without a duration are left out unless pinned. Pinned tracks are
+    /// played even if they alone run longer.
+    pub length: Option<f64>,
+    /// How the energy should develop over the set. With a shape, tracks without an energy are
+    /// left out unless pinned.
+    pub energy: Option<EnergyShape>,
+    /// Most tracks in the same key to play in a row.
+    pub max_key_run: Option<usize>,
+    /// Most tracks of the same genre to play in a row.
+    pub max_genre_run: Option<usize>,
+    pub tag_placements: Vec<TagPlacement>,
+    /// How far the decks' pitch faders go, in percent either way. Mixing into a track further
+    /// off in tempo than that breaks a rule.
+    pub pitch_range: Option<f64>,
+}
+
+/// Orders the tracks of a set so each mix is harmonically as close as possible.
+pub struct SetPlanner<'a> {
+    distances: &'a DistanceMatrix,
+}
+
+impl<'a> SetPlanner<'a> {
+    pub fn new(distances: &'a DistanceMatrix) -> Self {
+        Self { distances }
+    }
+
+    /// Number of transitions on the shortest path from the key `from` ends in to the key `to`
+    /// starts in.
+    pub fn transition_cost(&self, from: &Track, to: &Track) -> usize {
+        self.distances
+            .distance(from.outgoing_key(), to.incoming_key())
+            .unwrap_or(UNREACHABLE_COST)
+    }
+
+    /// Total transition cost of playing `tracks` in order.
+    pub fn cost(&self, tracks: &[Track]) -> usize {
+        tracks
+            .windows(2)
+            .map(|pair| self.transition_cost(&pair[0], &pair[1]))
+            .sum()
+    }
+
+    /// Transition cost of playing `tracks` in `order`, plus the cost of the rules it breaks.
+    fn order_cost(&self, tracks: &[Track], order: &[usize], options: &PlanOptions) -> usize {
+        let transitions: usize = order
+            .windows(2)
+            .map(|pair| self.transition_cost(&tracks[pair[0]], &tracks[pair[1]]))
+            .sum();
+        transitions + RULE_COST * broken_rules(tracks, order, options)
+    }
+
+    /// The cost of the rules `order` breaks, which is 0 without any rules.
+    fn rule_cost(&self, tracks: &[Track], order: &[usize], options: &PlanOptions) -> usize {
+        if has_rules(options) {
+            RULE_COST * broken_rules(tracks, order, options)
+        } else {
+            0
+        }
+    }
+
+    /// Transition cost of mixing `order[from]` into `order[to]`, 0 if either is past an end of
+    /// the set.
+    fn mix_cost(&self, tracks: &[Track], order: &[usize], from: Option<usize>, to: usize) -> isize {
+        match (from.and_then(|from| order.get(from)), order.get(to)) {
+            (Some(&from), Some(&to)) => self.transition_cost(&tracks[from], &tracks[to]) as isize,
+            _ => 0,
+        }
+    }
+
+    /// The average cost per transition, so sets of different lengths can be compared.
+    fn average_cost(&self, tracks: &[Track], order: &[usize], options: &PlanOptions) -> f64 {
+        self.order_cost(tracks, order, options) as f64 / order.len().saturating_sub(1).max(1) as f64
+    }
+
+    /// Greedily plays the closest of the `pool` tracks that still fit in the `length` next,
+    /// starting from `start` and saving `last` for the end. Ties go to the track listed first.
+    fn nearest_neighbor(
+        &self,
+        tracks: &[Track],
+        pool: &[usize],
+        start: usize,
+        options: &PlanOptions,
+    ) -> Vec<usize> {
+        let last = options.last.filter(|&last| last != start);
+        let mut order = vec![start];
+        let mut left = options.length.map(|length| {
+            length - duration(&tracks[start]) - last.map_or(0.0, |last| duration(&tracks[last]))
+        });
+        let mut unplayed: Vec<usize> = pool
+            .iter()
+            .copied()
+            .filter(|&i| i != start && Some(i) != last)
+            .collect();
+        loop {
+            let current = &tracks[order[order.len() - 1]];
+            let Some((next, _)) = unplayed
+                .iter()
+                .enumerate()
+                .filter(|&(_, &i)| left.is_none_or(|left| duration(&tracks[i]) <= left))
+                .min_by_key(|&(_, &i)| self.transition_cost(current, &tracks[i]))
+            else {
+                break;
+            };
+            let next = unplayed.remove(next);
+            if let Some(left) = &mut left {
+                *left -= duration(&tracks[next]);
+            }
+            order.push(next);
+        }
+        order.extend(last);
+        order
+    }
+
+    /// Swaps played tracks between `order[from]` and `order[to - 1]` for unplayed ones from
+    /// the `pool` that still fit in the `length` and lower the cost. Returns whether any were
+    /// swapped.
+    fn swap_unplayed(
+        &self,
+        tracks: &[Track],
+        pool: &[usize],
+        order: &mut [usize],
+        (from, to): (usize, usize),
+        options: &PlanOptions,
+    ) -> bool {
+        let Some(length) = options.length else {
+            return false;
+        };
+        let mut swapped = false;
+        let mut cost = self.order_cost(tracks, order, options);
+        let mut total: f64 = order.iter().map(|&i| duration(&tracks[i])).sum();
+        for position in from..to {
+            for &candidate in pool {
+                if order.contains(&candidate) {
+                    continue;
+                }
+                let played = order[position];
+                let total_swapped =
+                    total - duration(&tracks[played]) + duration(&tracks[candidate]);
+                if total_swapped > length {
+                    continue;
+                }
+                order[position] = candidate;
+                let swapped_cost = self.order_cost(tracks, order, options);
+                if swapped_cost < cost {
+                    cost = swapped_cost;
+                    total = total_swapped;
+                    swapped = true;
+                } else {
+                    order[position] = played;
+                }
+            }
+        }
+        swapped
+    }
+
+    /// Reverses stretches of the set between `order[from]` and `order[to - 1]` while that
+    /// lowers its cost. Returns whether any were reversed.
+    ///
+    /// Only the mixes into and out of a stretch change when it's reversed, besides the mixes
+    /// within it running the other way, so those are all that's costed, from running sums of
+    /// the mixes both ways. Rules are checked on the whole set.
+    fn two_opt(
+        &self,
+        tracks: &[Track],
+        order: &mut [usize],
+        (from, to): (usize, usize),
+        options: &PlanOptions,
+    ) -> bool {
+        let mut reversed_any = false;
+        let mut rules = self.rule_cost(tracks, order, options) as isize;
+        let mut improved = true;
+        while improved {
+            improved = false;
+            // The cost of the mixes before position `i`, played forwards and backwards.
+            let mut forwards = vec![0; order.len()];
+            let mut backwards = vec![0; order.len()];
+            for i in 1..order.len() {
+                forwards[i] = forwards[i - 1] + self.mix_cost(tracks, order, Some(i - 1), i);
+                backwards[i] = backwards[i - 1] + self.mix_cost(tracks, order, Some(i), i - 1);
+            }
+            for start in from..to {
+                for end in start + 1..to {
+                    let before = start.checked_sub(1);
+                    let delta = self.mix_cost(tracks, order, before, end)
+                        - self.mix_cost(tracks, order, before, start)
+                        + self.mix_cost(tracks, order, Some(start), end + 1)
+                        - self.mix_cost(tracks, order, Some(end), end + 1)
+                        + (backwards[end] - backwards[start])
+                        - (forwards[end] - forwards[start]);
+                    if delta >= 0 && !has_rules(options) {
+                        continue;
+                    }
+                    order[start..=end].reverse();
+                    let reversed = self.rule_cost(tracks, order, options) as isize;
+                    if delta + reversed - rules < 0 {
+                        rules = reversed;
+                        improved = true;
+                        reversed_any = true;
+                        break;
+                    }
+                    order[start..=end].reverse();
+                }
+                if improved {
+                    break;
+                }
+            }
+        }
+        reversed_any
+    }
+
+    /// Moves single tracks between `order[from]` and `order[to - 1]` elsewhere in that stretch
+    /// while that lowers the cost, which fixes what reversing can't, like a track in the wrong
+    /// half. Returns whether any were moved.
+    ///
+    /// Like [`two_opt`](Self::two_opt), only the mixes a move changes are costed.
+    fn relocate(
+        &self,
+        tracks: &[Track],
+        order: &mut [usize],
+        (from, to): (usize, usize),
+        options: &PlanOptions,
+    ) -> bool {
+        let mut moved = false;
+        let mut rules = self.rule_cost(tracks, order, options) as isize;
+        for source in from..to {
+            for target in from..to {
+                if source == target {
+                    continue;
+                }
+                let mix = |a: Option<usize>, b: usize| self.mix_cost(tracks, order, a, b);
+                let before = source.checked_sub(1);
+                // Taking the track out joins the tracks either side of it.
+                let mut delta =
+                    mix(before, source + 1) - mix(before, source) - mix(Some(source), source + 1);
+                // It then goes after `order[target]` when moving later, or before it otherwise.
+                let (after, until) = if source < target {
+                    (Some(target), target + 1)
+                } else {
+                    (target.checked_sub(1), target)
+                };
+                delta += mix(after, source) + mix(Some(source), until) - mix(after, until);
+                if delta >= 0 && !has_rules(options) {
+                    continue;
+                }
+                let track = order[source];
+                move_within(order, source, target, track);
+                let relocated = self.rule_cost(tracks, order, options) as isize;
+                if delta + relocated - rules < 0 {
+                    rules = relocated;
+                    moved = true;
+                } else {
+                    move_within(order, target, source, track);
+                }
+            }
+        }
+        moved
+    }
+
+    /// Rearranges the tracks between `order[from]` and `order[to - 1]` to follow `shape`, the
+    /// lowest energy tracks where the shape is lowest and so on up, then swaps tracks of the
+    /// same energy while that lowers the cost.
+    fn shape_energy(
+        &self,
+        tracks: &[Track],
+        order: &mut [usize],
+        (from, to): (usize, usize),
+        shape: &EnergyShape,
+        options: &PlanOptions,
+    ) {
+        let count = to.saturating_sub(from);
+        let targets: Vec<f64> = (0..count)
+            .map(|position| shape.target(position, count))
+            .collect();
+        let mut positions: Vec<usize> = (0..count).collect();
+        positions.sort_by(|&a, &b| targets[a].total_cmp(&targets[b]));
+        let mut by_energy = order[from..to].to_vec();
+        by_energy.sort_by_key(|&i| tracks[i].energy);
+        for (position, track) in positions.into_iter().zip(by_energy) {
+            order[from + position] = track;
+        }
+
+        let mut cost = self.order_cost(tracks, order, options);
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for a in from..to {
+                for b in a + 1..to {
+                    if tracks[order[a]].energy != tracks[order[b]].energy {
+                        continue;
+                    }
+                    order.swap(a, b);
+                    let swapped = self.order_cost(tracks, order, options);
+                    if swapped < cost {
+                        cost = swapped;
+                        improved = true;
+                    } else {
+                        order.swap(a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Orders `tracks` for the lowest total transition cost: the best greedy ordering from any
+    /// starting track, improved by reversing stretches of it and moving single tracks. Pinned
+    /// opening and closing tracks stay where they are and only the tracks between them are
+    /// ordered. Given a length, the greedy orderings start from tracks that fit and stop once no
+    /// more do, the one playing the most tracks wins, then the one with the lowest average cost
+    /// per transition and then the longest, and its tracks are swapped for unplayed ones while
+    /// that helps. Broken key, genre and tag rules count as expensive transitions, so they're
+    /// only broken when there's no way around them. Given an energy shape, the tracks are then
+    /// rearranged to follow it, keeping as much harmony as tracks of the same energy allow. The
+    /// result only depends on the tracks and their order, so the same set always comes out the
+    /// same.
+    ///
+    /// Panics if a pinned index is out of bounds.
+    pub fn plan(&self, tracks: &[Track], options: &PlanOptions) -> Vec<Track> {
+        self.plan_order(tracks, options)
+            .into_iter()
+            .map(|i| tracks[i].clone())
+            .collect()
+    }
+
+    /// Like [`plan`](Self::plan), but returns the indices of the tracks in the order they're
+    /// played.
+    pub fn plan_order(&self, tracks: &[Track], options: &PlanOptions) -> Vec<usize> {
+        let pinned = |i| Some(i) == options.first || Some(i) == options.last;
+        let pool: Vec<usize> = (0..tracks.len())
+            .filter(|&i| options.length.is_none() || tracks[i].duration.is_some() || pinned(i))
+            .filter(|&i| options.energy.is_none() || tracks[i].energy.is_some() || pinned(i))
+            .collect();
+        let last = options.last.map_or(0.0, |last| duration(&tracks[last]));
+        let starts: Vec<usize> = match options.first {
+            Some(first) => vec![first],
+            None => pool
+                .iter()
+                .copied()
+                .filter(|&start| pool.len() == 1 || Some(start) != options.last)
+                .filter(|&start| {
+                    Some(start) == options.last
+                        || options
+                            .length
+                            .is_none_or(|length| duration(&tracks[start]) + last <= length)
+                })
+                .collect(),
+        };
+        // The most tracks, mixed best, and then the longest of those.
+        let played = |order: &[usize]| -> f64 { order.iter().map(|&i| duration(&tracks[i])).sum() };
+        let Some(mut order) = starts
+            .into_iter()
+            .map(|start| self.nearest_neighbor(tracks, &pool, start, options))
+            .min_by(|a, b| {
+                b.len()
+                    .cmp(&a.len())
+                    .then(
+                        self.average_cost(tracks, a, options)
+                            .total_cmp(&self.average_cost(tracks, b, options)),
+                    )
+                    .then(played(b).total_cmp(&played(a)))
+            })
+            .or_else(|| options.last.map(|last| vec![last]))
+        else {
+            return vec![];
+        };
+        let from = usize::from(options.first.is_some());
+        let to = order.len() - usize::from(options.last.is_some() && order.len() > 1);
+        let range = (from, to);
+        loop {
+            while self.two_opt(tracks, &mut order, range, options)
+                | self.relocate(tracks, &mut order, range, options)
+            {}
+            if !self.swap_unplayed(tracks, &pool, &mut order, range, options) {
+                break;
+            }
+        }
+        if let Some(energy) = &options.energy {
+            self.shape_energy(tracks, &mut order, range, energy, options);
+        }
+        order
+    }
+}
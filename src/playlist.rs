@@ -0,0 +1,237 @@
+use crate::csv::{csv_row, parse_csv};
+use crate::error::{Error, Result};
+use crate::library::{parse_duration, Track};
+use crate::render::Notation;
+use crate::scale::Scale;
+use std::path::{Path, PathBuf};
+
+/// The formats playlists can be read and written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    /// One file path or URL per line, optionally after an `#EXTINF:seconds,Artist - Title` line.
+    M3u,
+    /// A header naming the columns, of which `path`, `name` (or `artist` and `title`), `key`,
+    /// `tempo` (or `bpm`) and `duration` are read and the rest kept as they are.
+    Csv,
+}
+
+impl PlaylistFormat {
+    /// CSV for `.csv` files and M3U for anything else.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::M3u,
+        }
+    }
+}
+
+/// A track of a playlist, with whatever the playlist says about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistEntry {
+    /// `Artist - Title`, or the file name when the playlist doesn't say.
+    pub name: String,
+    /// The file as written in the playlist, which may be relative to the playlist's directory.
+    pub path: Option<PathBuf>,
+    pub key: Option<Scale>,
+    pub tempo: Option<f64>,
+    /// Length in seconds.
+    pub duration: Option<f64>,
+    /// The entry as written, so writing the playlist in a new order keeps everything else
+    /// about it.
+    text: String,
+}
+
+impl PlaylistEntry {
+    /// The entry as a track, if its key is known.
+    pub fn track(&self) -> Option<Track> {
+        self.key.map(|key| Track {
+            tempo: self.tempo,
+            path: self.path.clone(),
+            duration: self.duration,
+            ..Track::new(self.name.clone(), key)
+        })
+    }
+
+    /// Takes the key, tempo and duration of `track` where the playlist doesn't give them.
+    pub fn fill(&mut self, track: &Track) {
+        self.key = self.key.or(Some(track.key));
+        self.tempo = self.tempo.or(track.tempo);
+        self.duration = self.duration.or(track.duration);
+    }
+}
+
+/// A playlist, read so its tracks can be put in a new order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playlist {
+    pub format: PlaylistFormat,
+    /// The `#EXTM3U` line or the CSV header.
+    header: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+fn file_name(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.rsplit_once('.')
+        .map_or(name, |(stem, _)| stem)
+        .to_string()
+}
+
+impl Playlist {
+    pub fn parse(text: &str, format: PlaylistFormat, notation: &Notation) -> Result<Self> {
+        match format {
+            PlaylistFormat::M3u => Ok(Self::parse_m3u(text)),
+            PlaylistFormat::Csv => Self::parse_csv(text, notation),
+        }
+    }
+
+    fn parse_m3u(text: &str) -> Self {
+        let mut playlist = Self {
+            format: PlaylistFormat::M3u,
+            header: None,
+            entries: vec![],
+        };
+        let mut lines = Vec::new();
+        let (mut name, mut duration) = (None, None);
+        for line in text.lines().map(str::trim_end) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with("#EXTM3U") && playlist.entries.is_empty() && lines.is_empty() {
+                playlist.header = Some(line.to_string());
+                continue;
+            }
+            lines.push(line);
+            if let Some(info) = line.strip_prefix("#EXTINF:") {
+                let (seconds, title) = info.split_once(',').unwrap_or((info, ""));
+                // `-1` means the length is unknown.
+                duration = seconds
+                    .split_whitespace()
+                    .next()
+                    .and_then(|seconds| seconds.parse().ok())
+                    .filter(|seconds: &f64| *seconds >= 0.0);
+                name = Some(title.trim().to_string()).filter(|title| !title.is_empty());
+            }
+            if line.starts_with('#') {
+                continue;
+            }
+            playlist.entries.push(PlaylistEntry {
+                name: name.take().unwrap_or_else(|| file_name(line)),
+                path: Some(PathBuf::from(line.trim())),
+                key: None,
+                tempo: None,
+                duration: duration.take(),
+                text: lines.join("\n"),
+            });
+            lines.clear();
+        }
+        playlist
+    }
+
+    fn parse_csv(text: &str, notation: &Notation) -> Result<Self> {
+        let mut records = parse_csv(text).into_iter();
+        let Some((_, header)) = records.next() else {
+            return Ok(Self {
+                format: PlaylistFormat::Csv,
+                header: None,
+                entries: vec![],
+            });
+        };
+        let column = |names: &[&str]| {
+            header
+                .iter()
+                .position(|column| names.contains(&column.trim().to_lowercase().as_str()))
+        };
+        let path = column(&["path", "file", "location"]);
+        let name = column(&["name", "track"]);
+        let artist = column(&["artist"]);
+        let title = column(&["title"]);
+        let key = column(&["key"]);
+        let tempo = column(&["tempo", "bpm"]);
+        let duration = column(&["duration", "length"]);
+        if path.is_none() && name.is_none() && title.is_none() {
+            return Err(Error::InvalidPlaylist {
+                line: 1,
+                message: "expected a `path`, `name` or `title` column".to_string(),
+            });
+        }
+
+        let mut entries = Vec::new();
+        for (line, record) in records {
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| record.get(column))
+                    .map(|field| field.trim())
+                    .filter(|field| !field.is_empty())
+            };
+            let invalid = |column: &str, value: &str| Error::InvalidPlaylist {
+                line,
+                message: format!("invalid {column} `{value}`"),
+            };
+            let entry_path = field(path);
+            let entry_name = match (field(name), field(artist), field(title)) {
+                (Some(name), _, _) => name.to_string(),
+                (None, Some(artist), Some(title)) => format!("{artist} - {title}"),
+                (None, None, Some(title)) => title.to_string(),
+                _ => entry_path.map(file_name).unwrap_or_default(),
+            };
+            entries.push(PlaylistEntry {
+                name: entry_name,
+                path: entry_path.map(PathBuf::from),
+                key: field(key)
+                    .map(|key| {
+                        notation.parse(key).map_err(|error| Error::InvalidPlaylist {
+                            line,
+                            message: error.to_string(),
+                        })
+                    })
+                    .transpose()?,
+                tempo: field(tempo)
+                    .map(|tempo| tempo.parse().map_err(|_| invalid("tempo", tempo)))
+                    .transpose()?,
+                duration: field(duration)
+                    .map(|duration| {
+                        parse_duration(duration).ok_or_else(|| invalid("duration", duration))
+                    })
+                    .transpose()?,
+                text: csv_row(&record),
+            });
+        }
+        Ok(Self {
+            format: PlaylistFormat::Csv,
+            header: Some(csv_row(&header)),
+            entries,
+        })
+    }
+
+    /// The playlist with its entries in `order`, given as indices into
+    /// [`entries`](Self::entries).
+    ///
+    /// Panics if an index is out of bounds.
+    pub fn write(&self, order: &[usize]) -> String {
+        let mut text = String::new();
+        for line in self
+            .header
+            .iter()
+            .chain(order.iter().map(|&i| &self.entries[i].text))
+        {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// An M3U playlist of `tracks` in order, with their names and lengths on `#EXTINF` lines for
+/// DJ software to show before it reads the files. Tracks without a known file are left out.
+pub fn m3u_playlist(tracks: &[&Track]) -> String {
+    let mut text = String::from("#EXTM3U\n");
+    for track in tracks {
+        let Some(path) = &track.path else { continue };
+        let seconds = track
+            .duration
+            .map_or(-1, |duration| duration.round() as i64);
+        text.push_str(&format!("#EXTINF:{seconds},{}\n", track.name));
+        text.push_str(&format!("{}\n", path.display()));
+    }
+    text
+}
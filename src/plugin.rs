@@ -0,0 +1,358 @@
+//! Rule sets from third parties as sandboxed WebAssembly plugins, so rule packs can ship code
+//! without the trust native plugins would need.
+//!
+//! A plugin is a WebAssembly module, like a Rust `cdylib` built for `wasm32-unknown-unknown`,
+//! exporting two functions:
+//!
+//! ```text
+//! camelol_plugin_version() -> i32
+//! camelol_score(from: i32, from_minor: i32, to: i32, to_minor: i32, transition: i32,
+//!     change: i32) -> i32
+//! ```
+//!
+//! `camelol_plugin_version` returns 1, the version of the interface described here. When the
+//! plugin is loaded, every transition of the standard wheel is handed to `camelol_score`:
+//! `from` and `to` are the wheel numbers of the keys, `from_minor` and `to_minor` are 1 for A
+//! keys, `transition` is 0 for `ChangeIndex`, 1 for `Vertical`, 2 for `Diagonal`, 3 for
+//! `MajorToMinor` and 4 for `FlatToMinor`, and `change` is the positions a `ChangeIndex`
+//! moves, 0 for the others. A negative score leaves the transition out of the wheel, and any
+//! other is what a path pays for the step, at most 10000 like a
+//! [`RuleScript`](crate::RuleScript) cost.
+//!
+//! Plugins run on the `wasmi` interpreter, which validates them before anything runs. They
+//! can't import anything, so they have no way to reach files, the network or the host. Each
+//! call has a million units of fuel, about one per instruction, at most 16 MiB of memory and
+//! calls nesting at most 1000 deep. SIMD and 64-bit memories aren't supported.
+
+use crate::error::{Error, Result};
+use crate::rules::{Preset, TransitionRules, MAX_STEP_COST};
+use crate::scale::{make_nodes, Scale, ScaleKind};
+use crate::transition::{make_transition, possible_transitions, ScaleTransition};
+use crate::wheel::Wheel;
+use camelol_core::WHEEL_SIZE;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use wasmi::{
+    CompilationMode, Config, EnforcedLimits, Engine, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc, WasmParams,
+};
+
+/// The version of the plugin interface this host implements.
+const PLUGIN_VERSION: i32 = 1;
+/// Fuel a plugin may burn per call, its start function included.
+const FUEL: u64 = 1_000_000;
+/// Most bytes of memory a plugin may have, 256 pages.
+const MAX_MEMORY: usize = 16 << 20;
+/// How deep a plugin's calls may nest.
+const MAX_CALL_DEPTH: usize = 1000;
+/// Most elements a table of a plugin may have.
+const MAX_TABLE_SIZE: usize = 100_000;
+
+/// The arguments of `camelol_score`.
+type ScoreArgs = (i32, i32, i32, i32, i32, i32);
+
+/// A rule set from a WebAssembly plugin, with what it scored every transition of the standard
+/// wheel when it was loaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RulePlugin {
+    /// What the transitions the plugin keeps cost, by the key they leave.
+    costs: HashMap<(Scale, ScaleTransition), u32>,
+}
+
+impl RulePlugin {
+    /// Loads the plugin in the WebAssembly file at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_wasm(&fs::read(path)?)
+    }
+
+    /// Loads the plugin in the WebAssembly binary `wasm`, having it score every transition.
+    pub fn from_wasm(wasm: &[u8]) -> Result<Self> {
+        if !wasm.starts_with(b"\0asm") {
+            return Err(Error::Plugin("not a WebAssembly module".to_string()));
+        }
+        let mut config = Config::default();
+        config
+            .consume_fuel(true)
+            .set_max_recursion_depth(MAX_CALL_DEPTH)
+            .compilation_mode(CompilationMode::Eager)
+            .enforced_limits(EnforcedLimits::strict());
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(trap)?;
+        if let Some(import) = module.imports().next() {
+            return Err(Error::Plugin(format!(
+                "imports `{}.{}`, but plugins can't import anything",
+                import.module(),
+                import.name()
+            )));
+        }
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY)
+            .table_elements(MAX_TABLE_SIZE)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(FUEL).map_err(trap)?;
+        let instance = Linker::new(&engine)
+            .instantiate_and_start(&mut store, &module)
+            .map_err(trap)?;
+        let version = exported::<()>(&instance, &store, "camelol_plugin_version", 0)?;
+        match call(&mut store, &version, ())? {
+            PLUGIN_VERSION => {}
+            version => {
+                return Err(Error::Plugin(format!(
+                    "written for version {version} of the plugin interface, but only \
+                     {PLUGIN_VERSION} is supported"
+                )));
+            }
+        }
+        let score = exported::<ScoreArgs>(&instance, &store, "camelol_score", 6)?;
+        let mut costs = HashMap::new();
+        for from in make_nodes() {
+            for transition in possible_transitions() {
+                let to = make_transition(from, transition);
+                let (kind, change) = match transition {
+                    ScaleTransition::ChangeIndex(change) => (0, change as i32),
+                    ScaleTransition::Vertical => (1, 0),
+                    ScaleTransition::Diagonal => (2, 0),
+                    ScaleTransition::MajorToMinor => (3, 0),
+                    ScaleTransition::FlatToMinor => (4, 0),
+                };
+                let minor = |scale: Scale| i32::from(scale.kind == ScaleKind::Minor);
+                let args = (
+                    from.index as i32 + 1,
+                    minor(from),
+                    to.index as i32 + 1,
+                    minor(to),
+                    kind,
+                    change,
+                );
+                let cost = call(&mut store, &score, args).map_err(|error| match error {
+                    Error::Plugin(trap) => Error::Plugin(format!(
+                        "scoring {transition:?} from {from} to {to}: {trap}"
+                    )),
+                    error => error,
+                })?;
+                if cost >= 0 {
+                    costs.insert((from, transition), (cost as u32).min(MAX_STEP_COST));
+                }
+            }
+        }
+        Ok(Self { costs })
+    }
+
+    /// The standard wheel with only the transitions both `preset` and the plugin allow.
+    pub fn wheel(&self, preset: Preset) -> Wheel {
+        Wheel::with_transitions_where(WHEEL_SIZE, |from, transition, to| {
+            preset.allows(from, transition, to) && self.allows(from, transition, to)
+        })
+    }
+}
+
+impl TransitionRules for RulePlugin {
+    /// Whether the plugin scored the transition 0 or more.
+    fn allows(&self, from: Scale, transition: ScaleTransition, _to: Scale) -> bool {
+        self.costs.contains_key(&(from, transition))
+    }
+
+    /// What the plugin scored the step, 1 for transitions it leaves out.
+    fn cost(&self, from: Scale, transition: ScaleTransition, _to: Scale) -> u32 {
+        self.costs.get(&(from, transition)).copied().unwrap_or(1)
+    }
+}
+
+/// The function the plugin exports as `name`, which has to take `params` i32s and return one.
+fn exported<Params: WasmParams>(
+    instance: &Instance,
+    store: &Store<StoreLimits>,
+    name: &str,
+    params: usize,
+) -> Result<TypedFunc<Params, i32>> {
+    instance
+        .get_func(store, name)
+        .ok_or_else(|| Error::Plugin(format!("doesn't export the function `{name}`")))?
+        .typed(store)
+        .map_err(|_| {
+            Error::Plugin(format!(
+                "`{name}` has to take {params} i32 parameters and return an i32"
+            ))
+        })
+}
+
+/// Calls `function` of the plugin with `args` on a fresh budget of fuel.
+fn call<Params: WasmParams>(
+    store: &mut Store<StoreLimits>,
+    function: &TypedFunc<Params, i32>,
+    args: Params,
+) -> Result<i32> {
+    store.set_fuel(FUEL).map_err(trap)?;
+    function.call(store, args).map_err(trap)
+}
+
+/// Why the plugin couldn't be loaded or stopped running.
+fn trap(error: wasmi::Error) -> Error {
+    Error::Plugin(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scale::scale;
+
+    /// A plugin scoring every transition with the body of `score`, in the text format.
+    fn scoring(score: &str) -> String {
+        format!(
+            r#"(module
+                (memory 1)
+                (func (export "camelol_plugin_version") (result i32) (i32.const 1))
+                (func $score (export "camelol_score")
+                    (param $from i32) (param $from_minor i32) (param $to i32)
+                    (param $to_minor i32) (param $transition i32) (param $change i32)
+                    (result i32)
+                    {score}))"#
+        )
+    }
+
+    fn load(text: &str) -> Result<RulePlugin> {
+        RulePlugin::from_wasm(&wat::parse_str(text).expect("fixture should assemble"))
+    }
+
+    /// The message of the plugin error `result` failed with.
+    fn failure(result: Result<RulePlugin>) -> String {
+        match result {
+            Err(Error::Plugin(message)) => message,
+            other => panic!("expected a plugin error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn scores_every_transition() {
+        // Diagonals are left out, other steps cost the number of the key they leave, and
+        // `Vertical` asks for more than a rule set may charge.
+        let plugin = load(&scoring(
+            "(if (result i32) (i32.eq (local.get $transition) (i32.const 2))
+                (then (i32.const -1))
+                (else (if (result i32) (i32.eq (local.get $transition) (i32.const 1))
+                    (then (i32.const 50000))
+                    (else (local.get $from)))))",
+        ))
+        .unwrap();
+        let from = scale(7, ScaleKind::Minor);
+        for transition in possible_transitions() {
+            let to = make_transition(from, transition);
+            let expected = match transition {
+                ScaleTransition::Diagonal => None,
+                ScaleTransition::Vertical => Some(MAX_STEP_COST),
+                _ => Some(8),
+            };
+            assert_eq!(plugin.allows(from, transition, to), expected.is_some());
+            assert_eq!(plugin.cost(from, transition, to), expected.unwrap_or(1));
+        }
+        let wheel = plugin.wheel(Preset::Extended);
+        assert!(wheel
+            .transitions()
+            .all(|(_, transition, _)| transition != ScaleTransition::Diagonal));
+    }
+
+    #[test]
+    fn stops_plugins_that_run_out_of_fuel() {
+        let message = failure(load(&scoring(
+            "(loop $forever (br $forever)) (i32.const 0)",
+        )));
+        assert!(message.contains("fuel"), "{message}");
+    }
+
+    #[test]
+    fn stops_start_functions_that_run_out_of_fuel() {
+        let message = failure(load(
+            r#"(module
+                (func $start (loop $forever (br $forever)))
+                (start $start))"#,
+        ));
+        assert!(message.contains("fuel"), "{message}");
+    }
+
+    #[test]
+    fn refuses_more_memory_than_the_limit() {
+        let message = failure(load(
+            r#"(module
+                (memory 257)
+                (func (export "camelol_plugin_version") (result i32) (i32.const 1)))"#,
+        ));
+        assert!(message.contains("memory"), "{message}");
+        // Growing past the limit fails the way `memory.grow` reports it, with -1, which
+        // leaves every transition out.
+        let plugin = load(&scoring("(memory.grow (i32.const 256))")).unwrap();
+        assert_eq!(plugin, RulePlugin::default());
+    }
+
+    #[test]
+    fn stops_deep_recursion() {
+        let message = failure(load(&scoring(
+            "(call $score (local.get $from) (local.get $from_minor) (local.get $to)
+                (local.get $to_minor) (local.get $transition) (local.get $change))",
+        )));
+        assert!(message.contains("stack"), "{message}");
+    }
+
+    #[test]
+    fn traps_out_of_bounds_loads_and_stores() {
+        for access in [
+            "(i32.load (i32.const 65534))",
+            "(i32.store (i32.const 65536) (i32.const 1)) (i32.const 0)",
+            "(i64.load offset=4294967295 (i32.const 1)) (drop) (i32.const 0)",
+        ] {
+            let message = failure(load(&scoring(access)));
+            assert!(message.contains("out of bounds"), "{access}: {message}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_modules() {
+        let valid = wat::parse_str(scoring("(i32.const 1)")).unwrap();
+        let mut bad_version = valid.clone();
+        bad_version[4] = 2;
+        for wasm in [
+            b"not wasm".to_vec(),
+            b"\0asX\x01\0\0\0".to_vec(),
+            bad_version,
+            valid[..valid.len() - 3].to_vec(),
+            b"\0asm\x01\0\0\0\x01\x05\x01\x60".to_vec(),
+            Vec::new(),
+        ] {
+            assert!(matches!(
+                RulePlugin::from_wasm(&wasm),
+                Err(Error::Plugin(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_imports() {
+        let message = failure(load(
+            r#"(module
+                (import "env" "read" (func))
+                (func (export "camelol_plugin_version") (result i32) (i32.const 1)))"#,
+        ));
+        assert!(message.contains("`env.read`"), "{message}");
+    }
+
+    #[test]
+    fn checks_the_interface() {
+        let message = failure(load(
+            r#"(module (func (export "camelol_plugin_version") (result i32) (i32.const 2)))"#,
+        ));
+        assert!(message.contains("version 2"), "{message}");
+        let message = failure(load(
+            r#"(module (func (export "camelol_plugin_version") (result i32) (i32.const 1)))"#,
+        ));
+        assert!(message.contains("`camelol_score`"), "{message}");
+        let message = failure(load(
+            r#"(module
+                (func (export "camelol_plugin_version") (result i32) (i32.const 1))
+                (func (export "camelol_score") (param i32) (result i32) (i32.const 1)))"#,
+        ));
+        assert!(message.contains("6 i32 parameters"), "{message}");
+    }
+}
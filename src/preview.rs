@@ -0,0 +1,81 @@
+use crate::chord::scale_notes;
+use crate::midi::send;
+use crate::scale::{Scale, WheelNumbering};
+use midly::num::u7;
+use midly::MidiMessage;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+/// How a path is played back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviewOptions {
+    /// MIDI channel from 0 to 15.
+    pub channel: u8,
+    pub velocity: u8,
+    /// Length of each arpeggio note. The closing chord of every key lasts twice as long.
+    pub note_length: Duration,
+    /// Which keys the wheel numbers of the path stand for.
+    pub numbering: WheelNumbering,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            channel: 0,
+            velocity: 96,
+            note_length: Duration::from_millis(180),
+            numbering: WheelNumbering::CAMELOT,
+        }
+    }
+}
+
+/// MIDI note numbers of a key's preview: the tonic triad arpeggiated up to the octave, starting
+/// from the tonic between middle C and the B above it, in standard camelot numbering.
+pub fn preview_notes(scale: Scale) -> [u8; 4] {
+    let notes = scale_notes(scale);
+    let root = 60 + scale.tonic() as u8;
+    let above = |degree: usize| root + ((notes[degree] + 12 - notes[0]) % 12) as u8;
+    [root, above(2), above(4), root + 12]
+}
+
+fn notes(
+    output: &mut impl Write,
+    options: &PreviewOptions,
+    notes: &[u8],
+    length: Duration,
+) -> io::Result<()> {
+    for &key in notes {
+        let message = MidiMessage::NoteOn {
+            key: u7::new(key),
+            vel: u7::new(options.velocity.min(127)),
+        };
+        send(output, options.channel, message)?;
+    }
+    thread::sleep(length);
+    for &key in notes {
+        let message = MidiMessage::NoteOff {
+            key: u7::new(key),
+            vel: u7::new(0),
+        };
+        send(output, options.channel, message)?;
+    }
+    Ok(())
+}
+
+/// Plays every key of `keys` in turn on a raw MIDI output: an arpeggio of its tonic triad
+/// followed by the triad held as a chord.
+pub fn play_preview(
+    output: &mut impl Write,
+    keys: &[Scale],
+    options: &PreviewOptions,
+) -> io::Result<()> {
+    for &key in keys {
+        let arpeggio = preview_notes(options.numbering.standard(key));
+        for note in arpeggio {
+            notes(output, options, &[note], options.note_length)?;
+        }
+        notes(output, options, &arpeggio[..3], options.note_length * 2)?;
+    }
+    Ok(())
+}
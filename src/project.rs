@@ -0,0 +1,120 @@
+use crate::color::{wheel_color, Rgb};
+use crate::distance::DistanceMatrix;
+use crate::library::{KeySegment, Track};
+use crate::scale::Scale;
+use crate::suggest::compatibility;
+use crate::transition::{make_transition, possible_transitions, Interval, ScaleTransition};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Version of the set project format, raised whenever a change could break readers.
+pub const SET_PROJECT_VERSION: u32 = 1;
+
+/// A planned set for timeline tools, described by `schema/set.schema.json`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SetProject {
+    pub version: u32,
+    pub name: String,
+    pub tracks: Vec<ProjectTrack>,
+    /// The mix from each track into the next, one fewer than there are tracks.
+    pub transitions: Vec<ProjectTransition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectTrack {
+    /// `Artist - Title`, as in the track library.
+    pub name: String,
+    pub artist: Option<String>,
+    pub title: String,
+    pub key: Scale,
+    /// Like `A minor`.
+    pub key_name: String,
+    /// The key's wheel color, like `#86EAD9`.
+    pub color: Rgb,
+    /// How sure the key is, from 0 to 1, if known.
+    pub confidence: Option<f64>,
+    /// Whether the key is unsure enough to check by ear before the set.
+    pub needs_review: bool,
+    pub tempo: Option<f64>,
+    /// In seconds.
+    pub duration: Option<f64>,
+    pub path: Option<PathBuf>,
+    /// The keys of a track that modulates, empty for tracks in one key.
+    pub segments: Vec<KeySegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectTransition {
+    /// Indices into the tracks.
+    pub from: usize,
+    pub to: usize,
+    /// Wheel moves from the key the outgoing track ends in to the key the incoming one starts
+    /// in, `None` if they can't reach each other.
+    pub steps: Option<usize>,
+    /// From 1 for the same key down towards 0.
+    pub compatibility: f64,
+    /// The single wheel move between different keys, if there is one.
+    pub transition: Option<ScaleTransition>,
+    /// From the tonic of the outgoing key up to the incoming one's.
+    pub interval: Interval,
+    /// Percent to pitch the incoming track by to match the outgoing track's tempo, if both
+    /// are known.
+    pub pitch: Option<f64>,
+    /// How far that pitch moves the incoming track's key when played without key lock.
+    pub semitones: Option<f64>,
+}
+
+impl ProjectTrack {
+    pub(crate) fn new(track: &Track) -> Self {
+        let (artist, title) = track.artist_and_title();
+        Self {
+            name: track.name.clone(),
+            artist: artist.map(str::to_string),
+            title: title.to_string(),
+            key: track.key,
+            key_name: track.key.key_name().to_string(),
+            color: wheel_color(track.key),
+            confidence: track.confidence,
+            needs_review: track.needs_review(),
+            tempo: track.tempo,
+            duration: track.duration,
+            path: track.path.clone(),
+            segments: track.segments.clone(),
+        }
+    }
+}
+
+impl ProjectTransition {
+    fn new(from: usize, to: usize, tracks: &[Track], distances: &DistanceMatrix) -> Self {
+        let (outgoing, incoming) = (&tracks[from], &tracks[to]);
+        let keys = (outgoing.outgoing_key(), incoming.incoming_key());
+        let steps = distances.distance(keys.0, keys.1);
+        let pitch = outgoing.pitch_into(incoming);
+        Self {
+            from,
+            to,
+            steps,
+            compatibility: compatibility(steps),
+            transition: possible_transitions()
+                .into_iter()
+                .find(|&transition| make_transition(keys.0, transition) == keys.1),
+            interval: Interval::between(keys.0, keys.1),
+            pitch,
+            semitones: pitch.map(|pitch| 12.0 * (1.0 + pitch / 100.0).log2()),
+        }
+    }
+}
+
+impl SetProject {
+    /// Describes `tracks`, played in order, as a set named `name`.
+    pub fn new(name: &str, tracks: &[Track], distances: &DistanceMatrix) -> Self {
+        Self {
+            version: SET_PROJECT_VERSION,
+            name: name.to_string(),
+            tracks: tracks.iter().map(ProjectTrack::new).collect(),
+            transitions: (1..tracks.len())
+                .map(|to| ProjectTransition::new(to - 1, to, tracks, distances))
+                .collect(),
+        }
+    }
+}
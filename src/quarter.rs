@@ -0,0 +1,143 @@
+//! A prebuilt 24-position wheel for quarter-tone collections, like tracks with maqam-leaning
+//! tunings, that sits between the keys of the camelot wheel.
+
+use crate::error::{Error, Result};
+use crate::mixable::MixableKey;
+use crate::rules::TransitionRules;
+use crate::scale::{Scale, ScaleKind};
+use crate::transition::{make_transition_on, possible_transitions, ScaleTransition};
+use crate::wheel::Wheel;
+use camelol_core::WHEEL_SIZE;
+use std::fmt;
+use std::str::FromStr;
+
+/// Positions around the quarter-tone wheel: every camelot number, and after each the keys a
+/// neutral third above it.
+pub const QUARTER_TONE_WHEEL_SIZE: usize = 2 * WHEEL_SIZE;
+
+/// The transitions of the quarter-tone wheel. One position is a neutral third, three quarter
+/// tones short of a major third and the interval maqam music is built around, so two are a
+/// fifth like one step on the camelot wheel. Steps of a fifth and the relative key are the
+/// usual moves, and going a neutral third either way costs twice as much, since it leaves the
+/// twelve-tone keys for the quarter-tone ones or comes back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuarterToneRules;
+
+impl TransitionRules for QuarterToneRules {
+    fn allows(&self, _from: Scale, transition: ScaleTransition, _to: Scale) -> bool {
+        matches!(
+            transition,
+            ScaleTransition::Vertical | ScaleTransition::ChangeIndex(1 | -1 | 2 | -2)
+        )
+    }
+
+    fn cost(&self, _from: Scale, transition: ScaleTransition, _to: Scale) -> u32 {
+        match transition {
+            ScaleTransition::ChangeIndex(1 | -1) => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// The quarter-tone wheel with the transitions of [`QuarterToneRules`].
+pub fn quarter_tone_wheel() -> Wheel {
+    Wheel::with_rules(QUARTER_TONE_WHEEL_SIZE, &QuarterToneRules)
+}
+
+/// A key on the quarter-tone wheel, written like the camelot code `8A` for the keys the wheels
+/// share and like `8.5A` for the key halfway to 9A, a neutral third above 8A.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QuarterToneKey(pub Scale);
+
+impl QuarterToneKey {
+    /// The key of the camelot wheel on the quarter-tone wheel.
+    pub const fn from_camelot(scale: Scale) -> Self {
+        Self(Scale {
+            index: scale.index * 2,
+            kind: scale.kind,
+        })
+    }
+
+    /// The camelot key, if this is one of the keys the wheels share rather than a quarter-tone
+    /// one.
+    pub const fn camelot(self) -> Option<Scale> {
+        if self.0.index.is_multiple_of(2) {
+            Some(Scale {
+                index: self.0.index / 2,
+                kind: self.0.kind,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Pitch class of the tonic in quarter tones above C, so 8A has 18 for A and 8.5A has 1
+    /// for C a quarter tone sharp.
+    pub const fn tonic(self) -> usize {
+        let camelot = Scale {
+            index: self.0.index / 2,
+            kind: self.0.kind,
+        };
+        (camelot.tonic() * 2 + self.0.index % 2 * 7) % QUARTER_TONE_WHEEL_SIZE
+    }
+}
+
+impl fmt::Display for QuarterToneKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.0.kind {
+            ScaleKind::Minor => 'A',
+            ScaleKind::Major => 'B',
+        };
+        let number = self.0.index / 2 + 1;
+        if self.0.index.is_multiple_of(2) {
+            write!(f, "{number}{kind}")
+        } else {
+            write!(f, "{number}.5{kind}")
+        }
+    }
+}
+
+impl FromStr for QuarterToneKey {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let invalid = || Error::InvalidKey {
+            input: input.to_string(),
+            suggestions: vec![],
+        };
+        let s = input.trim();
+        let (half, s) = match s.split_once(".5") {
+            Some((number, kind)) => (1, format!("{number}{kind}")),
+            None => (0, s.to_string()),
+        };
+        let scale = Scale::parse_on(&s, WHEEL_SIZE).map_err(|_| invalid())?;
+        Ok(Self(Scale {
+            index: scale.index * 2 + half,
+            kind: scale.kind,
+        }))
+    }
+}
+
+impl MixableKey for QuarterToneKey {
+    type Transition = ScaleTransition;
+
+    fn neighbors(&self) -> Vec<(ScaleTransition, Self)> {
+        possible_transitions()
+            .into_iter()
+            .map(|transition| {
+                let to = make_transition_on(self.0, transition, QUARTER_TONE_WHEEL_SIZE);
+                (transition, to)
+            })
+            .filter(|&(transition, to)| QuarterToneRules.allows(self.0, transition, to))
+            .map(|(transition, to)| (transition, Self(to)))
+            .collect()
+    }
+
+    fn transition_cost(&self, transition: &ScaleTransition, to: &Self) -> u32 {
+        QuarterToneRules.cost(self.0, *transition, to.0)
+    }
+
+    fn display(&self) -> String {
+        self.to_string()
+    }
+}
@@ -0,0 +1,208 @@
+use crate::error::Result;
+use crate::scale::Scale;
+use crate::transition::ScaleTransition;
+use crate::wheel::Wheel;
+use itertools::Itertools;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The kinds of question a quiz asks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum QuizKind {
+    Neighbors,
+    Transition,
+}
+
+impl QuizKind {
+    pub const ALL: [QuizKind; 2] = [QuizKind::Neighbors, QuizKind::Transition];
+}
+
+impl Display for QuizKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            QuizKind::Neighbors => "neighbors",
+            QuizKind::Transition => "transition",
+        })
+    }
+}
+
+/// A question about the wheel, answered by the wheel itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuizQuestion {
+    /// Which keys are one transition from this one?
+    Neighbors(Scale),
+    /// Which transition takes the first key to the second?
+    Transition(Scale, Scale),
+}
+
+/// The right answer to a [`QuizQuestion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuizAnswer {
+    /// Every one of these keys, in wheel order, and no others.
+    Keys(Vec<Scale>),
+    /// Any one of these transitions.
+    Transitions(Vec<ScaleTransition>),
+}
+
+impl QuizAnswer {
+    /// Whether `keys` answer right, in any order and with repeats counted once.
+    pub fn accepts_keys(&self, keys: &[Scale]) -> bool {
+        let QuizAnswer::Keys(expected) = self else {
+            return false;
+        };
+        let given: Vec<Scale> = keys
+            .iter()
+            .copied()
+            .unique()
+            .sorted_by_key(|key| key.position())
+            .collect();
+        given == *expected
+    }
+
+    pub fn accepts_transition(&self, transition: ScaleTransition) -> bool {
+        matches!(self, QuizAnswer::Transitions(expected) if expected.contains(&transition))
+    }
+}
+
+impl QuizQuestion {
+    /// A question about `wheel` picked at random, either kind as likely, or `None` for a wheel
+    /// without transitions to ask about.
+    pub fn random<R: Rng>(wheel: &Wheel, rng: &mut R) -> Option<Self> {
+        let (from, _, to) = wheel.transitions().choose(rng)?;
+        Some(if rng.gen_bool(0.5) {
+            QuizQuestion::Neighbors(from)
+        } else {
+            QuizQuestion::Transition(from, to)
+        })
+    }
+
+    pub fn kind(self) -> QuizKind {
+        match self {
+            QuizQuestion::Neighbors(_) => QuizKind::Neighbors,
+            QuizQuestion::Transition(..) => QuizKind::Transition,
+        }
+    }
+
+    /// The right answer on `wheel`.
+    pub fn answer(self, wheel: &Wheel) -> Result<QuizAnswer> {
+        Ok(match self {
+            QuizQuestion::Neighbors(key) => QuizAnswer::Keys(
+                wheel
+                    .neighbors(key)?
+                    .into_iter()
+                    .map(|(_, to)| to)
+                    .unique()
+                    .sorted_by_key(|to| to.position())
+                    .collect(),
+            ),
+            QuizQuestion::Transition(from, to) => QuizAnswer::Transitions(
+                wheel
+                    .neighbors(from)?
+                    .into_iter()
+                    .filter(|(_, next)| *next == to)
+                    .map(|(transition, _)| transition)
+                    .collect(),
+            ),
+        })
+    }
+}
+
+/// A question answered, as a line of the quiz log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuizResult {
+    pub kind: QuizKind,
+    pub correct: bool,
+    /// Seconds since the unix epoch.
+    pub time: u64,
+}
+
+impl Display for QuizResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let verdict = if self.correct { "right" } else { "wrong" };
+        write!(f, "{} {} {verdict}", self.time, self.kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseQuizError(String);
+
+impl Display for ParseQuizError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid quiz entry `{}`", self.0)
+    }
+}
+
+impl Error for ParseQuizError {}
+
+impl FromStr for QuizResult {
+    type Err = ParseQuizError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseQuizError(s.to_string());
+        let [time, kind, verdict] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(error());
+        };
+        Ok(QuizResult {
+            kind: QuizKind::ALL
+                .into_iter()
+                .find(|known| known.to_string() == kind)
+                .ok_or_else(error)?,
+            correct: match verdict {
+                "right" => true,
+                "wrong" => false,
+                _ => return Err(error()),
+            },
+            time: time.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+/// Every question answered so far, over every quiz, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuizLog {
+    pub results: Vec<QuizResult>,
+}
+
+impl QuizLog {
+    /// Parses a quiz log with one `<unix time> <kind> <right|wrong>` entry per line.
+    pub fn parse(log: &str) -> Result<Self, ParseQuizError> {
+        let results = log
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { results })
+    }
+
+    /// Records a question of `kind` as answered right now and returns the new entry.
+    pub fn record(&mut self, kind: QuizKind, correct: bool) -> QuizResult {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let result = QuizResult {
+            kind,
+            correct,
+            time,
+        };
+        self.results.push(result);
+        result
+    }
+
+    /// How many of the questions of `kind` were answered right, and how many were asked, of
+    /// the `last` asked or of all of them.
+    pub fn score(&self, kind: QuizKind, last: Option<usize>) -> (usize, usize) {
+        let asked = self
+            .results
+            .iter()
+            .rev()
+            .filter(|result| result.kind == kind)
+            .take(last.unwrap_or(usize::MAX));
+        asked.fold((0, 0), |(right, total), result| {
+            (right + usize::from(result.correct), total + 1)
+        })
+    }
+}
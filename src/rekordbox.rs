@@ -0,0 +1,99 @@
+use crate::library::{absolute_path, Track};
+use crate::scale::{note_name, Scale, ScaleKind};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Escapes `text` for an XML attribute value.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The `file://localhost/...` URL Rekordbox identifies tracks by, with relative paths taken
+/// from the current directory.
+fn file_url(path: &Path) -> String {
+    let path = absolute_path(path).to_string_lossy().replace('\\', "/");
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{path}")
+    };
+    let mut url = String::from("file://localhost");
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' | b':' => {
+                url.push(byte as char)
+            }
+            byte => {
+                let _ = write!(url, "%{byte:02X}");
+            }
+        }
+    }
+    url
+}
+
+/// The key as Rekordbox writes it, like `Am` or `Db`.
+pub(crate) fn tonality(key: Scale) -> String {
+    let tonic = note_name(key.tonic(), key.conventional_accidental());
+    match key.kind {
+        ScaleKind::Major => tonic.to_string(),
+        ScaleKind::Minor => format!("{tonic}m"),
+    }
+}
+
+/// Writes the tracks as a playlist named `playlist` in Rekordbox's XML format, which Rekordbox
+/// imports into its collection and playlists. Rekordbox finds tracks by file, so tracks
+/// without a path are left out.
+pub fn rekordbox_xml(tracks: &[Track], playlist: &str) -> String {
+    let tracks: Vec<(&Track, &Path)> = tracks
+        .iter()
+        .filter_map(|track| Some((track, track.path.as_deref()?)))
+        .collect();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<DJ_PLAYLISTS Version=\"1.0.0\">\n");
+    let _ = writeln!(
+        xml,
+        "  <PRODUCT Name=\"camelol\" Version=\"{}\" Company=\"\"/>",
+        env!("CARGO_PKG_VERSION")
+    );
+    let _ = writeln!(xml, "  <COLLECTION Entries=\"{}\">", tracks.len());
+    for (id, (track, path)) in tracks.iter().enumerate() {
+        let (artist, name) = track.artist_and_title();
+        let _ = write!(
+            xml,
+            "    <TRACK TrackID=\"{}\" Name=\"{}\" Artist=\"{}\" Tonality=\"{}\" Location=\"{}\"",
+            id + 1,
+            escape(name),
+            escape(artist.unwrap_or_default()),
+            tonality(track.key),
+            escape(&file_url(path))
+        );
+        if let Some(tempo) = track.tempo {
+            let _ = write!(xml, " AverageBpm=\"{tempo:.2}\"");
+        }
+        xml.push_str("/>\n");
+    }
+    xml.push_str("  </COLLECTION>\n  <PLAYLISTS>\n");
+    xml.push_str("    <NODE Type=\"0\" Name=\"ROOT\" Count=\"1\">\n");
+    let _ = writeln!(
+        xml,
+        "      <NODE Name=\"{}\" Type=\"1\" KeyType=\"0\" Entries=\"{}\">",
+        escape(playlist),
+        tracks.len()
+    );
+    for id in 1..=tracks.len() {
+        let _ = writeln!(xml, "        <TRACK Key=\"{id}\"/>");
+    }
+    xml.push_str("      </NODE>\n    </NODE>\n  </PLAYLISTS>\n</DJ_PLAYLISTS>\n");
+    xml
+}
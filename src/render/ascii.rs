@@ -0,0 +1,77 @@
+use crate::scale::{make_nodes, Scale, ScaleKind};
+use itertools::Itertools;
+use std::f64::consts::PI;
+
+const WIDTH: usize = 53;
+const HEIGHT: usize = 21;
+
+// Terminal cells are roughly twice as tall as they are wide, so rings are stretched horizontally.
+const OUTER_RADIUS: (f64, f64) = (23.0, 10.0);
+const INNER_RADIUS: (f64, f64) = (13.0, 5.5);
+
+fn ring_radius(kind: ScaleKind) -> (f64, f64) {
+    match kind {
+        ScaleKind::Major => OUTER_RADIUS,
+        ScaleKind::Minor => INNER_RADIUS,
+    }
+}
+
+/// Grid cell at `angle` radians (counter-clockwise from 3 o'clock) on the given ring.
+fn cell(angle: f64, (rx, ry): (f64, f64)) -> (usize, usize) {
+    let x = (WIDTH / 2) as f64 + rx * angle.cos();
+    let y = (HEIGHT / 2) as f64 - ry * angle.sin();
+    (x.round() as usize, y.round() as usize)
+}
+
+/// Wheel numbers sit where the hours do on a clock.
+fn key_angle(scale: Scale) -> f64 {
+    PI / 2.0 - (scale.index + 1) as f64 * PI / 6.0
+}
+
+/// Draws the wheel as ASCII art, minor keys on the inner ring and major keys on the outer one.
+///
+/// `highlight` is drawn as `[8A]` and the keys of `path` as `(8A)`, with the route spelled out
+/// under the wheel.
+pub fn ascii_wheel(highlight: Option<Scale>, path: &[Scale]) -> String {
+    let mut wheel = ascii_wheel_marked(highlight, path);
+
+    if !path.is_empty() {
+        wheel.push_str("\n\n");
+        wheel.push_str(&path.iter().join(" -> "));
+    }
+
+    wheel
+}
+
+/// Like [`ascii_wheel`], marking `marked` as `(8A)` without spelling out a route, such as the
+/// keys that mix with `highlight`.
+pub fn ascii_wheel_marked(highlight: Option<Scale>, marked: &[Scale]) -> String {
+    let mut grid = vec![vec![' '; WIDTH]; HEIGHT];
+
+    for ring in [OUTER_RADIUS, INNER_RADIUS] {
+        for step in 0..96 {
+            let (x, y) = cell(step as f64 * PI / 48.0, ring);
+            grid[y][x] = '.';
+        }
+    }
+
+    for scale in make_nodes() {
+        let label = if Some(scale) == highlight {
+            format!("[{scale}]")
+        } else if marked.contains(&scale) {
+            format!("({scale})")
+        } else {
+            scale.to_string()
+        };
+
+        let (x, y) = cell(key_angle(scale), ring_radius(scale.kind));
+        let start = x.saturating_sub(label.len() / 2);
+        for (i, c) in label.chars().enumerate() {
+            grid[y][start + i] = c;
+        }
+    }
+
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>().trim_end().to_string())
+        .join("\n")
+}
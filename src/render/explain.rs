@@ -0,0 +1,90 @@
+use crate::render::Notation;
+use crate::scale::{Scale, ScaleKind};
+use crate::transition::ScaleTransition;
+
+/// What taking `transition` from `from` does, like `switch to the relative major`, and why a
+/// DJ would, for people still learning the wheel.
+fn describe(from: Scale, transition: ScaleTransition) -> (String, &'static str) {
+    let minor = from.kind == ScaleKind::Minor;
+    let other = if minor { "major" } else { "minor" };
+    match transition {
+        ScaleTransition::Vertical => (
+            format!("switch to the relative {other}"),
+            if minor {
+                "the same notes with a brighter mood"
+            } else {
+                "the same notes with a darker mood"
+            },
+        ),
+        ScaleTransition::ChangeIndex(1) => (
+            "boost energy one step".to_string(),
+            "the smoothest way up the wheel",
+        ),
+        ScaleTransition::ChangeIndex(2) => (
+            "boost energy two steps".to_string(),
+            "a bigger lift that still shares most notes",
+        ),
+        ScaleTransition::ChangeIndex(-1) => (
+            "drop energy one step".to_string(),
+            "the smoothest way to cool things down",
+        ),
+        ScaleTransition::ChangeIndex(-2) => (
+            "drop energy two steps".to_string(),
+            "a bigger release that still shares most notes",
+        ),
+        ScaleTransition::ChangeIndex(7) => (
+            "jump seven steps".to_string(),
+            "the key rises a semitone, a dramatic energy boost",
+        ),
+        ScaleTransition::ChangeIndex(-7) => (
+            "jump seven steps back".to_string(),
+            "the key falls a semitone, a sudden drop in energy",
+        ),
+        ScaleTransition::ChangeIndex(amount) => (
+            format!("move {amount} steps around the wheel"),
+            "a bold change of key",
+        ),
+        ScaleTransition::Diagonal => (
+            format!(
+                "cross diagonally to {other}, one step {}",
+                if minor { "down" } else { "up" }
+            ),
+            "a change of mood and energy at once",
+        ),
+        ScaleTransition::MajorToMinor => (
+            format!("switch to the parallel {other}"),
+            "the same tonic, so the bassline still fits while the mood flips",
+        ),
+        ScaleTransition::FlatToMinor => (
+            format!(
+                "switch to the {other} key a semitone {}",
+                if minor { "below" } else { "above" }
+            ),
+            "a surprising turn that works best over a breakdown",
+        ),
+    }
+}
+
+/// Narrates `steps` in plain language, one sentence per step, like
+/// `Switch to the relative major (8A -> 8B): the same notes with a brighter mood.`
+pub fn explain_steps(
+    steps: impl IntoIterator<Item = (Scale, ScaleTransition, Scale)>,
+    notation: &Notation,
+) -> Vec<String> {
+    steps
+        .into_iter()
+        .enumerate()
+        .map(|(i, (from, transition, to))| {
+            let (action, reason) = describe(from, transition);
+            let action = match i {
+                0 => action[..1].to_uppercase() + &action[1..],
+                _ => format!("Then {action}"),
+            };
+            format!(
+                "{action} ({} -> {}): {reason}.",
+                notation.label(from),
+                notation.label(to)
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,60 @@
+use crate::rekordbox::escape;
+use crate::render::Notation;
+use crate::scale::Scale;
+use crate::transition::{Interval, ScaleTransition};
+use itertools::Itertools;
+use std::fmt::Write;
+
+/// Renders keys and the transitions between them as GraphML for tools like Gephi and yEd. Keys
+/// carry their name in `notation`, and transitions their name, the interval between the tonics
+/// and the `weight` a path pays for them. Repeated keys and transitions are written once.
+pub fn graphml(
+    scales: impl IntoIterator<Item = Scale>,
+    transitions: impl IntoIterator<Item = (Scale, ScaleTransition, Scale)>,
+    notation: &Notation,
+    weight: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (id, domain, name, kind) in [
+        ("key", "node", "key", "string"),
+        ("name", "node", "name", "string"),
+        ("transition", "edge", "transition", "string"),
+        ("interval", "edge", "interval", "string"),
+        ("weight", "edge", "weight", "int"),
+    ] {
+        let _ = writeln!(
+            xml,
+            "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{name}\" attr.type=\"{kind}\"/>"
+        );
+    }
+    xml.push_str("  <graph id=\"wheel\" edgedefault=\"directed\">\n");
+    for scale in scales.into_iter().unique() {
+        let _ = writeln!(
+            xml,
+            concat!(
+                "    <node id=\"k{scale}\"><data key=\"key\">{scale}</data>",
+                "<data key=\"name\">{}</data></node>"
+            ),
+            escape(&notation.key_name(scale)),
+            scale = scale,
+        );
+    }
+    for (from, transition, to) in transitions.into_iter().unique() {
+        let _ = writeln!(
+            xml,
+            concat!(
+                "    <edge source=\"k{from}\" target=\"k{to}\">",
+                "<data key=\"transition\">{transition:?}</data>",
+                "<data key=\"interval\">{}</data><data key=\"weight\">{}</data></edge>"
+            ),
+            Interval::between(from, to),
+            weight(from, transition, to),
+            from = from,
+            to = to,
+            transition = transition,
+        );
+    }
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
@@ -0,0 +1,24 @@
+use crate::scale::Scale;
+use crate::transition::ScaleTransition;
+use itertools::Itertools;
+use std::fmt::Write;
+
+fn node(scale: Scale) -> String {
+    format!("k{scale}[\"{scale}\"]")
+}
+
+/// Renders transitions as a left-to-right Mermaid flowchart. Repeated transitions are drawn once.
+pub fn mermaid_flowchart(
+    transitions: impl IntoIterator<Item = (Scale, ScaleTransition, Scale)>,
+) -> String {
+    let mut chart = String::from("flowchart LR\n");
+    for (from, transition, to) in transitions.into_iter().unique() {
+        let _ = writeln!(
+            chart,
+            "    {} -->|\"{transition:?}\"| {}",
+            node(from),
+            node(to)
+        );
+    }
+    chart
+}
@@ -0,0 +1,19 @@
+mod ascii;
+mod explain;
+mod graphml;
+mod mermaid;
+mod notation;
+#[cfg(feature = "png")]
+mod png;
+mod svg;
+mod terminal;
+
+pub use ascii::{ascii_wheel, ascii_wheel_marked};
+pub use explain::explain_steps;
+pub use graphml::graphml;
+pub use mermaid::mermaid_flowchart;
+pub use notation::{KeyDisplay, Notation};
+#[cfg(feature = "png")]
+pub use png::png_wheel;
+pub use svg::svg_wheel;
+pub use terminal::{ascii_symbols, paint, paint_scale, ColorSupport};
@@ -0,0 +1,131 @@
+use crate::error::Result;
+use crate::scale::{invalid_key, Accidental, Locale, ModalKey, Mode, Scale, WheelNumbering};
+use camelol_core::KEY_COUNT;
+
+/// Which names keys are written with.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum KeyDisplay {
+    /// `8A`
+    #[default]
+    Camelot,
+    /// `A minor`
+    Musical,
+    /// `8A (A minor)`
+    Both,
+}
+
+/// How keys are written in text output.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Notation {
+    pub display: KeyDisplay,
+    /// Language of musical key names, both when writing and parsing them.
+    pub locale: Locale,
+    /// Which musical key every wheel number stands for, both when naming keys and parsing
+    /// their names.
+    pub numbering: WheelNumbering,
+    /// Spelling of every key's musical name other than its conventional one, indexed by
+    /// [`Scale::position`].
+    accidentals: [Option<Accidental>; KEY_COUNT],
+}
+
+impl Notation {
+    pub fn new(display: KeyDisplay) -> Self {
+        Self {
+            display,
+            locale: Locale::default(),
+            numbering: WheelNumbering::default(),
+            accidentals: [None; KEY_COUNT],
+        }
+    }
+
+    /// Spells every key with `accidental` instead of its conventional spelling.
+    pub fn spell_all(&mut self, accidental: Accidental) {
+        self.accidentals = [Some(accidental); KEY_COUNT];
+    }
+
+    /// Spells `scale` with `accidental`, leaving the other keys alone.
+    pub fn spell(&mut self, scale: Scale, accidental: Accidental) {
+        if let Some(spelling) = self.accidentals.get_mut(scale.position()) {
+            *spelling = Some(accidental);
+        }
+    }
+
+    /// How `scale`'s black keys are spelled.
+    fn accidental(&self, scale: Scale) -> Accidental {
+        self.accidentals
+            .get(scale.position())
+            .copied()
+            .flatten()
+            .unwrap_or_else(|| self.numbering.standard(scale).conventional_accidental())
+    }
+
+    pub fn key_name(&self, scale: Scale) -> String {
+        let accidental = self.accidental(scale);
+        self.numbering
+            .standard(scale)
+            .key_name_with(accidental)
+            .in_locale(self.locale)
+            .to_string()
+    }
+
+    /// The names of the notes of `scale`, spelled and in the language its name is written in,
+    /// like `A B C D E F G` for 8A.
+    pub fn note_names(&self, scale: Scale) -> [&'static str; 7] {
+        let accidental = self.accidental(scale);
+        self.numbering
+            .standard(scale)
+            .note_names_in(accidental, self.locale)
+    }
+
+    /// The wheel key `key` is written as, the nearest one for modes other than major and minor.
+    pub fn wheel_key(&self, key: ModalKey) -> Scale {
+        self.numbering.from_standard(key.scale())
+    }
+
+    pub fn label(&self, scale: Scale) -> String {
+        match self.display {
+            KeyDisplay::Camelot => scale.to_string(),
+            KeyDisplay::Musical => self.key_name(scale),
+            KeyDisplay::Both => format!("{scale} ({})", self.key_name(scale)),
+        }
+    }
+
+    /// Like [`Notation::label`], with modal keys written by their own name, like
+    /// `8A (D Dorian)`.
+    pub fn modal_label(&self, key: ModalKey) -> String {
+        let scale = self.wheel_key(key);
+        if matches!(key.mode, Mode::Ionian | Mode::Aeolian) {
+            return self.label(scale);
+        }
+        match self.display {
+            KeyDisplay::Camelot => scale.to_string(),
+            KeyDisplay::Musical => key.to_string(),
+            KeyDisplay::Both => format!("{scale} ({key})"),
+        }
+    }
+
+    /// Parses a camelot code, a musical key name in [`Notation::locale`], or a modal key like
+    /// `D Dorian`.
+    pub fn parse_modal(&self, input: &str) -> Result<ModalKey> {
+        input
+            .trim()
+            .parse()
+            .map(|scale| self.numbering.standard(scale))
+            .or_else(|_| Scale::from_key_name(input, self.locale))
+            .map(ModalKey::from)
+            .or_else(|_| input.parse())
+            .map_err(|_| invalid_key(input, self.locale))
+    }
+
+    /// Parses a key like [`Notation::parse_modal`], with modal keys mapped onto the nearest
+    /// wheel key.
+    pub fn parse(&self, input: &str) -> Result<Scale> {
+        Ok(self.wheel_key(self.parse_modal(input)?))
+    }
+}
+
+impl Default for Notation {
+    fn default() -> Self {
+        Self::new(KeyDisplay::default())
+    }
+}
@@ -0,0 +1,290 @@
+//! The wheel of [`svg_wheel`](super::svg_wheel) drawn as a PNG image, for apps that won't show
+//! SVG inline. The shapes are rasterized here with a few samples per pixel for smooth edges, and
+//! labels use a built-in 5 by 7 pixel font, so no renderer or font files are needed.
+
+use super::svg::{
+    anchor, arrow_curve, key_angle, point, ring, CENTER, INNER_RADIUS, MIDDLE_RADIUS, OUTER_RADIUS,
+    SIZE,
+};
+use crate::color::{wheel_color, Rgb};
+use crate::scale::{make_nodes, Scale, ScaleKind};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::f64::consts::PI;
+use std::io::Write;
+
+const INK: Rgb = Rgb(0x22, 0x22, 0x22);
+const WHITE: Rgb = Rgb(0xFF, 0xFF, 0xFF);
+const HUB: Rgb = Rgb(0xF4, 0xF4, 0xF4);
+
+/// Samples per pixel along each axis.
+const SAMPLES: u32 = 3;
+
+/// Rows of the digits and of `A` and `B`, the leftmost pixel in the highest of five bits.
+const GLYPHS: [(char, [u8; 7]); 12] = [
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('A', [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+];
+
+/// Size of a font pixel, so labels are about as tall as the SVG's 16 point text.
+const FONT_PIXEL: f64 = 1.7;
+
+fn distance_to_line(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).clamp(0.0, 1.0)
+    };
+    let (x, y) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (x * x + y * y).sqrt()
+}
+
+/// Angle of a point clockwise from 12 o'clock, from 0 to 2π, like [`key_angle`].
+fn angle_of(p: (f64, f64)) -> f64 {
+    (p.0 - CENTER).atan2(CENTER - p.1).rem_euclid(2.0 * PI)
+}
+
+/// How far a point is from the outline of a key's segment.
+fn outline_distance(scale: Scale, p: (f64, f64)) -> f64 {
+    let (inner, outer) = ring(scale.kind);
+    let (start, end) = (key_angle(scale) - PI / 12.0, key_angle(scale) + PI / 12.0);
+    let radius = (p.0 - CENTER).hypot(p.1 - CENTER);
+    // Angle from the segment's middle, from -π to π.
+    let offset = (angle_of(p) - key_angle(scale) + PI).rem_euclid(2.0 * PI) - PI;
+    let arc = |r: f64| {
+        if offset.abs() <= PI / 12.0 {
+            (radius - r).abs()
+        } else {
+            let corner = |angle: f64| {
+                let (x, y) = point(angle, r);
+                (p.0 - x).hypot(p.1 - y)
+            };
+            corner(start).min(corner(end))
+        }
+    };
+    let edge = |angle: f64| distance_to_line(p, point(angle, inner), point(angle, outer));
+    arc(inner).min(arc(outer)).min(edge(start)).min(edge(end))
+}
+
+/// The segment a point lies in or, just outside the wheel, next to.
+fn segment_at(p: (f64, f64)) -> Scale {
+    let radius = (p.0 - CENTER).hypot(p.1 - CENTER);
+    let kind = if radius < MIDDLE_RADIUS {
+        ScaleKind::Minor
+    } else {
+        ScaleKind::Major
+    };
+    let hour = (angle_of(p) / (PI / 6.0)).round() as usize;
+    Scale {
+        index: (hour + 11) % 12,
+        kind,
+    }
+}
+
+/// A label, with the corner its text starts at.
+struct Label {
+    text: Vec<[u8; 7]>,
+    origin: (f64, f64),
+    bold: bool,
+}
+
+impl Label {
+    fn new(scale: Scale, bold: bool) -> Self {
+        let text: Vec<[u8; 7]> = scale
+            .to_string()
+            .chars()
+            .filter_map(|c| GLYPHS.iter().find(|(glyph, _)| *glyph == c))
+            .map(|(_, rows)| *rows)
+            .collect();
+        let width = (text.len() * 6 - 1) as f64 * FONT_PIXEL;
+        let (x, y) = anchor(scale);
+        Self {
+            text,
+            origin: (x - width / 2.0, y - 3.5 * FONT_PIXEL),
+            bold,
+        }
+    }
+
+    fn covers(&self, p: (f64, f64)) -> bool {
+        let lit = |x: f64, y: f64| {
+            let (column, row) = ((x / FONT_PIXEL).floor(), (y / FONT_PIXEL).floor());
+            if column < 0.0 || !(0.0..7.0).contains(&row) {
+                return false;
+            }
+            let (column, row) = (column as usize, row as usize);
+            self.text
+                .get(column / 6)
+                .is_some_and(|rows| column % 6 < 5 && rows[row] & (0x10 >> (column % 6)) != 0)
+        };
+        let (x, y) = (p.0 - self.origin.0, p.1 - self.origin.1);
+        if !(0.0..self.text.len() as f64 * 6.0 * FONT_PIXEL).contains(&x) || y < 0.0 {
+            return false;
+        }
+        // Bold text is each pixel drawn again a little to the right.
+        lit(x, y) || (self.bold && lit(x - 0.5 * FONT_PIXEL, y))
+    }
+}
+
+/// An arrow between two keys as a polyline, with the corners of its head.
+struct Arrow {
+    line: Vec<(f64, f64)>,
+    head: [(f64, f64); 3],
+    /// Corners of a box around the arrow, to skip points far away from it quickly.
+    bounds: ((f64, f64), (f64, f64)),
+}
+
+impl Arrow {
+    fn new(from: Scale, to: Scale) -> Self {
+        let [start, control, end] = arrow_curve(from, to);
+        let line: Vec<(f64, f64)> = (0..=24)
+            .map(|i| {
+                let t = i as f64 / 24.0;
+                let u = 1.0 - t;
+                (
+                    u * u * start.0 + 2.0 * u * t * control.0 + t * t * end.0,
+                    u * u * start.1 + 2.0 * u * t * control.1 + t * t * end.1,
+                )
+            })
+            .collect();
+        // The SVG's marker: 18 units long, its tip 3.6 past the end of the line.
+        let (dx, dy) = (end.0 - control.0, end.1 - control.1);
+        let length = dx.hypot(dy).max(1.0);
+        let (dx, dy) = (dx / length, dy / length);
+        let base = (end.0 - dx * 14.4, end.1 - dy * 14.4);
+        let head = [
+            (end.0 + dx * 3.6, end.1 + dy * 3.6),
+            (base.0 - dy * 9.0, base.1 + dx * 9.0),
+            (base.0 + dy * 9.0, base.1 - dx * 9.0),
+        ];
+        let corners = line.iter().chain(&head);
+        let bounds = corners.fold(
+            ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN)),
+            |((x1, y1), (x2, y2)), (x, y)| ((x1.min(*x), y1.min(*y)), (x2.max(*x), y2.max(*y))),
+        );
+        Self { line, head, bounds }
+    }
+
+    fn covers(&self, p: (f64, f64)) -> bool {
+        let ((x1, y1), (x2, y2)) = self.bounds;
+        if p.0 < x1 - 2.0 || p.0 > x2 + 2.0 || p.1 < y1 - 2.0 || p.1 > y2 + 2.0 {
+            return false;
+        }
+        let side =
+            |a: (f64, f64), b: (f64, f64)| (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+        let [a, b, c] = self.head;
+        let sides = [side(a, b), side(b, c), side(c, a)];
+        sides.iter().all(|side| *side >= 0.0)
+            || sides.iter().all(|side| *side <= 0.0)
+            || self
+                .line
+                .windows(2)
+                .any(|pair| distance_to_line(p, pair[0], pair[1]) <= 1.5)
+    }
+}
+
+/// The color at a point of the wheel, in SVG units, if any.
+fn sample(
+    p: (f64, f64),
+    highlight: Option<Scale>,
+    labels: &[Label],
+    arrows: &[Arrow],
+) -> Option<Rgb> {
+    if arrows.iter().any(|arrow| arrow.covers(p)) || labels.iter().any(|label| label.covers(p)) {
+        return Some(INK);
+    }
+    let radius = (p.0 - CENTER).hypot(p.1 - CENTER);
+    let near = |scale: Scale, distance: f64| {
+        let (inner, outer) = ring(scale.kind);
+        (inner - distance..=outer + distance).contains(&radius)
+            && outline_distance(scale, p) <= distance
+    };
+    if highlight.is_some_and(|highlight| near(highlight, 2.0)) {
+        return Some(INK);
+    }
+    if radius > OUTER_RADIUS + 1.0 {
+        return None;
+    }
+    let segment = segment_at(p);
+    if outline_distance(segment, p) <= 1.0 {
+        Some(WHITE)
+    } else if radius < INNER_RADIUS {
+        Some(HUB)
+    } else {
+        Some(wheel_color(segment))
+    }
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32fast::hash(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Draws the wheel like [`svg_wheel`](super::svg_wheel) as a PNG image `size` pixels square,
+/// with a transparent background.
+pub fn png_wheel(highlight: Option<Scale>, path: &[Scale], size: u32) -> Vec<u8> {
+    let labels: Vec<Label> = make_nodes()
+        .into_iter()
+        .map(|scale| Label::new(scale, path.contains(&scale) || Some(scale) == highlight))
+        .collect();
+    let arrows: Vec<Arrow> = path
+        .windows(2)
+        .map(|pair| Arrow::new(pair[0], pair[1]))
+        .collect();
+
+    let unit = SIZE / size.max(1) as f64;
+    let mut pixels = Vec::with_capacity((size as usize * 4 + 1) * size as usize);
+    for y in 0..size {
+        // Every row starts with its filter type, none.
+        pixels.push(0);
+        for x in 0..size {
+            let (mut red, mut green, mut blue, mut covered) = (0.0, 0.0, 0.0, 0.0_f64);
+            for sy in 0..SAMPLES {
+                for sx in 0..SAMPLES {
+                    let p = (
+                        (x as f64 + (sx as f64 + 0.5) / SAMPLES as f64) * unit,
+                        (y as f64 + (sy as f64 + 0.5) / SAMPLES as f64) * unit,
+                    );
+                    if let Some(Rgb(r, g, b)) = sample(p, highlight, &labels, &arrows) {
+                        red += r as f64;
+                        green += g as f64;
+                        blue += b as f64;
+                        covered += 1.0;
+                    }
+                }
+            }
+            let channel = |sum: f64| (sum / covered.max(1.0)).round() as u8;
+            let alpha = (covered * 255.0 / (SAMPLES * SAMPLES) as f64).round() as u8;
+            pixels.extend_from_slice(&[channel(red), channel(green), channel(blue), alpha]);
+        }
+    }
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&size.to_be_bytes());
+    header.extend_from_slice(&size.to_be_bytes());
+    // 8 bits per channel, RGBA, then the only compression, filter and interlace methods.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &header);
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing to a `Vec` can't fail.
+    let _ = encoder.write_all(&pixels);
+    chunk(&mut png, b"IDAT", &encoder.finish().unwrap_or_default());
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
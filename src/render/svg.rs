@@ -0,0 +1,140 @@
+use crate::color::wheel_color;
+use crate::scale::{make_nodes, Scale, ScaleKind};
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+pub(super) const SIZE: f64 = 520.0;
+pub(super) const CENTER: f64 = SIZE / 2.0;
+pub(super) const INNER_RADIUS: f64 = 90.0;
+pub(super) const MIDDLE_RADIUS: f64 = 165.0;
+pub(super) const OUTER_RADIUS: f64 = 240.0;
+
+/// Wheel numbers sit where the hours do on a clock. Angles are clockwise from 12 o'clock.
+pub(super) fn key_angle(scale: Scale) -> f64 {
+    (scale.index + 1) as f64 * PI / 6.0
+}
+
+pub(super) fn point(angle: f64, radius: f64) -> (f64, f64) {
+    (CENTER + radius * angle.sin(), CENTER - radius * angle.cos())
+}
+
+pub(super) fn ring(kind: ScaleKind) -> (f64, f64) {
+    match kind {
+        ScaleKind::Minor => (INNER_RADIUS, MIDDLE_RADIUS),
+        ScaleKind::Major => (MIDDLE_RADIUS, OUTER_RADIUS),
+    }
+}
+
+/// Center of a key's segment, where its label and any path arrows attach.
+pub(super) fn anchor(scale: Scale) -> (f64, f64) {
+    let (inner, outer) = ring(scale.kind);
+    point(key_angle(scale), (inner + outer) / 2.0)
+}
+
+fn segment(scale: Scale) -> String {
+    let (inner, outer) = ring(scale.kind);
+    let (start, end) = (key_angle(scale) - PI / 12.0, key_angle(scale) + PI / 12.0);
+    let (x1, y1) = point(start, outer);
+    let (x2, y2) = point(end, outer);
+    let (x3, y3) = point(end, inner);
+    let (x4, y4) = point(start, inner);
+    format!(
+        "M {x1:.2} {y1:.2} A {outer} {outer} 0 0 1 {x2:.2} {y2:.2} \
+         L {x3:.2} {y3:.2} A {inner} {inner} 0 0 0 {x4:.2} {y4:.2} Z"
+    )
+}
+
+/// Start, control point and end of the quadratic curve of an arrow between two keys, bowed
+/// toward the middle of the wheel and stopping short of the target's label.
+pub(super) fn arrow_curve(from: Scale, to: Scale) -> [(f64, f64); 3] {
+    let (x1, y1) = anchor(from);
+    let (x2, y2) = anchor(to);
+    let (cx, cy) = (
+        CENTER + ((x1 + x2) / 2.0 - CENTER) * 0.6,
+        CENTER + ((y1 + y2) / 2.0 - CENTER) * 0.6,
+    );
+    let (dx, dy) = (x2 - cx, y2 - cy);
+    let length = (dx * dx + dy * dy).sqrt().max(1.0);
+    let (x2, y2) = (x2 - dx / length * 18.0, y2 - dy / length * 18.0);
+    [(x1, y1), (cx, cy), (x2, y2)]
+}
+
+fn arrow(from: Scale, to: Scale) -> String {
+    let [(x1, y1), (cx, cy), (x2, y2)] = arrow_curve(from, to);
+    format!("M {x1:.2} {y1:.2} Q {cx:.2} {cy:.2} {x2:.2} {y2:.2}")
+}
+
+/// Draws the wheel as an SVG image with its standard colors. `highlight` gets a thick outline and
+/// consecutive keys of `path` are joined by arrows.
+pub fn svg_wheel(highlight: Option<Scale>, path: &[Scale]) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SIZE}" height="{SIZE}" "#,
+            r#"viewBox="0 0 {SIZE} {SIZE}">"#
+        ),
+        SIZE = SIZE,
+    );
+    let _ = writeln!(
+        svg,
+        concat!(
+            r#"  <defs><marker id="arrow" viewBox="0 0 10 10" refX="8" refY="5" "#,
+            r#"markerWidth="6" markerHeight="6" orient="auto-start-reverse">"#,
+            r##"<path d="M 0 0 L 10 5 L 0 10 z" fill="#222"/></marker></defs>"##
+        )
+    );
+    let _ = writeln!(
+        svg,
+        r##"  <circle cx="{CENTER}" cy="{CENTER}" r="{INNER_RADIUS}" fill="#f4f4f4"/>"##
+    );
+
+    for scale in make_nodes() {
+        let (stroke, stroke_width) = if Some(scale) == highlight {
+            ("#222", 4)
+        } else {
+            ("#fff", 2)
+        };
+        let _ = writeln!(
+            svg,
+            r#"  <path d="{}" fill="{}" stroke="{stroke}" stroke-width="{stroke_width}"/>"#,
+            segment(scale),
+            wheel_color(scale),
+        );
+    }
+
+    for scale in make_nodes() {
+        let (x, y) = anchor(scale);
+        let weight = if path.contains(&scale) || Some(scale) == highlight {
+            "bold"
+        } else {
+            "normal"
+        };
+        let _ = writeln!(
+            svg,
+            concat!(
+                r#"  <text x="{x:.2}" y="{y:.2}" font-family="sans-serif" font-size="16" "#,
+                r##"font-weight="{weight}" fill="#222" text-anchor="middle" "##,
+                r#"dominant-baseline="central">{scale}</text>"#
+            ),
+            x = x,
+            y = y,
+            weight = weight,
+            scale = scale,
+        );
+    }
+
+    for pair in path.windows(2) {
+        let _ = writeln!(
+            svg,
+            concat!(
+                r##"  <path d="{}" fill="none" stroke="#222" stroke-width="3" "##,
+                r##"marker-end="url(#arrow)"/>"##
+            ),
+            arrow(pair[0], pair[1])
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,60 @@
+use crate::color::{wheel_color, Rgb};
+use crate::render::Notation;
+use crate::scale::Scale;
+use std::env;
+use std::io::{stdout, IsTerminal};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ColorSupport {
+    None,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// The best color depth the terminal advertises, ignoring whether stdout is a terminal.
+    pub fn depth() -> Self {
+        match env::var("COLORTERM").as_deref() {
+            Ok("truecolor" | "24bit") => ColorSupport::TrueColor,
+            _ => ColorSupport::Ansi256,
+        }
+    }
+
+    /// Colors only when stdout is a terminal that isn't `TERM=dumb`, and `NO_COLOR` isn't set.
+    pub fn detect() -> Self {
+        if !stdout().is_terminal()
+            || env::var("TERM").is_ok_and(|term| term == "dumb")
+            || env::var_os("NO_COLOR").is_some_and(|no_color| !no_color.is_empty())
+        {
+            return ColorSupport::None;
+        }
+        Self::depth()
+    }
+}
+
+/// `text` with the symbols camelol writes spelled in ASCII, like `vii°` as `viio`.
+pub fn ascii_symbols(text: &str) -> String {
+    text.replace('°', "o")
+}
+
+fn ansi256(Rgb(r, g, b): Rgb) -> u8 {
+    let level = |c: u8| (c as u16 * 5 + 127) / 255;
+    (16 + 36 * level(r) + 6 * level(g) + level(b)) as u8
+}
+
+/// Paints `text` in `color`, or leaves it alone without color support.
+pub fn paint(text: &str, color: Rgb, support: ColorSupport) -> String {
+    match support {
+        ColorSupport::None => text.to_string(),
+        ColorSupport::Ansi256 => format!("\x1b[38;5;{}m{text}\x1b[0m", ansi256(color)),
+        ColorSupport::TrueColor => {
+            let Rgb(r, g, b) = color;
+            format!("\x1b[38;2;{r};{g};{b}m{text}\x1b[0m")
+        }
+    }
+}
+
+/// A key's name, written in `notation`, in its wheel color.
+pub fn paint_scale(scale: Scale, notation: Notation, support: ColorSupport) -> String {
+    paint(&notation.label(scale), wheel_color(scale), support)
+}
@@ -0,0 +1,174 @@
+use crate::color::wheel_color;
+use crate::distance::DistanceMatrix;
+use crate::library::{format_duration, Track};
+use crate::project::{ProjectTransition, SetProject};
+use crate::rekordbox::escape;
+use crate::render::{explain_steps, svg_wheel, Notation};
+use crate::scale::Scale;
+use itertools::Itertools;
+use std::fmt::Write;
+
+const STYLE: &str =
+    "body { font-family: sans-serif; max-width: 960px; margin: 2em auto; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; }
+td.key { font-weight: bold; }
+tr.review td { font-style: italic; }
+.wheel { text-align: center; margin: 2em 0; }
+li { margin-bottom: 0.5em; }";
+
+/// What to keep in mind mixing a transition, in a sentence or two.
+fn transition_note(
+    transition: &ProjectTransition,
+    from: Scale,
+    to: Scale,
+    notation: &Notation,
+    pitch_range: Option<f64>,
+) -> String {
+    let mut note = match (transition.steps, transition.transition) {
+        (None, _) => "These keys aren't connected on the wheel, so bridge them with a breakdown, \
+                      an effect or a clean cut."
+            .to_string(),
+        (Some(0), _) => {
+            "The same key, so the tracks can blend for as long as they like.".to_string()
+        }
+        (_, Some(step)) => explain_steps([(from, step, to)], notation).concat(),
+        (Some(steps), None) => format!(
+            "{steps} moves apart on the wheel, so keep the overlap short or mix over drums only."
+        ),
+    };
+    if let (Some(pitch), Some(semitones)) = (transition.pitch, transition.semitones) {
+        if let Some(range) = pitch_range.filter(|&range| pitch.abs() > range) {
+            let _ = write!(
+                note,
+                " The tempos are {pitch:+.1}% apart, beyond the {range}% the pitch faders go, so \
+                 cut between the tracks or bridge them without beatmatching."
+            );
+        } else if pitch.abs() >= 0.05 {
+            let _ = write!(
+                note,
+                " Pitch the incoming track by {pitch:+.1}% to match tempos, which moves its key \
+                 {semitones:+.2} semitones without key lock."
+            );
+        }
+    }
+    note
+}
+
+/// A single HTML page describing a set played in the order of `tracks`, to share before a
+/// back-to-back: a table of the tracks, the wheel with the set's route on it and notes on
+/// every transition. With a `pitch_range`, how far the decks' pitch faders go in percent either
+/// way, tempo changes they can't reach are called out.
+pub fn html_report(
+    name: &str,
+    tracks: &[Track],
+    distances: &DistanceMatrix,
+    notation: &Notation,
+    pitch_range: Option<f64>,
+) -> String {
+    let project = SetProject::new(name, tracks, distances);
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(html, "<title>{}</title>", escape(name));
+    let _ = writeln!(html, "<style>\n{STYLE}\n</style>\n</head>\n<body>");
+    let _ = writeln!(html, "<h1>{}</h1>", escape(name));
+    let length: Option<f64> = tracks.iter().map(|track| track.duration).sum();
+    let _ = writeln!(
+        html,
+        "<p>{} tracks{}</p>",
+        tracks.len(),
+        length.map_or(String::new(), |length| format!(
+            ", {}",
+            format_duration(length)
+        ))
+    );
+
+    let _ = writeln!(html, "<table>");
+    let _ = writeln!(
+        html,
+        concat!(
+            "<tr><th>#</th><th>Track</th><th>Key</th><th>Confidence</th><th>BPM</th>",
+            "<th>Energy</th><th>Length</th></tr>"
+        )
+    );
+    for (number, track) in tracks.iter().enumerate() {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        let _ = writeln!(
+            html,
+            concat!(
+                "<tr{}><td>{}</td><td>{}</td><td class=\"key\" style=\"background: {}\">{}</td>",
+                "<td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>"
+            ),
+            if track.needs_review() {
+                " class=\"review\""
+            } else {
+                ""
+            },
+            number + 1,
+            escape(&track.name),
+            wheel_color(track.key),
+            escape(&notation.label(track.key)),
+            optional(
+                track
+                    .confidence
+                    .map(|confidence| format!("{:.0}%", confidence * 100.0))
+            ),
+            optional(track.tempo.map(|tempo| format!("{tempo:.1}"))),
+            optional(track.energy.map(|energy| energy.to_string())),
+            optional(track.duration.map(format_duration)),
+        );
+    }
+    let _ = writeln!(html, "</table>");
+    let review: Vec<&str> = tracks
+        .iter()
+        .filter(|track| track.needs_review())
+        .map(|track| track.name.as_str())
+        .collect();
+    if !review.is_empty() {
+        let _ = writeln!(
+            html,
+            "<p>Check the keys of these tracks by ear, their detection was unsure: {}.</p>",
+            escape(&review.join(", "))
+        );
+    }
+
+    // Tracks that modulate pass through every key they start and end in.
+    let route: Vec<Scale> = tracks
+        .iter()
+        .flat_map(|track| [track.incoming_key(), track.outgoing_key()])
+        .dedup()
+        .collect();
+    let _ = writeln!(
+        html,
+        "<div class=\"wheel\">\n{}</div>",
+        svg_wheel(route.first().copied(), &route)
+    );
+
+    if !project.transitions.is_empty() {
+        let _ = writeln!(html, "<h2>Transitions</h2>\n<ol>");
+        for transition in &project.transitions {
+            let (outgoing, incoming) = (&tracks[transition.from], &tracks[transition.to]);
+            let (from, to) = (outgoing.outgoing_key(), incoming.incoming_key());
+            let _ = writeln!(
+                html,
+                "<li><strong>{} &rarr; {}</strong> ({} &rarr; {}, compatibility {:.2})<br>{}</li>",
+                escape(&outgoing.name),
+                escape(&incoming.name),
+                escape(&notation.label(from)),
+                escape(&notation.label(to)),
+                transition.compatibility,
+                escape(&transition_note(
+                    transition,
+                    from,
+                    to,
+                    notation,
+                    pitch_range
+                )),
+            );
+        }
+        let _ = writeln!(html, "</ol>");
+    }
+    let _ = writeln!(html, "</body>\n</html>");
+    html
+}
@@ -0,0 +1,304 @@
+use crate::engine::{default_path_count, default_suggestion_count, Engine};
+use crate::error::Error;
+use crate::library::{Track, TrackLibrary};
+use crate::matches::{track_match, DEFAULT_PITCH_RANGE};
+use crate::planner::{PlanOptions, SetPlanner};
+use crate::project::{ProjectTrack, SetProject};
+use crate::scale::Scale;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The first of the codes JSON-RPC leaves to servers, for searches stopped by their time limit.
+const SEARCH_STOPPED: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct PathsParams {
+    from: Scale,
+    to: Scale,
+    #[serde(default = "default_path_count")]
+    n: usize,
+}
+
+#[derive(Deserialize)]
+struct KeyParams {
+    key: Scale,
+}
+
+#[derive(Deserialize)]
+struct ReachParams {
+    from: Scale,
+    steps: usize,
+}
+
+#[derive(Deserialize)]
+struct BridgesParams {
+    from: Scale,
+    to: Scale,
+    #[serde(default = "default_bridge_steps")]
+    steps: usize,
+}
+
+fn default_bridge_steps() -> usize {
+    1
+}
+
+#[derive(Deserialize)]
+struct DistanceParams {
+    from: Scale,
+    to: Scale,
+}
+
+#[derive(Deserialize)]
+struct SuggestParams {
+    from: Scale,
+    #[serde(default = "default_suggestion_count")]
+    n: usize,
+}
+
+#[derive(Deserialize)]
+struct TrackSuggestParams {
+    track: String,
+    #[serde(default = "default_suggestion_count")]
+    n: usize,
+    #[serde(default = "default_pitch_range")]
+    pitch_range: f64,
+}
+
+fn default_pitch_range() -> f64 {
+    DEFAULT_PITCH_RANGE
+}
+
+#[derive(Deserialize)]
+struct PlanParams {
+    /// Names of the tracks to plan the set from, the whole library if omitted.
+    tracks: Option<Vec<String>>,
+    first: Option<String>,
+    last: Option<String>,
+    minutes: Option<f64>,
+    #[serde(default = "default_set_name")]
+    name: String,
+}
+
+fn default_set_name() -> String {
+    "camelol".to_string()
+}
+
+/// A library track to mix into, scored like [`best_matches`](crate::best_matches).
+#[derive(Serialize)]
+struct TrackSuggestion {
+    track: ProjectTrack,
+    compatibility: f64,
+    pitch: Option<f64>,
+    score: f64,
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|error| (INVALID_PARAMS, error.to_string()))
+}
+
+fn invalid_params(error: Error) -> (i64, String) {
+    match error {
+        Error::SearchStopped => (SEARCH_STOPPED, error.to_string()),
+        error => (INVALID_PARAMS, error.to_string()),
+    }
+}
+
+/// Where the track named `name` is in `library`.
+fn library_track(library: &TrackLibrary, name: &str) -> Result<usize, (i64, String)> {
+    library.position(name).ok_or_else(|| {
+        (
+            INVALID_PARAMS,
+            format!("no track named `{name}` in the library"),
+        )
+    })
+}
+
+/// The `n` library tracks `name` mixes into best, best first.
+fn suggest_tracks(
+    engine: &Engine,
+    library: &TrackLibrary,
+    TrackSuggestParams {
+        track,
+        n,
+        pitch_range,
+    }: TrackSuggestParams,
+) -> Result<Vec<TrackSuggestion>, (i64, String)> {
+    let from = library_track(library, &track)?;
+    let tracks = library.tracks();
+    let mut matches: Vec<_> = tracks
+        .iter()
+        .enumerate()
+        .filter(|&(to, _)| to != from)
+        .filter_map(|(to, other)| {
+            track_match(&tracks[from], other, to, &engine.distances, pitch_range)
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(matches
+        .into_iter()
+        .take(n)
+        .map(|found| TrackSuggestion {
+            track: ProjectTrack::new(&tracks[found.to]),
+            compatibility: found.compatibility,
+            pitch: found.pitch,
+            score: found.score,
+        })
+        .collect())
+}
+
+/// The set planned from the library tracks named in `params`, or all of them.
+fn plan(
+    engine: &Engine,
+    library: &TrackLibrary,
+    params: PlanParams,
+) -> Result<SetProject, (i64, String)> {
+    // Positions in the library of the tracks to plan from.
+    let positions = match &params.tracks {
+        Some(names) => names
+            .iter()
+            .map(|name| library_track(library, name))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => (0..library.tracks().len()).collect(),
+    };
+    let tracks: Vec<Track> = positions
+        .iter()
+        .map(|&position| library.tracks()[position].clone())
+        .collect();
+    let pinned = |name: &Option<String>| {
+        name.as_deref()
+            .map(|name| {
+                let track = library_track(library, name)?;
+                positions
+                    .iter()
+                    .position(|&position| position == track)
+                    .ok_or_else(|| (INVALID_PARAMS, format!("`{name}` isn't among the tracks")))
+            })
+            .transpose()
+    };
+    let options = PlanOptions {
+        first: pinned(&params.first)?,
+        last: pinned(&params.last)?,
+        length: params.minutes.map(|minutes| minutes * 60.0),
+        ..PlanOptions::default()
+    };
+    if options.first.is_some() && options.first == options.last {
+        return Err((
+            INVALID_PARAMS,
+            "the set can't open and close with the same track".to_string(),
+        ));
+    }
+    let set = SetPlanner::new(&engine.distances).plan(&tracks, &options);
+    Ok(SetProject::new(&params.name, &set, &engine.distances))
+}
+
+fn call(
+    engine: &Engine,
+    library: &TrackLibrary,
+    method: &str,
+    raw: Value,
+) -> Result<Value, (i64, String)> {
+    let result = match method {
+        "paths" => {
+            let PathsParams { from, to, n } = params(raw)?;
+            json!(engine.paths(from, to, n).map_err(invalid_params)?)
+        }
+        "neighbors" => {
+            let KeyParams { key } = params(raw)?;
+            json!(engine.neighbors(key).map_err(invalid_params)?)
+        }
+        "predecessors" => {
+            let KeyParams { key } = params(raw)?;
+            json!(engine.predecessors(key).map_err(invalid_params)?)
+        }
+        "reach" => {
+            let ReachParams { from, steps } = params(raw)?;
+            json!(engine.reachable(from, steps).map_err(invalid_params)?)
+        }
+        "bridges" => {
+            let BridgesParams { from, to, steps } = params(raw)?;
+            json!(engine.bridges(from, to, steps).map_err(invalid_params)?)
+        }
+        "distance" => {
+            let DistanceParams { from, to } = params(raw)?;
+            json!(engine.distance(from, to))
+        }
+        "mashup" => {
+            let DistanceParams { from, to } = params(raw)?;
+            json!(engine.mashup(from, to))
+        }
+        "suggest" => {
+            let SuggestParams { from, n } = params(raw)?;
+            json!(engine.suggest(from, n))
+        }
+        "tracks" => json!(library
+            .tracks()
+            .iter()
+            .map(ProjectTrack::new)
+            .collect::<Vec<_>>()),
+        "suggest_tracks" => json!(suggest_tracks(engine, library, params(raw)?)?),
+        "plan" => json!(plan(engine, library, params(raw)?)?),
+        _ => {
+            return Err((METHOD_NOT_FOUND, format!("unknown method `{method}`")));
+        }
+    };
+    Ok(result)
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answers a single JSON-RPC 2.0 request. The supported methods are `paths`, `neighbors`,
+/// `predecessors`, `reach`, `bridges`, `distance`, `mashup` and `suggest`, taking the same
+/// parameters as the matching commands, and the queries of `library`: `tracks`, listing it,
+/// `suggest_tracks`, ranking the tracks to mix into from a `track` by name, and `plan`,
+/// ordering the named `tracks`, or all of them, into a set described like `plan --json`.
+pub fn handle_request(engine: &Engine, library: &TrackLibrary, request: &str) -> Value {
+    let request = match serde_json::from_str::<Value>(request) {
+        Ok(request) => request,
+        Err(error) => return error_response(Value::Null, PARSE_ERROR, error.to_string()),
+    };
+    let request = match serde_json::from_value::<Request>(request) {
+        Ok(request) => request,
+        Err(error) => return error_response(Value::Null, INVALID_REQUEST, error.to_string()),
+    };
+
+    match call(engine, library, &request.method, request.params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err((code, message)) => error_response(request.id, code, message),
+    }
+}
+
+/// Answers the newline-delimited JSON-RPC requests of `input` one by one, writing each
+/// response to `output` as a line of its own as soon as it's ready, until `input` ends. Blank
+/// lines are skipped.
+pub fn answer_lines(
+    engine: &Engine,
+    library: &TrackLibrary,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(output, "{}", handle_request(engine, library, &line))?;
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! The interface a custom rule set implements to decide the wheel's transitions and what paths
+//! pay for them, so rule sets like a [`RuleScript`](crate::RuleScript) can be swapped in
+//! without forking the crate.
+
+use crate::scale::Scale;
+use crate::transition::{Interval, ScaleTransition};
+use crate::wheel::Wheel;
+use camelol_core::WHEEL_SIZE;
+
+/// Most a rule set charges for a step, so `--prefer` weights can scale it and still fit the
+/// `u32` step costs of a path search.
+pub(crate) const MAX_STEP_COST: u32 = 10_000;
+
+pub trait TransitionRules {
+    /// Whether the wheel keeps `transition` from `from` to `to`.
+    fn allows(&self, from: Scale, transition: ScaleTransition, to: Scale) -> bool;
+
+    /// What a path pays for the step from `from` to `to`.
+    fn cost(&self, from: Scale, transition: ScaleTransition, to: Scale) -> u32;
+}
+
+/// Whether the step from `from` to `to` moves the tonic by a semitone or a tritone, the root
+/// movements that clash the most, whichever transition takes it.
+pub fn is_clash(from: Scale, to: Scale) -> bool {
+    matches!(Interval::between(from, to).movement(), 1 | 6)
+}
+
+/// The built-in rule sets, from the safest transitions to all the wheel knows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preset {
+    /// One step around the wheel and the relative major or minor.
+    Strict,
+    /// The strict moves and the parallel major or minor.
+    Classic,
+    /// Every transition, including jumps of two and seven steps, diagonal moves and the major
+    /// key on the minor key's dominant.
+    #[default]
+    Extended,
+}
+
+impl Preset {
+    /// The standard wheel with the preset's transitions.
+    pub fn wheel(self) -> Wheel {
+        Wheel::with_rules(WHEEL_SIZE, &self)
+    }
+}
+
+impl TransitionRules for Preset {
+    fn allows(&self, _from: Scale, transition: ScaleTransition, _to: Scale) -> bool {
+        matches!(
+            (self, transition),
+            (
+                _,
+                ScaleTransition::Vertical | ScaleTransition::ChangeIndex(1 | -1)
+            ) | (Preset::Classic, ScaleTransition::MajorToMinor)
+                | (Preset::Extended, _)
+        )
+    }
+
+    fn cost(&self, _from: Scale, _transition: ScaleTransition, _to: Scale) -> u32 {
+        1
+    }
+}
@@ -0,0 +1,60 @@
+use crate::error::{closest, Error, Result};
+use camelol_core::WHEEL_SIZE;
+pub use camelol_core::{
+    note_name, scale, Accidental, KeyName, Locale, ModalKey, Mode, OpenKey, ParseScaleError, Scale,
+    ScaleKind, WheelNumbering,
+};
+
+/// Every scale on the wheel, ordered by [`Scale::position`].
+pub fn make_nodes() -> Vec<Scale> {
+    make_nodes_on(WHEEL_SIZE)
+}
+
+/// Every scale on a wheel with `size` positions, ordered by [`Scale::position`].
+pub fn make_nodes_on(size: usize) -> Vec<Scale> {
+    (0..size * 2)
+        .filter_map(|position| Scale::from_position_on(position, size))
+        .collect()
+}
+
+/// Parses a camelot key like `8A`, keeping the rejected input in the error.
+pub fn parse_key(input: &str) -> Result<Scale> {
+    input
+        .trim()
+        .parse()
+        .map_err(|_| invalid_key(input, Locale::English))
+}
+
+/// An [`Error::InvalidKey`] suggesting the camelot codes and key names in `locale` that
+/// `input` is closest to, like 8A and 8B for `8C` or A minor for `A mnor`.
+pub(crate) fn invalid_key(input: &str, locale: Locale) -> Error {
+    // Input with a number is a camelot code, anything else a key name.
+    let camelot = input.contains(|c: char| c.is_ascii_digit());
+    let mut candidates = Vec::new();
+    for scale in make_nodes() {
+        if camelot {
+            candidates.push((scale.to_string(), scale.to_string()));
+            continue;
+        }
+        for accidental in [Accidental::Flat, Accidental::Sharp] {
+            let name = scale
+                .key_name_with(accidental)
+                .in_locale(locale)
+                .to_string();
+            if locale == Locale::English {
+                // The short forms, `Am` and `A`.
+                let note = note_name(scale.tonic(), accidental);
+                let short = match scale.kind {
+                    ScaleKind::Minor => format!("{note}m"),
+                    ScaleKind::Major => note.to_string(),
+                };
+                candidates.push((short, name.clone()));
+            }
+            candidates.push((name.clone(), name));
+        }
+    }
+    Error::InvalidKey {
+        input: input.to_string(),
+        suggestions: closest(input.trim(), candidates),
+    }
+}
@@ -0,0 +1,353 @@
+//! Transition rules written in a small script, so harmonic rules can be tried out without
+//! touching Rust.
+//!
+//! A script sets `valid`, which transitions the wheel keeps, and `cost`, what a step costs a
+//! path, one rule per line. Both are optional, and `#` starts a comment:
+//!
+//! ```text
+//! # no tritones, and changing between minor and major costs extra
+//! valid = movement != 6
+//! cost = 1 + (from_minor != to_minor) * 2
+//! ```
+//!
+//! Rules are integer expressions where 0 is false and anything else is true. They can use
+//! `from` and `to`, the wheel numbers of the keys, `from_minor` and `to_minor`, which are 1 for
+//! A keys, `semitones`, the interval between their tonics from 0 to 11, `movement`, how far the
+//! tonic moves the shorter way round, `change`, the positions a `ChangeIndex` transition moves
+//! and 0 for the others, and `vertical`, `diagonal`, `major_to_minor` and `flat_to_minor`, which
+//! are 1 for that transition. From loosest to tightest binding the operators are `c ? a : b`,
+//! `||`, `&&`, `==` and `!=`, `<`, `<=`, `>` and `>=`, `+` and `-`, `*`, `/` and `%`, then
+//! unary `-` and `!`. Dividing by zero gives 0, negative costs count as 0 and costs over
+//! 10000 as 10000. A rule nests parentheses, unary operators and choices at most 50 deep, in
+//! at most 1000 tokens.
+
+use crate::error::{closest, did_you_mean, Error, Result};
+use crate::rules::{Preset, TransitionRules, MAX_STEP_COST};
+use crate::scale::{Scale, ScaleKind};
+use crate::transition::{Interval, ScaleTransition};
+use crate::wheel::Wheel;
+use camelol_core::WHEEL_SIZE;
+
+const VARIABLES: [&str; 11] = [
+    "from",
+    "to",
+    "from_minor",
+    "to_minor",
+    "semitones",
+    "movement",
+    "change",
+    "vertical",
+    "diagonal",
+    "major_to_minor",
+    "flat_to_minor",
+];
+
+/// How deep a rule may nest parentheses, unary operators and choices, since both parsing and
+/// evaluating recurse once per level.
+const MAX_NESTING: usize = 50;
+/// Most tokens in a rule, which bounds how deep chains of binary operators get.
+const MAX_TOKENS: usize = 1000;
+
+/// Operators of each binary precedence level, loosest first.
+const BINARY_LEVELS: [&[&str]; 6] = [
+    &["||"],
+    &["&&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+/// Longer symbols first, so `<=` isn't read as `<` followed by `=`.
+const SYMBOLS: [&str; 18] = [
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "(", ")", "?", ":",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(i64),
+    Variable(&'static str),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Choice(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(next) = rest.chars().next() {
+        let length = if next.is_ascii_digit() {
+            let length = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let number = rest[..length]
+                .parse()
+                .map_err(|_| format!("number `{}` is too large", &rest[..length]))?;
+            tokens.push(Token::Number(number));
+            length
+        } else if next.is_ascii_alphabetic() || next == '_' {
+            let length = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..length].to_string()));
+            length
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .ok_or_else(|| format!("unexpected `{next}`"))?;
+            tokens.push(Token::Symbol(symbol));
+            symbol.len()
+        };
+        rest = rest[length..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Parentheses, unary operators and choices being parsed, one inside the other.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek_symbol(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Symbol(symbol)) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        if self.peek_symbol() == Some(symbol) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{symbol}`"))
+        }
+    }
+
+    /// Parses with `parse` one level deeper, up to [`MAX_NESTING`].
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        if self.depth >= MAX_NESTING {
+            return Err(format!("nested more than {MAX_NESTING} deep"));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn choice(&mut self) -> Result<Expr, String> {
+        let condition = self.binary(0)?;
+        if self.peek_symbol() != Some("?") {
+            return Ok(condition);
+        }
+        self.position += 1;
+        let then = self.nested(Self::choice)?;
+        self.expect(":")?;
+        let otherwise = self.nested(Self::choice)?;
+        Ok(Expr::Choice(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        let Some(operators) = BINARY_LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        while let Some(operator) = self
+            .peek_symbol()
+            .filter(|symbol| operators.contains(symbol))
+        {
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::Name(name)) => VARIABLES
+                .iter()
+                .find(|variable| **variable == name)
+                .map(|variable| Expr::Variable(variable))
+                .ok_or_else(|| format!("unknown variable `{name}`{}", suggest(&name, &VARIABLES))),
+            Some(Token::Symbol(operator @ ("-" | "!"))) => {
+                Ok(Expr::Unary(operator, Box::new(self.nested(Self::unary)?)))
+            }
+            Some(Token::Symbol("(")) => {
+                let inner = self.nested(Self::choice)?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(Token::Symbol(symbol)) => Err(format!("unexpected `{symbol}`")),
+            None => Err("expression ends early".to_string()),
+        }
+    }
+}
+
+const RULES: [&str; 2] = ["valid", "cost"];
+
+/// `, did you mean `movement`?` for the `names` closest to a misspelled `name`.
+fn suggest(name: &str, names: &[&str]) -> String {
+    let candidates = names
+        .iter()
+        .map(|candidate| (candidate.to_string(), format!("`{candidate}`")));
+    did_you_mean(&closest(name, candidates))
+}
+
+fn parse_expr(input: &str) -> Result<Expr, String> {
+    let tokens = tokenize(input)?;
+    if tokens.len() > MAX_TOKENS {
+        return Err(format!("longer than {MAX_TOKENS} tokens"));
+    }
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        depth: 0,
+    };
+    let expr = parser.choice()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some(_) => Err("unexpected text after the expression".to_string()),
+    }
+}
+
+fn variable(name: &str, from: Scale, transition: ScaleTransition, to: Scale) -> i64 {
+    let interval = Interval::between(from, to);
+    match name {
+        "from" => from.index as i64 + 1,
+        "to" => to.index as i64 + 1,
+        "from_minor" => (from.kind == ScaleKind::Minor) as i64,
+        "to_minor" => (to.kind == ScaleKind::Minor) as i64,
+        "semitones" => interval.semitones as i64,
+        "movement" => interval.movement() as i64,
+        "change" => match transition {
+            ScaleTransition::ChangeIndex(amount) => amount as i64,
+            _ => 0,
+        },
+        "vertical" => (transition == ScaleTransition::Vertical) as i64,
+        "diagonal" => (transition == ScaleTransition::Diagonal) as i64,
+        "major_to_minor" => (transition == ScaleTransition::MajorToMinor) as i64,
+        "flat_to_minor" => (transition == ScaleTransition::FlatToMinor) as i64,
+        _ => 0,
+    }
+}
+
+impl Expr {
+    fn eval(&self, from: Scale, transition: ScaleTransition, to: Scale) -> i64 {
+        let eval = |expr: &Expr| expr.eval(from, transition, to);
+        match self {
+            Expr::Number(number) => *number,
+            Expr::Variable(name) => variable(name, from, transition, to),
+            Expr::Unary("-", inner) => eval(inner).wrapping_neg(),
+            Expr::Unary(_, inner) => (eval(inner) == 0) as i64,
+            Expr::Choice(condition, then, otherwise) => match eval(condition) {
+                0 => eval(otherwise),
+                _ => eval(then),
+            },
+            Expr::Binary("||", left, right) => (eval(left) != 0 || eval(right) != 0) as i64,
+            Expr::Binary("&&", left, right) => (eval(left) != 0 && eval(right) != 0) as i64,
+            Expr::Binary(operator, left, right) => {
+                let (left, right) = (eval(left), eval(right));
+                match *operator {
+                    "==" => (left == right) as i64,
+                    "!=" => (left != right) as i64,
+                    "<" => (left < right) as i64,
+                    "<=" => (left <= right) as i64,
+                    ">" => (left > right) as i64,
+                    ">=" => (left >= right) as i64,
+                    "+" => left.wrapping_add(right),
+                    "-" => left.wrapping_sub(right),
+                    "*" => left.wrapping_mul(right),
+                    "/" => left.checked_div(right).unwrap_or(0),
+                    _ => left.checked_rem(right).unwrap_or(0),
+                }
+            }
+        }
+    }
+}
+
+/// The `valid` and `cost` rules of a script, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct RuleScript {
+    valid: Option<Expr>,
+    cost: Option<Expr>,
+}
+
+impl RuleScript {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut script = Self::default();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| Error::InvalidScript {
+                line: number + 1,
+                message,
+            };
+            let (name, expr) = line
+                .split_once('=')
+                .filter(|(_, expr)| !expr.starts_with('='))
+                .ok_or_else(|| error("expected `valid = ...` or `cost = ...`".to_string()))?;
+            let rule = match name.trim() {
+                "valid" => &mut script.valid,
+                "cost" => &mut script.cost,
+                name => {
+                    let message = format!("unknown rule `{name}`{}", suggest(name, &RULES));
+                    return Err(error(message));
+                }
+            };
+            if rule.is_some() {
+                return Err(error(format!("`{}` is set twice", name.trim())));
+            }
+            *rule = Some(parse_expr(expr).map_err(error)?);
+        }
+        Ok(script)
+    }
+
+    /// The standard wheel with only the transitions both `preset` and the script allow.
+    pub fn wheel(&self, preset: Preset) -> Wheel {
+        Wheel::with_transitions_where(WHEEL_SIZE, |from, transition, to| {
+            preset.allows(from, transition, to) && self.allows(from, transition, to)
+        })
+    }
+}
+
+impl TransitionRules for RuleScript {
+    /// Whether the `valid` rule keeps the transition, true without one.
+    fn allows(&self, from: Scale, transition: ScaleTransition, to: Scale) -> bool {
+        self.valid
+            .as_ref()
+            .is_none_or(|valid| valid.eval(from, transition, to) != 0)
+    }
+
+    /// What the `cost` rule charges for the step, 1 without one so paths are ranked by their
+    /// number of transitions.
+    fn cost(&self, from: Scale, transition: ScaleTransition, to: Scale) -> u32 {
+        self.cost.as_ref().map_or(1, |cost| {
+            cost.eval(from, transition, to)
+                .clamp(0, i64::from(MAX_STEP_COST)) as u32
+        })
+    }
+}
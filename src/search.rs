@@ -0,0 +1,480 @@
+use crate::error::{Error, Result};
+use crate::render::{paint_scale, ColorSupport, Notation};
+use crate::scale::Scale;
+use crate::transition::{Interval, ScaleTransition};
+use itertools::Itertools;
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use petgraph::Graph;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::iter;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// When a path search gives up, so a caller like the server can keep one query from blocking
+/// forever, and how far its paths may go.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimit {
+    /// Stop once this moment passes.
+    pub deadline: Option<Instant>,
+    /// Stop once this is set, from any thread.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Take at most this many energy boosts, `ChangeIndex(1)` or `ChangeIndex(2)`, in a row,
+    /// since several back to back wear out a dancefloor even when they're the shortest way.
+    pub max_boost_run: Option<usize>,
+}
+
+impl SearchLimit {
+    /// A limit of `duration` from now, or none for a duration too long to tell the moment.
+    pub fn within(duration: Duration) -> Self {
+        Self {
+            deadline: Instant::now().checked_add(duration),
+            ..Self::default()
+        }
+    }
+
+    /// Whether `path` may go on with `transition` under [`max_boost_run`](Self::max_boost_run).
+    fn allows(&self, path: &Step, transition: ScaleTransition) -> bool {
+        let boost = |transition| matches!(transition, ScaleTransition::ChangeIndex(1 | 2));
+        self.max_boost_run.is_none_or(|max_run| {
+            !boost(transition)
+                || path
+                    .back()
+                    .take_while(|step| step.transition.is_some_and(boost))
+                    .count()
+                    < max_run
+        })
+    }
+
+    pub(crate) fn reached(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(AtomicOrdering::Relaxed))
+    }
+}
+
+/// Built-in measures for [`multi_path_dijkstra`] to minimize along a path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathCost {
+    /// The number of transitions.
+    #[default]
+    Hops,
+    /// The total movement of the tonic, see [`Interval::movement`], for basslines that move as
+    /// little as possible. Fewer transitions win between paths that move as far.
+    Semitones,
+}
+
+impl PathCost {
+    /// The cost of taking `transition` from `from` to `to`.
+    pub fn step(self, from: Scale, _transition: ScaleTransition, to: Scale) -> u32 {
+        match self {
+            PathCost::Hops => 1,
+            PathCost::Semitones => Interval::between(from, to).movement() as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub cost: u64,
+    pub node: Scale,
+    pub transition: Option<ScaleTransition>,
+    pub path: Vec<Scale>,
+    pub transition_path: Vec<ScaleTransition>,
+}
+
+impl Path {
+    pub fn scales(&self) -> &[Scale] {
+        &self.path
+    }
+
+    /// Every step of the path as `(from, transition, to)`.
+    pub fn steps(&self) -> Vec<(Scale, ScaleTransition, Scale)> {
+        self.path
+            .iter()
+            .tuple_windows()
+            .zip(&self.transition_path)
+            .map(|((from, to), transition)| (*from, *transition, *to))
+            .collect()
+    }
+
+    /// The interval between the tonics of every step, see [`Interval::between`].
+    pub fn intervals(&self) -> Vec<Interval> {
+        self.path
+            .iter()
+            .tuple_windows()
+            .map(|(from, to)| Interval::between(*from, *to))
+            .collect()
+    }
+
+    fn key_sequence(&self) -> impl Iterator<Item = usize> + '_ {
+        self.path
+            .iter()
+            .chain(iter::once(&self.node))
+            .map(|scale| scale.position())
+    }
+}
+
+impl Eq for Path {}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Path) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+/// Paths are ordered by cost first, then by their number of transitions. Otherwise equal paths
+/// are ordered lexicographically by their key sequence (wheel number, then A before B), so
+/// results come back in a stable order.
+///
+/// The comparison is reversed so that `BinaryHeap` pops the smallest path first.
+impl Ord for Path {
+    fn cmp(&self, other: &Path) -> Ordering {
+        other
+            .cost
+            .cmp(&self.cost)
+            .then_with(|| other.path.len().cmp(&self.path.len()))
+            .then_with(|| other.key_sequence().cmp(self.key_sequence()))
+    }
+}
+
+impl PartialOrd for Path {
+    fn partial_cmp(&self, other: &Path) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The last key of a path on the search frontier, linked back to the step before it so paths
+/// share their common beginning instead of each copying it.
+struct Step {
+    scale: Scale,
+    transition: Option<ScaleTransition>,
+    previous: Option<Rc<Step>>,
+}
+
+impl Step {
+    /// This step and every one before it, last first.
+    fn back(&self) -> impl Iterator<Item = &Step> {
+        iter::successors(Some(self), |step| step.previous.as_deref())
+    }
+
+    fn path(&self, cost: u64) -> Path {
+        let mut path = self.back().map(|step| step.scale).collect::<Vec<_>>();
+        let mut transition_path = self
+            .back()
+            .filter_map(|step| step.transition)
+            .collect::<Vec<_>>();
+        path.reverse();
+        transition_path.reverse();
+        Path {
+            cost,
+            node: self.scale,
+            transition: self.transition,
+            path,
+            transition_path,
+        }
+    }
+}
+
+/// Compares the key sequences of two paths of the same length like [`Path`] does, walking back
+/// only until they meet at a shared beginning.
+fn compare_keys(a: &Rc<Step>, b: &Rc<Step>) -> Ordering {
+    let mut order = Ordering::Equal;
+    let (mut a, mut b) = (Some(a), Some(b));
+    while let (Some(step_a), Some(step_b)) = (a, b) {
+        if Rc::ptr_eq(step_a, step_b) {
+            break;
+        }
+        // The difference closest to the start decides, so keep overwriting.
+        order = step_a
+            .scale
+            .position()
+            .cmp(&step_b.scale.position())
+            .then(order);
+        a = step_a.previous.as_ref();
+        b = step_b.previous.as_ref();
+    }
+    order
+}
+
+/// A path on the search frontier, together with the graph node it currently ends at and a
+/// lower bound on the cost of any path to the target that extends it.
+struct Candidate {
+    step: Rc<Step>,
+    node: NodeIndex<u32>,
+    cost: u64,
+    length: usize,
+    estimate: u64,
+}
+
+impl Eq for Candidate {}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+/// Reversed like [`Path`], so the lowest estimate pops first and ties are broken the way
+/// paths are ordered.
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| other.cost.cmp(&self.cost))
+            .then_with(|| other.length.cmp(&self.length))
+            .then_with(|| compare_keys(&other.step, &self.step))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Transitions needed from every node to the nearest of `targets`, walking the edges backwards.
+/// `None` marks nodes that can't reach any.
+fn transitions_to(
+    graph: &Graph<Scale, ScaleTransition>,
+    targets: &[NodeIndex<u32>],
+) -> Vec<Option<u64>> {
+    let mut remaining = vec![None; graph.node_count()];
+    let mut queue = VecDeque::new();
+    for target in targets {
+        remaining[target.index()] = Some(0);
+        queue.push_back(*target);
+    }
+
+    while let Some(node) = queue.pop_front() {
+        let left = remaining[node.index()].unwrap_or(0);
+        for previous in graph.neighbors_directed(node, Direction::Incoming) {
+            if remaining[previous.index()].is_none() {
+                remaining[previous.index()] = Some(left + 1);
+                queue.push_back(previous);
+            }
+        }
+    }
+
+    remaining
+}
+
+/// The `n` cheapest paths from `source` to `target`, where `cost` prices every step as
+/// `(from, transition, to)`, like [`PathCost::step`]. A step that costs nothing never leads
+/// back to a key already on the path, so free cycles can't stall the search.
+///
+/// The search is an A* search: the transitions still needed to reach the target times the
+/// cheapest step can't overestimate the remaining cost, so paths heading for the target are
+/// explored first and keys that can't reach it are never expanded. It stops with
+/// [`Error::SearchStopped`] once `limit` is reached.
+pub fn multi_path_dijkstra(
+    graph: &Graph<Scale, ScaleTransition>,
+    source: NodeIndex<u32>,
+    target: NodeIndex<u32>,
+    n: usize,
+    cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+    limit: &SearchLimit,
+) -> Result<Vec<Path>> {
+    multi_path_search(graph, &[source], &[target], n, cost, limit)
+}
+
+/// Like [`multi_path_dijkstra`], for the `n` cheapest paths from any of `sources` to any of
+/// `targets`. A key in both sets is a path of its own, with no transitions.
+pub fn multi_path_search(
+    graph: &Graph<Scale, ScaleTransition>,
+    sources: &[NodeIndex<u32>],
+    targets: &[NodeIndex<u32>],
+    n: usize,
+    cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+    limit: &SearchLimit,
+) -> Result<Vec<Path>> {
+    let mut min_heap = BinaryHeap::new();
+    let mut paths = Vec::new();
+
+    let node_count = graph.node_count();
+    let targets = targets
+        .iter()
+        .copied()
+        .filter(|target| target.index() < node_count)
+        .collect::<Vec<_>>();
+    let remaining = transitions_to(graph, &targets);
+    let cheapest = graph
+        .edge_references()
+        .filter_map(|edge| {
+            Some(cost(
+                *graph.node_weight(edge.source())?,
+                *edge.weight(),
+                *graph.node_weight(edge.target())?,
+            ))
+        })
+        .min()
+        .map_or(0, u64::from);
+
+    for source in sources.iter().copied().unique() {
+        let Some(&scale) = graph.node_weight(source) else {
+            continue;
+        };
+        let Some(start) = remaining[source.index()] else {
+            continue;
+        };
+        min_heap.push(Candidate {
+            step: Rc::new(Step {
+                scale,
+                transition: None,
+                previous: None,
+            }),
+            node: source,
+            cost: 0,
+            length: 1,
+            // Saturating only lowers the estimate, so it stays a lower bound.
+            estimate: start.saturating_mul(cheapest),
+        });
+    }
+
+    let mut expanded = 0;
+    while let Some(candidate) = min_heap.pop() {
+        if limit.reached() {
+            tracing::debug!(
+                expanded,
+                found = paths.len(),
+                "path search stopped by its limit"
+            );
+            return Err(Error::SearchStopped);
+        }
+        expanded += 1;
+
+        if targets.contains(&candidate.node) {
+            paths.push(candidate.step.path(candidate.cost));
+            if paths.len() >= n {
+                break;
+            }
+        }
+
+        for edge in graph.edges(candidate.node) {
+            let Some(&neighbor) = graph.node_weight(edge.target()) else {
+                continue;
+            };
+            let Some(left) = remaining[edge.target().index()] else {
+                continue;
+            };
+            if !limit.allows(&candidate.step, *edge.weight()) {
+                continue;
+            }
+            let step = cost(candidate.step.scale, *edge.weight(), neighbor);
+            if step == 0 && candidate.step.back().any(|step| step.scale == neighbor) {
+                continue;
+            }
+            let path_cost = candidate.cost.saturating_add(u64::from(step));
+            min_heap.push(Candidate {
+                step: Rc::new(Step {
+                    scale: neighbor,
+                    transition: Some(*edge.weight()),
+                    previous: Some(Rc::clone(&candidate.step)),
+                }),
+                node: edge.target(),
+                cost: path_cost,
+                length: candidate.length + 1,
+                estimate: path_cost.saturating_add(left.saturating_mul(cheapest)),
+            });
+        }
+    }
+
+    tracing::trace!(
+        sources = sources.len(),
+        targets = targets.len(),
+        expanded,
+        found = paths.len(),
+        "searched paths"
+    );
+    Ok(paths)
+}
+
+/// Renders a path as `12A -> ChangeIndex(1) (perfect fifth) -> 1A -> Vertical (minor third) -> 1B`.
+pub fn format_path(path: &Path) -> String {
+    format_path_colored(path, Notation::default(), ColorSupport::None)
+}
+
+/// Like [`format_path`], with keys written in `notation` and painted in their wheel colors.
+pub fn format_path_colored(path: &Path, notation: Notation, colors: ColorSupport) -> String {
+    let transitions = path
+        .transition_path
+        .iter()
+        .zip(path.intervals())
+        .map(Some)
+        .chain(iter::repeat(None));
+
+    path.path
+        .iter()
+        .map(|scale| paint_scale(*scale, notation, colors))
+        .zip(transitions)
+        .flat_map(|(scale, transition)| match transition {
+            Some((transition, interval)) => vec![scale, format!("{transition:?} ({interval})")],
+            None => vec![scale],
+        })
+        .join(" -> ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wheel::Wheel;
+
+    /// The keys of every path, like `["12A", "1A", "1B"]`.
+    fn keys(paths: &[Path]) -> Vec<Vec<String>> {
+        paths
+            .iter()
+            .map(|path| path.path.iter().map(Scale::to_string).collect())
+            .collect()
+    }
+
+    fn paths(from: &str, to: &str, n: usize) -> Vec<Path> {
+        let wheel = Wheel::new();
+        let key = |key| wheel.parse_key(key).unwrap();
+        wheel.paths(key(from), key(to), n).unwrap()
+    }
+
+    #[test]
+    fn orders_equal_cost_paths_the_same_every_time() {
+        let first = paths("12A", "1B", 20);
+        for _ in 0..10 {
+            assert_eq!(keys(&paths("12A", "1B", 20)), keys(&first));
+        }
+        // Ties in cost and length are broken by the key sequence.
+        let ranks: Vec<_> = first
+            .iter()
+            .map(|path| {
+                let sequence: Vec<_> = path.path.iter().map(|key| key.position()).collect();
+                (path.cost, path.path.len(), sequence)
+            })
+            .collect();
+        assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]), "{ranks:?}");
+    }
+
+    #[test]
+    fn finds_every_two_step_path_from_12a_to_1b() {
+        let found = paths("12A", "1B", 9);
+        assert_eq!(
+            keys(&found[..8]),
+            [
+                ["12A", "1A", "1B"],
+                ["12A", "2A", "1B"],
+                ["12A", "3B", "1B"],
+                ["12A", "5A", "1B"],
+                ["12A", "8B", "1B"],
+                ["12A", "10A", "1B"],
+                ["12A", "11B", "1B"],
+                ["12A", "12B", "1B"],
+            ]
+        );
+        assert!(found[..8].iter().all(|path| path.cost == 2));
+        assert_eq!(found[8].cost, 3);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::library::{absolute_path, Track};
+use std::path::{Component, Path};
+
+/// The version Serato writes at the start of every crate.
+const CRATE_VERSION: &str = "1.0/Serato ScratchLive Crate";
+/// Columns the crate shows, so the keys and tempos the set was planned by are on screen.
+const COLUMNS: [&str; 4] = ["song", "artist", "bpm", "key"];
+
+/// `text` in the big-endian UTF-16 Serato stores strings in.
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+/// A field of a crate: its four-letter tag, the length of its value and the value.
+fn field(tag: &[u8; 4], value: &[u8]) -> Vec<u8> {
+    let length = u32::try_from(value.len()).expect("crate fields are small");
+    let mut field = tag.to_vec();
+    field.extend(length.to_be_bytes());
+    field.extend(value);
+    field
+}
+
+/// The path as Serato refers to tracks, relative to the root of the drive they're on and with
+/// forward slashes, like `Users/dj/Music/one.mp3`. Relative paths are taken from the current
+/// directory.
+fn crate_path(path: &Path) -> String {
+    absolute_path(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Writes the tracks as a Serato crate, in order, which shows up in Serato's crates pane when
+/// saved as `_Serato_/Subcrates/<name>.crate` on the drive the tracks are on. Serato finds
+/// tracks by file, so tracks without a path are left out.
+pub fn serato_crate(tracks: &[Track]) -> Vec<u8> {
+    let mut bytes = field(b"vrsn", &utf16(CRATE_VERSION));
+    for column in COLUMNS {
+        let mut value = field(b"tvcn", &utf16(column));
+        value.extend(field(b"tvcw", &utf16("0")));
+        bytes.extend(field(b"ovct", &value));
+    }
+    for path in tracks.iter().filter_map(|track| track.path.as_deref()) {
+        bytes.extend(field(b"otrk", &field(b"ptrk", &utf16(&crate_path(path)))));
+    }
+    bytes
+}
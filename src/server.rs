@@ -0,0 +1,251 @@
+use crate::chord::Mashup;
+use crate::color::{wheel_color, Rgb};
+#[cfg(feature = "midi")]
+use crate::control::KeyOutput;
+use crate::engine::{
+    default_path_count, default_suggestion_count, Engine, Neighbor, PathSummary,
+    DEFAULT_SEARCH_TIME,
+};
+use crate::error::Error;
+use crate::osc::OscOutput;
+use crate::overlay::OverlayOutput;
+use crate::scale::Scale;
+use crate::session::Session;
+use crate::suggest::Suggestion;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::broadcast;
+
+const LIVE_SUGGESTION_COUNT: usize = 5;
+
+struct AppState {
+    engine: Engine,
+    /// Everything reported as now playing over `/live`, shared by all connected clients.
+    live: Mutex<Session>,
+    updates: broadcast::Sender<String>,
+    outputs: Mutex<LiveOutputs>,
+}
+
+/// Where the `/live` session's current key and suggestions are sent besides the websocket.
+#[derive(Default)]
+pub struct LiveOutputs {
+    /// Hardware following the current and suggested key.
+    #[cfg(feature = "midi")]
+    pub midi: Option<KeyOutput>,
+    pub osc: Option<OscOutput>,
+    /// A text file or named pipe for stream overlays.
+    pub overlay: Option<OverlayOutput>,
+}
+
+impl LiveOutputs {
+    fn update(&mut self, now_playing: Scale, suggestions: &[Suggestion]) -> io::Result<()> {
+        #[cfg(feature = "midi")]
+        if let Some(midi) = &mut self.midi {
+            let suggested = suggestions.first().map(|suggestion| suggestion.scale);
+            midi.update(now_playing, suggested)?;
+        }
+        if let Some(osc) = &self.osc {
+            osc.update(now_playing, suggestions)?;
+        }
+        if let Some(overlay) = &self.overlay {
+            overlay.update(now_playing, suggestions)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct PathsQuery {
+    from: Scale,
+    to: Scale,
+    #[serde(default = "default_path_count")]
+    n: usize,
+}
+
+#[derive(Deserialize)]
+struct MashupQuery {
+    from: Scale,
+    to: Scale,
+}
+
+#[derive(Deserialize)]
+struct SuggestQuery {
+    from: Scale,
+    #[serde(default = "default_suggestion_count")]
+    n: usize,
+}
+
+#[derive(Serialize)]
+struct ErrorMessage {
+    error: String,
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Error::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::SearchStopped => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let error = ErrorMessage {
+            error: self.to_string(),
+        };
+        (status, Json(error)).into_response()
+    }
+}
+
+async fn paths(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PathsQuery>,
+) -> Result<Json<Vec<PathSummary>>, Error> {
+    Ok(Json(state.engine.paths(query.from, query.to, query.n)?))
+}
+
+async fn neighbors(
+    State(state): State<Arc<AppState>>,
+    UrlPath(key): UrlPath<Scale>,
+) -> Result<Json<Vec<Neighbor>>, Error> {
+    Ok(Json(state.engine.neighbors(key)?))
+}
+
+async fn predecessors(
+    State(state): State<Arc<AppState>>,
+    UrlPath(key): UrlPath<Scale>,
+) -> Result<Json<Vec<Neighbor>>, Error> {
+    Ok(Json(state.engine.predecessors(key)?))
+}
+
+async fn mashup(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MashupQuery>,
+) -> Json<Mashup> {
+    Json(state.engine.mashup(query.from, query.to))
+}
+
+async fn suggestions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SuggestQuery>,
+) -> Json<Vec<Suggestion>> {
+    Json(state.engine.suggest(query.from, query.n))
+}
+
+#[derive(Deserialize)]
+struct LiveRequest {
+    now_playing: Scale,
+}
+
+#[derive(Serialize)]
+struct LiveUpdate {
+    now_playing: Scale,
+    now_playing_color: Rgb,
+    suggestions: Vec<Suggestion>,
+}
+
+async fn live(State(state): State<Arc<AppState>>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| live_socket(state, socket))
+}
+
+/// Handles one `/live` client. Every `{"now_playing": "8A"}` message it sends is recorded in the
+/// shared session, and the suggestions that follow are pushed to all connected clients.
+async fn live_socket(state: Arc<AppState>, mut socket: WebSocket) {
+    let mut updates = state.updates.subscribe();
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                let Some(Ok(message)) = message else {
+                    return;
+                };
+                let Message::Text(text) = message else {
+                    continue;
+                };
+
+                match serde_json::from_str::<LiveRequest>(&text) {
+                    Ok(request) => {
+                        let update = {
+                            // A panicked client can't leave the session half-written, so keep
+                            // serving it.
+                            let mut session =
+                                state.live.lock().unwrap_or_else(PoisonError::into_inner);
+                            session.record(request.now_playing);
+                            let mut suggestions =
+                                session.suggest(&state.engine.distances, None, 0.0);
+                            suggestions.truncate(LIVE_SUGGESTION_COUNT);
+                            let mut outputs =
+                                state.outputs.lock().unwrap_or_else(PoisonError::into_inner);
+                            if let Err(error) = outputs.update(request.now_playing, &suggestions)
+                            {
+                                tracing::warn!(%error, "live output failed");
+                            }
+                            LiveUpdate {
+                                now_playing: request.now_playing,
+                                now_playing_color: wheel_color(request.now_playing),
+                                suggestions,
+                            }
+                        };
+                        if let Ok(update) = serde_json::to_string(&update) {
+                            let _ = state.updates.send(update);
+                        }
+                    }
+                    Err(error) => {
+                        let error = ErrorMessage { error: error.to_string() };
+                        let Ok(error) = serde_json::to_string(&error) else {
+                            continue;
+                        };
+                        if socket.send(Message::Text(error.into())).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+            update = updates.recv() => {
+                let update = match update {
+                    Ok(update) => update,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                if socket.send(Message::Text(update.into())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// The server's routes. Path queries search for at most the engine's
+/// [`search_time`](Engine::search_time), or [`DEFAULT_SEARCH_TIME`] without one.
+pub fn router(engine: Engine) -> Router {
+    router_with_outputs(engine, LiveOutputs::default())
+}
+
+/// Like [`router`], but also sends the `/live` session's keys to `outputs`.
+pub fn router_with_outputs(mut engine: Engine, outputs: LiveOutputs) -> Router {
+    engine.search_time.get_or_insert(DEFAULT_SEARCH_TIME);
+    Router::new()
+        .route("/paths", get(paths))
+        .route("/neighbors/{key}", get(neighbors))
+        .route("/predecessors/{key}", get(predecessors))
+        .route("/mashup", get(mashup))
+        .route("/suggest", get(suggestions))
+        .route("/live", get(live))
+        .with_state(Arc::new(AppState {
+            engine,
+            live: Mutex::new(Session::default()),
+            updates: broadcast::channel(16).0,
+            outputs: Mutex::new(outputs),
+        }))
+}
+
+/// Serves `router` on `addr` until the process is stopped.
+pub async fn serve(router: Router, addr: SocketAddr) -> io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router).await
+}
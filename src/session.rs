@@ -0,0 +1,171 @@
+use crate::distance::DistanceMatrix;
+use crate::markov::MarkovModel;
+use crate::scale::Scale;
+use crate::suggest::{suggest, Suggestion};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How strongly suggestions are pulled toward keys far away from everything played so far.
+const EXPLORATION_WEIGHT: f64 = 0.25;
+
+/// Share of its score a suggestion loses for going back to the key played just before the
+/// current one. Keys played further back lose less.
+const RECENT_PENALTY: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Play {
+    pub scale: Scale,
+    /// Seconds since the unix epoch.
+    pub time: u64,
+}
+
+impl Display for Play {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.time, self.scale)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSessionError(String);
+
+impl Display for ParseSessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid session entry `{}`", self.0)
+    }
+}
+
+impl Error for ParseSessionError {}
+
+impl FromStr for Play {
+    type Err = ParseSessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseSessionError(s.to_string());
+        let (time, scale) = s.trim().split_once(' ').ok_or_else(error)?;
+        Ok(Play {
+            scale: scale.trim().parse().map_err(|_| error())?,
+            time: time.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+/// The tracks played so far tonight, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub plays: Vec<Play>,
+}
+
+impl Session {
+    /// Parses a session log with one `<unix time> <key>` entry per line.
+    pub fn parse(log: &str) -> Result<Self, ParseSessionError> {
+        let plays = log
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { plays })
+    }
+
+    /// Records `scale` as played right now and returns the new entry.
+    pub fn record(&mut self, scale: Scale) -> Play {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let play = Play { scale, time };
+        self.plays.push(play);
+        play
+    }
+
+    pub fn current(&self) -> Option<Scale> {
+        self.plays.last().map(|play| play.scale)
+    }
+
+    /// How many of the last plays in a row were in the current key.
+    pub fn current_run(&self) -> usize {
+        let Some(current) = self.current() else {
+            return 0;
+        };
+        self.plays
+            .iter()
+            .rev()
+            .take_while(|play| play.scale == current)
+            .count()
+    }
+
+    /// Drops the current key from `suggestions` once it has been played `max_run` times in a
+    /// row, so the set doesn't get stuck on one key.
+    pub fn limit_key_run(&self, suggestions: &mut Vec<Suggestion>, max_run: usize) {
+        if self.current_run() >= max_run {
+            suggestions.retain(|suggestion| Some(suggestion.scale) != self.current());
+        }
+    }
+
+    /// Lowers the scores of keys played in the last `window` tracks before the current one,
+    /// most for the latest and less for each track further back, so the set moves on instead
+    /// of going back and forth between two keys.
+    pub fn avoid_recent(&self, suggestions: &mut [Suggestion], window: usize) {
+        let recent = self.plays.iter().rev().skip(1).take(window);
+        for (age, play) in recent.enumerate() {
+            let penalty = RECENT_PENALTY * (window - age) as f64 / window as f64;
+            for suggestion in suggestions.iter_mut() {
+                if suggestion.scale == play.scale && Some(play.scale) != self.current() {
+                    suggestion.score *= 1.0 - penalty;
+                }
+            }
+        }
+        rank(suggestions);
+    }
+
+    pub fn times_played(&self, scale: Scale) -> usize {
+        self.plays.iter().filter(|play| play.scale == scale).count()
+    }
+
+    /// Suggestions following the last played key, adjusted for the rest of the session.
+    ///
+    /// Each key's score is divided by one plus the number of times it was already played, and
+    /// keys far away from every played key get a bonus so the set drifts into unexplored parts
+    /// of the wheel.
+    pub fn suggest(
+        &self,
+        distances: &DistanceMatrix,
+        model: Option<&MarkovModel>,
+        blend: f64,
+    ) -> Vec<Suggestion> {
+        let Some(current) = self.current() else {
+            return vec![];
+        };
+
+        let novelty = |scale: Scale| {
+            self.plays
+                .iter()
+                .filter_map(|play| distances.distance(play.scale, scale))
+                .min()
+                .unwrap_or(0)
+        };
+        let max_novelty = distances.diameter().max(1) as f64;
+
+        let mut suggestions = suggest(distances, current, model, blend)
+            .into_iter()
+            .map(|suggestion| Suggestion {
+                score: suggestion.score / (1 + self.times_played(suggestion.scale)) as f64
+                    + EXPLORATION_WEIGHT * novelty(suggestion.scale) as f64 / max_novelty,
+                ..suggestion
+            })
+            .collect::<Vec<_>>();
+        rank(&mut suggestions);
+        suggestions
+    }
+}
+
+/// Sorts suggestions best first.
+fn rank(suggestions: &mut [Suggestion]) {
+    suggestions.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.scale.position().cmp(&b.scale.position()))
+    });
+}
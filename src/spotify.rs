@@ -0,0 +1,156 @@
+use crate::api::get_json;
+use crate::error::Result;
+use crate::library::Track;
+use crate::scale::{Scale, ScaleKind};
+use serde::Deserialize;
+
+const API_URL: &str = "https://api.spotify.com/v1";
+/// Most tracks the audio features endpoint takes at once.
+const FEATURES_BATCH: usize = 100;
+
+#[derive(Deserialize)]
+struct PlaylistPage {
+    items: Vec<PlaylistItem>,
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PlaylistItem {
+    /// Missing for tracks removed from Spotify.
+    track: Option<PlaylistTrack>,
+}
+
+#[derive(Deserialize)]
+struct PlaylistTrack {
+    /// Missing for local files.
+    id: Option<String>,
+    name: String,
+    artists: Vec<Artist>,
+    duration_ms: u64,
+}
+
+#[derive(Deserialize)]
+struct Artist {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct AudioFeaturesPage {
+    audio_features: Vec<Option<AudioFeatures>>,
+}
+
+#[derive(Deserialize)]
+struct AudioFeatures {
+    key: i32,
+    mode: i32,
+    tempo: f64,
+    /// From 0 to 1.
+    energy: f64,
+}
+
+/// Converts Spotify's key, a pitch class from C up or -1 when unknown, and mode, 1 for major
+/// and 0 for minor, to a key on the wheel.
+pub fn spotify_key(key: i32, mode: i32) -> Option<Scale> {
+    let kind = match mode {
+        1 => ScaleKind::Major,
+        0 => ScaleKind::Minor,
+        _ => return None,
+    };
+    (0..12)
+        .contains(&key)
+        .then(|| Scale::from_tonic(key as usize, kind))
+}
+
+/// The playlist id in a playlist link, `spotify:playlist:` URI or bare id.
+pub fn playlist_id(playlist: &str) -> &str {
+    let playlist = playlist.trim();
+    let id = playlist
+        .strip_prefix("spotify:playlist:")
+        .or_else(|| playlist.split_once("/playlist/").map(|(_, id)| id))
+        .unwrap_or(playlist);
+    id.split(['?', '/']).next().unwrap_or(id)
+}
+
+/// Reads playlists and their tracks' audio features from the Spotify Web API.
+pub struct SpotifyClient {
+    token: String,
+    api: String,
+}
+
+impl SpotifyClient {
+    /// A client authorized with an OAuth access `token`.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self::with_api_url(token, API_URL)
+    }
+
+    /// A client for the API at `api`, like a caching proxy.
+    pub fn with_api_url(token: impl Into<String>, api: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            api: api.into(),
+        }
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        get_json("Spotify", url, Some(&self.token))
+    }
+
+    /// Every track of `playlist`, a link, URI or id, with the key and tempo Spotify
+    /// measured for it. Local files and tracks without a detected key are left out.
+    pub fn playlist_tracks(&self, playlist: &str) -> Result<Vec<Track>> {
+        let mut tracks = vec![];
+        let mut next = Some(format!(
+            concat!(
+                "{}/playlists/{}/tracks?limit=100",
+                "&fields=next,items(track(id,name,artists(name),duration_ms))"
+            ),
+            self.api,
+            playlist_id(playlist)
+        ));
+        while let Some(url) = next {
+            let page: PlaylistPage = self.get(&url)?;
+            tracks.extend(page.items.into_iter().filter_map(|item| item.track));
+            next = page.next;
+        }
+        let tracks: Vec<(String, PlaylistTrack)> = tracks
+            .into_iter()
+            .filter_map(|track| Some((track.id.clone()?, track)))
+            .collect();
+
+        let mut library = vec![];
+        for batch in tracks.chunks(FEATURES_BATCH) {
+            let ids: Vec<&str> = batch.iter().map(|(id, _)| id.as_str()).collect();
+            let features: AudioFeaturesPage = self.get(&format!(
+                "{}/audio-features?ids={}",
+                self.api,
+                ids.join(",")
+            ))?;
+            for ((_, track), features) in batch.iter().zip(features.audio_features) {
+                let Some(features) = features else {
+                    continue;
+                };
+                let Some(key) = spotify_key(features.key, features.mode) else {
+                    continue;
+                };
+                let artists: Vec<&str> = track
+                    .artists
+                    .iter()
+                    .map(|artist| artist.name.as_str())
+                    .collect();
+                library.push(Track {
+                    tempo: (features.tempo > 0.0).then_some(features.tempo),
+                    duration: Some(track.duration_ms as f64 / 1000.0),
+                    // Spotify rates energy from 0 to 1 rather than in levels.
+                    energy: Some((features.energy * 10.0).round().clamp(1.0, 10.0) as u8),
+                    ..Track::new(format!("{} - {}", artists.join(", "), track.name), key)
+                });
+            }
+        }
+        tracing::info!(
+            tracks = library.len(),
+            without_key = tracks.len() - library.len(),
+            "read the Spotify playlist"
+        );
+        Ok(library)
+    }
+}
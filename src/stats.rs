@@ -0,0 +1,292 @@
+use crate::distance::DistanceMatrix;
+use crate::library::Track;
+use crate::scale::Scale;
+use crate::transition::ScaleTransition;
+use crate::wheel::Wheel;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+
+/// How well connected the wheel's transitions make it, to sanity-check custom rules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphStats {
+    pub keys: usize,
+    pub transitions: usize,
+    /// The longest shortest path between any two connected keys.
+    pub diameter: usize,
+    /// The mean shortest path length over all pairs of distinct, connected keys.
+    pub average_distance: f64,
+    /// How many keys have each number of outgoing transitions.
+    pub degrees: BTreeMap<usize, usize>,
+    /// Every `(from, to)` pair where `to` can't be reached from `from`.
+    pub unreachable: Vec<(Scale, Scale)>,
+}
+
+impl GraphStats {
+    pub fn new(wheel: &Wheel) -> Self {
+        let distances = DistanceMatrix::new(wheel);
+        let mut degrees = BTreeMap::new();
+        for scale in wheel.scales() {
+            let degree = wheel
+                .neighbors(scale)
+                .map_or(0, |neighbors| neighbors.len());
+            *degrees.entry(degree).or_insert(0) += 1;
+        }
+
+        let mut unreachable = Vec::new();
+        let (mut total, mut pairs) = (0, 0);
+        for from in wheel.scales() {
+            for to in wheel.scales().filter(|to| *to != from) {
+                match distances.distance(from, to) {
+                    Some(distance) => {
+                        total += distance;
+                        pairs += 1;
+                    }
+                    None => unreachable.push((from, to)),
+                }
+            }
+        }
+
+        Self {
+            keys: wheel.scales().count(),
+            transitions: wheel.transitions().count(),
+            diameter: distances.diameter(),
+            average_distance: if pairs == 0 {
+                0.0
+            } else {
+                total as f64 / pairs as f64
+            },
+            degrees,
+            unreachable,
+        }
+    }
+}
+
+/// How many tracks of a library are in a key, and how many are close enough to mix into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyCoverage {
+    pub key: Scale,
+    /// Tracks whose main key it is.
+    pub tracks: usize,
+    /// Tracks in this key or one transition from it, which can follow a track in it.
+    pub nearby: usize,
+    /// Whether the key has fewer than half the tracks of the average key, so it's worth
+    /// looking for more.
+    pub gap: bool,
+}
+
+/// The tracks of a library in every key of the wheel, in wheel order.
+pub fn key_coverage(wheel: &Wheel, tracks: &[Track]) -> Vec<KeyCoverage> {
+    let distances = DistanceMatrix::new(wheel);
+    let scales = wheel
+        .scales()
+        .sorted_by_key(|scale| scale.position())
+        .collect::<Vec<_>>();
+    let counts = scales
+        .iter()
+        .map(|&scale| tracks.iter().filter(|track| track.key == scale).count())
+        .collect::<Vec<_>>();
+    let average = tracks.len() as f64 / scales.len().max(1) as f64;
+    scales
+        .iter()
+        .zip(&counts)
+        .map(|(&key, &count)| KeyCoverage {
+            key,
+            tracks: count,
+            nearby: scales
+                .iter()
+                .zip(&counts)
+                .filter(|(&other, _)| {
+                    distances
+                        .distance(key, other)
+                        .is_some_and(|distance| distance <= 1)
+                })
+                .map(|(_, &count)| count)
+                .sum(),
+            gap: (count as f64) < average / 2.0,
+        })
+        .collect()
+}
+
+/// How often each of the wheel's transitions is taken across every shortest path between every
+/// pair of distinct keys, in the order the wheel adds them. Transitions no shortest path needs
+/// are listed with 0.
+pub fn transition_usage(wheel: &Wheel) -> Vec<(ScaleTransition, usize)> {
+    let distances = DistanceMatrix::new(wheel);
+    let scales = wheel.scales().collect::<Vec<_>>();
+    let edges = wheel.transitions().collect::<Vec<_>>();
+    let size = scales
+        .iter()
+        .map(|scale| scale.position() + 1)
+        .max()
+        .unwrap_or(0);
+
+    // Number of shortest paths between every pair, filled in by increasing distance.
+    let mut counts = vec![0usize; size * size];
+    for from in &scales {
+        let row = from.position() * size;
+        counts[row + from.position()] = 1;
+        let by_distance = scales
+            .iter()
+            .filter_map(|to| Some((distances.distance(*from, *to)?, *to)))
+            .sorted_by_key(|(distance, _)| *distance);
+        for (distance, to) in by_distance.filter(|(distance, _)| *distance > 0) {
+            counts[row + to.position()] = edges
+                .iter()
+                .filter(|(_, _, target)| *target == to)
+                .filter(|(source, _, _)| distances.distance(*from, *source) == Some(distance - 1))
+                .map(|(source, _, _)| counts[row + source.position()])
+                .sum();
+        }
+    }
+
+    let mut usage = edges
+        .iter()
+        .map(|(_, transition, _)| (*transition, 0))
+        .unique_by(|(transition, _)| *transition)
+        .collect::<Vec<_>>();
+    for (from, to) in scales.iter().cartesian_product(&scales) {
+        let Some(total) = distances.distance(*from, *to).filter(|total| *total > 0) else {
+            continue;
+        };
+        for (source, transition, target) in &edges {
+            let (Some(before), Some(after)) = (
+                distances.distance(*from, *source),
+                distances.distance(*target, *to),
+            ) else {
+                continue;
+            };
+            if before + 1 + after != total {
+                continue;
+            }
+            let paths = counts[from.position() * size + source.position()]
+                * counts[target.position() * size + to.position()];
+            if let Some((_, count)) = usage.iter_mut().find(|(used, _)| used == transition) {
+                *count += paths;
+            }
+        }
+    }
+    usage
+}
+
+/// How a key was played across the sets of a [`MixingReport`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyHabit {
+    pub key: Scale,
+    /// Tracks played in the key.
+    pub plays: usize,
+    /// Mixes out of a track in the key.
+    pub mixes_out: usize,
+    /// Of those, the mixes into another track in the same key.
+    pub stays: usize,
+}
+
+/// How someone actually mixes, from the keys of the sets they played, for feedback on their
+/// habits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixingReport {
+    pub sets: usize,
+    /// Mixes from one track into the next.
+    pub mixes: usize,
+    /// How often each transition was the move between the keys of a mix, most used first, for
+    /// the mixes between keys one transition apart.
+    pub transitions: Vec<(ScaleTransition, usize)>,
+    /// Mixes that stayed in the same key.
+    pub same_key: usize,
+    /// Mixes between keys more than one transition apart.
+    pub jumps: usize,
+    /// Mixes between keys the wheel doesn't connect at all.
+    pub unconnected: usize,
+    /// The mean number of transitions between the keys of a mix, over the connected mixes.
+    pub average_distance: f64,
+    /// Every key played, most played first.
+    pub keys: Vec<KeyHabit>,
+    /// The keys of the wheel never played, in wheel order.
+    pub unplayed: Vec<Scale>,
+}
+
+impl MixingReport {
+    /// The keys every mix out of stayed in the same key, most played first.
+    pub fn never_left(&self) -> impl Iterator<Item = Scale> + '_ {
+        self.keys
+            .iter()
+            .filter(|habit| habit.mixes_out > 0 && habit.stays == habit.mixes_out)
+            .map(|habit| habit.key)
+    }
+}
+
+/// Reports on the mixes of `sets`, each the keys of one set in the order they were played, as
+/// the transitions of `wheel` would take them.
+pub fn mixing_report<S: AsRef<[Scale]>>(wheel: &Wheel, sets: &[S]) -> MixingReport {
+    let distances = DistanceMatrix::new(wheel);
+    let mut transitions: Vec<(ScaleTransition, usize)> = vec![];
+    let mut keys: BTreeMap<usize, KeyHabit> = BTreeMap::new();
+    let (mut mixes, mut same_key, mut jumps, mut unconnected, mut total) = (0, 0, 0, 0, 0);
+
+    for set in sets {
+        let set = set.as_ref();
+        for &key in set {
+            keys.entry(key.position())
+                .or_insert(KeyHabit {
+                    key,
+                    plays: 0,
+                    mixes_out: 0,
+                    stays: 0,
+                })
+                .plays += 1;
+        }
+        for pair in set.windows(2) {
+            let [from, to] = [pair[0], pair[1]];
+            mixes += 1;
+            if let Some(habit) = keys.get_mut(&from.position()) {
+                habit.mixes_out += 1;
+                habit.stays += usize::from(from == to);
+            }
+            let Some(distance) = distances.distance(from, to) else {
+                unconnected += 1;
+                continue;
+            };
+            total += distance;
+            match distance {
+                0 => same_key += 1,
+                1 => {
+                    let neighbors = wheel.neighbors(from).unwrap_or_default();
+                    let Some(&(transition, _)) = neighbors.iter().find(|(_, key)| *key == to)
+                    else {
+                        continue;
+                    };
+                    match transitions.iter_mut().find(|(used, _)| *used == transition) {
+                        Some((_, count)) => *count += 1,
+                        None => transitions.push((transition, 1)),
+                    }
+                }
+                _ => jumps += 1,
+            }
+        }
+    }
+
+    // Ties stay in the order they were first taken.
+    transitions.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let connected = mixes - unconnected;
+    let mut keys: Vec<KeyHabit> = keys.into_values().collect();
+    keys.sort_by_key(|habit| std::cmp::Reverse(habit.plays));
+    let unplayed = wheel
+        .scales()
+        .filter(|scale| keys.iter().all(|habit| habit.key != *scale))
+        .sorted_by_key(|scale| scale.position())
+        .collect();
+    MixingReport {
+        sets: sets.len(),
+        mixes,
+        transitions,
+        same_key,
+        jumps,
+        unconnected,
+        average_distance: if connected == 0 {
+            0.0
+        } else {
+            total as f64 / connected as f64
+        },
+        keys,
+        unplayed,
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(feature = "audio")]
+use crate::audio::{chroma, decode_audio, AUDIO_EXTENSIONS};
+use crate::chord::layering_score;
+#[cfg(feature = "audio")]
+use crate::detect::detect_key;
+use crate::detect::KeyEstimate;
+use crate::error::Result;
+#[cfg(feature = "midi")]
+use crate::midi::detect_midi_key;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Stems whose pitches correlate less than this with their best key have no key to speak of.
+const MIN_STEM_CORRELATION: f64 = 0.5;
+/// Audio stems need their strongest pitch class to stand out this much over the average to
+/// have a key, which drums and noise don't.
+#[cfg(feature = "audio")]
+const MIN_CHROMA_PEAK: f64 = 1.5;
+
+/// The key of one stem of a track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StemKey {
+    pub path: PathBuf,
+    /// `None` for stems without a clear key.
+    pub estimate: Option<KeyEstimate>,
+}
+
+/// How well a stem of one track layers over a stem of another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StemMatch<'a> {
+    pub a: &'a StemKey,
+    pub b: &'a StemKey,
+    pub score: f64,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Whether `path` is a stem that can be analyzed with the enabled features.
+fn is_stem(path: &Path) -> bool {
+    let extension = extension(path);
+    #[cfg(feature = "audio")]
+    if AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+        return true;
+    }
+    cfg!(feature = "midi") && matches!(extension.as_str(), "mid" | "midi")
+}
+
+/// Estimates the key of a stem from its audio or, for MIDI files, its notes. Returns `None` if
+/// the stem has no clear key.
+pub fn analyze_stem(path: &Path) -> Result<Option<KeyEstimate>> {
+    let estimate = match extension(path).as_str() {
+        #[cfg(feature = "midi")]
+        "mid" | "midi" => detect_midi_key(&fs::read(path)?)?,
+        #[cfg(feature = "audio")]
+        _ => {
+            let chroma = chroma(&decode_audio(path)?);
+            let mean = chroma.iter().sum::<f64>() / 12.0;
+            let peak = chroma.iter().copied().fold(0.0, f64::max);
+            // Noisy stems spread evenly over every pitch class, however well the little
+            // unevenness left happens to correlate with a key.
+            (peak >= MIN_CHROMA_PEAK * mean)
+                .then(|| detect_key(&chroma))
+                .flatten()
+        }
+        #[cfg(not(feature = "audio"))]
+        _ => None,
+    };
+    Ok(estimate.filter(|estimate| estimate.correlation >= MIN_STEM_CORRELATION))
+}
+
+/// Estimates the key of every stem of a track, given the folder its stems were separated
+/// into, like `separated/htdemucs/<track>`, or a single stem file.
+pub fn analyze_stems(path: &Path) -> Result<Vec<StemKey>> {
+    let mut files = vec![];
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.is_file() && is_stem(&file) {
+                files.push(file);
+            }
+        }
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+    files
+        .into_iter()
+        .map(|path| {
+            Ok(StemKey {
+                estimate: analyze_stem(&path)?,
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Every pairing of a stem of `a` with a stem of `b` that both have a key, best first, scored
+/// by how well they layer.
+pub fn match_stems<'a>(a: &'a [StemKey], b: &'a [StemKey]) -> Vec<StemMatch<'a>> {
+    let mut matches: Vec<StemMatch> = a
+        .iter()
+        .flat_map(|a| b.iter().map(move |b| (a, b)))
+        .filter_map(|(a, b)| {
+            let (key_a, key_b) = (a.estimate?.key, b.estimate?.key);
+            Some(StemMatch {
+                a,
+                b,
+                score: layering_score(key_a, key_b),
+            })
+        })
+        .collect();
+    matches.sort_by(|x, y| y.score.total_cmp(&x.score));
+    matches
+}
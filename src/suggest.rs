@@ -0,0 +1,138 @@
+use crate::color::{wheel_color, Rgb};
+use crate::distance::DistanceMatrix;
+use crate::markov::MarkovModel;
+use crate::scale::{ModalKey, Mode, Scale};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Suggestion {
+    #[serde(rename = "key")]
+    pub scale: Scale,
+    pub color: Rgb,
+    pub distance: Option<usize>,
+    pub score: f64,
+}
+
+/// How well two keys mix given their distance on the wheel: 1 for the same key, halving and so
+/// on with every transition in between, and 0 if they aren't connected at all.
+pub fn compatibility(distance: Option<usize>) -> f64 {
+    distance.map_or(0.0, |distance| 1.0 / (1.0 + distance as f64))
+}
+
+/// How much of a key's compatibility is lost when it is played in `mode` rather than the plain
+/// major or minor key of its wheel position. Modes further from major and minor sound less
+/// like the key they are mapped onto.
+pub fn mode_penalty(mode: Mode) -> f64 {
+    match mode {
+        Mode::Ionian | Mode::Aeolian => 0.0,
+        Mode::Dorian | Mode::Mixolydian => 0.1,
+        Mode::Lydian | Mode::Phrygian => 0.2,
+        Mode::Locrian => 0.4,
+    }
+}
+
+/// [`compatibility`] of the wheel keys nearest to `from` and `to`, reduced by the
+/// [`mode_penalty`] of both. Identical modal keys are always fully compatible.
+pub fn modal_compatibility(distances: &DistanceMatrix, from: ModalKey, to: ModalKey) -> f64 {
+    if from == to {
+        return 1.0;
+    }
+    compatibility(distances.distance(from.scale(), to.scale()))
+        * (1.0 - mode_penalty(from.mode))
+        * (1.0 - mode_penalty(to.mode))
+}
+
+/// The tempos a DJ is currently playing in, in beats per minute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl TempoRange {
+    /// The tempos a pitch fader going `percent` either way reaches from `tempo`.
+    pub fn around(tempo: f64, percent: f64) -> Self {
+        Self {
+            min: tempo * (1.0 - percent / 100.0),
+            max: tempo * (1.0 + percent / 100.0),
+        }
+    }
+
+    /// `tempo` as it would be mixed into the range: as is, at double time or at half time,
+    /// whichever falls in it first. `None` if none does.
+    pub fn matching_tempo(&self, tempo: f64) -> Option<f64> {
+        [tempo, tempo * 2.0, tempo / 2.0]
+            .into_iter()
+            .find(|tempo| (self.min..=self.max).contains(tempo))
+    }
+}
+
+/// Parses `MIN-MAX`, like `122-128`.
+impl FromStr for TempoRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tempos = s.split_once('-').and_then(|(min, max)| {
+            let parse = |tempo: &str| {
+                tempo
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|tempo| tempo.is_finite() && *tempo > 0.0)
+            };
+            Some((parse(min)?, parse(max)?))
+        });
+        match tempos {
+            Some((min, max)) if min <= max => Ok(Self { min, max }),
+            _ => Err(format!("expected a tempo range like `122-128`, got `{s}`")),
+        }
+    }
+}
+
+/// `score` for a track whose key is known with `confidence`, scaled by it so that a perfect
+/// match on a key flagged for review ranks below a near match on a key that's certain. Keys
+/// without a confidence count as certain.
+pub fn weigh_confidence(score: f64, confidence: Option<f64>) -> f64 {
+    score * confidence.unwrap_or(1.0)
+}
+
+/// Ranks every key as a follow-up to `current`, best first.
+///
+/// Keys score `1 / (1 + distance)` on the wheel. With a model, that score is blended with the
+/// learned transition probability, `blend` being the share given to the model.
+pub fn suggest(
+    distances: &DistanceMatrix,
+    current: Scale,
+    model: Option<&MarkovModel>,
+    blend: f64,
+) -> Vec<Suggestion> {
+    let mut suggestions = distances
+        .scales()
+        .map(|scale| {
+            let distance = distances.distance(current, scale);
+            let graph_score = compatibility(distance);
+            let score = match model {
+                Some(model) => {
+                    (1.0 - blend) * graph_score + blend * model.probability(current, scale)
+                }
+                None => graph_score,
+            };
+            Suggestion {
+                scale,
+                color: wheel_color(scale),
+                distance,
+                score,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.scale.position().cmp(&b.scale.position()))
+    });
+    suggestions
+}
@@ -0,0 +1,216 @@
+use crate::error::Result;
+use crate::scale::Scale;
+use crate::search::{Path, SearchLimit};
+use crate::transition::ScaleTransition;
+use crate::wheel::Wheel;
+use itertools::Itertools;
+
+/// Most keys a tour visits in the cheapest order there is. Trying every order of more takes
+/// too long, so their order is built up greedily and then improved instead.
+const MAX_EXACT_STOPS: usize = 12;
+
+/// The cheapest path between every pair of `keys` under `cost`, `None` where there's none.
+fn legs(
+    wheel: &Wheel,
+    keys: &[Scale],
+    cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+    limit: &SearchLimit,
+) -> Result<Vec<Vec<Option<Path>>>> {
+    keys.iter()
+        .map(|&from| {
+            keys.iter()
+                .map(|&to| Ok(wheel.paths_within(from, to, 1, &cost, limit)?.pop()))
+                .collect()
+        })
+        .collect()
+}
+
+/// What visiting the keys in `order` costs, after starting at key 0 if `fixed_start`, or
+/// `None` if some of them can't be reached from the one before.
+fn order_cost(order: &[usize], legs: &[Vec<Option<Path>>], fixed_start: bool) -> Option<u64> {
+    let start = fixed_start.then_some(0);
+    start
+        .into_iter()
+        .chain(order.iter().copied())
+        .tuple_windows()
+        .map(|(from, to)| legs[from][to].as_ref().map(|path| path.cost))
+        .sum()
+}
+
+/// The cheapest order of `stops` by trying all of them at once, keeping for every set of
+/// stops visited and the stop ending it the cheapest way there.
+fn exact_order(
+    stops: &[usize],
+    legs: &[Vec<Option<Path>>],
+    fixed_start: bool,
+) -> Option<Vec<usize>> {
+    let count = stops.len();
+    let full = (1 << count) - 1;
+    // The cost of the cheapest way to visit a set of stops ending at one, and the stop before.
+    let mut best = vec![vec![None; count]; 1 << count];
+    for (last, &stop) in stops.iter().enumerate() {
+        let cost = if fixed_start {
+            legs[0][stop].as_ref().map(|path| path.cost)
+        } else {
+            Some(0)
+        };
+        best[1 << last][last] = cost.map(|cost| (cost, None));
+    }
+    for visited in 1..=full {
+        for last in 0..count {
+            let Some((cost, _)) = best[visited][last] else {
+                continue;
+            };
+            for next in (0..count).filter(|next| visited & (1 << next) == 0) {
+                let Some(leg) = &legs[stops[last]][stops[next]] else {
+                    continue;
+                };
+                let entry = &mut best[visited | (1 << next)][next];
+                if entry.is_none_or(|(known, _)| cost + leg.cost < known) {
+                    *entry = Some((cost + leg.cost, Some(last)));
+                }
+            }
+        }
+    }
+
+    let mut last = (0..count)
+        .filter_map(|last| Some((best[full][last]?.0, last)))
+        .min()?
+        .1;
+    let mut visited = full;
+    let mut order = vec![stops[last]];
+    while let Some((_, Some(previous))) = best[visited][last] {
+        visited &= !(1 << last);
+        last = previous;
+        order.push(stops[last]);
+    }
+    order.reverse();
+    Some(order)
+}
+
+/// A cheap order of `stops`: every stop goes next to the cheapest one left, and then stretches
+/// of the order are turned around for as long as that makes it cheaper. Without a start, every
+/// stop is tried as the first.
+fn greedy_order(
+    stops: &[usize],
+    legs: &[Vec<Option<Path>>],
+    fixed_start: bool,
+) -> Option<Vec<usize>> {
+    let leg_cost = |from: usize, to: usize| legs[from][to].as_ref().map(|path| path.cost);
+    let firsts: Vec<Option<usize>> = if fixed_start {
+        vec![None]
+    } else {
+        stops.iter().copied().map(Some).collect()
+    };
+    let mut best: Option<(u64, Vec<usize>)> = None;
+    for first in firsts {
+        let mut order: Vec<usize> = first.into_iter().collect();
+        let mut left: Vec<usize> = stops
+            .iter()
+            .copied()
+            .filter(|&stop| Some(stop) != first)
+            .collect();
+        while !left.is_empty() {
+            let from = order.last().copied().unwrap_or(0);
+            let Some(next) = (0..left.len())
+                .filter_map(|index| Some((leg_cost(from, left[index])?, index)))
+                .min()
+            else {
+                break;
+            };
+            order.push(left.remove(next.1));
+        }
+        if !left.is_empty() {
+            continue;
+        }
+
+        let Some(mut cost) = order_cost(&order, legs, fixed_start) else {
+            continue;
+        };
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for (start, end) in (0..order.len()).tuple_combinations() {
+                order[start..=end].reverse();
+                match order_cost(&order, legs, fixed_start) {
+                    Some(turned) if turned < cost => {
+                        cost = turned;
+                        improved = true;
+                    }
+                    _ => order[start..=end].reverse(),
+                }
+            }
+        }
+        if best.as_ref().is_none_or(|(known, _)| cost < *known) {
+            best = Some((cost, order));
+        }
+    }
+    best.map(|(_, order)| order)
+}
+
+/// The cheapest path under `cost` found that visits every one of `keys` in any order, like
+/// the keys of tracks promised to be played, starting at `start` or else at whichever of the
+/// keys makes it cheapest. Up to [`MAX_EXACT_STOPS`] keys the path is the cheapest there is,
+/// and beyond that a cheap one. Returns `None` if no path visits them all, and stops with
+/// [`Error::SearchStopped`](crate::Error::SearchStopped) once `limit` is reached.
+pub fn key_tour(
+    wheel: &Wheel,
+    start: Option<Scale>,
+    keys: &[Scale],
+    cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+    limit: &SearchLimit,
+) -> Result<Option<Path>> {
+    let mut nodes: Vec<Scale> = start
+        .into_iter()
+        .chain(keys.iter().copied())
+        .unique()
+        .collect();
+    for &key in &nodes {
+        wheel.node(key)?;
+    }
+    let fixed_start = start.is_some();
+    if nodes.len() == 1 {
+        let key = nodes.remove(0);
+        return Ok(wheel.paths_within(key, key, 1, &cost, limit)?.pop());
+    }
+    let stops: Vec<usize> = (usize::from(fixed_start)..nodes.len()).collect();
+    if stops.is_empty() {
+        return Ok(None);
+    }
+
+    let legs = legs(wheel, &nodes, &cost, limit)?;
+    let order = if stops.len() <= MAX_EXACT_STOPS {
+        exact_order(&stops, &legs, fixed_start)
+    } else {
+        greedy_order(&stops, &legs, fixed_start)
+    };
+    let Some(order) = order else {
+        return Ok(None);
+    };
+    tracing::debug!(keys = nodes.len(), "found a key tour");
+
+    let mut ordered = fixed_start.then_some(0).into_iter().chain(order);
+    let Some(first) = ordered.next() else {
+        return Ok(None);
+    };
+    let mut tour = Path {
+        cost: 0,
+        node: nodes[first],
+        transition: None,
+        path: vec![nodes[first]],
+        transition_path: vec![],
+    };
+    let mut from = first;
+    for to in ordered {
+        let Some(leg) = &legs[from][to] else {
+            return Ok(None);
+        };
+        tour.cost += leg.cost;
+        tour.path.extend(&leg.path[1..]);
+        tour.transition_path.extend(&leg.transition_path);
+        tour.node = leg.node;
+        tour.transition = tour.transition_path.last().copied();
+        from = to;
+    }
+    Ok(Some(tour))
+}
@@ -0,0 +1,102 @@
+use crate::library::{absolute_path, Track};
+use crate::rekordbox::escape;
+use crate::scale::{Scale, ScaleKind};
+use std::fmt::Write;
+use std::path::Path;
+
+/// The volume Traktor on macOS names the startup disk, for files outside `/Volumes`.
+const STARTUP_VOLUME: &str = "Macintosh HD";
+
+/// Where Traktor finds a file: its volume, like `C:` or a macOS disk name, its folder written
+/// like `/:Users/:dj/:Music/:`, and its name. Relative paths are taken from the current
+/// directory.
+fn location(path: &Path) -> (String, String, String) {
+    let path = absolute_path(path).to_string_lossy().replace('\\', "/");
+    let mut parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    let volume = match parts.first() {
+        Some(drive) if drive.len() == 2 && drive.ends_with(':') => parts.remove(0).to_string(),
+        Some(&"Volumes") if parts.len() > 2 => {
+            parts.remove(0);
+            parts.remove(0).to_string()
+        }
+        _ => STARTUP_VOLUME.to_string(),
+    };
+    let file = parts.pop().unwrap_or_default().to_string();
+    let dir: String = parts.iter().map(|part| format!("/:{part}")).collect();
+    (volume, format!("{dir}/:"), file)
+}
+
+/// The key as Traktor numbers it, 0 to 11 for C to B major and 12 to 23 for C to B minor.
+fn musical_key(key: Scale) -> usize {
+    match key.kind {
+        ScaleKind::Major => key.tonic(),
+        ScaleKind::Minor => 12 + key.tonic(),
+    }
+}
+
+/// Writes the tracks as a playlist named `playlist` in Traktor's NML format, which Traktor
+/// imports from its browser with the tracks' keys in Open Key notation. Traktor finds tracks
+/// by file, so tracks without a path are left out.
+pub fn traktor_nml(tracks: &[Track], playlist: &str) -> String {
+    let tracks: Vec<_> = tracks
+        .iter()
+        .filter_map(|track| Some((track, location(track.path.as_deref()?))))
+        .collect();
+
+    let mut nml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\" ?>\n");
+    nml.push_str("<NML VERSION=\"19\">\n");
+    nml.push_str("  <HEAD COMPANY=\"www.native-instruments.com\" PROGRAM=\"Traktor\"/>\n");
+    let _ = writeln!(nml, "  <COLLECTION ENTRIES=\"{}\">", tracks.len());
+    for (track, (volume, dir, file)) in &tracks {
+        let (artist, title) = track.artist_and_title();
+        let _ = writeln!(
+            nml,
+            "    <ENTRY TITLE=\"{}\" ARTIST=\"{}\">",
+            escape(title),
+            escape(artist.unwrap_or_default())
+        );
+        let _ = writeln!(
+            nml,
+            "      <LOCATION DIR=\"{}\" FILE=\"{}\" VOLUME=\"{}\"/>",
+            escape(dir),
+            escape(file),
+            escape(volume)
+        );
+        let _ = write!(nml, "      <INFO KEY=\"{}\"", track.key.open_key());
+        if let Some(duration) = track.duration {
+            let _ = write!(nml, " PLAYTIME=\"{}\"", duration.round());
+        }
+        nml.push_str("/>\n");
+        if let Some(tempo) = track.tempo {
+            let _ = writeln!(nml, "      <TEMPO BPM=\"{tempo:.6}\" BPM_QUALITY=\"100\"/>");
+        }
+        let _ = writeln!(
+            nml,
+            "      <MUSICAL_KEY VALUE=\"{}\"/>",
+            musical_key(track.key)
+        );
+        nml.push_str("    </ENTRY>\n");
+    }
+    nml.push_str("  </COLLECTION>\n  <PLAYLISTS>\n");
+    nml.push_str("    <NODE TYPE=\"FOLDER\" NAME=\"$ROOT\">\n      <SUBNODES COUNT=\"1\">\n");
+    let _ = writeln!(
+        nml,
+        "        <NODE TYPE=\"PLAYLIST\" NAME=\"{}\">",
+        escape(playlist)
+    );
+    let _ = writeln!(
+        nml,
+        "          <PLAYLIST ENTRIES=\"{}\" TYPE=\"LIST\">",
+        tracks.len()
+    );
+    for (_, (volume, dir, file)) in &tracks {
+        let _ = writeln!(
+            nml,
+            "            <ENTRY><PRIMARYKEY TYPE=\"TRACK\" KEY=\"{}\"/></ENTRY>",
+            escape(&format!("{volume}{dir}{file}"))
+        );
+    }
+    nml.push_str("          </PLAYLIST>\n        </NODE>\n      </SUBNODES>\n    </NODE>\n");
+    nml.push_str("  </PLAYLISTS>\n</NML>\n");
+    nml
+}
@@ -0,0 +1,6 @@
+use camelol_core::TRANSITIONS;
+pub use camelol_core::{make_transition, make_transition_on, Interval, ScaleTransition};
+
+pub fn possible_transitions() -> Vec<ScaleTransition> {
+    TRANSITIONS.to_vec()
+}
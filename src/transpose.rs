@@ -0,0 +1,116 @@
+use crate::color::{wheel_color, Rgb};
+use crate::distance::DistanceMatrix;
+use crate::scale::Scale;
+use serde::Serialize;
+
+/// The furthest a track is shifted, in semitones up or down, before it sounds wrong.
+const MAX_SHIFT: i32 = 2;
+
+/// Keys this many wheel moves apart or fewer mix without tricks.
+const FITTING_DISTANCE: usize = 1;
+
+/// A key shift that makes a track fit, played with key lock so its tempo stays the same.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Transposition {
+    /// Semitones to shift the track's key by, up or down.
+    pub semitones: i32,
+    /// The key the track sounds in once shifted.
+    pub key: Scale,
+    pub color: Rgb,
+    /// Percent on the pitch fader that would move the key as far without key lock, changing
+    /// the tempo with it.
+    pub pitch: f64,
+    /// Wheel moves from the current key to the shifted one.
+    pub distance: usize,
+}
+
+/// A tempo to play a track at with key lock off, and the key it sounds in there.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Stretch {
+    /// Semitones the key moves by, up or down.
+    pub semitones: i32,
+    /// Percent on the pitch fader.
+    pub pitch: f64,
+    /// Beats per minute the track plays at.
+    pub tempo: f64,
+    /// The key the track sounds in.
+    pub key: Scale,
+    pub color: Rgb,
+}
+
+/// Percent on the pitch fader that moves a track's key by `semitones` without key lock.
+fn semitone_pitch(semitones: i32) -> f64 {
+    (2f64.powf(f64::from(semitones) / 12.0) - 1.0) * 100.0
+}
+
+/// Every whole-semitone stretch of a track in `key` at `tempo` beats per minute with key lock
+/// off, up to `pitch_range` percent either way, lowest tempo first and the track as it is
+/// among them. The tempos in between land between keys, so these are the ones that stretch the
+/// track into another key.
+pub fn stretches(key: Scale, tempo: f64, pitch_range: f64) -> Vec<Stretch> {
+    (-12..=12)
+        .map(|semitones| (semitones, semitone_pitch(semitones)))
+        .filter(|(_, pitch)| pitch.abs() <= pitch_range)
+        .map(|(semitones, pitch)| {
+            let key = transpose(key, semitones);
+            Stretch {
+                semitones,
+                pitch,
+                tempo: tempo * (1.0 + pitch / 100.0),
+                key,
+                color: wheel_color(key),
+            }
+        })
+        .collect()
+}
+
+/// `key` shifted by `semitones`, up or down.
+pub fn transpose(key: Scale, semitones: i32) -> Scale {
+    let tonic = (key.tonic() as i32 + semitones).rem_euclid(12) as usize;
+    Scale::from_tonic(tonic, key.kind)
+}
+
+/// The key shifts of up to two semitones that bring a track in `candidate` within one wheel
+/// move of `current`, closest fit first and smallest shift between equals. Empty if the track
+/// already fits or no shift helps. With a `pitch_range`, how far the pitch fader goes in
+/// percent either way, only shifts it can reach are kept.
+pub fn transpositions(
+    distances: &DistanceMatrix,
+    current: Scale,
+    candidate: Scale,
+    pitch_range: Option<f64>,
+) -> Vec<Transposition> {
+    if distances
+        .distance(current, candidate)
+        .is_some_and(|distance| distance <= FITTING_DISTANCE)
+    {
+        return vec![];
+    }
+    let mut transpositions: Vec<Transposition> = (-MAX_SHIFT..=MAX_SHIFT)
+        .filter(|&semitones| semitones != 0)
+        .filter_map(|semitones| {
+            let key = transpose(candidate, semitones);
+            let distance = distances
+                .distance(current, key)
+                .filter(|&distance| distance <= FITTING_DISTANCE)?;
+            let pitch = semitone_pitch(semitones);
+            if pitch_range.is_some_and(|range| pitch.abs() > range) {
+                return None;
+            }
+            Some(Transposition {
+                semitones,
+                key,
+                color: wheel_color(key),
+                pitch,
+                distance,
+            })
+        })
+        .collect();
+    transpositions.sort_by_key(|transposition| {
+        (
+            transposition.distance,
+            transposition.semitones.unsigned_abs(),
+        )
+    });
+    transpositions
+}
@@ -0,0 +1,683 @@
+use crate::color::{wheel_color, Rgb};
+use crate::distance::DistanceMatrix;
+use crate::library::Track;
+use crate::matches::{track_match, TrackMatch};
+use crate::scale::{make_nodes, Scale, ScaleKind};
+use crate::search::{format_path, Path, PathCost, SearchLimit};
+use crate::suggest::{suggest, Suggestion};
+use crate::wheel::Wheel;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::style::Colored;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::symbols::border;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::canvas::Canvas;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::f64::consts::PI;
+use std::io;
+use std::time::Duration;
+
+const PATH_COUNT: usize = 10;
+/// Scores of candidates from which they're shown as good or fair mixes into the set. Keys one
+/// transition apart at the same tempo score 0.5, and two apart about 0.33.
+const GOOD_MIX: f64 = 0.5;
+const FAIR_MIX: f64 = 0.3;
+/// Next keys the live view ranks.
+const LIVE_OPTIONS: usize = 8;
+
+/// Rows of the big digits and letters the live view shows the current key in, `#` for a
+/// filled cell.
+const GLYPH_ROWS: usize = 5;
+const fn glyph(c: char) -> [&'static str; GLYPH_ROWS] {
+    match c {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", "###", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        'A' => [" # ", "# #", "###", "# #", "# #"],
+        'B' => ["## ", "# #", "## ", "# #", "## "],
+        _ => ["   ", "   ", "   ", "   ", "   "],
+    }
+}
+/// How long a search may hold up the interface before the paths are left empty.
+const SEARCH_TIME: Duration = Duration::from_millis(200);
+/// Borders for plain mode, in ASCII instead of box drawing characters.
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Runs `app` in the terminal, with colors turned off if `plain`. Styles like bold and reversed
+/// text stay.
+fn run<T>(plain: bool, app: impl FnOnce(&mut DefaultTerminal) -> io::Result<T>) -> io::Result<T> {
+    if plain {
+        Colored::set_ansi_color_disabled(true);
+    }
+    ratatui::run(app)
+}
+
+/// A bordered block, with ASCII borders if `plain`.
+fn block(plain: bool) -> Block<'static> {
+    let block = Block::bordered();
+    if plain {
+        block.border_set(ASCII_BORDER)
+    } else {
+        block
+    }
+}
+
+struct Explorer<'a> {
+    wheel: &'a Wheel,
+    cursor: Scale,
+    target: Option<Scale>,
+    paths: Vec<Path>,
+    selected: ListState,
+    plain: bool,
+}
+
+impl<'a> Explorer<'a> {
+    fn new(wheel: &'a Wheel, cursor: Scale, plain: bool) -> Self {
+        Self {
+            wheel,
+            cursor,
+            target: None,
+            paths: vec![],
+            selected: ListState::default(),
+            plain,
+        }
+    }
+
+    fn update_paths(&mut self) {
+        self.paths = match self.target {
+            Some(target) => self
+                .wheel
+                .paths_within(
+                    self.cursor,
+                    target,
+                    PATH_COUNT,
+                    |from, transition, to| PathCost::Hops.step(from, transition, to),
+                    &SearchLimit::within(SEARCH_TIME),
+                )
+                .unwrap_or_default(),
+            None => vec![],
+        };
+        self.selected.select((!self.paths.is_empty()).then_some(0));
+    }
+
+    fn move_cursor(&mut self, cursor: Scale) {
+        self.cursor = cursor;
+        self.update_paths();
+    }
+
+    fn select_path(&mut self, offset: isize) {
+        if self.paths.is_empty() {
+            return;
+        }
+        let current = self.selected.selected().unwrap_or(0) as isize;
+        let next = (current + offset).rem_euclid(self.paths.len() as isize);
+        self.selected.select(Some(next as usize));
+    }
+
+    fn selected_path(&self) -> Option<&Path> {
+        self.selected.selected().and_then(|i| self.paths.get(i))
+    }
+
+    /// Returns `false` once the explorer should quit.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(self.cursor.change_index(1)),
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(self.cursor.change_index(-1)),
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('k') | KeyCode::Char('j') => {
+                self.move_cursor(self.cursor.swap_kind())
+            }
+            KeyCode::Enter | KeyCode::Char('t') => {
+                self.target = Some(self.cursor);
+                self.update_paths();
+            }
+            KeyCode::Char('c') => {
+                self.target = None;
+                self.update_paths();
+            }
+            KeyCode::Tab | KeyCode::Char('n') => self.select_path(1),
+            KeyCode::BackTab | KeyCode::Char('p') => self.select_path(-1),
+            _ => {}
+        }
+        true
+    }
+
+    /// The key as the wheel shows it. In plain mode, where there may be no colors to tell
+    /// them apart, the cursor, the target, the keys on the selected path and the cursor's
+    /// neighbors are marked.
+    fn key_label(&self, key: Scale) -> String {
+        if !self.plain {
+            return key.to_string();
+        }
+        let neighbors = self.wheel.reachable(self.cursor, 1).unwrap_or_default();
+        if key == self.cursor {
+            format!("[{key}]")
+        } else if Some(key) == self.target {
+            format!("({key})")
+        } else if self
+            .selected_path()
+            .is_some_and(|path| path.path.contains(&key))
+        {
+            format!("*{key}")
+        } else if neighbors.iter().any(|(neighbor, _)| *neighbor == key) {
+            format!("+{key}")
+        } else {
+            key.to_string()
+        }
+    }
+
+    fn key_style(&self, key: Scale) -> Style {
+        let neighbors = self.wheel.reachable(self.cursor, 1).unwrap_or_default();
+        let on_path = self
+            .selected_path()
+            .is_some_and(|path| path.path.contains(&key));
+
+        if key == self.cursor {
+            Style::new().fg(Color::Black).bg(Color::Yellow).bold()
+        } else if Some(key) == self.target {
+            Style::new().fg(Color::Black).bg(Color::Magenta).bold()
+        } else if on_path {
+            Style::new().fg(Color::Cyan).bold()
+        } else if neighbors.iter().any(|(neighbor, _)| *neighbor == key) {
+            Style::new().fg(Color::Green)
+        } else {
+            Style::new().fg(Color::DarkGray)
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [wheel_area, paths_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+
+        let keys = make_nodes()
+            .into_iter()
+            .map(|key| (key, self.key_label(key), self.key_style(key)))
+            .collect::<Vec<_>>();
+
+        let wheel = Canvas::default()
+            .block(block(self.plain).title(if self.plain {
+                " Camelot wheel: [cursor] (target) *path +next "
+            } else {
+                " Camelot wheel "
+            }))
+            .x_bounds([-1.3, 1.3])
+            .y_bounds([-1.3, 1.3])
+            .paint(move |ctx| {
+                for (key, label, style) in &keys {
+                    // Wheel numbers sit where the hours do on a clock, minor keys on the inner
+                    // ring.
+                    let angle = PI / 2.0 - (key.index + 1) as f64 * PI / 6.0;
+                    let radius = match key.kind {
+                        ScaleKind::Major => 1.0,
+                        ScaleKind::Minor => 0.6,
+                    };
+                    ctx.print(
+                        radius * angle.cos(),
+                        radius * angle.sin(),
+                        Line::from(Span::styled(label.clone(), *style)),
+                    );
+                }
+            });
+        frame.render_widget(wheel, wheel_area);
+
+        let title = match self.target {
+            Some(target) => format!(" Paths {} -> {} ", self.cursor, target),
+            None => " Paths (press enter to pick a target) ".to_string(),
+        };
+        let paths = List::new(
+            self.paths
+                .iter()
+                .map(|path| ListItem::new(format_path(path))),
+        )
+        .block(block(self.plain).title(title))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(paths, paths_area, &mut self.selected);
+
+        let help = Paragraph::new(if self.plain {
+            concat!(
+                "left/right move  up/down switch ring  enter pick target  c clear  ",
+                "tab next path  q quit"
+            )
+        } else {
+            "←/→ move  ↑/↓ switch ring  enter pick target  c clear  tab next path  q quit"
+        })
+        .dark_gray();
+        frame.render_widget(help, help_area);
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Runs the interactive wheel explorer until the user quits, starting with the cursor on `start`.
+/// If `plain`, it's drawn in ASCII, with the keys it colors marked as well.
+pub fn explore(wheel: &Wheel, start: Scale, plain: bool) -> io::Result<()> {
+    run(plain, |terminal| {
+        Explorer::new(wheel, start, plain).run(terminal)
+    })
+}
+
+struct SetBuilder<'a> {
+    tracks: &'a [Track],
+    distances: &'a DistanceMatrix,
+    pitch_range: f64,
+    /// Indices of the tracks in the set, in order.
+    set: Vec<usize>,
+    /// Indices of the tracks not in the set, in library order.
+    pool: Vec<usize>,
+    /// The set before every edit, the latest last, and the sets undone since, the latest last.
+    history: Vec<Vec<usize>>,
+    undone: Vec<Vec<usize>>,
+    selected: ListState,
+    plain: bool,
+}
+
+impl<'a> SetBuilder<'a> {
+    fn new(
+        tracks: &'a [Track],
+        distances: &'a DistanceMatrix,
+        pitch_range: f64,
+        set: Vec<usize>,
+        plain: bool,
+    ) -> Self {
+        let mut builder = Self {
+            tracks,
+            distances,
+            pitch_range,
+            set: vec![],
+            pool: vec![],
+            history: vec![],
+            undone: vec![],
+            selected: ListState::default(),
+            plain,
+        };
+        builder.restore(set);
+        builder
+    }
+
+    /// Makes `set` the set, with every other track back in the pool.
+    fn restore(&mut self, set: Vec<usize>) {
+        self.pool = (0..self.tracks.len())
+            .filter(|track| !set.contains(track))
+            .collect();
+        self.set = set;
+        self.select(self.selected.selected().unwrap_or(0));
+    }
+
+    /// Keeps the set as it is for [`undo`](Self::undo), before an edit.
+    fn remember(&mut self) {
+        self.history.push(self.set.clone());
+        self.undone.clear();
+    }
+
+    /// Takes back the latest edit not taken back yet.
+    fn undo(&mut self) {
+        if let Some(set) = self.history.pop() {
+            self.undone.push(self.set.clone());
+            self.restore(set);
+        }
+    }
+
+    /// Makes the latest edit taken back by [`undo`](Self::undo) again.
+    fn redo(&mut self) {
+        if let Some(set) = self.undone.pop() {
+            self.history.push(self.set.clone());
+            self.restore(set);
+        }
+    }
+
+    fn select(&mut self, position: usize) {
+        let last = self.pool.len().checked_sub(1);
+        self.selected.select(last.map(|last| position.min(last)));
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        if self.pool.is_empty() {
+            return;
+        }
+        let current = self.selected.selected().unwrap_or(0) as isize;
+        let next = (current + offset).rem_euclid(self.pool.len() as isize);
+        self.selected.select(Some(next as usize));
+    }
+
+    /// Moves the selected candidate to the end of the set.
+    fn add(&mut self) {
+        let Some(position) = self.selected.selected() else {
+            return;
+        };
+        if position < self.pool.len() {
+            self.remember();
+            self.set.push(self.pool.remove(position));
+            self.select(position);
+        }
+    }
+
+    /// Puts the last track of the set back into the pool and selects it.
+    fn remove_last(&mut self) {
+        let Some(&track) = self.set.last() else {
+            return;
+        };
+        self.remember();
+        self.set.pop();
+        let position = self.pool.partition_point(|&other| other < track);
+        self.pool.insert(position, track);
+        self.select(position);
+    }
+
+    /// How well the last track of the set mixes into the candidate `track`, or `None` if it
+    /// doesn't or the set is empty.
+    fn candidate_match(&self, track: usize) -> Option<TrackMatch> {
+        let last = &self.tracks[*self.set.last()?];
+        track_match(
+            last,
+            &self.tracks[track],
+            track,
+            self.distances,
+            self.pitch_range,
+        )
+    }
+
+    /// Returns `false` once the builder should quit.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Enter | KeyCode::Char('a') => self.add(),
+            KeyCode::Backspace | KeyCode::Char('d') => self.remove_last(),
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('r') => self.redo(),
+            _ => {}
+        }
+        true
+    }
+
+    fn track_line(track: &Track) -> String {
+        let tempo = track
+            .tempo
+            .map_or("-".to_string(), |tempo| format!("{tempo:.1}"));
+        format!("{:>4}  {:>6}  {}", track.key.to_string(), tempo, track.name)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [pool_area, set_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+
+        // Candidates are colored by how well the set's last track mixes into them.
+        let candidates = self.pool.iter().map(|&track| {
+            let line = Self::track_line(&self.tracks[track]);
+            if self.set.is_empty() {
+                return ListItem::new(format!("      {line}"));
+            }
+            let Some(found) = self.candidate_match(track) else {
+                return ListItem::new(format!("   -  {line}")).dark_gray();
+            };
+            let style = if found.score >= GOOD_MIX {
+                Style::new().fg(Color::Green)
+            } else if found.score >= FAIR_MIX {
+                Style::new().fg(Color::Yellow)
+            } else {
+                Style::new().fg(Color::Red)
+            };
+            ListItem::new(format!("{:.2}  {line}", found.score)).style(style)
+        });
+        let title = match self.set.last() {
+            Some(&last) => format!(" Mixing out of {} ", self.tracks[last].name),
+            None => " Candidates (press enter to start the set) ".to_string(),
+        };
+        let pool = List::new(candidates)
+            .block(block(self.plain).title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(pool, pool_area, &mut self.selected);
+
+        let set = List::new(self.set.iter().enumerate().map(|(position, &track)| {
+            ListItem::new(format!(
+                "{:>3}  {}",
+                position + 1,
+                Self::track_line(&self.tracks[track])
+            ))
+        }))
+        .block(block(self.plain).title(format!(" Set ({} tracks) ", self.set.len())));
+        frame.render_widget(set, set_area);
+
+        let help = Paragraph::new(if self.plain {
+            concat!(
+                "up/down pick candidate  enter add to set  backspace remove last  ",
+                "u undo  r redo  q done"
+            )
+        } else {
+            concat!(
+                "↑/↓ pick candidate  enter add to set  backspace remove last  ",
+                "u undo  r redo  q done"
+            )
+        })
+        .dark_gray();
+        frame.render_widget(help, help_area);
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<Vec<usize>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(self.set);
+                }
+            }
+        }
+    }
+}
+
+/// Builds a set from `tracks` interactively, starting from the tracks in `set`, with the
+/// candidates beside the set and colored by how well the set's last track mixes into them,
+/// scored like [`best_matches`](crate::best_matches) within `pitch_range` percent. Every edit
+/// can be undone and redone. Returns the indices of the set's tracks once the user is done. If
+/// `plain`, it's drawn in ASCII.
+pub fn build_set(
+    tracks: &[Track],
+    distances: &DistanceMatrix,
+    pitch_range: f64,
+    set: Vec<usize>,
+    plain: bool,
+) -> io::Result<Vec<usize>> {
+    run(plain, |terminal| {
+        SetBuilder::new(tracks, distances, pitch_range, set, plain).run(terminal)
+    })
+}
+
+fn color(Rgb(r, g, b): Rgb) -> Color {
+    Color::Rgb(r, g, b)
+}
+
+/// `text` in big letters, each cell two characters wide so the glyphs come out square, and
+/// filled with `#` instead of blocks if `plain`.
+fn big_text(text: &str, style: Style, plain: bool) -> Vec<Line<'static>> {
+    let filled = if plain { "##" } else { "██" };
+    (0..GLYPH_ROWS)
+        .map(|row| {
+            let line: Vec<String> = text
+                .chars()
+                .map(|c| glyph(c)[row].replace('#', filled).replace(' ', "  "))
+                .collect();
+            Line::styled(line.join("  "), style)
+        })
+        .collect()
+}
+
+struct Live<'a> {
+    wheel: &'a Wheel,
+    distances: DistanceMatrix,
+    current: Scale,
+    /// Digits of a wheel number typed so far, waiting for `a` or `b`.
+    typed: String,
+    error: Option<String>,
+    options: Vec<Suggestion>,
+    plain: bool,
+}
+
+impl<'a> Live<'a> {
+    fn new(wheel: &'a Wheel, current: Scale, plain: bool) -> Self {
+        let mut live = Self {
+            wheel,
+            distances: DistanceMatrix::new(wheel),
+            current,
+            typed: String::new(),
+            error: None,
+            options: vec![],
+            plain,
+        };
+        live.set_current(current);
+        live
+    }
+
+    fn set_current(&mut self, current: Scale) {
+        self.current = current;
+        self.options = suggest(&self.distances, current, None, 0.0)
+            .into_iter()
+            .filter(|option| option.scale != current && option.score > 0.0)
+            .take(LIVE_OPTIONS)
+            .collect();
+    }
+
+    /// Sets the current key to the typed wheel number on the `letter` ring, or switches the
+    /// current key to that ring if no number was typed.
+    fn enter(&mut self, letter: char) {
+        let number = match self.typed.as_str() {
+            "" => (self.current.index + 1).to_string(),
+            typed => typed.to_string(),
+        };
+        self.typed.clear();
+        let input = format!("{number}{letter}");
+        match self.wheel.parse_key(&input) {
+            Ok(key) => self.set_current(key),
+            Err(_) => self.error = Some(format!("no key {input} on the wheel")),
+        }
+    }
+
+    /// Returns `false` once the live view should quit.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        self.error = None;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(digit @ '0'..='9') if self.typed.len() < 2 => self.typed.push(digit),
+            KeyCode::Char(letter @ ('a' | 'b' | 'A' | 'B')) => {
+                self.enter(letter.to_ascii_uppercase())
+            }
+            KeyCode::Backspace => {
+                self.typed.pop();
+            }
+            KeyCode::Right => self.set_current(self.current.change_index(1)),
+            KeyCode::Left => self.set_current(self.current.change_index(-1)),
+            KeyCode::Up | KeyCode::Down => self.set_current(self.current.swap_kind()),
+            _ => {}
+        }
+        true
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [current_area, typed_area, options_area, help_area] = Layout::vertical([
+            Constraint::Length(GLYPH_ROWS as u16 + 2),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let current = Paragraph::new(big_text(
+            &self.current.to_string(),
+            Style::new().fg(color(wheel_color(self.current))).bold(),
+            self.plain,
+        ))
+        .centered()
+        .block(block(self.plain).title(" Playing "));
+        frame.render_widget(current, current_area);
+
+        let typed = match &self.error {
+            Some(error) => Line::from(error.clone()).red(),
+            None if self.typed.is_empty() => Line::from(""),
+            None => Line::from(format!("{}_", self.typed)).bold(),
+        };
+        frame.render_widget(Paragraph::new(typed).centered(), typed_area);
+
+        let neighbors = self.wheel.neighbors(self.current).unwrap_or_default();
+        let options = List::new(self.options.iter().enumerate().map(|(rank, option)| {
+            let transition = neighbors
+                .iter()
+                .find(|(_, key)| *key == option.scale)
+                .map_or_else(
+                    || format!("{} steps", option.distance.unwrap_or_default()),
+                    |(transition, _)| format!("{transition:?}"),
+                );
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:>2}  ", rank + 1)),
+                Span::styled(
+                    format!(" {:>3} ", option.scale.to_string()),
+                    Style::new().fg(Color::Black).bg(color(option.color)).bold(),
+                ),
+                Span::raw(format!("  {:.2}  {transition}", option.score)),
+            ]))
+        }))
+        .block(block(self.plain).title(" Next "));
+        frame.render_widget(options, options_area);
+
+        let help = Paragraph::new(if self.plain {
+            "type 8a, 12b...  a/b switch ring  left/right step  q quit"
+        } else {
+            "type 8a, 12b...  a/b switch ring  ←/→ step  q quit"
+        })
+        .dark_gray()
+        .centered();
+        frame.render_widget(help, help_area);
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Runs a full-screen view for glancing at mid-set, starting on `start`: typing a wheel number
+/// and `a` or `b` sets the key playing, shown big, with the best keys to mix into ranked below
+/// it. If `plain`, it's drawn in ASCII.
+pub fn live(wheel: &Wheel, start: Scale, plain: bool) -> io::Result<()> {
+    run(plain, |terminal| {
+        Live::new(wheel, start, plain).run(terminal)
+    })
+}
@@ -0,0 +1,126 @@
+use crate::error::Result;
+use crate::library::Track;
+use crate::scale::{Locale, Scale};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Reads a key the way VirtualDJ writes them: musical names like `Am` with bare notes for
+/// major keys, camelot codes like `8A`, or Open Key codes like `1m`.
+pub fn virtualdj_key(key: &str) -> Option<Scale> {
+    let key = key.trim();
+    if let Ok(key) = key.parse() {
+        return Some(key);
+    }
+    Scale::from_open_key(key)
+        .or_else(|_| Scale::from_key_name(key, Locale::English))
+        .ok()
+}
+
+/// VirtualDJ stores analyzed tempos as seconds per beat and tagged ones as beats per minute.
+fn virtualdj_tempo(bpm: &str) -> Option<f64> {
+    let bpm: f64 = bpm.trim().parse().ok().filter(|&bpm: &f64| bpm > 0.0)?;
+    Some(if bpm < 10.0 { 60.0 / bpm } else { bpm })
+}
+
+#[derive(Default)]
+struct Song {
+    path: String,
+    artist: Option<String>,
+    title: Option<String>,
+    tagged_key: Option<Scale>,
+    scanned_key: Option<Scale>,
+    tagged_tempo: Option<f64>,
+    scanned_tempo: Option<f64>,
+    duration: Option<f64>,
+    genre: Option<String>,
+}
+
+impl Song {
+    fn read(&mut self, element: &BytesStart) -> Result<()> {
+        let tags = element.name().as_ref() == b"Tags";
+        for attribute in element.attributes() {
+            let attribute = attribute.map_err(quick_xml::Error::from)?;
+            let value = attribute.unescape_value()?.into_owned();
+            match (tags, attribute.key.as_ref()) {
+                (true, b"Author") => self.artist = Some(value),
+                (true, b"Title") => self.title = Some(value),
+                (true, b"Genre") if !value.is_empty() => self.genre = Some(value),
+                (true, b"Key") => self.tagged_key = virtualdj_key(&value),
+                (true, b"Bpm") => self.tagged_tempo = virtualdj_tempo(&value),
+                (false, b"Key") => self.scanned_key = virtualdj_key(&value),
+                (false, b"Bpm") => self.scanned_tempo = virtualdj_tempo(&value),
+                (false, b"SongLength") => {
+                    self.duration = value.trim().parse().ok().filter(|&length| length > 0.0);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Tags win over analysis, since they're either set by hand or by a better analyzer.
+    fn into_track(self) -> Option<Track> {
+        let name = match (self.artist, self.title) {
+            (Some(artist), Some(title)) if !artist.is_empty() => format!("{artist} - {title}"),
+            (_, Some(title)) if !title.is_empty() => title,
+            // Paths may come from Windows, so split them by hand.
+            _ => {
+                let file = self.path.rsplit(['/', '\\']).next().unwrap_or(&self.path);
+                file.rsplit_once('.')
+                    .map_or(file, |(stem, _)| stem)
+                    .to_string()
+            }
+        };
+        Some(Track {
+            name,
+            key: self.tagged_key.or(self.scanned_key)?,
+            confidence: None,
+            tempo: self.tagged_tempo.or(self.scanned_tempo),
+            path: (!self.path.is_empty()).then(|| PathBuf::from(self.path)),
+            duration: self.duration,
+            energy: None,
+            genre: self.genre,
+            tags: vec![],
+            segments: vec![],
+            modified: None,
+        })
+    }
+}
+
+/// Reads the tracks of a VirtualDJ `database.xml` that have a key, in the database's order.
+pub fn read_virtualdj_database(path: &Path) -> Result<Vec<Track>> {
+    let xml = fs::read_to_string(path)?;
+    let mut reader = Reader::from_str(&xml);
+    let mut tracks = vec![];
+    let mut song = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) if element.name().as_ref() == b"Song" => {
+                let mut new = Song::default();
+                if let Some(path) = element
+                    .try_get_attribute("FilePath")
+                    .map_err(quick_xml::Error::from)?
+                {
+                    new.path = path.unescape_value()?.into_owned();
+                }
+                song = Some(new);
+            }
+            Event::Start(element) | Event::Empty(element)
+                if matches!(element.name().as_ref(), b"Tags" | b"Scan" | b"Infos") =>
+            {
+                if let Some(song) = &mut song {
+                    song.read(&element)?;
+                }
+            }
+            Event::End(element) if element.name().as_ref() == b"Song" => {
+                tracks.extend(song.take().and_then(Song::into_track));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    tracing::info!(tracks = tracks.len(), "read the VirtualDJ database");
+    Ok(tracks)
+}
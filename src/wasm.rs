@@ -0,0 +1,82 @@
+use crate::engine::Engine;
+use crate::scale::{self, Scale};
+use crate::wheel::Wheel;
+use std::sync::OnceLock;
+use wasm_bindgen::prelude::*;
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| Engine::new(Wheel::new()))
+}
+
+fn key(input: &str) -> Result<Scale, JsError> {
+    Ok(scale::parse_key(input)?)
+}
+
+fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(value).map_err(|error| JsError::new(&error.to_string()))
+}
+
+/// Parses a key and returns its camelot code.
+#[wasm_bindgen(js_name = parseKey)]
+pub fn parse_key(input: &str) -> Result<String, JsError> {
+    Ok(key(input)?.to_string())
+}
+
+#[wasm_bindgen]
+pub fn compatible(a: &str, b: &str) -> Result<bool, JsError> {
+    Ok(engine().compatible(key(a)?, key(b)?))
+}
+
+#[wasm_bindgen]
+pub fn compatibility(a: &str, b: &str) -> Result<f64, JsError> {
+    Ok(engine().compatibility(key(a)?, key(b)?))
+}
+
+/// Number of transitions between two keys, or `undefined` if they aren't connected.
+#[wasm_bindgen]
+pub fn distance(a: &str, b: &str) -> Result<Option<u32>, JsError> {
+    Ok(engine()
+        .distance(key(a)?, key(b)?)
+        .map(|distance| distance as u32))
+}
+
+/// The `n` shortest paths as `{ cost, keys, transitions }` objects.
+#[wasm_bindgen]
+pub fn paths(from: &str, to: &str, n: usize) -> Result<JsValue, JsError> {
+    to_js(&engine().paths(key(from)?, key(to)?, n)?)
+}
+
+/// The keys one transition away as `{ key, transition }` objects.
+#[wasm_bindgen]
+pub fn neighbors(of: &str) -> Result<JsValue, JsError> {
+    to_js(&engine().neighbors(key(of)?)?)
+}
+
+/// The keys one transition before as `{ key, transition }` objects.
+#[wasm_bindgen]
+pub fn predecessors(of: &str) -> Result<JsValue, JsError> {
+    to_js(&engine().predecessors(key(of)?)?)
+}
+
+/// The names of a key's seven notes from its tonic, like `["A", "B", "C", "D", "E", "F", "G"]`
+/// for 8A.
+#[wasm_bindgen(js_name = scaleNotes)]
+pub fn scale_notes(of: &str) -> Result<Vec<String>, JsError> {
+    let scale = key(of)?;
+    Ok(scale
+        .note_names(scale.conventional_accidental())
+        .map(str::to_string)
+        .to_vec())
+}
+
+/// The notes and chords two keys share as `{ from, to, notes, chords }`.
+#[wasm_bindgen]
+pub fn mashup(from: &str, to: &str) -> Result<JsValue, JsError> {
+    to_js(&engine().mashup(key(from)?, key(to)?))
+}
+
+#[wasm_bindgen]
+pub fn suggest(from: &str, n: usize) -> Result<JsValue, JsError> {
+    to_js(&engine().suggest(key(from)?, n))
+}
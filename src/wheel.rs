@@ -0,0 +1,313 @@
+use crate::error::{Error, Result};
+use crate::rules::TransitionRules;
+use crate::scale::{invalid_key, make_nodes_on, Locale, Scale};
+use crate::search::{multi_path_search, Path, PathCost, SearchLimit};
+use crate::transition::{make_transition_on, possible_transitions, ScaleTransition};
+use camelol_core::WHEEL_SIZE;
+use itertools::Itertools;
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::{Direction, Graph};
+use std::collections::VecDeque;
+
+/// The camelot wheel as a graph of scales connected by their valid transitions.
+///
+/// Scales are added in [`make_nodes_on`] order, so a scale's node index is its
+/// [`Scale::position`] and lookups need no hashing.
+#[derive(Debug, Clone)]
+pub struct Wheel {
+    size: usize,
+    graph: Graph<Scale, ScaleTransition>,
+}
+
+impl Wheel {
+    /// The standard 12-position camelot wheel.
+    pub fn new() -> Self {
+        Self::with_size(WHEEL_SIZE)
+    }
+
+    /// A wheel with `size` positions and the same transition rules, such as a 24-position wheel
+    /// for quarter-tone tunings.
+    pub fn with_size(size: usize) -> Self {
+        Self::with_transitions_where(size, |_, _, _| true)
+    }
+
+    /// A wheel with `size` positions and only the transitions `rules` allow.
+    pub fn with_rules(size: usize, rules: &impl TransitionRules) -> Self {
+        Self::with_transitions_where(size, |from, transition, to| {
+            rules.allows(from, transition, to)
+        })
+    }
+
+    /// A wheel with `size` positions and only the transitions `(from, transition, to)` that
+    /// `keep` accepts.
+    pub fn with_transitions_where(
+        size: usize,
+        mut keep: impl FnMut(Scale, ScaleTransition, Scale) -> bool,
+    ) -> Self {
+        let nodes = make_nodes_on(size);
+        let transitions = possible_transitions();
+        let mut graph = Graph::with_capacity(nodes.len(), nodes.len() * transitions.len());
+
+        for scale in &nodes {
+            graph.add_node(*scale);
+        }
+
+        for scale in &nodes {
+            for transition in &transitions {
+                let target_scale = make_transition_on(*scale, *transition, size);
+                if !keep(*scale, *transition, target_scale) {
+                    continue;
+                }
+                graph.add_edge(
+                    NodeIndex::new(scale.position()),
+                    NodeIndex::new(target_scale.position()),
+                    *transition,
+                );
+            }
+        }
+
+        tracing::debug!(
+            size,
+            keys = graph.node_count(),
+            transitions = graph.edge_count(),
+            "built the wheel"
+        );
+        Self { size, graph }
+    }
+
+    /// The wheel without the transitions `(from, transition, to)` that `remove` matches, for
+    /// queries that rule out more than the wheel's rules do.
+    pub fn without_transitions_where(
+        &self,
+        mut remove: impl FnMut(Scale, ScaleTransition, Scale) -> bool,
+    ) -> Self {
+        let kept: Vec<_> = self
+            .transitions()
+            .filter(|&(from, transition, to)| !remove(from, transition, to))
+            .collect();
+        Self::with_transitions_where(self.size, |from, transition, to| {
+            kept.contains(&(from, transition, to))
+        })
+    }
+
+    /// Number of positions around the wheel.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Parses a camelot code on this wheel, so `20A` is valid on a 24-position wheel.
+    pub fn parse_key(&self, input: &str) -> Result<Scale> {
+        Scale::parse_on(input.trim(), self.size).map_err(|_| invalid_key(input, Locale::English))
+    }
+
+    pub fn graph(&self) -> &Graph<Scale, ScaleTransition> {
+        &self.graph
+    }
+
+    /// The graph node of `scale`, or [`Error::KeyNotOnWheel`] if the wheel has no such key.
+    pub fn node(&self, scale: Scale) -> Result<NodeIndex<u32>> {
+        let node = NodeIndex::new(scale.position());
+        match self.graph.node_weight(node) {
+            Some(&found) if found == scale => Ok(node),
+            _ => Err(Error::KeyNotOnWheel(scale)),
+        }
+    }
+
+    pub fn scale(&self, node: NodeIndex<u32>) -> Option<Scale> {
+        self.graph.node_weight(node).copied()
+    }
+
+    pub fn scales(&self) -> impl Iterator<Item = Scale> + '_ {
+        self.graph.node_weights().copied()
+    }
+
+    /// The scales one transition away from `scale`, with the transition that reaches them.
+    pub fn neighbors(&self, scale: Scale) -> Result<Vec<(ScaleTransition, Scale)>> {
+        let mut neighbors = self
+            .graph
+            .edges(self.node(scale)?)
+            .filter_map(|edge| Some((*edge.weight(), self.scale(edge.target())?)))
+            .collect::<Vec<_>>();
+        // petgraph walks the most recently added edge first.
+        neighbors.reverse();
+        Ok(neighbors)
+    }
+
+    /// The scales one transition away that lead to `scale`, with the transition they take,
+    /// for planning backwards from a key to end on.
+    pub fn predecessors(&self, scale: Scale) -> Result<Vec<(ScaleTransition, Scale)>> {
+        let mut predecessors = self
+            .graph
+            .edges_directed(self.node(scale)?, Direction::Incoming)
+            .filter_map(|edge| Some((*edge.weight(), self.scale(edge.source())?)))
+            .collect::<Vec<_>>();
+        predecessors.reverse();
+        Ok(predecessors)
+    }
+
+    /// Every edge of the graph as `(from, transition, to)`.
+    pub fn transitions(&self) -> impl Iterator<Item = (Scale, ScaleTransition, Scale)> + '_ {
+        self.graph.raw_edges().iter().filter_map(|edge| {
+            Some((
+                self.scale(edge.source())?,
+                edge.weight,
+                self.scale(edge.target())?,
+            ))
+        })
+    }
+
+    pub fn paths(&self, source: Scale, target: Scale, n: usize) -> Result<Vec<Path>> {
+        self.paths_by(source, target, n, PathCost::Hops)
+    }
+
+    /// Like [`Wheel::paths`], ranking paths by `cost` instead of their number of transitions.
+    pub fn paths_by(
+        &self,
+        source: Scale,
+        target: Scale,
+        n: usize,
+        cost: PathCost,
+    ) -> Result<Vec<Path>> {
+        self.paths_with(source, target, n, |from, transition, to| {
+            cost.step(from, transition, to)
+        })
+    }
+
+    /// Like [`Wheel::paths`], pricing every step `(from, transition, to)` with `cost`, so a
+    /// cost model like the time of night or personal taste can rank the paths.
+    pub fn paths_with(
+        &self,
+        source: Scale,
+        target: Scale,
+        n: usize,
+        cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+    ) -> Result<Vec<Path>> {
+        self.paths_within(source, target, n, cost, &SearchLimit::default())
+    }
+
+    /// Like [`Wheel::paths_with`], giving up with [`Error::SearchStopped`] once `limit` is
+    /// reached.
+    pub fn paths_within(
+        &self,
+        source: Scale,
+        target: Scale,
+        n: usize,
+        cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+        limit: &SearchLimit,
+    ) -> Result<Vec<Path>> {
+        self.paths_between(&[source], &[target], n, cost, limit)
+    }
+
+    /// The `n` cheapest paths from any of `sources` to any of `targets`, like from any warm-up
+    /// key to any peak key, see [`Wheel::paths_within`].
+    pub fn paths_between(
+        &self,
+        sources: &[Scale],
+        targets: &[Scale],
+        n: usize,
+        cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+        limit: &SearchLimit,
+    ) -> Result<Vec<Path>> {
+        let nodes = |scales: &[Scale]| {
+            scales
+                .iter()
+                .map(|scale| self.node(*scale))
+                .collect::<Result<Vec<_>>>()
+        };
+        multi_path_search(
+            &self.graph,
+            &nodes(sources)?,
+            &nodes(targets)?,
+            n,
+            cost,
+            limit,
+        )
+    }
+
+    /// The cheapest path from `source` to each of `targets` under `cost`, cheapest first, like
+    /// which of a few tracks is easiest to get to. Targets that can't be reached come last,
+    /// without a path, and targets that cost the same keep their order.
+    pub fn nearest(
+        &self,
+        source: Scale,
+        targets: &[Scale],
+        cost: impl Fn(Scale, ScaleTransition, Scale) -> u32,
+    ) -> Result<Vec<(Scale, Option<Path>)>> {
+        let mut nearest = targets
+            .iter()
+            .map(|target| {
+                let mut paths = self.paths_with(source, *target, 1, &cost)?;
+                Ok((*target, paths.pop()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        nearest.sort_by_key(|(_, path)| path.as_ref().map_or(u64::MAX, |path| path.cost));
+        Ok(nearest)
+    }
+
+    /// Every scale reachable from `source` in at most `steps` transitions, together with the
+    /// minimal number of transitions needed. Sorted by step count, then by wheel position.
+    pub fn reachable(&self, source: Scale, steps: usize) -> Result<Vec<(Scale, usize)>> {
+        Ok(self
+            .distances_from(source)?
+            .into_iter()
+            .enumerate()
+            .filter_map(|(node, distance)| Some((self.scale(NodeIndex::new(node))?, distance?)))
+            .filter(|(_, distance)| *distance <= steps)
+            .sorted_by_key(|(scale, distance)| (*distance, scale.position()))
+            .collect())
+    }
+
+    /// The pivot keys between `from` and `to`: every other key reachable from `from` in at
+    /// most `steps` transitions that reaches `to` in at most `steps` more, so a track in it
+    /// bridges a part of a set in one key and a part in the other. Each comes with the
+    /// transitions from `from` and to `to`, sorted by their total, then by wheel position.
+    pub fn bridges(
+        &self,
+        from: Scale,
+        to: Scale,
+        steps: usize,
+    ) -> Result<Vec<(Scale, usize, usize)>> {
+        self.node(to)?;
+        let mut bridges = vec![];
+        for (key, before) in self.reachable(from, steps)? {
+            if key == from || key == to {
+                continue;
+            }
+            if let Some(after) = self.distances_from(key)?[to.position()] {
+                if after <= steps {
+                    bridges.push((key, before, after));
+                }
+            }
+        }
+        bridges.sort_by_key(|&(key, before, after)| (before + after, key.position()));
+        Ok(bridges)
+    }
+
+    /// Transition counts from `source` to every scale, indexed by node. `None` marks scales that
+    /// can't be reached.
+    pub fn distances_from(&self, source: Scale) -> Result<Vec<Option<usize>>> {
+        let source = self.node(source)?;
+        let mut distances = vec![None; self.graph.node_count()];
+        let mut queue = VecDeque::from([source]);
+        distances[source.index()] = Some(0);
+
+        while let Some(node) = queue.pop_front() {
+            let distance = distances[node.index()].unwrap_or(0);
+            for next in self.graph.neighbors(node) {
+                if distances[next.index()].is_none() {
+                    distances[next.index()] = Some(distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        Ok(distances)
+    }
+}
+
+impl Default for Wheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}